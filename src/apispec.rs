@@ -0,0 +1,201 @@
+use crate::engine::{read_body, send_request};
+use crate::error::CrawlerError;
+use crate::scope::Scope;
+use crate::template::RequestTemplate;
+use crate::{FuzzerConfig, ScanState};
+use reqwest::{Client, Method, Url};
+use serde_json::Value;
+use std::collections::HashSet;
+use tracing::debug;
+
+
+/// Where frameworks publish their Swagger/OpenAPI descriptions
+const SPEC_PATHS: &[&str] = &[
+    "swagger.json",
+    "swagger.yaml",
+    "swagger/v1/swagger.json",
+    "openapi.json",
+    "openapi.yaml",
+    "api-docs",
+    "v2/api-docs",
+    "v3/api-docs",
+];
+
+/// Where GraphQL servers usually listen
+const GRAPHQL_PATHS: &[&str] = &["graphql", "api/graphql"];
+
+/// Minimal introspection query: enough to tell whether introspection is on
+const INTROSPECTION: &str = r#"{"query":"query { __schema { queryType { name } types { name } } }"}"#;
+
+/// Value substituted for `{id}`-style path parameters
+const PARAMETER_VALUE: &str = "1";
+
+/// API descriptions found on the target and the endpoints they list
+#[derive(Default)]
+pub struct Discovery {
+    /// Spec URLs with the number of paths each listed
+    pub specs: Vec<(String, usize)>,
+    /// GraphQL endpoints answering introspection, with their type count
+    pub graphql: Vec<(String, usize)>,
+    /// Endpoint paths relative to the target URL, ready to scan
+    pub paths: Vec<String>,
+}
+
+/// Parse a JSON or YAML document that declares itself Swagger 2 or OpenAPI 3
+pub fn parse_spec(text: &str) -> Option<Value> {
+    let spec: Value = serde_json::from_str(text)
+        .ok()
+        .or_else(|| serde_yaml::from_str(text).ok())?;
+    let declared = spec.get("swagger").or_else(|| spec.get("openapi")).is_some();
+    (declared && spec.get("paths").is_some_and(Value::is_object)).then_some(spec)
+}
+
+/// Absolute endpoint paths of a spec, parameters filled with a placeholder.
+/// Swagger 2 prefixes them with `basePath`, OpenAPI 3 with its first server URL.
+pub fn spec_paths(spec: &Value, spec_url: &Url) -> Vec<String> {
+    let base = match spec.get("basePath").and_then(Value::as_str) {
+        Some(base_path) => base_path.to_string(),
+        None => spec.pointer("/servers/0/url")
+            .and_then(Value::as_str)
+            .and_then(|server| spec_url.join(server).ok())
+            .map(|server| server.path().to_string())
+            .unwrap_or_default(),
+    };
+    let base = base.trim_end_matches('/');
+
+    let Some(paths) = spec.get("paths").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    paths.keys()
+        .map(|path| {
+            let filled: Vec<String> = path.split('/')
+                .map(|segment| match segment.starts_with('{') && segment.ends_with('}') {
+                    true => PARAMETER_VALUE.to_string(),
+                    false => segment.to_string(),
+                })
+                .collect();
+            format!("{}/{}", base, filled.join("/").trim_start_matches('/'))
+        })
+        .collect()
+}
+
+/// Probe well-known API description paths and GraphQL endpoints beneath the
+/// target, and collect every endpoint the specs describe. Requests go through
+/// the scan's scope, blocklists, throttling and audit log.
+pub async fn discover(config: &FuzzerConfig, state: &ScanState, client: &Client) -> Result<Discovery, CrawlerError> {
+    let scope = Scope::new(&config.scope_include, &config.scope_exclude, &config.blocklist)?;
+    let target = Url::parse(&config.url).map_err(|e| CrawlerError::url(&config.url, e))?;
+    let base = format!("{}/", config.url.trim_end_matches('/'));
+    let get = FuzzerConfig { method: Method::GET, request_template: None, ..config.clone() };
+    let mut discovery = Discovery::default();
+    let mut seen = HashSet::new();
+
+    for path in SPEC_PATHS {
+        let url = format!("{}{}", base, path);
+        if let Err(reason) = scope.check(&url) {
+            debug!("🚧 Not probing {}: {}", state.redactor.redact(&url), reason);
+            continue;
+        }
+        let Ok(response) = send_request(&get, state, client, &url, None).await else {
+            continue;
+        };
+        if !response.status().is_success() {
+            continue;
+        }
+        let spec_url = response.url().clone();
+        let Ok((body, _)) = read_body(&get, response).await else {
+            continue;
+        };
+        let Some(spec) = parse_spec(&String::from_utf8_lossy(&body)) else {
+            debug!("{} is not an API description", state.redactor.redact(&url));
+            continue;
+        };
+
+        let endpoints = spec_paths(&spec, &spec_url);
+        discovery.specs.push((url, endpoints.len()));
+        discovery.paths.push(path.to_string());
+        for endpoint in endpoints {
+            // Only endpoints beneath the target can be scanned relative to it
+            let Some(relative) = endpoint.strip_prefix(target.path().trim_end_matches('/')) else {
+                continue;
+            };
+            let relative = relative.trim_matches('/').to_string();
+            if !relative.is_empty() && seen.insert(relative.clone()) {
+                discovery.paths.push(relative);
+            }
+        }
+    }
+
+    for path in GRAPHQL_PATHS {
+        let url = format!("{}{}", base, path);
+        if scope.check(&url).is_err() {
+            continue;
+        }
+        let introspect = FuzzerConfig {
+            method: Method::POST,
+            request_template: Some(RequestTemplate {
+                method: Method::POST.to_string(),
+                url: url.clone(),
+                headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+                body: Some(INTROSPECTION.to_string()),
+                positions: 0,
+            }),
+            ..config.clone()
+        };
+        let Ok(response) = send_request(&introspect, state, client, &url, Some("")).await else {
+            continue;
+        };
+        let Ok((body, _)) = read_body(config, response).await else {
+            continue;
+        };
+        let Ok(answer) = serde_json::from_slice::<Value>(&body) else {
+            continue;
+        };
+        if let Some(types) = answer.pointer("/data/__schema/types").and_then(Value::as_array) {
+            discovery.graphql.push((url, types.len()));
+            discovery.paths.push(path.to_string());
+        }
+    }
+
+    Ok(discovery)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url() -> Url {
+        Url::parse("https://example.com/docs/openapi.json").unwrap()
+    }
+
+    #[test]
+    fn parses_json_and_yaml_specs_only() {
+        assert!(parse_spec(r#"{"swagger":"2.0","paths":{"/users":{}}}"#).is_some());
+        assert!(parse_spec("openapi: 3.0.0\npaths:\n  /users: {}\n").is_some());
+        assert!(parse_spec(r#"{"paths":{"/users":{}}}"#).is_none());
+        assert!(parse_spec("<html>Not found</html>").is_none());
+        assert!(parse_spec("openapi: 3.0.0\npaths: []\n").is_none());
+    }
+
+    #[test]
+    fn swagger_paths_use_base_path_and_fill_parameters() {
+        let spec = parse_spec(r#"{"swagger":"2.0","basePath":"/api/v1/","paths":{"/users/{id}":{},"/health":{}}}"#).unwrap();
+        let mut paths = spec_paths(&spec, &url());
+        paths.sort();
+        assert_eq!(paths, ["/api/v1/health", "/api/v1/users/1"]);
+    }
+
+    #[test]
+    fn openapi_paths_use_the_first_server() {
+        let absolute = parse_spec(
+            "openapi: 3.0.1\nservers:\n  - url: https://example.com/v2\npaths:\n  /orders/{orderId}/items: {}\n",
+        ).unwrap();
+        assert_eq!(spec_paths(&absolute, &url()), ["/v2/orders/1/items"]);
+
+        let relative = parse_spec(r#"{"openapi":"3.0.0","servers":[{"url":"/api"}],"paths":{"/me":{}}}"#).unwrap();
+        assert_eq!(spec_paths(&relative, &url()), ["/api/me"]);
+
+        let serverless = parse_spec(r#"{"openapi":"3.0.0","paths":{"/me":{}}}"#).unwrap();
+        assert_eq!(spec_paths(&serverless, &url()), ["/me"]);
+    }
+}
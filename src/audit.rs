@@ -0,0 +1,72 @@
+use reqwest::Method;
+use std::fs::OpenOptions;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Append-only log of every request sent during a scan
+pub struct AuditLog {
+    target: Mutex<Target>,
+}
+
+enum Target {
+    File(LineWriter<std::fs::File>),
+    /// Records an agent hands back to its coordinator with each result
+    Kept(Vec<String>),
+}
+
+impl AuditLog {
+    /// Open (or create) the audit log, appending to any previous runs
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(AuditLog {
+            target: Mutex::new(Target::File(LineWriter::new(file))),
+        })
+    }
+
+    /// Keep the records in memory until `take`n
+    pub fn kept() -> Self {
+        AuditLog {
+            target: Mutex::new(Target::Kept(Vec::new())),
+        }
+    }
+
+    /// Record one request as a tab-separated line:
+    /// `timestamp  request-id  method  url  outcome`
+    pub fn record(&self, request_id: Option<&str>, method: &Method, url: &str, outcome: &str) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_default();
+        let line = format!(
+            "{:.3}\t{}\t{}\t{}\t{}\n",
+            timestamp,
+            request_id.unwrap_or("-"),
+            method,
+            url,
+            outcome.replace(['\t', '\n'], " ")
+        );
+        self.append(line);
+    }
+
+    /// Add a line recorded elsewhere, such as by an agent
+    pub fn append(&self, line: String) {
+        if let Ok(mut target) = self.target.lock() {
+            match &mut *target {
+                Target::File(writer) => {
+                    let _ = writer.write_all(line.as_bytes());
+                }
+                Target::Kept(lines) => lines.push(line),
+            }
+        }
+    }
+
+    /// The lines kept since the last call
+    pub fn take(&self) -> Vec<String> {
+        match self.target.lock().as_deref_mut() {
+            Ok(Target::Kept(lines)) => std::mem::take(lines),
+            _ => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Most allowance an idle scan saves up, so a pause isn't followed by a burst
+const MAX_BURST: Duration = Duration::from_secs(1);
+
+/// `--max-bandwidth`: bytes per second such as `5MB/s`, `500KB` or `1048576`
+pub fn parse(spec: &str) -> Result<u64, String> {
+    let trimmed = spec.trim();
+    let size = trimmed.strip_suffix("/s").or_else(|| trimmed.strip_suffix("/S")).unwrap_or(trimmed);
+    match crate::parse_size(size) {
+        Ok(0) => Err(format!("Invalid bandwidth '{}': it must be above zero", spec)),
+        Ok(rate) => Ok(rate),
+        Err(_) => Err(format!("Invalid bandwidth '{}'. Use a size per second such as 5MB/s or 500KB/s", spec)),
+    }
+}
+
+/// A rate for humans: `5 MB/s`, `512 KB/s`
+pub fn describe(rate: u64) -> String {
+    match rate {
+        rate if rate >= 1 << 20 && rate % (1 << 20) == 0 => format!("{} MB/s", rate >> 20),
+        rate if rate >= 1 << 10 && rate % (1 << 10) == 0 => format!("{} KB/s", rate >> 10),
+        rate => format!("{} B/s", rate),
+    }
+}
+
+/// Response bytes shared out across every worker: each chunk read books its
+/// share of the schedule, and the reader sleeps until the schedule catches up
+#[derive(Debug)]
+pub struct Limiter {
+    rate: u64,
+    /// When everything downloaded so far has been paid for
+    paid_until: Mutex<Instant>,
+}
+
+impl Limiter {
+    pub fn new(rate: u64) -> Self {
+        Limiter { rate, paid_until: Mutex::new(Instant::now()) }
+    }
+
+    /// Account for `bytes` just downloaded, waiting out whatever they cost
+    /// above the rate
+    pub async fn consume(&self, bytes: usize) {
+        let wait = self.reserve(bytes, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn reserve(&self, bytes: usize, now: Instant) -> Duration {
+        let mut paid_until = self.paid_until.lock().unwrap();
+        let start = (*paid_until).max(now.checked_sub(MAX_BURST).unwrap_or(now));
+        *paid_until = start + Duration::from_secs_f64(bytes as f64 / self.rate as f64);
+        paid_until.saturating_duration_since(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rates_and_spaces_out_reads() {
+        assert_eq!(parse("5MB/s"), Ok(5 << 20));
+        assert_eq!(parse("500kb"), Ok(500 << 10));
+        assert!(parse("0/s").is_err());
+        assert!(parse("fast").is_err());
+        assert_eq!(describe(5 << 20), "5 MB/s");
+        assert_eq!(describe(1500), "1500 B/s");
+
+        let limiter = Limiter::new(1000);
+        let now = *limiter.paid_until.lock().unwrap() + Duration::from_secs(10);
+        // A second of idle allowance covers the first 1000 bytes
+        assert_eq!(limiter.reserve(1000, now), Duration::ZERO);
+        assert_eq!(limiter.reserve(500, now), Duration::from_millis(500));
+        assert_eq!(limiter.reserve(1500, now), Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,416 @@
+use crate::engine::{read_body, send_request};
+use crate::error::CrawlerError;
+use crate::{client_builder, FuzzerConfig, ScanState};
+use crate::term;
+use colored::*;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Response};
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::x509::extension::SubjectAlternativeName;
+use openssl::x509::{X509NameBuilder, X509};
+use reqwest::{Certificate, Client};
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio_native_tls::native_tls::{self, Identity};
+use tokio_native_tls::TlsAcceptor;
+use tracing::debug;
+
+
+pub const DEFAULT_REQUESTS: &str = "2000";
+pub const DEFAULT_THREADS: &str = "1,10,50,100,200";
+
+/// Slowdown past which a connection cost is named the bottleneck
+const SIGNIFICANT: f64 = 0.33;
+
+/// Gain from the previous thread count below which throughput has levelled off
+const STILL_SCALING: f64 = 1.2;
+
+/// What every benchmark request gets back, sized like a typical 404 page
+const NOT_FOUND: &str = "<html><head><title>404 Not Found</title></head>\
+    <body><h1>Not Found</h1><p>The requested URL was not found on this server.</p></body></html>";
+
+/// How one run reaches the local server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setup {
+    /// Plain HTTP to 127.0.0.1 over pooled connections, as a scan normally runs
+    KeepAlive,
+    /// A new connection for every request
+    NoKeepAlive,
+    /// A new connection for every request to `localhost`, looked up each time
+    Dns,
+    /// HTTPS over pooled connections
+    Tls,
+    /// HTTPS with a handshake for every request
+    TlsNoKeepAlive,
+}
+
+impl Setup {
+    fn label(self) -> &'static str {
+        match self {
+            Setup::KeepAlive => "keep-alive",
+            Setup::NoKeepAlive => "new connection per request",
+            Setup::Dns => "new connection + DNS lookup",
+            Setup::Tls => "HTTPS keep-alive",
+            Setup::TlsNoKeepAlive => "HTTPS handshake per request",
+        }
+    }
+
+    fn pooled(self) -> bool {
+        matches!(self, Setup::KeepAlive | Setup::Tls)
+    }
+
+    fn tls(self) -> bool {
+        matches!(self, Setup::Tls | Setup::TlsNoKeepAlive)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Run {
+    pub setup: Setup,
+    pub threads: usize,
+    /// Requests per second, answered or not
+    pub rate: f64,
+    pub errors: usize,
+}
+
+pub struct Options {
+    pub requests: usize,
+    pub threads: Vec<usize>,
+    /// How long the local server waits before answering
+    pub latency: Duration,
+}
+
+/// What limits throughput, judged from the runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bottleneck {
+    /// Opening connections; the share of throughput it costs
+    Pool(f64),
+    Dns(f64),
+    /// TLS handshakes
+    Tls(f64),
+    /// Request handling on this machine; throughput peaks at this thread count
+    Cpu(usize),
+    /// Waiting on the server; throughput was still rising at this thread count
+    Latency(usize),
+}
+
+impl Bottleneck {
+    pub fn describe(&self) -> String {
+        match self {
+            Bottleneck::Pool(cost) => format!(
+                "connection pool: opening a connection per request costs {:.0}% of throughput. \
+                 Keep --max-connections-per-host at or above --threads and --pool-idle-timeout above pauses in the scan",
+                cost * 100.0
+            ),
+            Bottleneck::Dns(cost) => format!(
+                "DNS: a lookup per connection costs {:.0}% of throughput. \
+                 --dns-resolver caches answers in-process and --resolve skips lookups entirely",
+                cost * 100.0
+            ),
+            Bottleneck::Tls(cost) => format!(
+                "TLS: a handshake per request costs {:.0}% of throughput. \
+                 Connection reuse matters most on HTTPS targets; avoid proxies or servers that close connections",
+                cost * 100.0
+            ),
+            Bottleneck::Cpu(threads) => format!(
+                "CPU: throughput levels off at {} threads, so request handling on this machine is the limit. \
+                 More threads won't scan faster",
+                threads
+            ),
+            Bottleneck::Latency(threads) => format!(
+                "server latency: throughput was still rising at {} threads, so requests spend their time waiting. \
+                 More threads will scan faster",
+                threads
+            ),
+        }
+    }
+}
+
+/// The biggest connection cost when one slows requests down noticeably,
+/// else whether more threads still help
+pub fn diagnose(scaling: &[Run], variants: &[Run]) -> Option<Bottleneck> {
+    let best = scaling.iter().max_by(|a, b| a.rate.total_cmp(&b.rate))?;
+    let rate = |setup: Setup| variants.iter().find(|run| run.setup == setup).map(|run| run.rate);
+    let slowdown = |slower: Option<f64>, faster: Option<f64>| match (slower, faster) {
+        (Some(slower), Some(faster)) if faster > 0.0 => (1.0 - slower / faster).max(0.0),
+        _ => 0.0,
+    };
+    let plain = Some(best.rate);
+    let fresh = rate(Setup::NoKeepAlive);
+    let costs = [
+        Bottleneck::Pool(slowdown(fresh, plain)),
+        Bottleneck::Dns(slowdown(rate(Setup::Dns), fresh)),
+        Bottleneck::Tls(slowdown(rate(Setup::TlsNoKeepAlive), fresh)),
+    ];
+    let cost = |bottleneck: &Bottleneck| match bottleneck {
+        Bottleneck::Pool(cost) | Bottleneck::Dns(cost) | Bottleneck::Tls(cost) => *cost,
+        _ => 0.0,
+    };
+    if let Some(worst) = costs.into_iter().filter(|b| cost(b) >= SIGNIFICANT).max_by(|a, b| cost(a).total_cmp(&cost(b))) {
+        return Some(worst);
+    }
+
+    // Still climbing at the highest thread count tried
+    let last = scaling.last()?;
+    let previous = scaling.len().checked_sub(2).map(|i| scaling[i]);
+    match previous {
+        Some(previous) if last.threads == best.threads && last.rate >= previous.rate * STILL_SCALING => {
+            Some(Bottleneck::Latency(last.threads))
+        }
+        _ => Some(Bottleneck::Cpu(best.threads)),
+    }
+}
+
+/// Benchmark scanning against in-process HTTP and HTTPS servers and print
+/// the results with the likely bottleneck
+pub async fn run(options: &Options) -> Result<(), CrawlerError> {
+    let (cert, acceptor) = self_signed()?;
+    let http = serve(None, options.latency).await?;
+    let https = serve(Some(acceptor), options.latency).await?;
+
+    term::out!(
+        "⏱️  Benchmarking against a local server: {} requests per run, {}ms server latency",
+        options.requests,
+        options.latency.as_millis()
+    );
+    term::out!("{:>9}  {:<30} {:>10} {:>8}", "Threads", "Setup", "req/s", "Errors");
+
+    let mut scaling = Vec::new();
+    for &threads in &options.threads {
+        let run = measure(Setup::KeepAlive, threads, options.requests, http, https, &cert).await?;
+        print_run(&run);
+        scaling.push(run);
+    }
+
+    let best = scaling.iter().max_by(|a, b| a.rate.total_cmp(&b.rate)).map_or(1, |run| run.threads);
+    let mut variants = Vec::new();
+    for setup in [Setup::NoKeepAlive, Setup::Dns, Setup::Tls, Setup::TlsNoKeepAlive] {
+        let run = measure(setup, best, options.requests, http, https, &cert).await?;
+        print_run(&run);
+        variants.push(run);
+    }
+
+    if let Some(bottleneck) = diagnose(&scaling, &variants) {
+        term::out!("\n🔍 Bottleneck: {}", bottleneck.describe());
+    }
+    term::out!(
+        "{}",
+        "ℹ️  The server shares this machine, so a remote target adds its own latency on top of these numbers.".dimmed()
+    );
+    Ok(())
+}
+
+fn print_run(run: &Run) {
+    let errors = match run.errors {
+        0 => "0".normal(),
+        n => n.to_string().yellow(),
+    };
+    term::out!("{:>9}  {:<30} {:>10.1} {:>8}", run.threads, run.setup.label(), run.rate, errors);
+}
+
+/// Send `requests` requests over `threads` workers through the scan's own
+/// client and request path, after a warm-up round that fills the pool
+async fn measure(
+    setup: Setup,
+    threads: usize,
+    requests: usize,
+    http: SocketAddr,
+    https: SocketAddr,
+    cert: &Certificate,
+) -> Result<Run, CrawlerError> {
+    let mut config = FuzzerConfig { threads, timeout: Some(10), ..Default::default() };
+    let base = match setup {
+        Setup::KeepAlive | Setup::NoKeepAlive => format!("http://{}/", http),
+        Setup::Dns => format!("http://localhost:{}/", http.port()),
+        Setup::Tls | Setup::TlsNoKeepAlive => {
+            // Pinned so HTTPS runs don't pay for lookups too
+            config.resolve.push(("localhost".to_string(), https));
+            format!("https://localhost:{}/", https.port())
+        }
+    };
+    let mut builder = client_builder(&config)?;
+    if !setup.pooled() {
+        builder = builder.pool_max_idle_per_host(0);
+    }
+    if setup.tls() {
+        builder = builder.add_root_certificate(cert.clone());
+    }
+    let client = builder.build()?;
+    let config = Arc::new(config);
+
+    burst(&config, &client, &base, threads, threads).await;
+    let start = Instant::now();
+    let errors = burst(&config, &client, &base, threads, requests).await;
+    let elapsed = start.elapsed().as_secs_f64().max(f64::EPSILON);
+    Ok(Run { setup, threads, rate: requests as f64 / elapsed, errors })
+}
+
+/// Requests that failed or whose body could not be read
+async fn burst(config: &Arc<FuzzerConfig>, client: &Client, base: &str, threads: usize, requests: usize) -> usize {
+    let state = Arc::new(ScanState::default());
+    let next = Arc::new(AtomicUsize::new(0));
+    let errors = Arc::new(AtomicUsize::new(0));
+    let mut workers = tokio::task::JoinSet::new();
+    for _ in 0..threads.max(1) {
+        let (config, state, client) = (Arc::clone(config), Arc::clone(&state), client.clone());
+        let (next, errors, base) = (Arc::clone(&next), Arc::clone(&errors), base.to_string());
+        workers.spawn(async move {
+            loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= requests {
+                    break;
+                }
+                let url = format!("{}bench/{}", base, i);
+                let answered = match send_request(&config, &state, &client, &url, None).await {
+                    Ok(response) => read_body(&config, response).await.is_ok(),
+                    Err(e) => {
+                        debug!("Bench request failed: {}", e);
+                        false
+                    }
+                };
+                if !answered {
+                    errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+    }
+    while workers.join_next().await.is_some() {}
+    errors.load(Ordering::Relaxed)
+}
+
+/// Start a server on a free loopback port answering every request with a
+/// 404, over TLS when given an acceptor. It also listens on `[::1]` where
+/// possible so `localhost` connects first time on either family.
+async fn serve(tls: Option<TlsAcceptor>, latency: Duration) -> Result<SocketAddr, CrawlerError> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let address = listener.local_addr()?;
+    let mut listeners = vec![listener];
+    match TcpListener::bind((Ipv6Addr::LOCALHOST, address.port())).await {
+        Ok(listener) => listeners.push(listener),
+        Err(e) => debug!("Bench server not listening on [::1]: {}", e),
+    }
+
+    for listener in listeners {
+        let tls = tls.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let tls = tls.clone();
+                tokio::spawn(async move {
+                    let result = match tls {
+                        Some(acceptor) => match acceptor.accept(stream).await {
+                            Ok(stream) => answer(stream, latency).await,
+                            Err(e) => return debug!("Bench TLS handshake failed: {}", e),
+                        },
+                        None => answer(stream, latency).await,
+                    };
+                    if let Err(e) = result {
+                        debug!("Bench connection failed: {}", e);
+                    }
+                });
+            }
+        });
+    }
+    Ok(address)
+}
+
+async fn answer<S>(stream: S, latency: Duration) -> Result<(), hyper::Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin + 'static,
+{
+    let service = service_fn(move |_| async move {
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        Ok::<_, Infallible>(Response::builder().status(404).body(Body::from(NOT_FOUND)).unwrap())
+    });
+    Http::new().serve_connection(stream, service).await
+}
+
+/// A throwaway certificate for `localhost`, as the client trusts it and as
+/// the server presents it
+fn self_signed() -> Result<(Certificate, TlsAcceptor), CrawlerError> {
+    let failed = |e: &dyn std::fmt::Display| CrawlerError::Config(format!("Cannot set up the local TLS server: {}", e));
+    let (cert, key) = certificate().map_err(|e| failed(&e))?;
+    let identity = Identity::from_pkcs8(&cert, &key).map_err(|e| failed(&e))?;
+    let acceptor = native_tls::TlsAcceptor::new(identity).map_err(|e| failed(&e))?;
+    Ok((Certificate::from_pem(&cert)?, TlsAcceptor::from(acceptor)))
+}
+
+/// A self-signed `localhost` certificate and its key, both PEM
+fn certificate() -> Result<(Vec<u8>, Vec<u8>), ErrorStack> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_nid(Nid::COMMONNAME, "localhost")?;
+    let name = name.build();
+
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(BigNum::from_u32(1)?.to_asn1_integer()?.as_ref())?;
+    builder.set_subject_name(&name)?;
+    builder.set_issuer_name(&name)?;
+    builder.set_pubkey(&key)?;
+    builder.set_not_before(Asn1Time::days_from_now(0)?.as_ref())?;
+    builder.set_not_after(Asn1Time::days_from_now(1)?.as_ref())?;
+    let names = SubjectAlternativeName::new().dns("localhost").build(&builder.x509v3_context(None, None))?;
+    builder.append_extension(names)?;
+    builder.sign(&key, MessageDigest::sha256())?;
+    Ok((builder.build().to_pem()?, key.private_key_to_pem_pkcs8()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(setup: Setup, threads: usize, rate: f64) -> Run {
+        Run { setup, threads, rate, errors: 0 }
+    }
+
+    fn scaling(rates: &[(usize, f64)]) -> Vec<Run> {
+        rates.iter().map(|&(threads, rate)| run(Setup::KeepAlive, threads, rate)).collect()
+    }
+
+    #[test]
+    fn names_the_costliest_connection_step() {
+        let scaling = scaling(&[(1, 500.0), (10, 4000.0), (50, 4100.0)]);
+        let variants = |fresh: f64, dns: f64, tls: f64| {
+            vec![run(Setup::NoKeepAlive, 50, fresh), run(Setup::Dns, 50, dns), run(Setup::TlsNoKeepAlive, 50, tls)]
+        };
+        assert!(matches!(diagnose(&scaling, &variants(1000.0, 950.0, 900.0)), Some(Bottleneck::Pool(c)) if c > 0.7));
+        assert!(matches!(diagnose(&scaling, &variants(4000.0, 1000.0, 3000.0)), Some(Bottleneck::Dns(c)) if c == 0.75));
+        assert!(matches!(diagnose(&scaling, &variants(3900.0, 3800.0, 1300.0)), Some(Bottleneck::Tls(_))));
+    }
+
+    #[test]
+    fn tells_cpu_limits_from_latency() {
+        let cheap = [run(Setup::NoKeepAlive, 50, 3900.0), run(Setup::Dns, 50, 3800.0)];
+        assert_eq!(diagnose(&scaling(&[(1, 500.0), (10, 4000.0), (50, 4100.0)]), &cheap), Some(Bottleneck::Cpu(50)));
+        assert_eq!(diagnose(&scaling(&[(10, 4000.0), (50, 3500.0)]), &cheap), Some(Bottleneck::Cpu(10)));
+        assert_eq!(diagnose(&scaling(&[(10, 900.0), (50, 3900.0)]), &cheap), Some(Bottleneck::Latency(50)));
+        assert_eq!(diagnose(&[], &cheap), None);
+    }
+
+    #[tokio::test]
+    async fn measures_plain_and_tls_servers() {
+        let (cert, acceptor) = self_signed().unwrap();
+        let http = serve(None, Duration::ZERO).await.unwrap();
+        let https = serve(Some(acceptor), Duration::ZERO).await.unwrap();
+        for setup in [Setup::KeepAlive, Setup::NoKeepAlive, Setup::Tls, Setup::TlsNoKeepAlive] {
+            let run = measure(setup, 4, 20, http, https, &cert).await.unwrap();
+            assert_eq!(run.errors, 0, "{:?}", setup);
+            assert!(run.rate > 0.0);
+        }
+    }
+}
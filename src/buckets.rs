@@ -0,0 +1,175 @@
+use crate::mutate::Mutations;
+use reqwest::Url;
+use std::collections::HashSet;
+
+/// Stands in for the target URL in banners and reports when no URL is given
+pub const TARGET: &str = "cloud storage buckets";
+
+/// Permutations tried around each keyword when no mutation rules are given
+const DEFAULT_PREFIXES: &[&str] = &["dev-", "staging-", "prod-", "test-", "backup-"];
+const DEFAULT_SUFFIXES: &[&str] = &[
+    "-backup", "-backups", "-dev", "-staging", "-prod", "-test", "-data", "-assets", "-static",
+    "-media", "-files", "-uploads", "-logs", "-public", "-private", "-archive",
+];
+
+/// Error codes the providers answer with when the bucket or container doesn't exist
+const MISSING_CODES: &[&str] = &["NoSuchBucket", "InvalidBucketName", "ContainerNotFound", "ResourceNotFound"];
+
+/// Storage services a bucket name is tried against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Provider {
+    Aws,
+    Gcp,
+    Azure,
+}
+
+impl Provider {
+    fn of(url: &str) -> Option<Self> {
+        let host = Url::parse(url).ok()?.host_str()?.to_string();
+        if host == "s3.amazonaws.com" || host.ends_with(".s3.amazonaws.com") {
+            Some(Provider::Aws)
+        } else if host == "storage.googleapis.com" {
+            Some(Provider::Gcp)
+        } else if host.ends_with(".blob.core.windows.net") {
+            Some(Provider::Azure)
+        } else {
+            None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Provider::Aws => "aws",
+            Provider::Gcp => "gcp",
+            Provider::Azure => "azure",
+        }
+    }
+}
+
+/// Built-in bucket name permutations (environment prefixes and purpose suffixes)
+pub fn default_mutations() -> Mutations {
+    Mutations {
+        prefixes: DEFAULT_PREFIXES.iter().map(|s| s.to_string()).collect(),
+        suffixes: DEFAULT_SUFFIXES.iter().map(|s| s.to_string()).collect(),
+        delimiters: vec!["-".to_string(), ".".to_string()],
+        ..Mutations::default()
+    }
+}
+
+/// S3/GCS naming rules: 3-63 lowercase letters, digits, dots and dashes,
+/// starting and ending with a letter or digit
+fn valid_bucket(name: &str) -> bool {
+    (3..=63).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric())
+        && !name.contains("..")
+}
+
+/// Azure storage account names: 3-24 lowercase letters and digits
+fn valid_azure_account(name: &str) -> bool {
+    (3..=24).contains(&name.len()) && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
+/// Every candidate URL for a keyword list: each permuted name in each
+/// provider's URL format. Delimiters are swapped after prefixes and suffixes
+/// are added, so `acme-backup` is also tried as `acme.backup` and `acmebackup`
+/// (the only form Azure accepts). Azure is probed for a same-named container's
+/// listing; dotted S3 names use path-style URLs, as they break the wildcard certificate.
+pub fn candidates(keywords: &[String], mutations: &Mutations) -> Vec<String> {
+    let swaps = Mutations { delimiters: mutations.delimiters.clone(), ..Mutations::default() };
+    let mut seen = HashSet::new();
+    let mut urls = Vec::new();
+    for keyword in keywords {
+        let names = mutations.apply(&keyword.to_lowercase()).into_iter().flat_map(|name| swaps.apply(&name));
+        for name in names {
+            let name = name.to_lowercase();
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            if valid_bucket(&name) {
+                urls.push(match name.contains('.') {
+                    true => format!("https://s3.amazonaws.com/{}/", name),
+                    false => format!("https://{}.s3.amazonaws.com/", name),
+                });
+                urls.push(format!("https://storage.googleapis.com/{}/", name));
+            }
+            if valid_azure_account(&name) {
+                urls.push(format!("https://{0}.blob.core.windows.net/{0}?restype=container&comp=list", name));
+            }
+        }
+    }
+    urls
+}
+
+/// Classify a bucket probe as `public` (listing enabled) or `private`
+/// (exists but refuses us); `None` means the bucket doesn't exist
+pub fn classify(url: &str, status: u16, body: &[u8]) -> Option<String> {
+    let provider = Provider::of(url)?;
+    let body = String::from_utf8_lossy(body);
+    if MISSING_CODES.iter().any(|code| body.contains(code)) {
+        return None;
+    }
+    let class = match status {
+        200..=299 => "public",
+        301 | 307 | 400 | 401 | 403 => "private",
+        _ => return None,
+    };
+    Some(format!("bucket:{}:{}", provider.name(), class))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates_follow_each_providers_naming_rules() {
+        let keywords = ["Acme".to_string(), "acme.corp".to_string(), "x".to_string()];
+        let urls = candidates(&keywords, &Mutations::default());
+        assert_eq!(urls, [
+            "https://acme.s3.amazonaws.com/",
+            "https://storage.googleapis.com/acme/",
+            "https://acme.blob.core.windows.net/acme?restype=container&comp=list",
+            "https://s3.amazonaws.com/acme.corp/",
+            "https://storage.googleapis.com/acme.corp/",
+        ]);
+    }
+
+    #[test]
+    fn default_mutations_permute_keywords() {
+        let urls = candidates(&["acme".to_string()], &default_mutations());
+        assert!(urls.contains(&"https://acme-backup.s3.amazonaws.com/".to_string()));
+        assert!(urls.contains(&"https://s3.amazonaws.com/acme.backup/".to_string()));
+        assert!(urls.contains(&"https://dev-acme.s3.amazonaws.com/".to_string()));
+        assert!(urls.contains(&"https://acmebackup.blob.core.windows.net/acmebackup?restype=container&comp=list".to_string()));
+        assert!(urls.iter().all(|url| !url.contains("acme-backup.blob")), "dashes are invalid in Azure accounts");
+    }
+
+    #[test]
+    fn bucket_names_are_validated() {
+        assert!(valid_bucket("my-bucket.logs"));
+        assert!(!valid_bucket("ab"));
+        assert!(!valid_bucket("-bucket"));
+        assert!(!valid_bucket("bucket."));
+        assert!(!valid_bucket("a..b"));
+        assert!(!valid_bucket("Bucket"));
+        assert!(!valid_bucket(&"a".repeat(64)));
+        assert!(valid_azure_account("acme2024"));
+        assert!(!valid_azure_account("acme-2024"));
+        assert!(!valid_azure_account(&"a".repeat(25)));
+    }
+
+    #[test]
+    fn classifies_public_private_and_missing_buckets() {
+        let s3 = "https://acme.s3.amazonaws.com/";
+        let azure = "https://acme.blob.core.windows.net/acme?restype=container&comp=list";
+        assert_eq!(classify(s3, 200, b"<ListBucketResult>").as_deref(), Some("bucket:aws:public"));
+        assert_eq!(classify(s3, 403, b"<Code>AccessDenied</Code>").as_deref(), Some("bucket:aws:private"));
+        assert_eq!(classify(s3, 404, b"<Code>NoSuchBucket</Code>"), None);
+        assert_eq!(classify("https://storage.googleapis.com/acme/", 401, b"").as_deref(), Some("bucket:gcp:private"));
+        assert_eq!(classify(azure, 404, b"<Code>ContainerNotFound</Code>"), None);
+        assert_eq!(classify(azure, 404, b"<Code>ResourceNotFound</Code>"), None);
+        assert_eq!(classify(azure, 403, b"").as_deref(), Some("bucket:azure:private"));
+        assert_eq!(classify("https://example.com/acme/", 200, b""), None);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::Variant;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of random paths requested to fingerprint a directory's not-found page
+pub const SAMPLE_COUNT: usize = 3;
+
+/// Not-found fingerprint for one directory: how it answers for paths that can't exist
+#[derive(Debug, Clone, Default)]
+pub struct Baseline {
+    samples: Vec<Variant>,
+}
+
+impl Baseline {
+    pub fn new(samples: Vec<Variant>) -> Self {
+        Baseline { samples }
+    }
+
+    /// Whether a response looks like this directory's (soft) 404 page
+    pub fn matches(&self, status: u16, size: u64) -> bool {
+        self.samples.iter().any(|sample| {
+            sample.status == status && {
+                let (small, large) = (sample.size.min(size), sample.size.max(size));
+                large - small <= (large / 20).max(32)
+            }
+        })
+    }
+
+    /// Whether any baseline sample answered with this status
+    pub fn has_status(&self, status: u16) -> bool {
+        self.samples.iter().any(|sample| sample.status == status)
+    }
+
+    /// Short description for verbose output, e.g. `200/5120B`
+    pub fn describe(&self) -> String {
+        self.samples
+            .iter()
+            .map(|s| format!("{}/{}B", s.status, s.size))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A path segment that will not exist on any real server
+pub fn random_segment() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let seed = format!("{}-{}-{}", nanos, std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed));
+    Sha256::digest(seed.as_bytes())
+        .iter()
+        .take(12)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
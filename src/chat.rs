@@ -0,0 +1,408 @@
+use crate::notify::format_elapsed;
+use crate::secrets::Severity;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Environment variable holding the Slack bot token, kept off the command line
+pub const SLACK_TOKEN_ENV: &str = "DIR_CRAWLER_SLACK_TOKEN";
+
+const SLACK_API: &str = "https://slack.com/api/chat.postMessage";
+
+/// Findings arriving this close together are posted as one message
+const BATCH_WINDOW: Duration = Duration::from_secs(2);
+
+/// Most findings listed in one message; the rest are counted
+const MAX_LISTED: usize = 20;
+
+/// Attempts per message when the service keeps answering 429
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Longest `Retry-After` honored before giving up on a message
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Where scan updates are posted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    /// Slack incoming webhook; posts to its channel, without threads
+    SlackWebhook(String),
+    /// Slack bot token and channel; updates go in a thread under the start message
+    SlackApi { token: String, channel: String },
+    DiscordWebhook(String),
+}
+
+/// A finding worth telling the team about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+    pub url: String,
+    pub status: u16,
+    pub severity: Severity,
+    pub tags: Vec<String>,
+}
+
+impl Hit {
+    pub fn new(finding: &crate::Finding, redactor: &crate::redact::Redactor) -> Self {
+        Hit {
+            url: redactor.redact(&finding.label()).into_owned(),
+            status: finding.status,
+            severity: finding.severity,
+            tags: finding.tags.clone(),
+        }
+    }
+}
+
+/// End-of-scan numbers for the completion message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub findings: usize,
+    /// Findings per severity, most severe first, zero counts left out
+    pub by_severity: Vec<(Severity, usize)>,
+    pub elapsed: Duration,
+    /// Stop condition that ended the scan early
+    pub stopped: Option<String>,
+}
+
+#[derive(Debug)]
+enum Event {
+    Hit(Hit),
+    Finished(Summary),
+}
+
+/// Posts a scan's start, its severe findings (batched) and its completion
+/// summary to Slack and Discord, one message at a time so bursts of
+/// findings don't run into rate limits
+pub struct Chat {
+    min_severity: Severity,
+    events: Mutex<Option<mpsc::UnboundedSender<Event>>>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Chat {
+    /// Announce the scan and start posting in the background
+    pub fn start(destinations: Vec<Destination>, min_severity: Severity, target: &str, scan_id: &str) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .unwrap_or_default();
+        let target = target.to_string();
+        let scan_id = scan_id.to_string();
+        let task = tokio::spawn(async move {
+            let threads = vec![None; destinations.len()];
+            Poster { client, destinations, threads, target }.run(&scan_id, rx).await;
+        });
+        Chat {
+            min_severity,
+            events: Mutex::new(Some(tx)),
+            task: Mutex::new(Some(task)),
+        }
+    }
+
+    /// Queue a finding if it is severe enough to mention
+    pub fn finding(&self, hit: Hit) {
+        if hit.severity >= self.min_severity {
+            self.send(Event::Hit(hit));
+        }
+    }
+
+    /// Post the completion summary and wait (briefly) for everything queued to go out
+    pub async fn finish(&self, summary: Summary) {
+        self.send(Event::Finished(summary));
+        self.events.lock().unwrap().take();
+        let task = self.task.lock().unwrap().take();
+        if let Some(task) = task {
+            if tokio::time::timeout(Duration::from_secs(30), task).await.is_err() {
+                warn!("Gave up waiting for chat messages to be delivered");
+            }
+        }
+    }
+
+    fn send(&self, event: Event) {
+        if let Some(events) = self.events.lock().unwrap().as_ref() {
+            let _ = events.send(event);
+        }
+    }
+}
+
+struct Poster {
+    client: reqwest::Client,
+    destinations: Vec<Destination>,
+    /// Slack thread each destination's updates go under, once started
+    threads: Vec<Option<String>>,
+    target: String,
+}
+
+impl Poster {
+    async fn run(&mut self, scan_id: &str, mut events: mpsc::UnboundedReceiver<Event>) {
+        self.post(&Message::Started { target: self.target.clone(), scan_id: scan_id.to_string() }).await;
+        while let Some(event) = events.recv().await {
+            // Let a burst of findings arrive, then post them together
+            let mut batch = vec![event];
+            if matches!(batch[0], Event::Hit(_)) {
+                tokio::time::sleep(BATCH_WINDOW).await;
+            }
+            while let Ok(event) = events.try_recv() {
+                batch.push(event);
+            }
+
+            let mut hits = Vec::new();
+            let mut finished = None;
+            for event in batch {
+                match event {
+                    Event::Hit(hit) => hits.push(hit),
+                    Event::Finished(summary) => finished = Some(summary),
+                }
+            }
+            if !hits.is_empty() {
+                hits.sort_by_key(|hit| std::cmp::Reverse(hit.severity));
+                self.post(&Message::Findings(hits)).await;
+            }
+            if let Some(summary) = finished {
+                self.post(&Message::Finished { target: self.target.clone(), summary }).await;
+            }
+        }
+    }
+
+    async fn post(&mut self, message: &Message) {
+        for i in 0..self.destinations.len() {
+            let thread = self.threads[i].clone();
+            match self.deliver(&self.destinations[i], message, thread.as_deref()).await {
+                Ok(Some(ts)) if self.threads[i].is_none() => self.threads[i] = Some(ts),
+                Ok(_) => {}
+                Err(e) => warn!("Posting to {} failed: {}", self.destinations[i].service(), e),
+            }
+        }
+    }
+
+    /// Send one message, waiting out 429s; returns the Slack message
+    /// timestamp that later updates can thread under
+    async fn deliver(&self, destination: &Destination, message: &Message, thread: Option<&str>) -> Result<Option<String>, String> {
+        let (url, body, token) = match destination {
+            Destination::SlackWebhook(url) => (url.as_str(), slack_payload(message, None, None), None),
+            Destination::SlackApi { token, channel } => (SLACK_API, slack_payload(message, Some(channel), thread), Some(token)),
+            Destination::DiscordWebhook(url) => (url.as_str(), discord_payload(message), None),
+        };
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            let mut request = self.client.post(url).json(&body);
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            let status = response.status();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                let wait = response.headers().get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(crate::throttle::parse_retry_after)
+                    .unwrap_or(Duration::from_secs(1));
+                if wait > MAX_RETRY_AFTER || attempt == MAX_ATTEMPTS {
+                    return Err(format!("rate limited (retry after {}s)", wait.as_secs()));
+                }
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+            if !status.is_success() {
+                return Err(format!("HTTP {}", status.as_u16()));
+            }
+            if token.is_none() {
+                return Ok(None);
+            }
+            // The Web API answers 200 with `ok: false` on errors
+            let reply: Value = response.json().await.map_err(|e| e.to_string())?;
+            if reply["ok"] != true {
+                return Err(reply["error"].as_str().unwrap_or("unknown error").to_string());
+            }
+            return Ok(reply["ts"].as_str().map(str::to_string));
+        }
+        Err("rate limited".to_string())
+    }
+}
+
+impl Destination {
+    fn service(&self) -> &'static str {
+        match self {
+            Destination::SlackWebhook(_) | Destination::SlackApi { .. } => "Slack",
+            Destination::DiscordWebhook(_) => "Discord",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Message {
+    Started { target: String, scan_id: String },
+    Findings(Vec<Hit>),
+    Finished { target: String, summary: Summary },
+}
+
+/// Slack `mrkdwn` needs only these three escaped
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn slack_payload(message: &Message, channel: Option<&str>, thread: Option<&str>) -> Value {
+    let text = match message {
+        Message::Started { target, scan_id } => {
+            format!(":mag: Scan started against `{}` (scan `{}`)", slack_escape(target), scan_id)
+        }
+        Message::Findings(hits) => {
+            let mut text = format!(":rotating_light: *{}*", heading(hits));
+            for hit in hits.iter().take(MAX_LISTED) {
+                text.push_str(&format!("\n• *{}* {} ({})", hit.severity, slack_escape(&hit.url), hit.status));
+                if !hit.tags.is_empty() {
+                    text.push_str(&format!(" `{}`", slack_escape(&hit.tags.join(", "))));
+                }
+            }
+            if hits.len() > MAX_LISTED {
+                text.push_str(&format!("\n…and {} more", hits.len() - MAX_LISTED));
+            }
+            text
+        }
+        Message::Finished { target, summary } => {
+            let (icon, state) = match &summary.stopped {
+                Some(reason) => (":octagonal_sign:", format!("stopped early ({})", slack_escape(reason))),
+                None => (":white_check_mark:", "finished".to_string()),
+            };
+            format!("{} Scan against `{}` {} in {}: {}", icon, slack_escape(target), state, format_elapsed(summary.elapsed), totals(summary))
+        }
+    };
+
+    let mut payload = json!({ "text": text });
+    if let Some(channel) = channel {
+        payload["channel"] = json!(channel);
+    }
+    if let Some(thread) = thread {
+        payload["thread_ts"] = json!(thread);
+        // The summary is worth seeing without opening the thread
+        if matches!(message, Message::Finished { .. }) {
+            payload["reply_broadcast"] = json!(true);
+        }
+    }
+    payload
+}
+
+fn discord_payload(message: &Message) -> Value {
+    let embed = match message {
+        Message::Started { target, scan_id } => json!({
+            "title": "🔍 Scan started",
+            "description": target,
+            "color": 0x3498db,
+            "footer": { "text": format!("scan {}", scan_id) },
+        }),
+        Message::Findings(hits) => {
+            let mut lines: Vec<String> = hits.iter()
+                .take(MAX_LISTED)
+                .map(|hit| {
+                    let mut line = format!("**{}** {} ({})", hit.severity.to_string().to_uppercase(), hit.url, hit.status);
+                    if !hit.tags.is_empty() {
+                        line.push_str(&format!(" `{}`", hit.tags.join(", ")));
+                    }
+                    line
+                })
+                .collect();
+            if hits.len() > MAX_LISTED {
+                lines.push(format!("…and {} more", hits.len() - MAX_LISTED));
+            }
+            let worst = hits.iter().map(|hit| hit.severity).max().unwrap_or_default();
+            json!({
+                "title": format!("🚨 {}", heading(hits)),
+                // Embed descriptions are capped at 4096 characters
+                "description": lines.join("\n").chars().take(4_000).collect::<String>(),
+                "color": severity_color(worst),
+            })
+        }
+        Message::Finished { target, summary } => {
+            let (title, color) = match &summary.stopped {
+                Some(_) => ("🛑 Scan stopped early", 0xe67e22),
+                None => ("✅ Scan finished", 0x2ecc71),
+            };
+            let mut description = format!("{}\n{} in {}", target, totals(summary), format_elapsed(summary.elapsed));
+            if let Some(reason) = &summary.stopped {
+                description.push_str(&format!("\n{}", reason));
+            }
+            json!({ "title": title, "description": description, "color": color })
+        }
+    };
+    json!({ "username": "dir_crawler", "embeds": [embed] })
+}
+
+/// `3 new findings` or `1 new critical finding`
+fn heading(hits: &[Hit]) -> String {
+    match hits {
+        [hit] => format!("1 new {} finding", hit.severity),
+        _ => format!("{} new findings", hits.len()),
+    }
+}
+
+/// `12 findings (1 critical, 2 high, 9 info)`
+fn totals(summary: &Summary) -> String {
+    let plural = if summary.findings == 1 { "" } else { "s" };
+    let counts: Vec<String> = summary.by_severity.iter().map(|(severity, n)| format!("{} {}", n, severity)).collect();
+    match counts.is_empty() {
+        true => format!("{} finding{}", summary.findings, plural),
+        false => format!("{} finding{} ({})", summary.findings, plural, counts.join(", ")),
+    }
+}
+
+/// Discord embed side color for a severity
+fn severity_color(severity: Severity) -> u32 {
+    match severity {
+        Severity::Critical => 0x8e1b1b,
+        Severity::High => 0xe74c3c,
+        Severity::Medium => 0xf1c40f,
+        Severity::Low => 0x3498db,
+        Severity::Info => 0x95a5a6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(url: &str, severity: Severity) -> Hit {
+        Hit { url: url.to_string(), status: 200, severity, tags: vec!["exposed:git".to_string()] }
+    }
+
+    fn summary(stopped: Option<&str>) -> Summary {
+        Summary {
+            findings: 12,
+            by_severity: vec![(Severity::Critical, 1), (Severity::High, 2), (Severity::Info, 9)],
+            elapsed: Duration::from_secs(185),
+            stopped: stopped.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn formats_slack_messages_and_threads() {
+        let findings = Message::Findings(vec![hit("http://t/.git/HEAD?a=1&b=<2>", Severity::Critical)]);
+        let payload = slack_payload(&findings, Some("C123"), Some("1700000000.000100"));
+        assert_eq!(payload["text"], ":rotating_light: *1 new critical finding*\n• *critical* http://t/.git/HEAD?a=1&amp;b=&lt;2&gt; (200) `exposed:git`");
+        assert_eq!(payload["channel"], "C123");
+        assert_eq!(payload["thread_ts"], "1700000000.000100");
+        assert!(payload.get("reply_broadcast").is_none());
+
+        let finished = Message::Finished { target: "http://t/".to_string(), summary: summary(None) };
+        let payload = slack_payload(&finished, None, Some("1700000000.000100"));
+        assert_eq!(payload["text"], ":white_check_mark: Scan against `http://t/` finished in 3m 05s: 12 findings (1 critical, 2 high, 9 info)");
+        assert_eq!(payload["reply_broadcast"], true);
+        assert!(slack_payload(&finished, None, None).get("thread_ts").is_none());
+    }
+
+    #[test]
+    fn formats_discord_embeds() {
+        let hits: Vec<Hit> = (0..25).map(|i| hit(&format!("http://t/{}", i), if i == 3 { Severity::Critical } else { Severity::High })).collect();
+        let payload = discord_payload(&Message::Findings(hits));
+        let embed = &payload["embeds"][0];
+        assert_eq!(embed["title"], "🚨 25 new findings");
+        assert_eq!(embed["color"], 0x8e1b1b);
+        let description = embed["description"].as_str().unwrap();
+        assert!(description.starts_with("**HIGH** http://t/0 (200) `exposed:git`\n"));
+        assert!(description.ends_with("\n…and 5 more"));
+
+        let payload = discord_payload(&Message::Finished { target: "http://t/".to_string(), summary: summary(Some("max time of 30s reached")) });
+        assert_eq!(payload["embeds"][0]["title"], "🛑 Scan stopped early");
+        assert_eq!(payload["embeds"][0]["description"], "http://t/\n12 findings (1 critical, 2 high, 9 info) in 3m 05s\nmax time of 30s reached");
+    }
+}
@@ -0,0 +1,847 @@
+use crate::{bench, chat, cors, distributed, dump, har, methods, replay, review, wordgen, wordlist, workspace};
+use clap::builder::FalseyValueParser;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, Args, Command, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::Shell;
+use std::ffi::OsString;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Name the program is installed under, as completions and man pages call it
+pub const BIN_NAME: &str = "dir_crawler";
+
+/// Every scan flag can also be set through an environment variable: this
+/// prefix, then the flag's name in upper case with `_` for `-`
+pub const ENV_PREFIX: &str = "DIR_CRAWLER_";
+
+/// The command line: one subcommand per job, with the scan's flags under
+/// `scan` (and `vhost`, which is a scan of the Host header). Scripts written
+/// for the single flat command (`dir_crawler <url> [flags]`) keep working:
+/// the scan flags are also taken, unlisted, without a subcommand.
+#[derive(Debug, Parser)]
+#[command(
+    name = "Directory Crawler 🕵️",
+    version = "2.1",
+    author = "Cybersecurity Enthusiast",
+    about = "Advanced Directory Fuzzing Tool with Extension Support",
+    arg_required_else_help = true,
+    subcommand_negates_reqs = true
+)]
+pub struct Cli {
+    /// Write the man page to stdout, or pages for every command into DIR
+    #[arg(long, value_name = "DIR")]
+    pub generate_man: Option<Option<String>>,
+    /// Print without colors (also NO_COLOR=1); automatic when output isn't a terminal
+    #[arg(long, global = true)]
+    pub no_color: bool,
+    /// Print without emoji; automatic when output isn't a terminal
+    #[arg(long, global = true)]
+    pub no_emoji: bool,
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+    /// A legacy command line's scan flags
+    #[command(flatten)]
+    pub scan: Option<ScanArgs>,
+}
+
+impl Cli {
+    /// Parse `args` as the program's command line, reading scan flags left
+    /// off it from their `DIR_CRAWLER_*` variables
+    pub fn try_parse_env<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut command = definition();
+        let mut matches = command.try_get_matches_from_mut(args)?;
+        if let Some((name, _)) = matches.subcommand() {
+            let given = |id: &clap::Id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine);
+            if scan_flag_ids().iter().any(given) {
+                let message = format!("scan flags go after `scan`, and can't be given to `{}`", name);
+                return Err(command.error(ErrorKind::ArgumentConflict, message));
+            }
+        }
+        Cli::from_arg_matches_mut(&mut matches)
+    }
+
+    /// The command to run; a command line without one is a `scan`
+    pub fn into_command(self) -> Option<Commands> {
+        self.command.or(self.scan.map(Commands::Scan))
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Fuzz a target's paths, or one header or parameter of a fixed URL
+    Scan(ScanArgs),
+    /// Find subdomains by resolving every wordlist entry under a domain
+    Dns(DnsArgs),
+    /// Find virtual hosts by fuzzing the Host header of a fixed URL
+    Vhost(VhostArgs),
+    /// Convert, verify or decrypt a results file
+    Report {
+        #[command(subcommand)]
+        command: ReportCommand,
+    },
+    /// Pick an interrupted workspace scan up where it left off
+    Resume(ResumeArgs),
+    /// Install and list wordlists
+    Wordlists {
+        #[command(subcommand)]
+        command: WordlistsCommand,
+    },
+    /// List past scans in the workspace root
+    History(HistoryArgs),
+    /// Re-request a results file's findings and report which still exist, changed or disappeared
+    Replay(ReplayArgs),
+    /// Step through a results file, re-fetching each finding, and record verdicts and notes
+    Review(ReviewArgs),
+    /// Crawl a target and print a wordlist built from its pages, scripts and links
+    Wordgen(WordgenArgs),
+    /// Measure achievable requests per second against a local server and report the bottleneck
+    Bench(BenchArgs),
+    /// Print a completion script for a shell
+    Completions {
+        /// Shell to complete for
+        shell: Shell,
+    },
+}
+
+/// Every scan flag, each also read from its `DIR_CRAWLER_*` variable
+#[derive(Debug, Clone, Args)]
+pub struct ScanArgs {
+    /// Target URL to fuzz, or an address range with ports like 10.10.0.0/24:80,443,8080
+    pub url: Option<String>,
+    /// Custom wordlist path, or an http(s) URL to download and cache; repeat for FUZZ1, FUZZ2, ...
+    #[arg(short, long)]
+    pub wordlist: Vec<String>,
+    /// How wordlists combine across FUZZ1..FUZZn: clusterbomb (every combination) or pitchfork (line by line); buckets enumerates cloud storage buckets named after the wordlist's keywords (default: clusterbomb)
+    #[arg(long, value_parser = ["clusterbomb", "pitchfork", "buckets"])]
+    pub mode: Option<String>,
+    /// Expected SHA-256 of the (first) downloaded wordlist; the scan aborts on mismatch
+    #[arg(long)]
+    pub wordlist_sha256: Option<String>,
+    /// Skip this many wordlist entries (to split a list across machines or sessions)
+    #[arg(long)]
+    pub wordlist_offset: Option<usize>,
+    /// Use at most this many wordlist entries after the offset
+    #[arg(long)]
+    pub wordlist_limit: Option<usize>,
+    /// Also try variants of each word: case, prefix, suffix, delimiter or all (comma-separated)
+    #[arg(long)]
+    pub mutations: Option<String>,
+    /// File of mutation rules (case / prefix ... / suffix ... / delimiter ...), one per line
+    #[arg(long)]
+    pub mutation_rules: Option<String>,
+    /// Crawl the target first and add the words its pages, scripts and links use
+    #[arg(long)]
+    pub learn: bool,
+    /// Randomize request order instead of following the wordlist
+    #[arg(long)]
+    pub shuffle: bool,
+    /// Look less like a scanner: a random browser fingerprint, jittered requests in shuffled order and a few kept-alive connections
+    #[arg(long, conflicts_with_all = ["persona", "differential"])]
+    pub stealth: bool,
+    /// Directory downloaded wordlists are cached in
+    #[arg(long, default_value = wordlist::DEFAULT_CACHE)]
+    pub wordlist_cache: String,
+    /// File extensions to fuzz (comma-separated)
+    #[arg(short = 'x', long)]
+    pub extensions: Option<String>,
+    /// Number of concurrent threads
+    #[arg(short, long, default_value_t = 20)]
+    pub threads: usize,
+    /// Diagnostic verbosity: -v info, -vv debug (filter decisions), -vvv trace (every request)
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
+    #[arg(long, help = format!("With -v, show about this many bytes of each hit's body, HTML stripped to its text (default: {}, 0 = off)", review::DEFAULT_SNIPPET_BYTES))]
+    pub preview_bytes: Option<String>,
+    #[arg(long, value_name = "SAMPLE", help = format!("With -vvv, print the request and response headers of: matches, all, or N for one request in every N (default: {})", dump::DEFAULT_SAMPLE))]
+    pub dump: Option<String>,
+    /// Append diagnostics (at least debug level) to this file
+    #[arg(long)]
+    pub log_file: Option<String>,
+    /// Print only 'status url' result lines (nothing when --output is set); no banner or progress
+    #[arg(short, long)]
+    pub silent: bool,
+    /// Leave out the banner; startup info still goes to stderr
+    #[arg(long)]
+    pub no_banner: bool,
+    /// Filter by specific status codes (comma-separated)
+    #[arg(short = 'c', long = "status")]
+    pub status_codes: Option<String>,
+    /// Only report responses whose Content-Type is one of these (comma-separated, type/* allowed)
+    #[arg(long)]
+    pub match_content_type: Option<String>,
+    /// Don't report responses whose Content-Type is one of these (comma-separated, type/* allowed)
+    #[arg(long)]
+    pub filter_content_type: Option<String>,
+    /// Overall cap on a single request in seconds, including the body
+    #[arg(long)]
+    pub timeout: Option<u64>,
+    /// Seconds to wait for a connection (fails fast on dead hosts)
+    #[arg(long, default_value_t = 5)]
+    pub connect_timeout: u64,
+    /// Seconds a server may stay silent before its response is abandoned (slow streams keep going)
+    #[arg(long, default_value_t = 10)]
+    pub read_timeout: u64,
+    /// Send a request again this many times when the connection is refused or it times out, waiting 0.5s, 1s, 2s, ... in between
+    #[arg(long, default_value_t = 0)]
+    pub retries: u32,
+    /// Stop reading response bodies beyond this size, e.g. 512KB or 1MB (0 = unlimited)
+    #[arg(long, default_value = "1MB")]
+    pub max_body_size: String,
+    /// Limit requests in flight to a single host (default: --threads)
+    #[arg(long)]
+    pub max_connections_per_host: Option<usize>,
+    /// Cap the response bytes downloaded per second across the scan (e.g. 5MB/s, 500KB/s)
+    #[arg(long)]
+    pub max_bandwidth: Option<String>,
+    /// Skip a host's remaining requests after this many consecutive failures (0 = never)
+    #[arg(long, default_value_t = 20)]
+    pub host_error_threshold: u32,
+    /// Seconds to keep idle pooled connections open
+    #[arg(long, default_value_t = 90)]
+    pub pool_idle_timeout: u64,
+    /// TCP keepalive interval in seconds (0 to disable)
+    #[arg(long, default_value_t = 60)]
+    pub tcp_keepalive: u64,
+    /// HTTP Request Method (GET/POST)
+    #[arg(short, long, default_value = "GET")]
+    pub method: String,
+    /// Fuzz one header against the fixed URL, e.g. 'X-Forwarded-For: FUZZ' or 'FUZZ: 1'; hits are responses that differ from the baseline
+    #[arg(long)]
+    pub fuzz_header: Option<String>,
+    /// Fuzz the value of one query parameter on the fixed URL; flags reflected payloads and status or size changes
+    #[arg(long)]
+    pub fuzz_param: Option<String>,
+    /// Raw HTTP request file (e.g. saved from Burp) with a FUZZ marker in the path, headers or body
+    #[arg(long, conflicts_with = "recursive")]
+    pub request: Option<String>,
+    /// Page to fetch an anti-CSRF token from (absolute, or relative to the target); it replaces {{csrf}} in the request's headers or body
+    #[arg(long, conflicts_with = "agents")]
+    pub csrf_url: Option<String>,
+    /// Regex extracting the token from the --csrf-url page (first capture group, or the whole match)
+    #[arg(long, conflicts_with = "csrf_selector", requires = "csrf_url")]
+    pub csrf_regex: Option<String>,
+    /// CSS selector of the element holding the token, e.g. 'input[name=csrf_token]' (value or content attribute, else its text)
+    #[arg(long, requires = "csrf_url")]
+    pub csrf_selector: Option<String>,
+    /// Fetch a fresh CSRF token every N requests (default: keep the first one; 1 for single-use tokens)
+    #[arg(long, requires = "csrf_url")]
+    pub csrf_refresh: Option<u64>,
+    /// Tag HTML hits with the detected natural language
+    #[arg(long)]
+    pub detect_language: bool,
+    /// Stamp each request with a sequential ID in this header (e.g. X-Scan-Request-Id)
+    #[arg(long)]
+    pub request_id_header: Option<String>,
+    /// Serve live scan metrics in Prometheus format on this port (http://<host>:<port>/metrics)
+    #[arg(long)]
+    pub metrics_port: Option<u16>,
+    /// Append every request (ID, method, URL, outcome) to this file
+    #[arg(long)]
+    pub audit_log: Option<String>,
+    /// Only request URLs matching this glob (or re:<regex>); repeatable
+    #[arg(long)]
+    pub scope_include: Vec<String>,
+    /// Never request URLs matching this glob (or re:<regex>); repeatable
+    #[arg(long)]
+    pub scope_exclude: Vec<String>,
+    /// Extra blocklist pattern on top of the built-in logout/delete list; repeatable
+    #[arg(long)]
+    pub blocklist: Vec<String>,
+    /// Mask values following this regex (e.g. 'password=|token=') in logs and reports; repeatable
+    #[arg(long)]
+    pub redact: Vec<String>,
+    /// Disable automatic per-host slowdown on 429/503 responses
+    #[arg(long)]
+    pub no_adaptive_throttle: bool,
+    /// Write results to this file
+    #[arg(short, long)]
+    pub output: Option<String>,
+    /// Results format: json, md (Markdown report), ndjson (one finding per line, streamed live), csv (one row per finding, streamed live), curl (a command reproducing each finding), urls (deduplicated hit URLs for httpx/nuclei), sarif (code scanning and security dashboards), sqlite (scans, targets and findings tables in the --output database, which later scans add to); all but json and sqlite go to stdout without --output, which implies --silent
+    #[arg(short, long, default_value = "json")]
+    pub format: String,
+    /// Results file of an earlier scan: request its findings with If-None-Match/If-Modified-Since and keep them, tagged unchanged, when the server answers 304 (not available with --agents)
+    #[arg(long, conflicts_with = "agents")]
+    pub previous: Option<String>,
+    /// Order findings are listed and reported in: severity (most severe first) or modified (most recent Last-Modified first)
+    #[arg(long, default_value = "severity")]
+    pub sort: String,
+    /// With --format urls, only list hits with these statuses or status classes, e.g. 2xx,3xx,401
+    #[arg(long)]
+    pub urls_status: Option<String>,
+    /// Encrypt result files and stored responses with ChaCha20-Poly1305 (requires --key-file); append-only logs and workspaces can't be encrypted and are refused
+    #[arg(long, conflicts_with_all = ["audit_log", "log_file", "workspace"], requires = "key_file")]
+    pub encrypt_output: bool,
+    /// 32-byte key (raw or hex) for --encrypt-output / --decrypt
+    #[arg(long)]
+    pub key_file: Option<String>,
+    /// Decrypt a file written with --encrypt-output to stdout (or --output)
+    #[arg(long, requires = "key_file")]
+    pub decrypt: Option<String>,
+    /// Sign the results file with this Ed25519 PEM key (writes <output>.sig)
+    #[arg(long, requires = "output")]
+    pub sign_report: Option<String>,
+    /// Verify a results file against its detached .sig signature
+    #[arg(long)]
+    pub verify_report: Option<String>,
+    /// Ed25519 public key (PEM or hex) the report must be signed with
+    #[arg(long, requires = "verify_report")]
+    pub verify_key: Option<String>,
+    /// Save each matched response body into this directory (named by URL hash)
+    #[arg(long)]
+    pub store_responses: Option<String>,
+    /// Also save response headers alongside stored bodies
+    #[arg(long, requires = "store_responses")]
+    pub store_headers: bool,
+    /// Write every finding's request and response to this HTTP Archive (HAR 1.2) file
+    #[arg(long, conflicts_with = "agents")]
+    pub har: Option<String>,
+    /// Body bytes kept per request and response in the HAR file (e.g. 64KB, 1MB; 0 = all)
+    #[arg(long, default_value = har::DEFAULT_MAX_BODY, requires = "har")]
+    pub har_max_body: String,
+    /// Append every finding's request and response to this WARC 1.1 file as found (gzipped per record when it ends in .gz)
+    #[arg(long, conflicts_with_all = ["agents", "encrypt_output"])]
+    pub warc: Option<String>,
+    /// Screenshot 2xx and 3xx findings into this directory with headless Chromium, shown in the Markdown report (needs the screenshots cargo feature)
+    #[arg(long, value_name = "dir", conflicts_with = "encrypt_output")]
+    pub screenshots: Option<String>,
+    /// Scan matched bodies for API keys, AWS credentials, JWTs, private keys and emails
+    #[arg(long)]
+    pub detect_secrets: bool,
+    /// Show the <title> of HTML findings
+    #[arg(long)]
+    pub titles: bool,
+    /// Check findings for missing or weak CSP, HSTS, X-Frame-Options, nosniff and cookie flags
+    #[arg(long)]
+    pub audit_headers: bool,
+    /// Request each finding again with this Origin (default: https://evil.example) and tag permissive CORS policies
+    #[arg(long, value_name = "origin", num_args = 0..=1, default_missing_value = cors::DEFAULT_ORIGIN)]
+    pub cors: Option<String>,
+    /// Test redirecting findings for query parameters that send the redirect off-site
+    #[arg(long)]
+    pub open_redirects: bool,
+    /// Request each finding again with these methods (default: OPTIONS,TRACE; PUT and DELETE may change the target) and report successes and differences from GET
+    #[arg(long, value_name = "methods", num_args = 0..=1, default_missing_value = methods::DEFAULT_METHODS)]
+    pub probe_methods: Option<String>,
+    /// Try these user:password lines against 401 Basic auth prompts, one at a time per realm, stopping at the first accepted or on any answer other than 401
+    #[arg(long, value_name = "file")]
+    pub spray_basic_auth: Option<String>,
+    /// Pause between two attempts against the same realm (default: 1s)
+    #[arg(long, requires = "spray_basic_auth")]
+    pub spray_delay: Option<String>,
+    /// Rhai script defining any of on_request, matches, on_finding and on_scan_end (not available with --agents)
+    #[arg(long, conflicts_with = "agents")]
+    pub script: Option<String>,
+    /// Extra severity scoring rules file, one 'field|regex|points' per line (field: status, path, url, content-type or tag)
+    #[arg(long)]
+    pub severity_rules: Option<String>,
+    /// Extra secret rules file, one 'name|severity|regex' per line
+    #[arg(long)]
+    pub secret_rules: Option<String>,
+    /// Send a coherent header set (UA, Accept, sec-ch-ua) for: chrome, mobile-safari, googlebot, curl
+    #[arg(long)]
+    pub persona: Option<String>,
+    /// Extra request header 'Name: value'; repeatable
+    #[arg(short = 'H', long)]
+    pub header: Vec<String>,
+    /// Session cookies to send, e.g. 'session=abc; theme=dark'
+    #[arg(short = 'b', long)]
+    pub cookie: Option<String>,
+    /// YAML file describing a login request; performed before the scan and again whenever responses show the session expired
+    #[arg(long, conflicts_with_all = ["agents", "differential"])]
+    pub login_config: Option<String>,
+    /// Send this Host header instead of the URL's host
+    #[arg(long)]
+    pub host_header: Option<String>,
+    /// Pin a hostname to an address, curl-style host:port:address (keeps Host and SNI); repeatable
+    #[arg(long)]
+    pub resolve: Vec<String>,
+    /// Connect to the targets' IPv4 addresses only
+    #[arg(long, conflicts_with = "ipv6_only")]
+    pub ipv4_only: bool,
+    /// Connect to the targets' IPv6 addresses only
+    #[arg(long)]
+    pub ipv6_only: bool,
+    /// Send requests from this local address, on hosts with more than one
+    #[arg(long)]
+    pub source_ip: Option<IpAddr>,
+    /// Send requests from this network interface's address (e.g. eth1)
+    #[arg(long, conflicts_with = "source_ip")]
+    pub interface: Option<String>,
+    /// Resolve names through this DNS server (e.g. 1.1.1.1) or DoH URL (https://.../dns-query), cached in-process
+    #[arg(long)]
+    pub dns_resolver: Option<String>,
+    /// Compare every candidate under two identities and report differences (personas=desktop,mobile | auth)
+    #[arg(long)]
+    pub differential: Option<String>,
+    /// List available user-agent personas
+    #[arg(long)]
+    pub list_personas: bool,
+    /// Recurse into discovered directories
+    #[arg(short, long, conflicts_with = "agents")]
+    pub recursive: bool,
+    /// Maximum recursion depth
+    #[arg(long, default_value_t = 3)]
+    pub depth: usize,
+    /// Order to recurse in: bfs (broad coverage first), dfs (one branch deep first), priority (most promising hits first)
+    #[arg(long, default_value = "bfs")]
+    pub recursion_strategy: String,
+    /// Scan at most this many discovered directories per recursion level
+    #[arg(long, requires = "recursive")]
+    pub max_dirs_per_level: Option<usize>,
+    /// Fingerprint each directory's soft-404 page and filter responses matching it
+    #[arg(long)]
+    pub auto_calibrate: bool,
+    /// Skip the requests that check the target answers, and how fast, before scanning
+    #[arg(long)]
+    pub no_preflight: bool,
+    /// Before scanning, look for a WAF or CDN (Cloudflare, Akamai, ModSecurity, ...) and suggest stealthier settings
+    #[arg(long)]
+    pub waf_check: bool,
+    /// Like --waf-check, and apply the stealthier settings when a WAF or CDN is found
+    #[arg(long)]
+    pub waf_adapt: bool,
+    /// Before scanning, hash each target's /favicon.ico the way Shodan does and name known products
+    #[arg(long)]
+    pub favicon: bool,
+    /// Probe for Swagger/OpenAPI descriptions and GraphQL introspection, and scan the endpoints they list
+    #[arg(long)]
+    pub api_discovery: bool,
+    /// Scan every open HTTP(S) port in Nmap XML results (nmap -sV -oX scan.xml ...), with service info in the report
+    #[arg(long, conflicts_with_all = ["url", "request", "fuzz_header", "fuzz_param", "mode"])]
+    pub nmap_xml: Option<String>,
+    /// Scan every URL or address range listed in a file, one per line, each optionally followed by threads=N, delay=250ms and header="Name: value" for that target
+    #[arg(long, conflicts_with_all = ["url", "request", "fuzz_header", "fuzz_param", "mode", "nmap_xml"])]
+    pub targets: Option<String>,
+    /// With an address range or Nmap results, first drop hosts and ports that refuse TCP connections
+    #[arg(long)]
+    pub alive_check: bool,
+    /// Print every request the scan would send (or write them to this file) with a total count, without sending any
+    #[arg(long, num_args = 0..=1, default_missing_value = "", conflicts_with_all = ["learn", "api_discovery", "waf_check", "waf_adapt", "csrf_url", "login_config", "alive_check", "agents", "agent_listen"])]
+    pub dry_run: Option<String>,
+    /// Probe the target and every discovered directory for exposed .git, .svn, .hg, .env, .DS_Store and docker-compose.yml files
+    #[arg(long, conflicts_with = "agents")]
+    pub vcs_checks: bool,
+    /// Collapse findings with near-identical bodies (SimHash) into one with a count
+    #[arg(long)]
+    pub unique: bool,
+    /// Slowly re-request every finding from a fresh connection pool before reporting
+    #[arg(long)]
+    pub reconfirm: bool,
+    /// Milliseconds between re-confirmation requests
+    #[arg(long, default_value_t = 1000)]
+    pub reconfirm_delay: u64,
+    /// Stop the scan after this long, e.g. 90s, 30m or 2h, and write results so far
+    #[arg(long)]
+    pub max_time: Option<String>,
+    /// Stop the scan once this many requests have failed
+    #[arg(long)]
+    pub max_errors: Option<u64>,
+    /// Stop the scan once this many findings are recorded
+    #[arg(long)]
+    pub max_findings: Option<u64>,
+    /// Pipeline mode: no banner, progress bar or colors, and exit with 5 when a finding violates --fail-on (any finding without it)
+    #[arg(long)]
+    pub ci: bool,
+    /// Policy findings must not violate, e.g. "status=200 && path~=/\.git|/backup || severity>=high"; a violation exits with 5
+    #[arg(long)]
+    pub fail_on: Option<String>,
+    /// Write the --fail-on check as JUnit XML, one test case per finding
+    #[arg(long, conflicts_with = "encrypt_output")]
+    pub junit: Option<String>,
+    /// Show a desktop notification with the findings count and duration when the scan finishes, stops early, fails or is aborted with Ctrl-C
+    #[arg(long)]
+    pub notify: bool,
+    /// Post the scan's start, severe findings and summary to this Slack incoming webhook
+    #[arg(long)]
+    pub slack_webhook: Option<String>,
+    #[arg(long, conflicts_with = "slack_webhook", help = format!("Post to this Slack channel with a bot token (--slack-token or ${}), threading updates under the start message", chat::SLACK_TOKEN_ENV))]
+    pub slack_channel: Option<String>,
+    /// Slack bot token (chat:write) for --slack-channel
+    #[arg(long, requires = "slack_channel")]
+    pub slack_token: Option<String>,
+    /// Post the scan's start, severe findings and summary to this Discord webhook
+    #[arg(long)]
+    pub discord_webhook: Option<String>,
+    /// Least severe finding posted to Slack or Discord (info, low, medium, high, critical)
+    #[arg(long, default_value = "high")]
+    pub chat_severity: String,
+    /// Coordinate a distributed scan across remote agents (comma-separated host:port)
+    #[arg(long)]
+    pub agents: Option<String>,
+    #[arg(long, help = format!("Shared secret coordinators and agents authenticate each other with (default: ${})", distributed::SECRET_ENV))]
+    pub agent_secret: Option<String>,
+    /// Wordlist entries handed to an agent per work unit
+    #[arg(long, default_value_t = 500)]
+    pub chunk_size: usize,
+    /// Organize results, logs and stored responses under this workspace (default name: target + timestamp)
+    #[arg(long, num_args = 0..=1, default_missing_value = "", conflicts_with = "agent_listen")]
+    pub workspace: Option<String>,
+    /// Directory holding workspaces
+    #[arg(long, default_value = workspace::DEFAULT_ROOT)]
+    pub workspace_root: String,
+    /// Run as a scan agent listening for coordinator work on host:port
+    #[arg(long, conflicts_with_all = ["agents", "audit_log", "output", "store_responses"])]
+    pub agent_listen: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DnsArgs {
+    /// Domain to look for subdomains of, e.g. example.com
+    pub domain: String,
+    /// Names to try, one per line (default: the SecLists subdomain list, if installed)
+    #[arg(short, long)]
+    pub wordlist: Option<String>,
+    /// Lookups in flight at once
+    #[arg(short, long, default_value = "50")]
+    pub threads: usize,
+    /// Resolve through this DNS server (e.g. 1.1.1.1) or DoH URL instead of the system resolver
+    #[arg(long)]
+    pub resolver: Option<String>,
+    /// Write the names found to this file, one per line
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct VhostArgs {
+    /// Domain the wordlist's names are tried under (default: the URL's host)
+    #[arg(long)]
+    pub domain: Option<String>,
+    #[command(flatten)]
+    pub scan: ScanArgs,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ReportCommand {
+    /// Write a JSON results file in another format
+    Convert {
+        /// JSON results file from a previous scan
+        results: String,
+        /// Format to write: json, md, ndjson, csv, curl, urls, sarif or sqlite (added to the database in --output)
+        #[arg(short, long)]
+        format: String,
+        /// Write here instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Check a signed results file against its .sig
+    Verify {
+        /// Signed results file
+        results: String,
+        /// Only accept signatures by this public key (PEM or hex)
+        #[arg(long)]
+        key: Option<String>,
+    },
+    /// Decrypt a file written with --encrypt-output to stdout (or --output)
+    Decrypt {
+        /// Encrypted results file
+        file: String,
+        /// Key the file was encrypted with
+        #[arg(long)]
+        key_file: String,
+        /// Write the plaintext here instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ResumeArgs {
+    /// Workspace name, or a unique prefix of one
+    pub id: String,
+    /// Directory holding workspaces
+    #[arg(long, default_value = workspace::DEFAULT_ROOT)]
+    pub root: String,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum WordlistsCommand {
+    /// Download a wordlist collection and register it as the default source
+    Install {
+        /// Collection to install
+        #[arg(value_parser = ["seclists"])]
+        collection: String,
+        /// Directory to install into
+        #[arg(long, default_value = wordlist::DEFAULT_SECLISTS_DIR)]
+        dir: String,
+    },
+    /// Show available wordlists with their entry counts
+    List {
+        /// Directory downloaded wordlists are cached in
+        #[arg(long, default_value = wordlist::DEFAULT_CACHE)]
+        cache: String,
+    },
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct HistoryArgs {
+    /// Directory holding workspaces
+    #[arg(long, default_value = workspace::DEFAULT_ROOT, global = true)]
+    pub root: String,
+    #[command(subcommand)]
+    pub command: Option<HistoryCommand>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum HistoryCommand {
+    /// Print a past scan's summary and findings
+    Show {
+        /// Workspace name, or a unique prefix of one
+        id: String,
+    },
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ReplayArgs {
+    /// JSON results file from a previous scan
+    pub results: String,
+    /// Write the comparison as JSON to this file
+    #[arg(short, long)]
+    pub output: Option<String>,
+    /// Findings re-requested at once
+    #[arg(short, long, default_value = replay::DEFAULT_THREADS)]
+    pub threads: String,
+    /// Only replay findings with these statuses or classes, e.g. 2xx,401
+    #[arg(long)]
+    pub status: Option<String>,
+    /// Only replay findings reviewed as interesting, false-positive or done (comma-separated)
+    #[arg(long)]
+    pub verdict: Option<String>,
+    /// Only replay findings at least this severe (info, low, medium, high, critical)
+    #[arg(long, default_value = "info")]
+    pub min_severity: String,
+    /// Extra request header 'Name: value'; repeatable
+    #[arg(short = 'H', long)]
+    pub header: Vec<String>,
+    /// Session cookies to send with every request
+    #[arg(short = 'b', long)]
+    pub cookie: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ReviewArgs {
+    /// JSON results file from a previous scan
+    pub results: String,
+    /// Write the reviewed results here instead of updating the file in place
+    #[arg(short, long)]
+    pub output: Option<String>,
+    /// Also revisit findings that already have a verdict
+    #[arg(long)]
+    pub all: bool,
+    /// Body lines shown under each finding
+    #[arg(long, default_value = review::DEFAULT_PREVIEW_LINES)]
+    pub preview_lines: String,
+    /// Extra request header 'Name: value' for the live fetch; repeatable
+    #[arg(short = 'H', long)]
+    pub header: Vec<String>,
+    /// Session cookies for the live fetch
+    #[arg(short = 'b', long)]
+    pub cookie: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct WordgenArgs {
+    /// Page to start crawling from
+    pub url: String,
+    /// Links to follow away from the start page
+    #[arg(long, default_value_t = wordgen::DEFAULT_DEPTH)]
+    pub depth: usize,
+    /// Stop after fetching this many pages
+    #[arg(long, default_value_t = wordgen::DEFAULT_MAX_PAGES)]
+    pub max_pages: usize,
+    /// Shortest word kept
+    #[arg(long, default_value_t = wordgen::DEFAULT_MIN_LENGTH)]
+    pub min_length: usize,
+    /// Write the wordlist to this file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct BenchArgs {
+    /// Requests sent per run
+    #[arg(long, default_value = bench::DEFAULT_REQUESTS)]
+    pub requests: String,
+    /// Comma-separated thread counts to compare
+    #[arg(long, default_value = bench::DEFAULT_THREADS)]
+    pub threads: String,
+    /// Milliseconds the local server waits before answering, to model a remote target
+    #[arg(long, default_value = "0")]
+    pub latency: String,
+}
+
+impl ScanArgs {
+    /// Parse `dir_crawler scan`'s arguments, without the program and command
+    /// names, with the `DIR_CRAWLER_*` variables applied
+    pub fn try_parse_env<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let command = ScanArgs::augment_args(Command::new("scan").no_binary_name(true));
+        let mut matches = with_scan_env(command).try_get_matches_from(args)?;
+        ScanArgs::from_arg_matches_mut(&mut matches)
+    }
+}
+
+/// The whole command line definition, named as installed
+pub fn command() -> Command {
+    definition().name(BIN_NAME).bin_name(BIN_NAME)
+}
+
+/// The completion script for `shell`
+pub fn completions(shell: Shell) -> Vec<u8> {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command(), BIN_NAME, &mut script);
+    script
+}
+
+/// Write the top-level man page to stdout, or into `dir` a page for every
+/// command (`dir_crawler.1`, `dir_crawler-scan.1`, ...)
+pub fn man_pages(dir: Option<&Path>) -> std::io::Result<()> {
+    match dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            clap_mangen::generate_to(command(), dir)
+        }
+        None => clap_mangen::Man::new(command()).render(&mut std::io::stdout()),
+    }
+}
+
+/// The environment variable standing in for the scan flag `id`, e.g.
+/// `DIR_CRAWLER_PROXY` for `--proxy`
+pub fn env_name(id: &str) -> String {
+    format!("{}{}", ENV_PREFIX, id.to_uppercase().replace('-', "_"))
+}
+
+/// Let `arg` be set from the environment when it isn't on the command line.
+/// Switches are off for `0`, `false`, `no`, `off` or empty and on for
+/// anything else; repeatable flags take one value per line.
+fn with_env(arg: Arg) -> Arg {
+    let (action, name) = (arg.get_action().clone(), env_name(arg.get_id().as_str()));
+    let arg = arg.env(name).hide_env_values(true);
+    match action {
+        ArgAction::SetTrue => arg.value_parser(FalseyValueParser::new()),
+        ArgAction::Append => arg.value_delimiter('\n'),
+        _ => arg,
+    }
+}
+
+/// The ids of the scan flags
+fn scan_flag_ids() -> Vec<clap::Id> {
+    ScanArgs::augment_args(Command::new("scan"))
+        .get_arguments()
+        .map(|arg| arg.get_id().clone())
+        .collect()
+}
+
+/// Give every scan flag in `command` its `DIR_CRAWLER_*` variable
+fn with_scan_env(command: Command) -> Command {
+    let flags = scan_flag_ids();
+    command.mut_args(|arg| match flags.contains(arg.get_id()) {
+        true => with_env(arg),
+        false => arg,
+    })
+}
+
+/// The program's command line, with the scan flags read from the
+/// environment and their legacy top-level copies kept out of the help
+fn definition() -> Command {
+    let flags = scan_flag_ids();
+    with_scan_env(Cli::command())
+        .mut_args(|arg| match flags.contains(arg.get_id()) {
+            true => arg.hide(true),
+            false => arg,
+        })
+        .mut_subcommand("scan", with_scan_env)
+        .mut_subcommand("vhost", with_scan_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<OsString> {
+        line.split_whitespace().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn runs_legacy_command_lines_as_scan() {
+        definition().debug_assert();
+        let scan = |line: &str| match Cli::try_parse_env(args(line)).unwrap().into_command() {
+            Some(Commands::Scan(scan)) => scan,
+            _ => panic!("{} didn't parse as scan", line),
+        };
+        let legacy = scan("dir_crawler http://t -w list.txt -t 5");
+        assert_eq!((legacy.url.as_deref(), legacy.threads), (Some("http://t"), 5));
+        assert_eq!(legacy.wordlist, ["list.txt"]);
+        assert!(scan("dir_crawler --list-personas").list_personas);
+        assert_eq!(scan("dir_crawler scan http://t").threads, 20);
+        for line in ["dir_crawler history show example", "dir_crawler wordlists list"] {
+            let command = Cli::try_parse_env(args(line)).unwrap().into_command();
+            assert!(matches!(command, Some(Commands::History(_) | Commands::Wordlists { .. })), "{}", line);
+        }
+        assert!(Cli::try_parse_env(args("dir_crawler --generate-man")).unwrap().into_command().is_none());
+        assert!(Cli::try_parse_env(args("dir_crawler http://t history")).is_err());
+
+        let Some(Commands::Vhost(vhost)) = Cli::try_parse_env(args("dir_crawler vhost http://10.0.0.5 --domain corp.local -t 5")).unwrap().command else {
+            panic!("vhost didn't parse");
+        };
+        assert_eq!(vhost.domain.as_deref(), Some("corp.local"));
+        assert_eq!(vhost.scan.url.as_deref(), Some("http://10.0.0.5"));
+        assert!(Cli::try_parse_env(args("dir_crawler report convert results.json")).is_err());
+
+        // The global flags go before or after the command
+        for line in ["dir_crawler --no-color --no-emoji wordlists list", "dir_crawler wordlists list --no-color --no-emoji", "dir_crawler --no-color --no-emoji http://t"] {
+            let cli = Cli::try_parse_env(args(line)).unwrap();
+            assert!(cli.no_color && cli.no_emoji, "{}", line);
+        }
+    }
+
+    #[test]
+    fn reads_scan_flags_from_the_environment() {
+        assert_eq!(env_name("agent_secret"), distributed::SECRET_ENV);
+        assert_eq!(env_name("slack_token"), chat::SLACK_TOKEN_ENV);
+        std::env::set_var("DIR_CRAWLER_PREVIEW_BYTES", "64");
+        std::env::set_var("DIR_CRAWLER_DETECT_LANGUAGE", "no");
+        std::env::set_var("DIR_CRAWLER_SCOPE_EXCLUDE", "^/logout\n^/delete");
+        let parse = |line: &str| match Cli::try_parse_env(args(line)).unwrap().into_command() {
+            Some(Commands::Scan(scan)) => scan,
+            _ => panic!("didn't parse as scan"),
+        };
+
+        for line in ["dir_crawler scan http://t", "dir_crawler http://t"] {
+            let scan = parse(line);
+            assert_eq!(scan.preview_bytes.as_deref(), Some("64"));
+            assert!(!scan.detect_language);
+            assert_eq!(scan.scope_exclude, ["^/logout", "^/delete"]);
+        }
+        assert_eq!(ScanArgs::try_parse_env(["http://t"]).unwrap().preview_bytes.as_deref(), Some("64"));
+
+        // The command line wins
+        let scan = parse("dir_crawler scan http://t --preview-bytes 0 --detect-language");
+        assert_eq!(scan.preview_bytes.as_deref(), Some("0"));
+        assert!(scan.detect_language);
+        for name in ["DIR_CRAWLER_PREVIEW_BYTES", "DIR_CRAWLER_DETECT_LANGUAGE", "DIR_CRAWLER_SCOPE_EXCLUDE"] {
+            std::env::remove_var(name);
+        }
+    }
+
+    #[test]
+    fn generates_completions_and_man_pages() {
+        let script = String::from_utf8(completions(Shell::Bash)).unwrap();
+        assert!(script.contains("dir_crawler__subcmd__scan") && script.contains("--fuzz-header"));
+
+        let dir = std::env::temp_dir().join(format!("dir_crawler-man-{}", std::process::id()));
+        man_pages(Some(&dir)).unwrap();
+        let page = std::fs::read_to_string(dir.join("dir_crawler-scan.1")).unwrap();
+        assert!(page.contains("wordlist"));
+        assert!(dir.join("dir_crawler-report-convert.1").is_file());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+use crate::output::ScanReport;
+use crate::Finding;
+use reqwest::header::{HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Tag on findings carried over from `--previous` because the server
+/// answered 304 Not Modified
+pub const UNCHANGED_TAG: &str = "unchanged";
+
+/// Findings of an earlier scan that came with cache validators. Their URLs
+/// are requested conditionally, and a 304 stands for the same finding,
+/// unchanged, without the body being sent again.
+pub struct Previous {
+    findings: HashMap<String, Finding>,
+}
+
+impl Previous {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let report: ScanReport = serde_json::from_slice(&data).map_err(|_| {
+            format!("{} is not a JSON results file (decrypt encrypted results with --decrypt first)", path.display())
+        })?;
+        Ok(Self::new(report.findings))
+    }
+
+    fn new(findings: Vec<Finding>) -> Self {
+        // Template findings share one URL across payloads, so only plain
+        // requests can be matched up again
+        let findings = findings.into_iter()
+            .filter(|finding| finding.payload.is_none() && (finding.etag.is_some() || finding.last_modified.is_some()))
+            .map(|finding| (finding.url.clone(), finding))
+            .collect();
+        Previous { findings }
+    }
+
+    /// Findings that can be revalidated
+    pub fn len(&self) -> usize {
+        self.findings.len()
+    }
+
+    /// Ask for the URL only if it changed since the previous scan
+    pub fn condition(&self, url: &str, headers: &mut HeaderMap) {
+        let Some(finding) = self.findings.get(url) else {
+            return;
+        };
+        let validators = [(IF_NONE_MATCH, &finding.etag), (IF_MODIFIED_SINCE, &finding.last_modified)];
+        for (name, value) in validators {
+            if let Some(value) = value.as_deref().and_then(|value| HeaderValue::from_str(value).ok()) {
+                headers.entry(name).or_insert(value);
+            }
+        }
+    }
+
+    /// The previous finding for a URL that answered 304, tagged unchanged
+    pub fn unchanged(&self, url: &str) -> Option<Finding> {
+        let mut finding = self.findings.get(url)?.clone();
+        if !finding.tags.iter().any(|tag| tag == UNCHANGED_TAG) {
+            finding.tags.push(UNCHANGED_TAG.to_string());
+        }
+        Some(finding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revalidates_findings_with_validators() {
+        let findings: Vec<Finding> = serde_json::from_value(serde_json::json!([
+            { "url": "http://t/a", "status": 200, "etag": "\"v1\"", "last_modified": "Wed, 01 May 2024 10:00:00 GMT", "verdict": "interesting" },
+            { "url": "http://t/b", "status": 200 },
+            { "url": "http://t/api", "status": 200, "etag": "\"v2\"", "payload": "users" },
+        ])).unwrap();
+        let previous = Previous::new(findings);
+        assert_eq!(previous.len(), 1);
+
+        let mut headers = HeaderMap::new();
+        previous.condition("http://t/a", &mut headers);
+        assert_eq!(headers[IF_NONE_MATCH], "\"v1\"");
+        assert_eq!(headers[IF_MODIFIED_SINCE], "Wed, 01 May 2024 10:00:00 GMT");
+        let mut headers = HeaderMap::new();
+        previous.condition("http://t/b", &mut headers);
+        assert!(headers.is_empty());
+
+        let finding = previous.unchanged("http://t/a").unwrap();
+        assert_eq!(finding.tags, [UNCHANGED_TAG]);
+        assert_eq!(finding.verdict, Some(crate::review::Verdict::Interesting));
+        assert!(previous.unchanged("http://t/b").is_none());
+    }
+}
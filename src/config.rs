@@ -0,0 +1,286 @@
+use crate::error::CrawlerError;
+use crate::dns::AddressFamily;
+use crate::{interface, template, wordlist, FuzzerConfig};
+use reqwest::Method;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+/// Bounds of `--threads`
+pub const MIN_THREADS: usize = 1;
+pub const MAX_THREADS: usize = 1000;
+
+/// A `FuzzerConfig` for a program embedding the crate, set option by
+/// option and checked as a whole by `build`. Options not set keep the
+/// defaults of a bare `dir_crawler <url>`.
+pub struct FuzzerConfigBuilder {
+    config: FuzzerConfig,
+    wordlist: Option<PathBuf>,
+    status_codes: Option<Vec<u16>>,
+}
+
+impl FuzzerConfig {
+    /// Settings for a scan of `url`, which may leave out the scheme to have
+    /// the scan try HTTPS, then HTTP
+    pub fn builder(url: impl Into<String>) -> FuzzerConfigBuilder {
+        let url = url.into();
+        FuzzerConfigBuilder {
+            // Nobody is watching a terminal
+            config: FuzzerConfig { url: url.clone(), detect_scheme: !crate::has_scheme(&url), silent: true, ..Default::default() },
+            wordlist: None,
+            status_codes: None,
+        }
+    }
+
+    /// Check settings that are wrong on their own or don't work together;
+    /// the command line and `FuzzerConfigBuilder::build` both end here
+    pub(crate) fn validate(&self) -> Result<(), CrawlerError> {
+        if !(MIN_THREADS..=MAX_THREADS).contains(&self.threads) {
+            return Err(CrawlerError::Threads { threads: self.threads });
+        }
+        if !self.buckets {
+            reqwest::Url::parse(&self.url)
+                .map_err(|e| CrawlerError::Url { url: self.url.clone(), reason: e.to_string() })?;
+        }
+        for path in std::iter::once(&self.wordlist).chain(&self.position_wordlists) {
+            wordlist::check(path).map_err(|source| CrawlerError::Wordlist { path: path.clone(), source })?;
+        }
+
+        if let Some(address) = self.source_ip {
+            let family = match address {
+                IpAddr::V4(_) => (self.address_family == AddressFamily::Ipv6).then_some("--ipv6-only"),
+                IpAddr::V6(_) => (self.address_family == AddressFamily::Ipv4).then_some("--ipv4-only"),
+            };
+            if let Some(other) = family {
+                return Err(CrawlerError::Conflict { option: "--source-ip", other, why: "the source address is of the other family" });
+            }
+            interface::check(address)?;
+        }
+
+        let fixed = self.request_template.is_some();
+        let one_target = !self.targets.is_empty();
+        let positions = self.request_template.as_ref().map_or(0, |template| template.positions);
+        let conflicts = [
+            (self.buckets && fixed, "--mode buckets", "--request, --fuzz-header or --fuzz-param", "bucket enumeration builds its own URLs"),
+            (self.buckets && self.waf_check, "--waf-check", "--mode buckets", "it needs a target URL"),
+            (self.buckets && !self.extensions.is_empty(), "-x", "--mode buckets", "bucket names take no extensions"),
+            (positions > 1 && !self.extensions.is_empty(), "-x", "FUZZ1..FUZZn markers", "give the extensions a position of their own"),
+        ];
+        let directory_passes = [("--vcs-checks", self.vcs_checks), ("--api-discovery", self.api_discovery)];
+        let single_target_passes = [("--learn", self.learn), ("--api-discovery", self.api_discovery), ("--waf-check", self.waf_check)];
+        let conflicts = conflicts.into_iter()
+            .chain(directory_passes.map(|(option, given)| (
+                given && (fixed || self.buckets),
+                option,
+                "--request, --fuzz-header, --fuzz-param or --mode buckets",
+                "it needs a directory scan",
+            )))
+            .chain(single_target_passes.map(|(option, given)| (
+                given && one_target,
+                option,
+                "an address range, targets file or Nmap results",
+                "it works on a single target",
+            )));
+        for (conflict, option, other, why) in conflicts {
+            if conflict {
+                return Err(CrawlerError::Conflict { option, other, why });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FuzzerConfigBuilder {
+    /// Entries to try, one per line (default: SecLists' common list if
+    /// installed, or the built-in one)
+    pub fn wordlist(mut self, path: impl Into<PathBuf>) -> Self {
+        self.wordlist = Some(path.into());
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.config.threads = threads;
+        self
+    }
+
+    /// Extensions tried on every entry, without the dot
+    pub fn extensions<S: AsRef<str>>(mut self, extensions: impl IntoIterator<Item = S>) -> Self {
+        self.config.extensions = extensions.into_iter()
+            .map(|extension| extension.as_ref().trim().trim_start_matches('.').to_lowercase())
+            .filter(|extension| !extension.is_empty())
+            .collect();
+        self
+    }
+
+    pub fn method(mut self, method: Method) -> Self {
+        self.config.method = method;
+        self
+    }
+
+    /// A header sent with every request; call again for more
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn cookie(mut self, cookie: impl Into<String>) -> Self {
+        self.config.cookie = Some(cookie.into());
+        self
+    }
+
+    /// Statuses that make a finding (default: 200, 204, 301, 302, 307, 401
+    /// and 403; any change from the baseline with `fuzz_param`)
+    pub fn status_codes(mut self, codes: impl IntoIterator<Item = u16>) -> Self {
+        self.status_codes = Some(codes.into_iter().collect());
+        self
+    }
+
+    /// Scan directories found this many levels down
+    pub fn recursive(mut self, depth: usize) -> Self {
+        self.config.recursion_depth = depth;
+        self
+    }
+
+    /// Connect over IPv4 or IPv6 only (default: whichever answers first)
+    pub fn address_family(mut self, family: AddressFamily) -> Self {
+        self.config.address_family = family;
+        self
+    }
+
+    /// Send requests from this local address
+    pub fn source_ip(mut self, address: IpAddr) -> Self {
+        self.config.source_ip = Some(address);
+        self
+    }
+
+    pub fn connect_timeout(mut self, secs: u64) -> Self {
+        self.config.connect_timeout = secs;
+        self
+    }
+
+    /// Seconds a server may stay silent before the response is abandoned
+    pub fn read_timeout(mut self, secs: u64) -> Self {
+        self.config.read_timeout = secs;
+        self
+    }
+
+    /// Send a request again this many times when the connection is
+    /// refused or it times out
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.config.retries = retries;
+        self
+    }
+
+    /// Stop the scan after this many seconds
+    pub fn max_time(mut self, secs: u64) -> Self {
+        self.config.max_time = Some(secs).filter(|secs| *secs > 0);
+        self
+    }
+
+    /// Stop the scan after this many findings
+    pub fn max_findings(mut self, findings: u64) -> Self {
+        self.config.max_findings = Some(findings).filter(|findings| *findings > 0);
+        self
+    }
+
+    /// Fuzz the value of this query parameter on the fixed URL rather than paths
+    pub fn fuzz_param(mut self, name: impl Into<String>) -> Self {
+        self.config.fuzz_param = Some(name.into());
+        self
+    }
+
+    pub fn detect_secrets(mut self, enabled: bool) -> Self {
+        self.config.detect_secrets = enabled;
+        self
+    }
+
+    /// Record the `<title>` of every HTML page found
+    pub fn titles(mut self, enabled: bool) -> Self {
+        self.config.extract_titles = enabled;
+        self
+    }
+
+    /// Look for exposed VCS metadata and dotfiles in every directory
+    pub fn vcs_checks(mut self, enabled: bool) -> Self {
+        self.config.vcs_checks = enabled;
+        self
+    }
+
+    /// Scan the endpoints of published API descriptions too
+    pub fn api_discovery(mut self, enabled: bool) -> Self {
+        self.config.api_discovery = enabled;
+        self
+    }
+
+    /// Add words the target's own pages use
+    pub fn learn(mut self, enabled: bool) -> Self {
+        self.config.learn = enabled;
+        self
+    }
+
+    /// Check the target answers before scanning, failing the scan when it
+    /// doesn't (default: on)
+    pub fn preflight(mut self, enabled: bool) -> Self {
+        self.config.preflight = enabled;
+        self
+    }
+
+    /// Look for a WAF or CDN in front of the target first
+    pub fn waf_check(mut self, enabled: bool) -> Self {
+        self.config.waf_check = enabled;
+        self
+    }
+
+    pub fn build(self) -> Result<FuzzerConfig, CrawlerError> {
+        let mut config = self.config;
+        config.url = crate::validate_url(&config.url)?;
+        config.wordlist = self.wordlist
+            .or_else(wordlist::find_default)
+            .or_else(|| wordlist::builtin_available().then(|| PathBuf::from(wordlist::BUILTIN)))
+            .ok_or(CrawlerError::NoWordlist)?;
+        if let Some(param) = &config.fuzz_param {
+            let method = if config.method == Method::POST { "POST" } else { "GET" };
+            let template = template::RequestTemplate::for_param(&config.url, method, param)
+                .map_err(|e| CrawlerError::Config(e.to_string()))?;
+            // Against the unmodified response, as on the command line
+            config.request_template = Some(template);
+            config.auto_calibrate = true;
+            config.recursion_depth = 0;
+            config.detect_scheme = false;
+        }
+        config.status_codes = match self.status_codes {
+            Some(codes) => codes,
+            None if config.fuzz_param.is_some() => Vec::new(),
+            None => config.status_codes,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_and_rejects_configurations() {
+        let wordlist = std::env::temp_dir().join(format!("dir_crawler-builder-{}.txt", std::process::id()));
+        std::fs::write(&wordlist, "admin\n").unwrap();
+        let builder = || FuzzerConfig::builder("http://t/").wordlist(&wordlist);
+
+        let config = builder().threads(50).extensions([".PHP", "bak", ""]).build().unwrap();
+        assert_eq!((config.threads, config.extensions.as_slice()), (50, ["php".to_string(), "bak".to_string()].as_slice()));
+        assert!(config.silent);
+
+        assert!(matches!(builder().threads(0).build(), Err(CrawlerError::Threads { threads: 0 })));
+        assert!(matches!(builder().threads(MAX_THREADS + 1).build(), Err(CrawlerError::Threads { .. })));
+        assert!(matches!(FuzzerConfig::builder("http://[::1").wordlist(&wordlist).build(), Err(CrawlerError::Url { .. })));
+        let missing = builder().wordlist("/nonexistent/words.txt").build();
+        assert!(matches!(missing, Err(CrawlerError::Wordlist { .. })), "{:?}", missing.err());
+        let conflict = builder().fuzz_param("id").vcs_checks(true).build().unwrap_err();
+        assert_eq!(conflict.to_string(), "--vcs-checks can't be combined with --request, --fuzz-header, --fuzz-param or --mode buckets: it needs a directory scan");
+
+        let fuzzed = builder().fuzz_param("id").build().unwrap();
+        assert!(fuzzed.request_template.is_some() && fuzzed.status_codes.is_empty());
+        std::fs::remove_file(&wordlist).unwrap();
+    }
+}
@@ -0,0 +1,59 @@
+use reqwest::header::{HeaderMap, ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_ORIGIN};
+
+/// Origin sent by `--cors` without a value; any origin the target can't
+/// legitimately trust will do
+pub const DEFAULT_ORIGIN: &str = "https://evil.example";
+
+/// What a response to a request from a foreign `origin` lets that origin do,
+/// as a `cors:` tag:
+///
+/// - `cors:reflected`: the origin is echoed back, so any site can read the response
+/// - `cors:null`: the `null` origin of sandboxed frames and `data:` URLs is trusted
+/// - `cors:wildcard`: `*`, readable by any site but never with cookies
+///
+/// The first two get a `-credentials` suffix when `Access-Control-Allow-Credentials`
+/// also lets the browser send the victim's cookies along, which is the exploitable case.
+pub fn assess(origin: &str, headers: &HeaderMap) -> Option<String> {
+    let allowed = headers.get(ACCESS_CONTROL_ALLOW_ORIGIN)?.to_str().ok()?.trim();
+    let credentials = headers.get(ACCESS_CONTROL_ALLOW_CREDENTIALS)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"));
+    let kind = match allowed {
+        "*" => return Some("cors:wildcard".to_string()),
+        "null" => "null",
+        allowed if allowed.trim_end_matches('/').eq_ignore_ascii_case(origin.trim_end_matches('/')) => "reflected",
+        _ => return None,
+    };
+    Some(match credentials {
+        true => format!("cors:{}-credentials", kind),
+        false => format!("cors:{}", kind),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(origin: &'static str, credentials: Option<&'static str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static(origin));
+        if let Some(credentials) = credentials {
+            headers.insert(ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static(credentials));
+        }
+        headers
+    }
+
+    #[test]
+    fn flags_permissive_origins() {
+        let assess = |headers: HeaderMap| assess(DEFAULT_ORIGIN, &headers);
+        assert_eq!(assess(headers("https://evil.example", Some("true"))).as_deref(), Some("cors:reflected-credentials"));
+        assert_eq!(assess(headers("https://EVIL.example/", None)).as_deref(), Some("cors:reflected"));
+        assert_eq!(assess(headers("null", Some("True"))).as_deref(), Some("cors:null-credentials"));
+        assert_eq!(assess(headers("*", Some("true"))).as_deref(), Some("cors:wildcard"));
+
+        // Trusting only its own origin is what a safe policy looks like
+        assert_eq!(assess(headers("https://app.example.com", Some("true"))), None);
+        assert_eq!(assess(HeaderMap::new()), None);
+    }
+}
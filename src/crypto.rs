@@ -0,0 +1,250 @@
+use crate::error::CrawlerError;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+
+/// Header identifying files written by `--encrypt-output`
+const MAGIC: &[u8] = b"DCRAWLER-CHACHA20POLY1305-V1\n";
+const NONCE_LEN: usize = 12;
+
+/// ChaCha20-Poly1305 cipher used for everything the scan writes to disk
+#[derive(Clone)]
+pub struct OutputCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl OutputCipher {
+    /// Load a 32-byte key stored either raw or as 64 hex characters
+    pub fn from_key_file(path: &Path) -> Result<Self, CrawlerError> {
+        let data = fs::read(path)
+            .map_err(|e| format!("Cannot read key file {}: {}", path.display(), e))?;
+
+        let text = String::from_utf8_lossy(&data);
+        let hex = text.trim();
+        let key_bytes = if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            (0..32)
+                .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16))
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|e| format!("Invalid hex key: {}", e))?
+        } else if data.len() == 32 {
+            data
+        } else {
+            return Err(format!(
+                "Key file {} must hold 32 raw bytes or 64 hex characters (e.g. `openssl rand -hex 32 > key`)",
+                path.display()
+            ).into());
+        };
+
+        Ok(OutputCipher {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key_bytes)),
+        })
+    }
+
+    /// Encrypt with a fresh random nonce: `MAGIC || nonce || ciphertext`
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, CrawlerError> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| "Encryption failed")?;
+
+        let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Reverse `encrypt`, authenticating the contents
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, CrawlerError> {
+        let body = data
+            .strip_prefix(MAGIC)
+            .ok_or("Not a dir_crawler encrypted file")?;
+        if body.len() < NONCE_LEN {
+            return Err("Encrypted file is truncated".into());
+        }
+
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Decryption failed: wrong key or corrupted file".into())
+    }
+}
+
+/// Write an output file, encrypting it when a cipher is configured
+pub fn write_file(path: &Path, contents: &[u8], cipher: Option<&OutputCipher>) -> Result<(), CrawlerError> {
+    match cipher {
+        Some(cipher) => fs::write(path, cipher.encrypt(contents)?)?,
+        None => fs::write(path, contents)?,
+    }
+    Ok(())
+}
+
+/// Messages between a coordinator and an agent, sealed with
+/// ChaCha20-Poly1305 under a key both ends derive from the shared secret.
+/// Each direction numbers its messages and the number is the nonce, so a
+/// message that is replayed, dropped or reordered fails to open like a
+/// forged one.
+pub struct Channel {
+    cipher: ChaCha20Poly1305,
+    /// Direction of the messages this end seals: 0 from the end that
+    /// speaks first, 1 from the other
+    outgoing: u8,
+    sent: u64,
+    received: u64,
+}
+
+impl Channel {
+    pub fn new(key: &[u8; 32], initiator: bool) -> Self {
+        Channel {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+            outgoing: if initiator { 0 } else { 1 },
+            sent: 0,
+            received: 0,
+        }
+    }
+
+    pub fn seal(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, CrawlerError> {
+        let nonce = channel_nonce(self.outgoing, self.sent);
+        let sealed = self.cipher.encrypt(&nonce, plaintext).map_err(|_| "Encryption failed")?;
+        self.sent += 1;
+        Ok(sealed)
+    }
+
+    /// The next message from the other end, if that's what `sealed` is
+    pub fn open(&mut self, sealed: &[u8]) -> Result<Vec<u8>, CrawlerError> {
+        let nonce = channel_nonce(1 - self.outgoing, self.received);
+        let plaintext = self.cipher
+            .decrypt(&nonce, sealed)
+            .map_err(|_| "message failed authentication: wrong secret, or tampered with on the way")?;
+        self.received += 1;
+        Ok(plaintext)
+    }
+}
+
+fn channel_nonce(direction: u8, counter: u64) -> Nonce {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[0] = direction;
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    *Nonce::from_slice(&nonce)
+}
+
+/// `len` bytes from the operating system's CSPRNG, hex encoded
+pub fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// HMAC-SHA256 (RFC 2104) of `message` under `key`
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.iter().map(|b| b ^ byte).collect::<Vec<u8>>();
+    let inner = Sha256::new().chain_update(pad(0x36)).chain_update(message).finalize();
+    Sha256::new().chain_update(pad(0x5c)).chain_update(inner).finalize().into()
+}
+
+/// Compare two byte strings without leaking where they first differ
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher_from(key: &[u8], name: &str) -> Result<OutputCipher, CrawlerError> {
+        let path = std::env::temp_dir().join(format!("dir_crawler-test-{}-{}.key", std::process::id(), name));
+        fs::write(&path, key)?;
+        let cipher = OutputCipher::from_key_file(&path);
+        let _ = fs::remove_file(&path);
+        cipher
+    }
+
+    #[test]
+    fn round_trips_with_raw_and_hex_keys() {
+        let raw = cipher_from(&[7u8; 32], "raw").unwrap();
+        let hex = cipher_from("07".repeat(32).as_bytes(), "hex").unwrap();
+        let sealed = raw.encrypt(b"{\"findings\":[]}").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert_eq!(hex.decrypt(&sealed).unwrap(), b"{\"findings\":[]}");
+    }
+
+    #[test]
+    fn nonces_are_fresh() {
+        let cipher = cipher_from(&[1u8; 32], "nonce").unwrap();
+        assert_ne!(cipher.encrypt(b"same").unwrap(), cipher.encrypt(b"same").unwrap());
+    }
+
+    #[test]
+    fn rejects_tampering_wrong_keys_and_foreign_files() {
+        let cipher = cipher_from(&[1u8; 32], "tamper-a").unwrap();
+        let other = cipher_from(&[2u8; 32], "tamper-b").unwrap();
+        let mut sealed = cipher.encrypt(b"secret results").unwrap();
+        assert!(other.decrypt(&sealed).is_err());
+        *sealed.last_mut().unwrap() ^= 1;
+        assert!(cipher.decrypt(&sealed).is_err());
+        assert!(cipher.decrypt(b"plain json").is_err());
+        assert!(cipher.decrypt(MAGIC).is_err());
+    }
+
+    #[test]
+    fn rejects_keys_of_the_wrong_size() {
+        assert!(cipher_from(&[0u8; 16], "short").is_err());
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn hmac_matches_rfc_4231_vectors() {
+        // Test case 2: short key
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Test case 6: key longer than the block size is hashed first
+        assert_eq!(
+            hex(&hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn channels_open_each_message_once_and_in_order() {
+        let mut coordinator = Channel::new(&[3u8; 32], true);
+        let mut agent = Channel::new(&[3u8; 32], false);
+        let first = coordinator.seal(b"job 1").unwrap();
+        let second = coordinator.seal(b"job 2").unwrap();
+        assert!(agent.open(&second).is_err());
+        assert_eq!(agent.open(&first).unwrap(), b"job 1");
+        assert!(agent.open(&first).is_err());
+        assert_eq!(agent.open(&second).unwrap(), b"job 2");
+
+        // A reply can't pass for a request, nor a message under another key
+        let reply = agent.seal(b"result").unwrap();
+        assert!(Channel::new(&[3u8; 32], false).open(&reply).is_err());
+        assert!(Channel::new(&[4u8; 32], true).open(&reply).is_err());
+        assert_eq!(coordinator.open(&reply).unwrap(), b"result");
+    }
+
+    #[test]
+    fn constant_time_eq_compares_length_and_content() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+        assert_eq!(random_hex(16).len(), 32);
+        assert_ne!(random_hex(16), random_hex(16));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::engine::{read_body, send_request};
+use crate::error::CrawlerError;
+use crate::session::cookie_pairs;
+use crate::template::{percent_encode, RequestTemplate};
+use crate::{FuzzerConfig, ScanState};
+use regex::Regex;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Method};
+use scraper::{Html, Selector};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+
+/// Where the token goes in a request template's headers or body
+pub const PLACEHOLDER: &str = "{{csrf}}";
+
+/// How the token is pulled out of the page
+pub enum Extractor {
+    /// First capture group, or the whole match without one
+    Regex(Regex),
+    /// `value` or `content` attribute of the first matching element, else its text
+    Selector(Selector),
+}
+
+impl Extractor {
+    pub fn regex(pattern: &str) -> Result<Self, CrawlerError> {
+        Regex::new(pattern)
+            .map(Extractor::Regex)
+            .map_err(|e| format!("Invalid --csrf-regex: {}", e).into())
+    }
+
+    pub fn selector(css: &str) -> Result<Self, CrawlerError> {
+        Selector::parse(css)
+            .map(Extractor::Selector)
+            .map_err(|e| format!("Invalid --csrf-selector '{}': {}", css, e).into())
+    }
+
+    pub fn extract(&self, page: &str) -> Option<String> {
+        let token = match self {
+            Extractor::Regex(regex) => {
+                let captures = regex.captures(page)?;
+                captures.get(1).or_else(|| captures.get(0))?.as_str().to_string()
+            }
+            Extractor::Selector(selector) => {
+                let document = Html::parse_document(page);
+                let element = document.select(selector).next()?;
+                match element.value().attr("value").or_else(|| element.value().attr("content")) {
+                    Some(value) => value.to_string(),
+                    None => element.text().collect::<String>(),
+                }
+            }
+        };
+        let token = token.trim();
+        (!token.is_empty()).then(|| token.to_string())
+    }
+}
+
+/// A token and the cookies the page set alongside it (double-submit
+/// schemes check that the two match)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Token {
+    pub value: String,
+    /// `name=value; name=value`, ready for a Cookie header
+    pub cookies: String,
+}
+
+/// Page the anti-CSRF token is fetched from before fuzzed requests, and
+/// how often it is fetched again
+pub struct CsrfSource {
+    url: String,
+    extractor: Extractor,
+    /// Fetch a fresh token every this many requests; 0 keeps the first one
+    refresh_every: u64,
+    sent: AtomicU64,
+    current: RwLock<Option<Token>>,
+    fetching: tokio::sync::Mutex<()>,
+}
+
+impl CsrfSource {
+    pub fn new(url: String, extractor: Extractor, refresh_every: u64) -> Self {
+        CsrfSource {
+            url,
+            extractor,
+            refresh_every,
+            sent: AtomicU64::new(0),
+            current: RwLock::new(None),
+            fetching: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Token for the next request, fetching one first when there is none
+    /// yet or the current one is due for a refresh
+    pub async fn token(&self, config: &FuzzerConfig, state: &ScanState, client: &Client) -> Result<Token, CrawlerError> {
+        let sent = self.sent.fetch_add(1, Ordering::AcqRel);
+        let due = self.refresh_every > 0 && sent > 0 && sent.is_multiple_of(self.refresh_every);
+        if !due {
+            if let Some(token) = self.current.read().unwrap().clone() {
+                return Ok(token);
+            }
+        }
+
+        let _guard = self.fetching.lock().await;
+        // Another worker may have fetched one while this one waited
+        if !due {
+            if let Some(token) = self.current.read().unwrap().clone() {
+                return Ok(token);
+            }
+        }
+        let token = self.fetch(config, state, client).await?;
+        *self.current.write().unwrap() = Some(token.clone());
+        Ok(token)
+    }
+
+    async fn fetch(&self, config: &FuzzerConfig, state: &ScanState, client: &Client) -> Result<Token, CrawlerError> {
+        // A plain GET without the template, which also keeps it from
+        // needing a token of its own
+        let get = FuzzerConfig { method: Method::GET, request_template: None, ..config.clone() };
+        let response = Box::pin(send_request(&get, state, client, &self.url, None))
+            .await
+            .map_err(|e| format!("Cannot fetch CSRF token from {}: {}", state.redactor.redact(&self.url), e))?;
+        let status = response.status().as_u16();
+        let cookies = cookie_pairs(response.headers()).join("; ");
+        let (body, _) = read_body(&get, response).await?;
+        let value = self.extractor
+            .extract(&String::from_utf8_lossy(&body))
+            .ok_or_else(|| format!("No CSRF token found in {} (status {})", state.redactor.redact(&self.url), status))?;
+        Ok(Token { value, cookies })
+    }
+}
+
+/// Whether a template has somewhere to put the token
+pub fn has_placeholder(template: &RequestTemplate) -> bool {
+    template.headers.iter().any(|(_, value)| value.contains(PLACEHOLDER))
+        || template.body.as_ref().is_some_and(|body| body.contains(PLACEHOLDER))
+}
+
+/// The template with the token in place of the placeholder, percent-encoded
+/// in form bodies where `+` and `=` would otherwise be mangled
+pub fn inject(template: &RequestTemplate, token: &str) -> RequestTemplate {
+    let form = template.headers.iter().any(|(name, value)| {
+        name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()) && value.contains("application/x-www-form-urlencoded")
+    });
+    let in_body = match form {
+        true => percent_encode(token),
+        false => token.to_string(),
+    };
+    RequestTemplate {
+        headers: template.headers.iter()
+            .map(|(name, value)| (name.clone(), value.replace(PLACEHOLDER, token)))
+            .collect(),
+        body: template.body.as_ref().map(|body| body.replace(PLACEHOLDER, &in_body)),
+        ..template.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><head><meta name="csrf-token" content="meta+tok/en="></head>
+        <body><form method="post"><input type="hidden" name="csrfmiddlewaretoken" value="abc123">
+        <span id="token"> text-token </span></form></body></html>"#;
+
+    #[test]
+    fn extracts_with_selectors() {
+        let extract = |css: &str| Extractor::selector(css).unwrap().extract(PAGE);
+        assert_eq!(extract("input[name=csrfmiddlewaretoken]").as_deref(), Some("abc123"));
+        assert_eq!(extract("meta[name=csrf-token]").as_deref(), Some("meta+tok/en="));
+        assert_eq!(extract("#token").as_deref(), Some("text-token"));
+        assert_eq!(extract("input[name=missing]"), None);
+        assert!(Extractor::selector("input[").is_err());
+    }
+
+    #[test]
+    fn extracts_with_regexes() {
+        let extract = |pattern: &str| Extractor::regex(pattern).unwrap().extract(PAGE);
+        assert_eq!(extract(r#"name="csrfmiddlewaretoken" value="([^"]+)""#).as_deref(), Some("abc123"));
+        assert_eq!(extract("abc[0-9]+").as_deref(), Some("abc123"));
+        assert_eq!(extract("nothing-like-this"), None);
+        assert!(Extractor::regex("(").is_err());
+    }
+
+    #[test]
+    fn injects_into_headers_and_encodes_form_bodies() {
+        let raw = "POST /api/FUZZ HTTP/1.1\nHost: a\nX-CSRF-Token: {{csrf}}\n\
+                   Content-Type: application/x-www-form-urlencoded\n\ncsrf={{csrf}}&name=FUZZ";
+        let template = RequestTemplate::parse(raw, None).unwrap();
+        assert!(has_placeholder(&template));
+        let filled = inject(&template, "a+b/c=");
+        assert!(filled.headers.contains(&("X-CSRF-Token".to_string(), "a+b/c=".to_string())));
+        assert_eq!(filled.body_for("x").as_deref(), Some("csrf=a%2Bb%2Fc%3D&name=x"));
+
+        let json = RequestTemplate::parse("POST /FUZZ HTTP/1.1\nHost: a\n\n{\"csrf\":\"{{csrf}}\"}", None).unwrap();
+        assert_eq!(inject(&json, "a+b").body.as_deref(), Some("{\"csrf\":\"a+b\"}"));
+        assert!(!has_placeholder(&RequestTemplate::parse("GET /FUZZ HTTP/1.1\nHost: a\n", None).unwrap()));
+    }
+}
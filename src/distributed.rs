@@ -0,0 +1,771 @@
+use crate::audit;
+use crate::crypto;
+use crate::engine::scan_entries;
+use crate::error::CrawlerError;
+use crate::plugin;
+use crate::template::RequestTemplate;
+use crate::wordlist::Entries;
+use crate::{
+    build_client, count_urls, method_serde, redact, Differential, Finding, FuzzerConfig, RecursionStrategy, ScanState,
+};
+use crate::term;
+use colored::*;
+use hyper::body::HttpBody;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Request, Response, StatusCode};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+
+/// Environment variable read when `--agent-secret` isn't given
+pub const SECRET_ENV: &str = "DIR_CRAWLER_AGENT_SECRET";
+
+/// Time a handshake, or the upload of a job, may take
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sessions unused for this long are closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Handshakes an agent keeps open at once before refusing more
+const MAX_PENDING: usize = 16;
+
+/// Largest request head an agent reads
+const MAX_HEAD: usize = 16 * 1024;
+
+/// Largest handshake body an agent reads, before anyone is authenticated
+const MAX_HELLO: usize = 1024;
+
+/// Largest job or result either side reads
+const MAX_MESSAGE: usize = 64 * 1024 * 1024;
+
+/// What a coordinator opens a session with
+#[derive(Debug, Serialize, Deserialize)]
+struct Hello {
+    nonce: String,
+}
+
+/// The agent's answer: its nonce, which also names the session, and proof
+/// that it holds the shared secret
+#[derive(Debug, Serialize, Deserialize)]
+struct Challenge {
+    nonce: String,
+    proof: String,
+}
+
+/// The scan settings an agent accepts from a coordinator. Anything that
+/// touches the agent's own disk (output, stored responses, workspaces,
+/// logs) or its resources (threads) stays under the agent's control.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ScanParams {
+    url: String,
+    #[serde(with = "method_serde")]
+    method: Method,
+    request_template: Option<RequestTemplate>,
+    extensions: Vec<String>,
+    status_codes: Vec<u16>,
+    timeout: Option<u64>,
+    connect_timeout: u64,
+    read_timeout: u64,
+    max_body_size: Option<u64>,
+    max_connections_per_host: Option<usize>,
+    max_bandwidth: Option<u64>,
+    scan_id: String,
+    request_id_header: Option<String>,
+    scope_include: Vec<String>,
+    scope_exclude: Vec<String>,
+    blocklist: Vec<String>,
+    adaptive_throttle: bool,
+    detect_language: bool,
+    detect_secrets: bool,
+    secret_rules: Vec<String>,
+    extract_titles: bool,
+    audit_headers: bool,
+    cors_origin: Option<String>,
+    open_redirects: bool,
+    probe_methods: Vec<String>,
+    spray_credentials: Vec<crate::spray::Credential>,
+    spray_delay: u64,
+    target_overrides: Vec<crate::targets::TargetOverride>,
+    persona: Option<String>,
+    stealth: Option<crate::stealth::Fingerprint>,
+    headers: Vec<(String, String)>,
+    cookie: Option<String>,
+    host_header: Option<String>,
+    resolve: Vec<(String, SocketAddr)>,
+    address_family: crate::dns::AddressFamily,
+    differential: Option<Differential>,
+    recursion_depth: usize,
+    recursion_strategy: RecursionStrategy,
+    max_dirs_per_level: Option<usize>,
+    auto_calibrate: bool,
+    unique: bool,
+    fuzz_param: Option<String>,
+    buckets: bool,
+    host_error_threshold: Option<u32>,
+}
+
+impl ScanParams {
+    fn from_config(config: &FuzzerConfig) -> Self {
+        ScanParams {
+            url: config.url.clone(),
+            method: config.method.clone(),
+            request_template: config.request_template.clone(),
+            extensions: config.extensions.clone(),
+            status_codes: config.status_codes.clone(),
+            timeout: config.timeout,
+            connect_timeout: config.connect_timeout,
+            read_timeout: config.read_timeout,
+            max_body_size: config.max_body_size,
+            max_connections_per_host: config.max_connections_per_host,
+            max_bandwidth: config.max_bandwidth,
+            scan_id: config.scan_id.clone(),
+            request_id_header: config.request_id_header.clone(),
+            scope_include: config.scope_include.clone(),
+            scope_exclude: config.scope_exclude.clone(),
+            blocklist: config.blocklist.clone(),
+            adaptive_throttle: config.adaptive_throttle,
+            detect_language: config.detect_language,
+            detect_secrets: config.detect_secrets,
+            secret_rules: config.secret_rules.clone(),
+            extract_titles: config.extract_titles,
+            audit_headers: config.audit_headers,
+            cors_origin: config.cors_origin.clone(),
+            open_redirects: config.open_redirects,
+            probe_methods: config.probe_methods.clone(),
+            spray_credentials: config.spray_credentials.clone(),
+            spray_delay: config.spray_delay,
+            target_overrides: config.target_overrides.clone(),
+            persona: config.persona.clone(),
+            stealth: config.stealth.clone(),
+            headers: config.headers.clone(),
+            cookie: config.cookie.clone(),
+            host_header: config.host_header.clone(),
+            resolve: config.resolve.clone(),
+            address_family: config.address_family,
+            differential: config.differential.clone(),
+            recursion_depth: config.recursion_depth,
+            recursion_strategy: config.recursion_strategy,
+            max_dirs_per_level: config.max_dirs_per_level,
+            auto_calibrate: config.auto_calibrate,
+            unique: config.unique,
+            fuzz_param: config.fuzz_param.clone(),
+            buckets: config.buckets,
+            host_error_threshold: config.host_error_threshold,
+        }
+    }
+
+    /// The agent's configuration for one job: the coordinator's scan settings
+    /// with this agent's own concurrency. Request IDs carry the number the
+    /// coordinator gave the agent so sequences never collide across agents;
+    /// stop conditions are the coordinator's to enforce across all agents.
+    fn into_config(self, threads: usize, agent: usize) -> FuzzerConfig {
+        FuzzerConfig {
+            url: self.url,
+            fuzz_param: self.fuzz_param,
+            buckets: self.buckets,
+            threads,
+            silent: true,
+            status_codes: self.status_codes,
+            timeout: self.timeout,
+            connect_timeout: self.connect_timeout,
+            read_timeout: self.read_timeout,
+            max_body_size: self.max_body_size,
+            max_connections_per_host: self.max_connections_per_host,
+            max_bandwidth: self.max_bandwidth,
+            method: self.method,
+            request_template: self.request_template,
+            extensions: self.extensions,
+            detect_language: self.detect_language,
+            scan_id: format!("{}-a{}", self.scan_id, agent),
+            request_id_header: self.request_id_header,
+            scope_include: self.scope_include,
+            scope_exclude: self.scope_exclude,
+            blocklist: self.blocklist,
+            adaptive_throttle: self.adaptive_throttle,
+            detect_secrets: self.detect_secrets,
+            secret_rules: self.secret_rules,
+            extract_titles: self.extract_titles,
+            audit_headers: self.audit_headers,
+            cors_origin: self.cors_origin,
+            open_redirects: self.open_redirects,
+            probe_methods: self.probe_methods,
+            spray_credentials: self.spray_credentials,
+            spray_delay: self.spray_delay,
+            target_overrides: self.target_overrides,
+            persona: self.persona,
+            stealth: self.stealth,
+            headers: self.headers,
+            cookie: self.cookie,
+            host_header: self.host_header,
+            resolve: self.resolve,
+            address_family: self.address_family,
+            differential: self.differential,
+            recursion_depth: self.recursion_depth,
+            recursion_strategy: self.recursion_strategy,
+            max_dirs_per_level: self.max_dirs_per_level,
+            auto_calibrate: self.auto_calibrate,
+            unique: self.unique,
+            host_error_threshold: self.host_error_threshold,
+            ..FuzzerConfig::default()
+        }
+    }
+}
+
+/// Work unit sent from the coordinator to an agent
+#[derive(Debug, Serialize, Deserialize)]
+struct AgentJob {
+    params: ScanParams,
+    words: Vec<String>,
+    /// Number the coordinator gave this agent, unique within the scan
+    agent: usize,
+    /// Whether the coordinator keeps an audit log the requests go in
+    audit: bool,
+}
+
+/// Agent reply carrying the findings for a single work unit
+#[derive(Debug, Serialize, Deserialize)]
+struct AgentResult {
+    findings: Vec<Finding>,
+    /// Requests that failed while scanning the unit
+    #[serde(default)]
+    errors: u64,
+    /// Audit log lines of the unit's requests, when the coordinator keeps one
+    #[serde(default)]
+    audit: Vec<String>,
+}
+
+/// Hex HMAC under the shared secret of what a side (`label`) proves it
+/// holds the secret with: both sides' nonces of the session
+fn prove(secret: &str, label: &str, coordinator: &str, agent: &str) -> String {
+    crypto::hmac_sha256(secret.as_bytes(), format!("{}:{}:{}", label, coordinator, agent).as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Key a session's messages are sealed with, known only to holders of the
+/// shared secret and new for every session
+fn session_key(secret: &str, coordinator: &str, agent: &str) -> [u8; 32] {
+    crypto::hmac_sha256(secret.as_bytes(), format!("session:{}:{}", coordinator, agent).as_bytes())
+}
+
+/// A coordinator's session with one agent, over the agent's REST API:
+///
+/// - `POST /v1/sessions` with the coordinator's nonce opens it; the agent
+///   answers with its own nonce and an HMAC of both under the secret
+/// - `POST /v1/sessions/<agent nonce>/jobs` takes a sealed job and answers
+///   with its sealed result; a job only opens under the key derived from
+///   the secret, which is how the agent knows the coordinator holds it
+struct Link {
+    client: reqwest::Client,
+    jobs: String,
+    channel: crypto::Channel,
+}
+
+impl Link {
+    async fn open(agent: &str, secret: &str) -> Result<Link, CrawlerError> {
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .connect_timeout(HANDSHAKE_TIMEOUT)
+            .build()?;
+        let nonce = crypto::random_hex(32);
+        let response = client
+            .post(format!("http://{}/v1/sessions", agent))
+            .json(&Hello { nonce: nonce.clone() })
+            .timeout(HANDSHAKE_TIMEOUT)
+            .send()
+            .await?;
+        let challenge: Challenge = answer(response).await.and_then(|body| Ok(serde_json::from_slice(&body)?))?;
+        let expected = prove(secret, "agent", &nonce, &challenge.nonce);
+        if !crypto::constant_time_eq(challenge.proof.as_bytes(), expected.as_bytes()) {
+            return Err("the agent doesn't hold the shared secret".into());
+        }
+        if !challenge.nonce.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("malformed handshake".into());
+        }
+        Ok(Link {
+            client,
+            jobs: format!("http://{}/v1/sessions/{}/jobs", agent, challenge.nonce),
+            channel: crypto::Channel::new(&session_key(secret, &nonce, &challenge.nonce), true),
+        })
+    }
+
+    /// Send one job and wait for its result
+    async fn run(&mut self, job: &AgentJob) -> Result<AgentResult, CrawlerError> {
+        let sealed = self.channel.seal(&serde_json::to_vec(job)?)?;
+        let response = self.client.post(&self.jobs).body(sealed).send().await?;
+        let body = answer(response).await?;
+        Ok(serde_json::from_slice(&self.channel.open(&body)?)?)
+    }
+}
+
+/// The body of an agent's answer, or what went wrong
+async fn answer(mut response: reqwest::Response) -> Result<Vec<u8>, CrawlerError> {
+    let status = response.status();
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > MAX_MESSAGE {
+            return Err(format!("the agent's answer is over {} bytes", MAX_MESSAGE).into());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    if !status.is_success() {
+        return Err(format!("the agent answered {}: {}", status, String::from_utf8_lossy(&body)).into());
+    }
+    Ok(body)
+}
+
+/// Shard the wordlist across remote agents and merge their findings.
+///
+/// Chunks live in a shared queue that every agent connection pulls from,
+/// so an agent that finishes early keeps stealing work until the queue is
+/// drained. Chunks held by an agent that drops out are pushed back for the
+/// remaining agents to pick up.
+pub async fn run_coordinator(
+    config: Arc<FuzzerConfig>,
+    agents: &[String],
+    entries: Entries,
+    chunk_size: usize,
+    state: Arc<ScanState>,
+    secret: &str,
+) -> Result<Vec<Finding>, CrawlerError> {
+    let params = ScanParams::from_config(&config);
+    // Work units are index ranges, so combinations are only generated as they're handed out
+    let chunk_size = chunk_size as u64;
+    let queue: VecDeque<(u64, u64)> = (0..entries.len())
+        .step_by(chunk_size as usize)
+        .map(|start| (start, start + chunk_size))
+        .collect();
+    let queue = Arc::new(Mutex::new(queue));
+    let entries = Arc::new(entries);
+    let findings = Arc::new(Mutex::new(Vec::new()));
+    let mut handles = Vec::new();
+
+    for (number, agent) in agents.iter().enumerate() {
+        let agent = agent.clone();
+        let config = Arc::clone(&config);
+        let queue = Arc::clone(&queue);
+        let entries = Arc::clone(&entries);
+        let findings = Arc::clone(&findings);
+        let state = Arc::clone(&state);
+        let params = params.clone();
+        let secret = secret.to_string();
+
+        handles.push(tokio::spawn(async move {
+            let mut link = match Link::open(&agent, &secret).await {
+                Ok(link) => link,
+                Err(e) => {
+                    term::err!("⚠️  Agent {} refused the handshake: {}", agent.red(), e);
+                    return 0usize;
+                }
+            };
+            let mut completed = 0usize;
+
+            loop {
+                if state.stopped() {
+                    break;
+                }
+                let Some((start, end)) = queue.lock().await.pop_front() else {
+                    break;
+                };
+                let words = entries.slice(start, end);
+                let url_count = count_urls(&config.url, &words, &config.extensions);
+
+                let job = AgentJob {
+                    params: params.clone(),
+                    words,
+                    agent: number + 1,
+                    audit: state.audit_log.is_some(),
+                };
+
+                let result = tokio::select! {
+                    result = link.run(&job) => result,
+                    _ = state.cancellation.cancelled() => break,
+                };
+                match result {
+                    Ok(result) => {
+                        if let Some(audit_log) = &state.audit_log {
+                            for line in &result.audit {
+                                audit_log.append(state.redactor.redact(line).into_owned());
+                            }
+                        }
+                        for finding in &result.findings {
+                            state.sinks.record(&crate::sink::Recorded {
+                                finding,
+                                redactor: &state.redactor,
+                                first_sighting: true,
+                                snippet: None,
+                                agent: Some(agent.as_str()),
+                            });
+                        }
+                        if let Some(chat) = &state.chat {
+                            for finding in &result.findings {
+                                chat.finding(crate::chat::Hit::new(finding, &state.redactor));
+                            }
+                        }
+                        if let Some(stream) = &state.stream {
+                            for finding in &result.findings {
+                                if !stream.send(finding.clone()).await {
+                                    state.stop("the findings stream was dropped".to_string());
+                                }
+                            }
+                        }
+                        state.hits.fetch_add(result.findings.len() as u64, Ordering::Relaxed);
+                        for finding in &result.findings {
+                            state.metrics.finding(finding.status);
+                        }
+                        state.errors.fetch_add(result.errors, Ordering::Relaxed);
+                        findings.lock().await.extend(result.findings);
+                        state.progress.inc(url_count);
+                        state.check_limits(&config);
+                        completed += 1;
+                    }
+                    Err(e) => {
+                        // Hand the unit back so another agent can steal it
+                        queue.lock().await.push_front((start, end));
+                        term::err!("⚠️  Agent {} dropped out: {}", agent.red(), e);
+                        break;
+                    }
+                }
+            }
+
+            completed
+        }));
+    }
+
+    for handle in handles {
+        let completed = handle.await?;
+        info!("🛰️  Agent finished {} work units", completed);
+    }
+
+    // A stop condition leaves work queued on purpose; report what was found
+    let remaining = queue.lock().await.len();
+    if remaining > 0 && !state.stopped() {
+        return Err(format!("No agents left to finish {} remaining work units", remaining).into());
+    }
+
+    let findings = std::mem::take(&mut *findings.lock().await);
+    Ok(findings)
+}
+
+/// An agent's side of its sessions with coordinators
+struct Agent {
+    threads: usize,
+    secret: String,
+    redact_patterns: Vec<String>,
+    /// Open sessions by the agent's nonce
+    sessions: std::sync::Mutex<HashMap<String, Arc<Mutex<Session>>>>,
+}
+
+struct Session {
+    channel: crypto::Channel,
+    opened: Instant,
+    /// Whether a job has opened under the session key yet
+    authenticated: bool,
+    used: Instant,
+    /// Scan state the session's jobs share, so request IDs keep counting
+    /// and no URL is requested twice; made with the first job
+    state: Option<Arc<ScanState>>,
+}
+
+/// Serve work units from coordinators until the process is stopped. Only
+/// coordinators holding the shared secret get work accepted; the jobs of
+/// one session share scan state.
+pub async fn run_agent(
+    listen: &str,
+    threads: usize,
+    secret: String,
+    redact_patterns: Vec<String>,
+) -> Result<(), CrawlerError> {
+    let listener = TcpListener::bind(listen).await?;
+    term::err!("🛰️  Agent listening on {}", listen.green());
+    serve(listener, Arc::new(Agent { threads, secret, redact_patterns, sessions: Default::default() })).await
+}
+
+async fn serve(listener: TcpListener, agent: Arc<Agent>) -> Result<(), CrawlerError> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        debug!("🔗 Connection from {}", peer);
+
+        let agent = Arc::clone(&agent);
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let agent = Arc::clone(&agent);
+                async move { Ok::<_, Infallible>(agent.handle(request, peer).await) }
+            });
+            let served = Http::new()
+                .http1_only(true)
+                .http1_header_read_timeout(HANDSHAKE_TIMEOUT)
+                .max_buf_size(MAX_HEAD)
+                .serve_connection(stream, service)
+                .await;
+            if let Err(e) = served {
+                debug!("Connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+impl Agent {
+    async fn handle(&self, request: Request<Body>, peer: SocketAddr) -> Response<Body> {
+        let path: Vec<&str> = request.uri().path().trim_matches('/').split('/').collect();
+        let route = match (request.method(), path.as_slice()) {
+            (&Method::POST, ["v1", "sessions"]) => None,
+            (&Method::POST, ["v1", "sessions", session, "jobs"]) => Some(session.to_string()),
+            _ => return reply(StatusCode::NOT_FOUND, "no such endpoint"),
+        };
+        let (limit, reading) = match route {
+            None => (MAX_HELLO, "handshake"),
+            Some(_) => (MAX_MESSAGE, "job"),
+        };
+        let body = match tokio::time::timeout(HANDSHAKE_TIMEOUT, read_body(request.into_body(), limit)).await {
+            Ok(Ok(body)) => body,
+            Ok(Err(status)) => return reply(status, reading),
+            Err(_) => return reply(StatusCode::REQUEST_TIMEOUT, reading),
+        };
+        match route {
+            None => self.open_session(&body, peer),
+            Some(session) => self.run_job(&session, &body, peer).await,
+        }
+    }
+
+    /// Answer a coordinator's hello with this side's nonce and proof
+    fn open_session(&self, body: &[u8], peer: SocketAddr) -> Response<Body> {
+        let Ok(hello) = serde_json::from_slice::<Hello>(body) else {
+            return reply(StatusCode::BAD_REQUEST, "malformed handshake");
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        // Sessions nobody authenticated in time, or left idle, are dropped
+        sessions.retain(|_, session| match session.try_lock() {
+            Ok(session) if !session.authenticated => session.opened.elapsed() < HANDSHAKE_TIMEOUT,
+            Ok(session) => session.used.elapsed() < IDLE_TIMEOUT,
+            Err(_) => true,
+        });
+        let pending = sessions.values()
+            .filter(|session| session.try_lock().is_ok_and(|session| !session.authenticated))
+            .count();
+        if pending >= MAX_PENDING {
+            return reply(StatusCode::SERVICE_UNAVAILABLE, "too many handshakes under way");
+        }
+
+        let nonce = crypto::random_hex(32);
+        let challenge = Challenge {
+            proof: prove(&self.secret, "agent", &hello.nonce, &nonce),
+            nonce: nonce.clone(),
+        };
+        let session = Session {
+            channel: crypto::Channel::new(&session_key(&self.secret, &hello.nonce, &nonce), false),
+            opened: Instant::now(),
+            authenticated: false,
+            used: Instant::now(),
+            state: None,
+        };
+        sessions.insert(nonce, Arc::new(Mutex::new(session)));
+        debug!("🔗 Session opened by {}", peer);
+        match serde_json::to_vec(&challenge) {
+            Ok(body) => Response::new(Body::from(body)),
+            Err(_) => reply(StatusCode::INTERNAL_SERVER_ERROR, "handshake failed"),
+        }
+    }
+
+    /// Open a sealed job, scan it and seal the result
+    async fn run_job(&self, session: &str, body: &[u8], peer: SocketAddr) -> Response<Body> {
+        let session = self.sessions.lock().unwrap().get(session).cloned();
+        let Some(session) = session else {
+            return reply(StatusCode::NOT_FOUND, "no such session");
+        };
+        let mut session = session.lock().await;
+        let job = match session.channel.open(body) {
+            Ok(job) => job,
+            Err(e) => {
+                warn!("❌ Coordinator {} refused: {}", peer, e);
+                return reply(StatusCode::FORBIDDEN, "wrong agent secret");
+            }
+        };
+        if !session.authenticated {
+            info!("🔗 Coordinator {} authenticated", peer);
+            session.authenticated = true;
+        }
+        session.used = Instant::now();
+
+        let result = match serde_json::from_slice::<AgentJob>(&job) {
+            Ok(job) => self.scan(job, &mut session.state).await,
+            Err(e) => Err(e.into()),
+        };
+        let sealed = result
+            .and_then(|result| Ok(serde_json::to_vec(&result)?))
+            .and_then(|result| session.channel.seal(&result));
+        match sealed {
+            Ok(sealed) => Response::new(Body::from(sealed)),
+            Err(e) => {
+                warn!("❌ Work unit from {} failed: {}", peer, e);
+                reply(StatusCode::INTERNAL_SERVER_ERROR, "the work unit failed")
+            }
+        }
+    }
+
+    async fn scan(&self, job: AgentJob, state: &mut Option<Arc<ScanState>>) -> Result<AgentResult, CrawlerError> {
+        debug!("📦 Work unit: {} words against {}", job.words.len(), job.params.url);
+
+        let config = Arc::new(job.params.into_config(self.threads, job.agent));
+        let state = match state {
+            Some(state) => Arc::clone(state),
+            None => Arc::clone(state.insert(Arc::new(ScanState {
+                redactor: redact::Redactor::new(&self.redact_patterns)?,
+                plugins: plugin::Plugins::builtin(&config)?,
+                throttle: crate::targets::throttle(&config.target_overrides),
+                bandwidth: config.max_bandwidth.map(|rate| Arc::new(crate::bandwidth::Limiter::new(rate))),
+                audit_log: job.audit.then(audit::AuditLog::kept),
+                ..Default::default()
+            }))),
+        };
+        let client = build_client(&config)?;
+        let before = state.errors.load(Ordering::Relaxed);
+        let findings = scan_entries(config, client, Entries::List(job.words), Arc::clone(&state)).await?;
+        let errors = state.errors.load(Ordering::Relaxed) - before;
+        let audit = state.audit_log.as_ref().map(|log| log.take()).unwrap_or_default();
+        Ok(AgentResult { findings, errors, audit })
+    }
+}
+
+/// A request body, refused with 413 once it outgrows `limit`
+async fn read_body(mut body: Body, limit: usize) -> Result<Vec<u8>, StatusCode> {
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+        if data.len() + chunk.len() > limit {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+fn reply(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(message.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// An agent holding `secret` on a free loopback port
+    async fn agent(secret: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let agent = Agent { threads: 1, secret: secret.to_string(), redact_patterns: Vec::new(), sessions: Default::default() };
+        tokio::spawn(serve(listener, Arc::new(agent)));
+        address
+    }
+
+    fn job() -> AgentJob {
+        let config = FuzzerConfig { url: "http://127.0.0.1:9/".to_string(), ..FuzzerConfig::default() };
+        AgentJob { params: ScanParams::from_config(&config), words: Vec::new(), agent: 1, audit: false }
+    }
+
+    #[tokio::test]
+    async fn coordinators_and_agents_both_prove_the_secret() {
+        let address = agent("s3cret").await;
+        let mut link = Link::open(&address, "s3cret").await.unwrap();
+        assert!(link.run(&job()).await.unwrap().findings.is_empty());
+        assert!(link.run(&job()).await.is_ok());
+
+        let err = Link::open(&address, "guess").await.err().unwrap();
+        assert_eq!(err.to_string(), "the agent doesn't hold the shared secret");
+
+        // A coordinator without the secret can open a session but not use it
+        let mut forged = Link::open(&address, "s3cret").await.unwrap();
+        forged.channel = crypto::Channel::new(&[0u8; 32], true);
+        let err = forged.run(&job()).await.unwrap_err();
+        assert!(err.to_string().starts_with("the agent answered 403"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn request_ids_keep_counting_across_jobs() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            }
+        });
+        let config = FuzzerConfig {
+            url: target,
+            scan_id: "scan-1".to_string(),
+            request_id_header: Some("X-Request-ID".to_string()),
+            ..FuzzerConfig::default()
+        };
+        let job = |words: &[&str]| AgentJob {
+            params: ScanParams::from_config(&config),
+            words: words.iter().map(ToString::to_string).collect(),
+            agent: 3,
+            audit: true,
+        };
+
+        let mut link = Link::open(&agent("s3cret").await, "s3cret").await.unwrap();
+        let mut ids = Vec::new();
+        for words in [["admin", "login"], ["backup", "static"]] {
+            let result = link.run(&job(&words)).await.unwrap();
+            assert_eq!(result.audit.len(), 2);
+            ids.extend(result.audit.iter().map(|line| line.split('\t').nth(1).unwrap().to_string()));
+        }
+        ids.sort();
+        assert_eq!(ids, ["scan-1-a3-000001", "scan-1-a3-000002", "scan-1-a3-000003", "scan-1-a3-000004"]);
+    }
+
+    #[tokio::test]
+    async fn agents_refuse_oversized_handshakes() {
+        let address = agent("s3cret").await;
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let hello = client.post(format!("http://{}/v1/sessions", address)).body(vec![b'a'; MAX_HELLO + 1]);
+        assert_eq!(hello.send().await.unwrap().status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn proofs_depend_on_secret_side_and_nonces() {
+        assert_eq!(prove("key", "agent", "a", "b"), prove("key", "agent", "a", "b"));
+        assert_ne!(prove("key", "agent", "a", "b"), prove("key", "agent", "a", "c"));
+        assert_ne!(prove("key", "agent", "a", "b"), prove("other", "agent", "a", "b"));
+        assert_ne!(prove("key", "agent", "a", "b").as_bytes(), &session_key("key", "a", "b")[..]);
+        assert_eq!(prove("key", "agent", "a", "b").len(), 64);
+    }
+
+    #[test]
+    fn jobs_carry_scan_settings_but_no_local_side_effects() {
+        let config = FuzzerConfig {
+            url: "http://example.com/".to_string(),
+            extensions: vec!["php".to_string()],
+            output: Some("/tmp/results.json".into()),
+            store_responses: Some("/tmp/bodies".into()),
+            max_findings: Some(5),
+            scan_id: "scan-1".to_string(),
+            ..FuzzerConfig::default()
+        };
+        let json = serde_json::to_string(&ScanParams::from_config(&config)).unwrap();
+        assert!(!json.contains("/tmp/"));
+
+        let params: ScanParams = serde_json::from_str(&json).unwrap();
+        let agent = params.into_config(4, 2);
+        assert_eq!(agent.url, "http://example.com/");
+        assert_eq!(agent.extensions, ["php"]);
+        assert_eq!(agent.threads, 4);
+        assert!(agent.silent);
+        assert!(agent.output.is_none() && agent.store_responses.is_none() && agent.max_findings.is_none());
+        assert_eq!(agent.scan_id, "scan-1-a2");
+    }
+}
@@ -0,0 +1,378 @@
+use crate::error::CrawlerError;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+
+/// How long to wait for an upstream answer before giving up on a query
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Upper bound on how long an answer stays cached, whatever its TTL
+const MAX_TTL: Duration = Duration::from_secs(300);
+
+/// Where names are looked up instead of the system resolver
+#[derive(Debug, Clone)]
+pub enum Upstream {
+    /// Plain DNS over UDP
+    Udp(SocketAddr),
+    /// DNS-over-HTTPS (RFC 8484) endpoint
+    Doh(String),
+}
+
+impl Upstream {
+    /// Parse `1.1.1.1`, `[2606:4700::1111]:53`, or an `https://` DoH URL
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if spec.starts_with("https://") {
+            return Ok(Upstream::Doh(spec.to_string()));
+        }
+        if let Ok(addr) = spec.parse::<SocketAddr>() {
+            return Ok(Upstream::Udp(addr));
+        }
+        spec.trim_matches(['[', ']'])
+            .parse::<IpAddr>()
+            .map(|ip| Upstream::Udp(SocketAddr::new(ip, 53)))
+            .map_err(|_| format!("Invalid DNS resolver '{}'. Use an IP[:port] or an https:// DoH URL", spec))
+    }
+}
+
+/// Which of a host's addresses connections go to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressFamily {
+    /// Either, the first answered: IPv6 gets a head start and IPv4 is tried
+    /// alongside when it's slow (happy eyeballs)
+    #[default]
+    Any,
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    /// The local address to bind, which keeps hyper's connector to the
+    /// target's addresses of the same family
+    pub fn local_address(self) -> Option<IpAddr> {
+        match self {
+            AddressFamily::Any => None,
+            AddressFamily::Ipv4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            AddressFamily::Ipv6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+}
+
+/// Resolver shared by every client in the process, caching answers by TTL
+#[derive(Clone)]
+pub struct DnsResolver(Arc<Inner>);
+
+struct Inner {
+    upstream: Upstream,
+    cache: Mutex<HashMap<String, (Vec<IpAddr>, Instant)>>,
+    /// Client for DoH lookups; it resolves the DoH host itself through the system
+    doh_client: reqwest::Client,
+}
+
+static SHARED: OnceLock<DnsResolver> = OnceLock::new();
+
+/// The process-wide resolver for `spec`, created on first use so all
+/// workers and clients share one cache
+pub fn shared(spec: &str) -> Result<DnsResolver, String> {
+    if let Some(resolver) = SHARED.get() {
+        return Ok(resolver.clone());
+    }
+    let resolver = DnsResolver(Arc::new(Inner {
+        upstream: Upstream::parse(spec)?,
+        cache: Mutex::new(HashMap::new()),
+        doh_client: reqwest::Client::builder()
+            .timeout(QUERY_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?,
+    }));
+    Ok(SHARED.get_or_init(|| resolver).clone())
+}
+
+/// Drop every cached answer so the next lookups go upstream again
+pub fn flush() {
+    if let Some(resolver) = SHARED.get() {
+        resolver.0.cache.lock().unwrap().clear();
+    }
+}
+
+impl Inner {
+    async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>, CrawlerError> {
+        if let Some((ips, expires)) = self.cache.lock().unwrap().get(host) {
+            if *expires > Instant::now() {
+                return Ok(ips.clone());
+            }
+        }
+
+        let (v4, v6) = tokio::join!(self.query(host, TYPE_A), self.query(host, TYPE_AAAA));
+        let mut ips = Vec::new();
+        let mut ttl = MAX_TTL;
+        let mut last_error = None;
+        for answer in [v4, v6] {
+            match answer {
+                Ok((found, found_ttl)) => {
+                    if !found.is_empty() {
+                        ttl = ttl.min(found_ttl);
+                    }
+                    ips.extend(found);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        if ips.is_empty() {
+            return Err(last_error.unwrap_or_else(|| format!("no addresses found for {}", host).into()));
+        }
+        self.cache.lock().unwrap().insert(host.to_string(), (ips.clone(), Instant::now() + ttl));
+        Ok(ips)
+    }
+
+    /// Send one question upstream, returning the addresses and the smallest TTL
+    async fn query(&self, host: &str, qtype: u16) -> Result<(Vec<IpAddr>, Duration), CrawlerError> {
+        let id = rand_id();
+        let query = encode_query(id, host, qtype)?;
+        let response = match &self.upstream {
+            Upstream::Udp(server) => {
+                let bind: SocketAddr = if server.is_ipv4() {
+                    (Ipv4Addr::UNSPECIFIED, 0).into()
+                } else {
+                    (Ipv6Addr::UNSPECIFIED, 0).into()
+                };
+                let socket = UdpSocket::bind(bind).await?;
+                socket.send_to(&query, server).await?;
+                let mut buf = vec![0u8; 4096];
+                let (len, _) = tokio::time::timeout(QUERY_TIMEOUT, socket.recv_from(&mut buf))
+                    .await
+                    .map_err(|_| format!("no answer from {} within {}s", server, QUERY_TIMEOUT.as_secs()))??;
+                buf.truncate(len);
+                buf
+            }
+            Upstream::Doh(url) => self.doh_client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/dns-message")
+                .header(reqwest::header::ACCEPT, "application/dns-message")
+                .body(query)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?
+                .to_vec(),
+        };
+        decode_answers(&response, id, qtype)
+    }
+}
+
+impl DnsResolver {
+    /// Addresses of `host`, from the cache or the upstream
+    pub async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>, CrawlerError> {
+        self.0.lookup(host).await
+    }
+}
+
+impl Resolve for DnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = Arc::clone(&self.0);
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let ips = resolver.lookup(&host).await?;
+            let addrs: Addrs = Box::new(ips.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+/// Query ID that doesn't repeat across concurrent lookups
+fn rand_id() -> u16 {
+    let segment = crate::calibrate::random_segment();
+    u16::from_str_radix(&segment[..4], 16).unwrap_or_default()
+}
+
+/// Wire-format query for one name with recursion desired
+fn encode_query(id: u16, host: &str, qtype: u16) -> Result<Vec<u8>, CrawlerError> {
+    let mut packet = Vec::with_capacity(32 + host.len());
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&[0x01, 0x00]); // standard query, RD
+    packet.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]); // one question
+    for label in host.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("invalid hostname {}", host).into());
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+/// Skip a possibly compressed name, returning the offset just past it
+fn skip_name(packet: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *packet.get(pos)?;
+        match len {
+            0 => return Some(pos + 1),
+            // Compression pointer: two bytes and the name ends here
+            l if l & 0xC0 == 0xC0 => return Some(pos + 2),
+            l => pos += 1 + l as usize,
+        }
+    }
+}
+
+fn read_u16(packet: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*packet.get(pos)?, *packet.get(pos + 1)?]))
+}
+
+fn read_u32(packet: &[u8], pos: usize) -> Option<u32> {
+    Some(((read_u16(packet, pos)? as u32) << 16) | read_u16(packet, pos + 2)? as u32)
+}
+
+/// Pull the A/AAAA records out of a response (CNAME chains are answered inline)
+fn decode_answers(packet: &[u8], id: u16, qtype: u16) -> Result<(Vec<IpAddr>, Duration), CrawlerError> {
+    let malformed = || -> CrawlerError { "malformed DNS response".into() };
+    if read_u16(packet, 0).ok_or_else(malformed)? != id {
+        return Err("DNS response ID mismatch".into());
+    }
+    let flags = read_u16(packet, 2).ok_or_else(malformed)?;
+    match flags & 0x000F {
+        0 | 3 => {} // NOERROR, NXDOMAIN (no answers)
+        rcode => return Err(format!("DNS server returned error code {}", rcode).into()),
+    }
+
+    let questions = read_u16(packet, 4).ok_or_else(malformed)?;
+    let answers = read_u16(packet, 6).ok_or_else(malformed)?;
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(packet, pos).ok_or_else(malformed)? + 4;
+    }
+
+    let mut ips = Vec::new();
+    let mut ttl = MAX_TTL;
+    for _ in 0..answers {
+        pos = skip_name(packet, pos).ok_or_else(malformed)?;
+        let rtype = read_u16(packet, pos).ok_or_else(malformed)?;
+        let record_ttl = read_u32(packet, pos + 4).ok_or_else(malformed)?;
+        let rdlength = read_u16(packet, pos + 8).ok_or_else(malformed)? as usize;
+        let rdata = packet.get(pos + 10..pos + 10 + rdlength).ok_or_else(malformed)?;
+        pos += 10 + rdlength;
+
+        let ip = match (rtype, rdata.len()) {
+            (TYPE_A, 4) if qtype == TYPE_A => IpAddr::from(<[u8; 4]>::try_from(rdata).unwrap()),
+            (TYPE_AAAA, 16) if qtype == TYPE_AAAA => IpAddr::from(<[u8; 16]>::try_from(rdata).unwrap()),
+            _ => continue,
+        };
+        ips.push(ip);
+        ttl = ttl.min(Duration::from_secs(record_ttl as u64));
+    }
+    Ok((ips, ttl))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A response to `encode_query`'s question with the given answer records
+    fn response(id: u16, qtype: u16, rcode: u8, answers: &[(u16, u32, &[u8])]) -> Vec<u8> {
+        let mut packet = encode_query(id, "www.example.com", qtype).unwrap();
+        packet[2] = 0x81;
+        packet[3] = 0x80 | rcode;
+        packet[6..8].copy_from_slice(&(answers.len() as u16).to_be_bytes());
+        for (rtype, ttl, rdata) in answers {
+            packet.extend_from_slice(&[0xC0, 0x0C]); // pointer to the question's name
+            packet.extend_from_slice(&rtype.to_be_bytes());
+            packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+            packet.extend_from_slice(&ttl.to_be_bytes());
+            packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            packet.extend_from_slice(rdata);
+        }
+        packet
+    }
+
+    #[test]
+    fn encodes_labels_and_rejects_bad_names() {
+        let packet = encode_query(0xBEEF, "www.example.com.", TYPE_A).unwrap();
+        assert_eq!(&packet[..2], &[0xBE, 0xEF]);
+        assert_eq!(&packet[12..29], b"\x03www\x07example\x03com\x00");
+        assert_eq!(&packet[29..], &[0, 1, 0, 1]);
+        assert!(encode_query(1, "a..b", TYPE_A).is_err());
+        assert!(encode_query(1, &"a".repeat(64), TYPE_A).is_err());
+    }
+
+    #[test]
+    fn decodes_addresses_past_cname_records() {
+        let cname = b"\x03cdn\xC0\x10";
+        let packet = response(7, TYPE_A, 0, &[(5, 600, cname), (TYPE_A, 60, &[10, 0, 0, 1]), (TYPE_A, 30, &[10, 0, 0, 2])]);
+        let (ips, ttl) = decode_answers(&packet, 7, TYPE_A).unwrap();
+        assert_eq!(ips, [IpAddr::from([10, 0, 0, 1]), IpAddr::from([10, 0, 0, 2])]);
+        assert_eq!(ttl, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn decodes_aaaa_and_caps_ttl() {
+        let v6 = Ipv6Addr::LOCALHOST.octets();
+        let packet = response(9, TYPE_AAAA, 0, &[(TYPE_AAAA, 86_400, &v6), (TYPE_A, 60, &[10, 0, 0, 1])]);
+        let (ips, ttl) = decode_answers(&packet, 9, TYPE_AAAA).unwrap();
+        assert_eq!(ips, [IpAddr::V6(Ipv6Addr::LOCALHOST)]);
+        assert_eq!(ttl, MAX_TTL);
+    }
+
+    #[test]
+    fn nxdomain_is_empty_and_other_errors_fail() {
+        assert!(decode_answers(&response(1, TYPE_A, 3, &[]), 1, TYPE_A).unwrap().0.is_empty());
+        assert!(decode_answers(&response(1, TYPE_A, 2, &[]), 1, TYPE_A).is_err());
+        assert!(decode_answers(&response(1, TYPE_A, 0, &[]), 2, TYPE_A).is_err());
+    }
+
+    #[test]
+    fn truncated_packets_are_malformed() {
+        let packet = response(4, TYPE_A, 0, &[(TYPE_A, 60, &[10, 0, 0, 1])]);
+        for len in [0, 5, 20, packet.len() - 1] {
+            assert!(decode_answers(&packet[..len], 4, TYPE_A).is_err(), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn parses_upstreams() {
+        assert!(matches!(Upstream::parse("1.1.1.1"), Ok(Upstream::Udp(addr)) if addr.port() == 53));
+        assert!(matches!(Upstream::parse("[2606:4700::1111]:5353"), Ok(Upstream::Udp(addr)) if addr.port() == 5353));
+        assert!(matches!(Upstream::parse("https://1.1.1.1/dns-query"), Ok(Upstream::Doh(_))));
+        assert!(Upstream::parse("resolver.local").is_err());
+    }
+
+    #[tokio::test]
+    async fn connects_only_to_the_chosen_family() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.read(&mut [0u8; 1024]).await;
+                let _ = socket.write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n").await;
+            }
+        });
+        let client = |family: AddressFamily| {
+            let builder = reqwest::Client::builder();
+            match family.local_address() {
+                Some(address) => builder.local_address(address),
+                None => builder,
+            }
+            .build()
+            .unwrap()
+        };
+
+        for family in [AddressFamily::Any, AddressFamily::Ipv4] {
+            let response = client(family).get(&url).send().await.unwrap();
+            assert!(response.remote_addr().unwrap().is_ipv4());
+        }
+        assert!(client(AddressFamily::Ipv6).get(&url).send().await.is_err());
+    }
+}
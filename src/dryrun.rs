@@ -0,0 +1,136 @@
+use crate::engine::{candidates, request_key};
+use crate::error::CrawlerError;
+use crate::exposure;
+use crate::scope::Scope;
+use crate::wordlist::Entries;
+use crate::FuzzerConfig;
+use std::collections::HashSet;
+use std::io::Write;
+
+
+/// Requests listed by a dry run
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Plan {
+    pub requests: u64,
+    /// Generated but outside the scope, so never sent
+    pub out_of_scope: u64,
+    /// Generated again after an identical request, so never sent
+    pub duplicates: u64,
+}
+
+/// Write every request the scan would send to its targets, one per line,
+/// without sending any. Lines are `METHOD URL`, followed by tab-separated
+/// fuzzed headers and body for request templates. Directories found by
+/// recursion aren't known until a real scan runs.
+pub fn write(config: &FuzzerConfig, entries: &Entries, out: &mut dyn Write) -> Result<Plan, CrawlerError> {
+    let scope = Scope::new(&config.scope_include, &config.scope_exclude, &config.blocklist)?;
+    let roots = match config.targets.is_empty() {
+        true => vec![config.url.clone()],
+        false => config.targets.clone(),
+    };
+
+    let mut plan = Plan::default();
+    let mut requested = HashSet::new();
+    let mut emit = |line: String, url: &str, key: u64| -> std::io::Result<()> {
+        if scope.check(url).is_err() {
+            plan.out_of_scope += 1;
+            return Ok(());
+        }
+        if !requested.insert(key) {
+            plan.duplicates += 1;
+            return Ok(());
+        }
+        plan.requests += 1;
+        writeln!(out, "{}", line)
+    };
+
+    let method = config.request_template.as_ref().map_or(config.method.as_str(), |t| t.method.as_str());
+    for root in &roots {
+        if config.vcs_checks {
+            for check in exposure::CHECKS {
+                let url = format!("{}/{}", root.trim_end_matches('/'), check.path);
+                emit(format!("GET {}", url), &url, request_key("GET", &url, None))?;
+            }
+        }
+        for path in entries.iter() {
+            let mut candidates = candidates(config, root, &path);
+            candidates.sort();
+            for (url, payload) in candidates {
+                let key = request_key(method, &url, payload.as_deref());
+                emit(line(config, &url, payload.as_deref()), &url, key)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(plan)
+}
+
+/// `METHOD URL`, plus whatever the template fills with the payload
+fn line(config: &FuzzerConfig, url: &str, payload: Option<&str>) -> String {
+    let (Some(template), Some(payload)) = (&config.request_template, payload) else {
+        return format!("{} {}", config.method, url);
+    };
+    let mut line = format!("{} {}", template.method, url);
+    // Headers the payload changed; the rest are the same on every request
+    for (name, value) in template.headers_for(payload) {
+        if !template.headers.iter().any(|(n, v)| *n == name && *v == value) {
+            line.push_str(&format!("\t{}: {}", name, value));
+        }
+    }
+    if let Some(body) = template.body_for(payload) {
+        line.push('\t');
+        line.push_str(&body.replace('\r', "\\r").replace('\n', "\\n"));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::RequestTemplate;
+
+    fn list(config: &FuzzerConfig, words: &[&str]) -> (String, Plan) {
+        let entries = Entries::List(words.iter().map(|w| w.to_string()).collect());
+        let mut out = Vec::new();
+        let plan = write(config, &entries, &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), plan)
+    }
+
+    #[test]
+    fn lists_every_url_with_extensions_across_targets() {
+        let config = FuzzerConfig {
+            url: "http://10.0.0.1/".to_string(),
+            targets: vec!["http://10.0.0.1/".to_string(), "http://10.0.0.2:8080/".to_string()],
+            extensions: vec!["php".to_string()],
+            scope_exclude: vec!["*/backup*".to_string()],
+            ..Default::default()
+        };
+        let (out, plan) = list(&config, &["admin", "backup", "/admin/", "admin//"]);
+        assert_eq!(out, "GET http://10.0.0.1/admin\nGET http://10.0.0.1/admin.php\n\
+                         GET http://10.0.0.2:8080/admin\nGET http://10.0.0.2:8080/admin.php\n");
+        assert_eq!(plan, Plan { requests: 4, out_of_scope: 4, duplicates: 8 });
+    }
+
+    #[test]
+    fn keys_equivalent_requests_alike() {
+        let key = |url: &str| request_key("GET", url, None);
+        assert_eq!(key("http://example.com/a%2fb"), key("HTTP://Example.COM:80/a%2Fb#top"));
+        assert_eq!(key("http://example.com/x/../admin"), key("http://example.com/admin"));
+        assert_ne!(key("http://example.com/admin"), key("http://example.com/admin/"));
+        assert_ne!(key("http://example.com/admin"), request_key("POST", "http://example.com/admin", None));
+        assert_ne!(request_key("GET", "http://example.com/", Some("a")), request_key("GET", "http://example.com/", Some("b")));
+    }
+
+    #[test]
+    fn shows_what_templates_fill() {
+        let raw = "POST /api/FUZZ HTTP/1.1\nHost: example.com\nX-Role: FUZZ\nAccept: */*\n\nname=FUZZ\n&x=1";
+        let config = FuzzerConfig {
+            url: "http://example.com/".to_string(),
+            request_template: Some(RequestTemplate::parse(raw, Some("http://example.com")).unwrap()),
+            ..Default::default()
+        };
+        let (out, plan) = list(&config, &["admin"]);
+        assert_eq!(out, "POST http://example.com/api/admin\tX-Role: admin\tname=admin\\n&x=1\n");
+        assert_eq!(plan.requests, 1);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::har::Sent;
+use crate::redact::Redactor;
+use reqwest::header::HeaderMap;
+use reqwest::Version;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Requests dumped by default at `-vvv`
+pub const DEFAULT_SAMPLE: &str = "matches";
+
+/// Which requests `-vvv` prints in full, to see what a scan actually sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sample {
+    /// Those that became findings
+    Matches,
+    All,
+    /// One in every N sent
+    Every(u64),
+}
+
+impl FromStr for Sample {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        match spec {
+            "matches" => Ok(Sample::Matches),
+            "all" => Ok(Sample::All),
+            _ => match spec.parse::<u64>() {
+                Ok(0) | Err(_) => Err(format!("Invalid --dump '{}': use matches, all, or N for one request in every N", spec)),
+                Ok(n) => Ok(Sample::Every(n)),
+            },
+        }
+    }
+}
+
+impl Sample {
+    /// Whether the `seq`th request sent (counting from 0) is dumped as soon
+    /// as it's answered, rather than once it turns out to be a finding
+    pub fn on_send(self, seq: u64) -> bool {
+        match self {
+            Sample::Matches => false,
+            Sample::All => true,
+            Sample::Every(n) => seq.is_multiple_of(n),
+        }
+    }
+}
+
+/// How the target answered a dumped request
+pub enum Answer<'a> {
+    Head { version: Version, status: u16, headers: &'a HeaderMap },
+    Failed(&'a str),
+}
+
+/// The request head and body after `> `, the response head after `< `, as
+/// `curl -v` shows an exchange
+pub fn render(sent: &Sent, answer: Answer, redactor: &Redactor) -> String {
+    let mut lines: Vec<String> = sent.request_line().lines().map(|line| format!("> {}", line)).collect();
+    for (name, value) in &sent.headers {
+        lines.push(format!("> {}: {}", name, String::from_utf8_lossy(value.as_bytes())));
+    }
+    if let Some(body) = sent.body.as_deref().filter(|body| !body.is_empty()) {
+        lines.push(">".to_string());
+        match std::str::from_utf8(body) {
+            Ok(text) => lines.extend(text.lines().map(|line| format!("> {}", line))),
+            Err(_) => lines.push(format!("> [{} bytes of binary body]", body.len())),
+        }
+    }
+    match answer {
+        Answer::Head { version, status, headers } => {
+            let reason = reqwest::StatusCode::from_u16(status).ok()
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or_default();
+            lines.push(format!("< {:?} {} {}", version, status, reason));
+            for (name, value) in headers {
+                lines.push(format!("< {}: {}", name, String::from_utf8_lossy(value.as_bytes())));
+            }
+        }
+        Answer::Failed(error) => lines.push(format!("< {}", error)),
+    }
+    redactor.redact(&lines.join("\n")).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_both_sides_of_an_exchange() {
+        let request = reqwest::Client::new()
+            .post("http://t:8080/login?next=/")
+            .header("x-test", "1")
+            .body("user=admin")
+            .build()
+            .unwrap();
+        let sent = Sent::new(HeaderMap::new(), &request);
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "text/html".parse().unwrap());
+        let answer = Answer::Head { version: Version::HTTP_11, status: 404, headers: &headers };
+        assert_eq!(
+            render(&sent, answer, &Redactor::default()),
+            "> POST /login?next=/ HTTP/1.1\n> Host: t:8080\n> x-test: 1\n>\n> user=admin\n< HTTP/1.1 404 Not Found\n< content-type: text/html"
+        );
+
+        assert_eq!("matches".parse(), Ok(Sample::Matches));
+        assert!("0".parse::<Sample>().is_err());
+        let sample: Sample = "3".parse().unwrap();
+        assert_eq!((0..7).filter(|seq| sample.on_send(*seq)).count(), 3);
+    }
+}
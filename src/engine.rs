@@ -0,0 +1,1271 @@
+use crate::bandwidth;
+use crate::buckets;
+use crate::calibrate::{self, Baseline};
+use crate::chat;
+use crate::cors;
+use crate::csrf;
+use crate::dump;
+use crate::error::{self, CrawlerError};
+use crate::exposure;
+use crate::methods;
+use crate::frontier::{self, Frontier, Pending};
+use crate::har;
+use crate::plugin;
+use crate::redirect;
+use crate::review;
+use crate::sink::Recorded;
+use crate::spray::{self, Attempt, Outcome};
+use crate::stealth;
+use crate::targets;
+use crate::template::RequestTemplate;
+use crate::wordlist::Entries;
+use crate::{
+    build_client, content_type_matches, detect_language, generate_urls, is_session_header, scope, secrets,
+    severity, simhash, store, throttle, Differential, Finding, FuzzerConfig, ScanState, Variant,
+};
+use crate::term;
+use colored::*;
+use bytes::{Bytes, BytesMut};
+use reqwest::{Client, Response, Url};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::task;
+use tracing::{debug, info, trace, warn};
+
+
+/// Second client used to compare every candidate under another identity
+struct DifferentialClient {
+    primary_label: String,
+    label: String,
+    client: Client,
+}
+
+/// Client that sends every request from a foreign `Origin`, for `--cors`
+struct CorsClient {
+    origin: String,
+    client: Client,
+}
+
+/// Everything a worker task needs to probe URLs; cheap to clone per task
+#[derive(Clone)]
+struct Worker {
+    config: Arc<FuzzerConfig>,
+    client: Client,
+    state: Arc<ScanState>,
+    scope: Arc<scope::Scope>,
+    differential: Option<Arc<DifferentialClient>>,
+    cors: Option<Arc<CorsClient>>,
+    /// Client that doesn't follow redirects, for `--open-redirects`
+    redirect_client: Option<Client>,
+    /// The scan's configuration once per `--probe-methods` method
+    method_configs: Arc<Vec<FuzzerConfig>>,
+    sprayer: Option<Arc<spray::Sprayer>>,
+    scorer: Arc<severity::Scorer>,
+    found_paths: Arc<Mutex<Vec<Finding>>>,
+    /// Directory currently being fuzzed
+    base_url: String,
+    /// Soft-404 fingerprint of `base_url`, when calibration is enabled
+    baseline: Option<Arc<Baseline>>,
+    /// Directories found by this pass with the confidence of their hit
+    discovered_dirs: Arc<Mutex<Vec<(String, f64)>>>,
+}
+
+/// Host and port a URL is sent to, used to key per-host state
+pub fn host_key(url: &str) -> String {
+    Url::parse(url)
+        .map(|u| format!(
+            "{}:{}",
+            u.host_str().unwrap_or_default(),
+            u.port_or_known_default().unwrap_or_default()
+        ))
+        .unwrap_or_default()
+}
+
+/// A severity in its color: red for critical and high, yellow for medium
+pub fn severity_color(severity: secrets::Severity) -> ColoredString {
+    match severity {
+        secrets::Severity::Critical | secrets::Severity::High => severity.to_string().red().bold(),
+        secrets::Severity::Medium => severity.to_string().yellow(),
+        secrets::Severity::Low => severity.to_string().normal(),
+        secrets::Severity::Info => severity.to_string().dimmed(),
+    }
+}
+
+/// Identity of a request for scan-wide deduplication: the method, the URL
+/// as it goes on the wire (scheme and host case, default port, dot segments
+/// and escape case normalized, fragment dropped) and the template payload.
+/// Hashed, so long scans keep eight bytes per request rather than the URL.
+pub fn request_key(method: &str, url: &str, payload: Option<&str>) -> u64 {
+    let normalized = match Url::parse(url) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            uppercase_escapes(parsed.as_str())
+        }
+        Err(_) => url.to_string(),
+    };
+    let mut hasher = DefaultHasher::new();
+    (method, normalized, payload).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// `%2f` and `%2F` are the same byte
+fn uppercase_escapes(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+    let mut rest = url;
+    while let Some(at) = rest.find('%') {
+        out.push_str(&rest[..at]);
+        let escape = rest.get(at..at + 3).filter(|e| e[1..].bytes().all(|b| b.is_ascii_hexdigit()));
+        let taken = escape.map_or("%".to_string(), str::to_ascii_uppercase);
+        rest = &rest[at + taken.len()..];
+        out.push_str(&taken);
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Final URL of a response that was redirected away from the requested
+/// resource (the directory form of the same path doesn't count)
+fn redirect_target(test_url: &str, final_url: &Url) -> Option<String> {
+    let landed = final_url.as_str().trim_end_matches('/');
+    (landed != test_url.trim_end_matches('/')).then(|| final_url.to_string())
+}
+
+/// A response header's value, when it is text
+fn header_text(headers: &reqwest::header::HeaderMap, name: reqwest::header::HeaderName) -> Option<String> {
+    headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
+
+/// Whether two responses differ enough to report. Sizes within 5% are
+/// treated as equal so timestamps and CSRF tokens don't trigger noise.
+fn responses_differ(a: &Variant, b: &Variant) -> bool {
+    if a.status != b.status || a.redirected_to != b.redirected_to {
+        return true;
+    }
+    let (small, large) = (a.size.min(b.size), a.size.max(b.size));
+    large - small > (large / 20).max(32)
+}
+
+/// Shortest parameter value whose echo in a response counts as reflection
+const MIN_REFLECTION_LEN: usize = 3;
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+/// Read a response body, giving up if the server goes quiet for longer
+/// than the read timeout between chunks. Bodies over `--max-body-size`
+/// are cut off there, which is reported alongside the bytes read.
+pub async fn read_body(config: &FuzzerConfig, mut response: Response) -> Result<(Bytes, bool), CrawlerError> {
+    let idle = Duration::from_secs(config.read_timeout);
+    let limit = config.max_body_size.map_or(usize::MAX, |max| max as usize);
+    let bandwidth = response.extensions().get::<Arc<bandwidth::Limiter>>().cloned();
+    let mut body = BytesMut::new();
+    loop {
+        match tokio::time::timeout(idle, response.chunk()).await {
+            Ok(Ok(Some(chunk))) => {
+                if let Some(bandwidth) = &bandwidth {
+                    bandwidth.consume(chunk.len()).await;
+                }
+                if body.len() + chunk.len() > limit {
+                    // Dropping the response closes the connection mid-stream
+                    body.extend_from_slice(&chunk[..limit - body.len()]);
+                    return Ok((body.freeze(), true));
+                }
+                body.extend_from_slice(&chunk);
+            }
+            Ok(Ok(None)) => return Ok((body.freeze(), false)),
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => {
+                let host = error::authority(response.url());
+                return Err(CrawlerError::Http { host, reason: format!("body stalled for {}s", idle.as_secs()) });
+            }
+        }
+    }
+}
+
+/// Send one request with request IDs, throttling, and audit logging applied.
+/// A refused connection or a timeout is tried again up to `--retries`
+/// times, backing off in between. A response showing the login session
+/// expired triggers a re-login and one retry with the fresh session.
+/// Cancelling the scan drops the request wherever it is.
+pub async fn send_request(
+    config: &FuzzerConfig,
+    state: &ScanState,
+    client: &Client,
+    url: &str,
+    payload: Option<&str>,
+) -> Result<Response, CrawlerError> {
+    tokio::select! {
+        result = send_with_retries(config, state, client, url, payload) => result,
+        _ = state.cancellation.cancelled() => Err(CrawlerError::Cancelled),
+    }
+}
+
+async fn send_with_retries(
+    config: &FuzzerConfig,
+    state: &ScanState,
+    client: &Client,
+    url: &str,
+    payload: Option<&str>,
+) -> Result<Response, CrawlerError> {
+    let seen = state.session.as_ref().map(|session| session.generation());
+    let mut attempt = 0;
+    let response = loop {
+        match send_once(config, state, client, url, payload).await {
+            Err(e) if e.is_retryable() && attempt < config.retries => {
+                attempt += 1;
+                debug!("🔁 Retrying {} ({} of {}): {}", state.redactor.redact(url), attempt, config.retries, e);
+                tokio::time::sleep(RETRY_BACKOFF * 2u32.pow(attempt - 1)).await;
+            }
+            result => break result?,
+        }
+    };
+    if let (Some(session), Some(seen)) = (&state.session, seen) {
+        if session.expired(&response) {
+            match session.refresh(seen).await {
+                Ok(true) => {
+                    let retried = send_once(config, state, client, url, payload).await?;
+                    if session.expired(&retried) {
+                        session.back_off();
+                    }
+                    return Ok(retried);
+                }
+                Ok(false) => {}
+                Err(e) => warn!("🔐 Re-login failed: {}", state.redactor.redact(&e.to_string())),
+            }
+        }
+    }
+    Ok(response)
+}
+
+/// Wait before the first retry of a failed request, doubled for each after it
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+async fn send_once(
+    config: &FuzzerConfig,
+    state: &ScanState,
+    client: &Client,
+    url: &str,
+    payload: Option<&str>,
+) -> Result<Response, CrawlerError> {
+    let mut request = client.request(config.method.clone(), url);
+
+    // Cookies picked up during the scan, sent along with any `-b` cookies
+    let mut cookies = Vec::new();
+
+    // Raw request templates carry their own headers and body, with a fresh
+    // anti-CSRF token where they ask for one
+    if let (Some(template), Some(payload)) = (&config.request_template, payload) {
+        request = match &state.csrf {
+            Some(csrf) => {
+                let token = csrf.token(config, state, client).await
+                    .map_err(|e| CrawlerError::Http { host: host_key(url), reason: e.to_string() })?;
+                cookies.push(token.cookies);
+                csrf::inject(template, &token.value).apply(request, payload)
+            }
+            None => template.apply(request, payload),
+        };
+    }
+
+    // Logged-in session cookies and token, refreshed as they expire
+    if let Some(session) = &state.session {
+        let credentials = session.credentials();
+        cookies.push(credentials.cookies);
+        if let Some((name, value)) = credentials.token {
+            request = request.header(name, value);
+        }
+    }
+
+    // Headers the targets file sets for this target
+    if let Some(target) = targets::override_for(&config.target_overrides, url) {
+        for (name, value) in &target.headers {
+            request = request.header(name, value);
+        }
+    }
+
+    // These replace the client's default Cookie header, so it is repeated
+    cookies.retain(|cookie| !cookie.is_empty());
+    if !cookies.is_empty() {
+        let all: Vec<&str> = config.cookie.as_deref().into_iter()
+            .chain(cookies.iter().map(String::as_str))
+            .collect();
+        request = request.header(reqwest::header::COOKIE, all.join("; "));
+    }
+
+    // Stamp the request with a sequential ID for log correlation
+    let request_id = config.request_id_header.as_ref()
+        .map(|_| state.next_request_id(&config.scan_id));
+    if let (Some(header), Some(id)) = (&config.request_id_header, &request_id) {
+        request = request.header(header.as_str(), id.as_str());
+    }
+
+    // Respect any per-host slowdown triggered by rate limiting or set for
+    // the target, and look less regular under `--stealth`
+    let host = host_key(url);
+    state.throttle.wait(&host).await;
+    if config.stealth.is_some() {
+        tokio::time::sleep(stealth::jitter()).await;
+    }
+
+    // Plugins see (and may change) the request last
+    let mut request = request.build()?;
+    if let (Some(previous), None) = (&state.previous, payload) {
+        previous.condition(url, request.headers_mut());
+    }
+    state.plugins.on_request(&mut request);
+
+    // Keep what went out when findings are archived with their requests,
+    // or dumped under -vvv
+    let sent = (state.har.is_some() || state.warc.is_some() || config.dump.is_some())
+        .then(|| har::Sent::new(crate::default_headers(config), &request));
+
+    // Time to first byte is bounded by connecting plus one read interval
+    let first_byte = Duration::from_secs(config.connect_timeout + config.read_timeout);
+    let mut result = match tokio::time::timeout(first_byte, client.execute(request)).await {
+        Ok(sent) => sent.map_err(CrawlerError::from),
+        Err(_) => Err(CrawlerError::Timeout { host: host_key(url), after: Some(first_byte.as_secs()) }),
+    };
+    if let (Some(sample), Some(sent)) = (config.dump, &sent) {
+        if sample.on_send(state.dump_seq.fetch_add(1, Ordering::Relaxed)) {
+            let failure = result.as_ref().err().map(ToString::to_string);
+            let answer = match (&result, &failure) {
+                (Ok(response), _) => dump::Answer::Head {
+                    version: response.version(),
+                    status: response.status().as_u16(),
+                    headers: response.headers(),
+                },
+                (Err(_), failure) => dump::Answer::Failed(failure.as_deref().unwrap_or_default()),
+            };
+            trace!("🔎 {}\n{}", state.redactor.redact(url), dump::render(sent, answer, &state.redactor));
+        }
+    }
+    if let (Ok(response), Some(sent)) = (&mut result, sent) {
+        response.extensions_mut().insert(sent);
+    }
+    // Reading the body draws on the scan's download budget
+    if let (Ok(response), Some(bandwidth)) = (&mut result, &state.bandwidth) {
+        response.extensions_mut().insert(Arc::clone(bandwidth));
+    }
+
+    if config.adaptive_throttle {
+        if let Ok(response) = &result {
+            let retry_after = response.headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(throttle::parse_retry_after);
+            let adaptation = state.throttle
+                .record(&host, response.status().as_u16(), retry_after);
+            if let Some(message) = adaptation {
+                info!("🐢 {}", message);
+            }
+        }
+    }
+
+    match &result {
+        Ok(response) => state.metrics.response(response.status().as_u16()),
+        Err(e) => state.metrics.error(e.kind()),
+    }
+
+    // Dual-stack hosts can answer differently over IPv4 and IPv6; which
+    // address a host answers from is worth saying once, not per request
+    if let Some(address) = result.as_ref().ok().and_then(Response::remote_addr) {
+        match state.connected(&host, address.ip()) {
+            None => info!("🔌 {} answered from {}", host, address.ip()),
+            Some(previous) if previous != address.ip() => {
+                debug!("🔌 {} answered from {} (was {})", host, address.ip(), previous)
+            }
+            Some(_) => {}
+        }
+    }
+
+    let outcome = match &result {
+        Ok(response) => response.status().as_u16().to_string(),
+        Err(e) => format!("error: {}", e),
+    };
+    trace!("{} {} → {}", config.method, state.redactor.redact(url), state.redactor.redact(&outcome));
+
+    if let Some(audit_log) = &state.audit_log {
+        audit_log.record(
+            request_id.as_deref(),
+            &config.method,
+            &state.redactor.redact(url),
+            &state.redactor.redact(&outcome),
+        );
+    }
+
+    result
+}
+
+impl Worker {
+    fn status_matches(&self, status: u16) -> bool {
+        self.config.status_codes.is_empty() || self.config.status_codes.contains(&status)
+    }
+
+    /// Send one request with request IDs, throttling, and audit logging applied
+    async fn send(&self, client: &Client, url: &str, payload: Option<&str>) -> Result<Response, CrawlerError> {
+        send_request(&self.config, &self.state, client, url, payload).await
+    }
+
+    /// Fingerprint how a directory answers for paths that cannot exist
+    async fn calibrate(&self, base_url: &str) -> Option<Baseline> {
+        let base = base_url.trim_end_matches('/');
+        let suffixes = ["", ".html", "/"];
+        let mut samples = Vec::new();
+
+        for suffix in suffixes.iter().take(calibrate::SAMPLE_COUNT) {
+            // Templates are calibrated by filling the marker with nonsense
+            let (url, payload) = match &self.config.request_template {
+                Some(template) => {
+                    // Path-like suffixes only make sense where the payload lands in the URL
+                    let suffix = if template.url.contains(crate::template::MARKER) { suffix } else { "" };
+                    let payload = template.uniform_payload(&format!("{}{}", calibrate::random_segment(), suffix));
+                    (template.url_for(&payload), Some(payload))
+                }
+                None => (format!("{}/{}{}", base, calibrate::random_segment(), suffix), None),
+            };
+            if self.scope.check(&url).is_err() {
+                continue;
+            }
+            if let Ok(response) = self.send(&self.client, &url, payload.as_deref()).await {
+                let status = response.status().as_u16();
+                let size = read_body(&self.config, response).await.map(|(b, _)| b.len() as u64).unwrap_or_default();
+                samples.push(Variant { label: "baseline".to_string(), status, size, redirected_to: None });
+            }
+        }
+
+        if samples.is_empty() {
+            None
+        } else {
+            Some(Baseline::new(samples))
+        }
+    }
+
+    /// Whether a followed response landed on the directory form of the URL
+    fn is_directory(test_url: &str, final_url: &Url) -> bool {
+        test_url.ends_with('/') || final_url.as_str() == format!("{}/", test_url)
+    }
+
+    /// Request a single candidate URL and record it if it matches
+    async fn probe(&self, test_url: &str, payload: Option<&str>) {
+        let config = &self.config;
+        let state = &self.state;
+
+        // Never send anything outside the engagement scope
+        if let Err(reason) = self.scope.check(test_url) {
+            debug!("🚧 Skipping {}: {}", state.redactor.redact(test_url), reason);
+            return;
+        }
+
+        // Entries, extensions, mutations and recursion can all produce the same request
+        let method = config.request_template.as_ref().map_or(config.method.as_str(), |t| t.method.as_str());
+        if !state.first_request(request_key(method, test_url, payload)) {
+            trace!("Skipping {}: already requested", state.redactor.redact(test_url));
+            return;
+        }
+
+        // Hosts that stopped answering aren't worth a timeout per remaining word
+        let host = host_key(test_url);
+        if state.host_dead(&host) {
+            state.skipped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        // Hold a per-host slot for the whole probe, body reads included
+        let slot = match config.max_connections_per_host {
+            Some(limit) => state.host_slot(&host, limit).acquire_owned().await.ok(),
+            None => None,
+        };
+        if slot.is_some() && state.host_dead(&host) {
+            state.skipped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let result = self.send(&self.client, test_url, payload).await;
+        let failed = match &result {
+            Ok(_) => Some(false),
+            Err(e) => e.is_host_failure().then_some(true),
+        };
+        if let Some(run) = failed.and_then(|failed| state.record_host_outcome(&host, failed, config.host_error_threshold)) {
+            warn!("⚡ {} failed {} times in a row; skipping its remaining requests", host, run);
+        }
+        let response = match result {
+            Ok(response) => response,
+            // A bucket host that doesn't resolve is a bucket that doesn't exist
+            Err(CrawlerError::Dns { .. } | CrawlerError::Connect { .. }) if config.buckets => {
+                trace!("{} does not resolve", state.redactor.redact(test_url));
+                return;
+            }
+            Err(e) => {
+                state.errors.fetch_add(1, Ordering::Relaxed);
+                let message = format!("{} - {}", test_url, e);
+                warn!("❌ Error checking: {}", state.redactor.redact(&message));
+                return;
+            }
+        };
+
+        let status = response.status().as_u16();
+        let headers = response.headers().clone();
+        let final_url = response.url().clone();
+        let version = response.version();
+        let sent = response.extensions().get::<har::Sent>().cloned();
+
+        // Unchanged since the previous scan: its finding stands as it was
+        if status == 304 && payload.is_none() {
+            if let Some(finding) = state.previous.as_ref().and_then(|previous| previous.unchanged(test_url)) {
+                trace!("{} not modified since the previous scan", state.redactor.redact(test_url));
+                state.unchanged.fetch_add(1, Ordering::Relaxed);
+                if config.recursion_depth > 0 && Self::is_directory(test_url, &final_url) {
+                    let score = frontier::confidence(finding.status, &finding.tags);
+                    self.discovered_dirs.lock().await.push((final_url.to_string(), score));
+                }
+                self.record(finding, true, None).await;
+                return;
+            }
+        }
+
+        // Differential scans only report candidates whose responses diverge
+        if self.differential.is_none() && !self.status_matches(status) {
+            trace!("{} filtered by status {}", state.redactor.redact(test_url), status);
+            return;
+        }
+
+        // Content-Type narrows findings to what's being hunted: JSON APIs,
+        // files rather than the HTML catch-all page, ...
+        let content_type = headers.get(reqwest::header::CONTENT_TYPE).and_then(|ct| ct.to_str().ok());
+        if (!config.match_content_types.is_empty() && !content_type_matches(&config.match_content_types, content_type))
+            || content_type_matches(&config.filter_content_types, content_type)
+        {
+            trace!("{} filtered by content type {:?}", state.redactor.redact(test_url), content_type);
+            return;
+        }
+
+        // Only pull the body when something needs to look at it
+        let is_html = headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|ct| ct.to_str().ok())
+            .is_some_and(|ct| ct.contains("text/html"));
+        let needs_body = (config.detect_language && is_html)
+            || config.store_responses.is_some()
+            || state.plugins.needs_body()
+            || config.snippet_bytes.is_some()
+            || self.differential.is_some()
+            || self.baseline.is_some()
+            || config.unique
+            || config.fuzz_param.is_some()
+            || config.buckets
+            || state.har.is_some()
+            || state.warc.is_some();
+        let (body, truncated) = if needs_body {
+            read_body(config, response).await.unwrap_or_default()
+        } else {
+            Default::default()
+        };
+
+        // Parameter fuzzing: a payload echoed back is worth a look even when
+        // the page is otherwise unchanged (very short values echo by chance)
+        let reflected = config.fuzz_param.is_some()
+            && payload.is_some_and(|p| p.len() >= MIN_REFLECTION_LEN && contains(&body, p.as_bytes()));
+
+        // Drop responses matching this directory's not-found fingerprint
+        if let Some(baseline) = &self.baseline {
+            if baseline.matches(status, body.len() as u64) && !reflected {
+                debug!("🧱 Soft-404 filtered: {} ({} B)", state.redactor.redact(test_url), body.len());
+                return;
+            }
+        }
+
+        let mut tags = Vec::new();
+        let mut variants = Vec::new();
+        if config.buckets {
+            match buckets::classify(test_url, status, &body) {
+                Some(tag) => tags.push(tag),
+                None => {
+                    trace!("{} is not a bucket ({})", state.redactor.redact(test_url), status);
+                    return;
+                }
+            }
+        }
+        if reflected {
+            tags.push("reflected".to_string());
+        }
+        if let Some(baseline) = self.baseline.as_ref().filter(|_| config.fuzz_param.is_some()) {
+            if !baseline.has_status(status) {
+                tags.push("status-changed".to_string());
+            } else if !baseline.matches(status, body.len() as u64) {
+                tags.push("size-changed".to_string());
+            }
+        }
+        if truncated {
+            debug!("✂️  Body truncated at {} B: {}", body.len(), state.redactor.redact(test_url));
+            tags.push("truncated".to_string());
+        }
+
+        // Compare against the alternate identity
+        if let Some(diff) = &self.differential {
+            let primary = Variant {
+                label: diff.primary_label.clone(),
+                status,
+                size: body.len() as u64,
+                redirected_to: redirect_target(test_url, &final_url),
+            };
+            let alternate = match self.send(&diff.client, test_url, payload).await {
+                Ok(alt) => {
+                    let alt_status = alt.status().as_u16();
+                    let redirected_to = redirect_target(test_url, alt.url());
+                    let (alt_body, _) = read_body(config, alt).await.unwrap_or_default();
+                    Variant {
+                        label: diff.label.clone(),
+                        status: alt_status,
+                        size: alt_body.len() as u64,
+                        redirected_to,
+                    }
+                }
+                Err(e) => {
+                    state.errors.fetch_add(1, Ordering::Relaxed);
+                    let message = format!("{} - {}", test_url, e);
+                    warn!("❌ Differential request failed: {}", state.redactor.redact(&message));
+                    return;
+                }
+            };
+
+            let interesting = self.status_matches(primary.status) || self.status_matches(alternate.status);
+            if !interesting || !responses_differ(&primary, &alternate) {
+                return;
+            }
+            tags.push("differential".to_string());
+
+            // Content served without a session that differs from the logged-in view
+            // is the classic broken-access-control signal
+            if matches!(config.differential, Some(Differential::Auth))
+                && (200..300).contains(&alternate.status)
+                && alternate.redirected_to.is_none()
+            {
+                tags.push("unauthenticated-access".to_string());
+            }
+            variants = vec![primary, alternate];
+        }
+
+        // Tag HTML hits with their natural language
+        if config.detect_language && is_html {
+            if let Some(lang) = detect_language(&String::from_utf8_lossy(&body)) {
+                tags.push(format!("lang:{}", lang));
+            }
+        }
+
+        let mut finding = Finding {
+            url: test_url.to_string(),
+            payload: payload.map(str::to_string),
+            status,
+            size: Some(body.len() as u64),
+            severity: Default::default(),
+            score: 0,
+            content_type: content_type.map(str::to_string),
+            tags,
+            body_file: None,
+            screenshot: None,
+            secrets: Vec::new(),
+            variants,
+            simhash: None,
+            duplicates: 0,
+            verdict: None,
+            notes: Vec::new(),
+            title: None,
+            etag: header_text(&headers, reqwest::header::ETAG),
+            last_modified: header_text(&headers, reqwest::header::LAST_MODIFIED),
+            header_issues: Vec::new(),
+            methods: Vec::new(),
+            credentials: None,
+        };
+
+        // Secrets, titles and any other analysis passes, then `--script` predicates
+        let exchange = plugin::Exchange { headers: &headers, body: &body };
+        state.plugins.on_response(&exchange, &mut finding);
+        if !state.plugins.matches(&exchange, &finding) {
+            trace!("{} filtered by a plugin", state.redactor.redact(test_url));
+            return;
+        }
+
+        // Ask again from a foreign origin and see whether it would be let in
+        if let Some(cors) = &self.cors {
+            match self.send(&cors.client, test_url, payload).await {
+                Ok(response) => finding.tags.extend(cors::assess(&cors.origin, response.headers())),
+                Err(e) => debug!("CORS request failed: {}", state.redactor.redact(&format!("{} - {}", test_url, e))),
+            }
+        }
+
+        // A redirect whose target can be set from the query string
+        let landed = header_text(&headers, reqwest::header::LOCATION).or_else(|| redirect_target(test_url, &final_url));
+        if let (Some(client), Some(landed), None) = (&self.redirect_client, &landed, payload) {
+            if let Some(param) = self.open_redirect(client, test_url, landed).await {
+                finding.tags.push(format!("open-redirect:{}", param));
+            }
+        }
+
+        // How the same URL answers other methods
+        if payload.is_none() && !self.method_configs.is_empty() {
+            for method_config in self.method_configs.iter() {
+                match send_request(method_config, state, &self.client, test_url, None).await {
+                    Ok(response) => finding.methods.push(methods::MethodResponse::new(&method_config.method, &response)),
+                    Err(e) => debug!("{} request failed: {}", method_config.method, state.redactor.redact(&format!("{} - {}", test_url, e))),
+                }
+            }
+            finding.tags.extend(methods::tags(status, &finding.methods));
+        }
+
+        // Default and vendor credentials against Basic auth prompts
+        if let (Some(sprayer), 401, None) = (&self.sprayer, status, payload) {
+            if let Some(realm) = spray::basic_realm(&headers) {
+                finding.credentials = self.spray(sprayer, test_url, &realm).await;
+                if let Some(credential) = &finding.credentials {
+                    finding.tags.push(format!("basic-auth:{}", credential.user));
+                }
+            }
+        }
+
+        // Save the body for post-scan grepping
+        if let Some(dir) = &config.store_responses {
+            let label = finding.label();
+            match store::store_response(dir, &label, status, &headers, &body, config.store_headers, state) {
+                Ok(name) => finding.body_file = Some(name),
+                Err(e) => warn!("❌ Failed to store response for {}: {}", state.redactor.redact(&label), e),
+            }
+        }
+
+        // Fingerprint the body so catch-all pages can be collapsed
+        finding.simhash = config.unique.then(|| simhash::simhash(&String::from_utf8_lossy(&body)));
+        let first_sighting = finding.simhash.is_none_or(|hash| state.first_sighting(status, hash));
+
+        // Queue discovered directories for recursion; ones past the depth
+        // limit still go on the frontier so they show up as unexplored
+        if config.recursion_depth > 0 && Self::is_directory(test_url, &final_url) {
+            let score = frontier::confidence(status, &finding.tags);
+            self.discovered_dirs.lock().await.push((final_url.to_string(), score));
+        }
+
+        let received = har::Received { status, version, headers: &headers, body: &body, final_url: final_url.as_str() };
+        self.archive(sent.as_ref(), received, truncated);
+        let snippet = config.snippet_bytes.map(|max| review::snippet(&body, content_type, max));
+        self.record(finding, first_sighting, snippet).await;
+    }
+
+    /// The parameter that steers a redirecting finding off-site, if any:
+    /// every candidate at once per payload, then one at a time to name the
+    /// one that did
+    async fn open_redirect(&self, client: &Client, test_url: &str, landed: &str) -> Option<String> {
+        let params = redirect::params(test_url, Some(landed));
+        for payload in redirect::PAYLOADS {
+            if !self.redirects_off_site(client, &redirect::with_params(test_url, &params, payload)?).await {
+                continue;
+            }
+            for param in &params {
+                let url = redirect::with_params(test_url, std::slice::from_ref(param), payload)?;
+                if self.redirects_off_site(client, &url).await {
+                    return Some(param.clone());
+                }
+            }
+        }
+        None
+    }
+
+    async fn redirects_off_site(&self, client: &Client, url: &str) -> bool {
+        if self.scope.check(url).is_err() {
+            return false;
+        }
+        match self.send(client, url, None).await {
+            Ok(response) => header_text(response.headers(), reqwest::header::LOCATION)
+                .is_some_and(|location| redirect::off_site(url, &location)),
+            Err(e) => {
+                debug!("Open redirect check failed: {}", self.state.redactor.redact(&format!("{} - {}", url, e)));
+                false
+            }
+        }
+    }
+
+    /// The credential a Basic auth realm accepts at this URL. The first URL
+    /// of a realm tries the whole list, one attempt at a time; later ones
+    /// only what it accepted there.
+    async fn spray(&self, sprayer: &spray::Sprayer, test_url: &str, realm: &str) -> Option<spray::Credential> {
+        let slot = sprayer.realm(&host_key(test_url), realm);
+        let mut outcome = slot.lock().await;
+        let first_pass = outcome.is_none();
+        let candidates: Vec<usize> = match *outcome {
+            Some(Outcome::Accepted(index)) => vec![index],
+            Some(_) => return None,
+            None => (0..sprayer.credentials.len()).collect(),
+        };
+
+        for (attempt, index) in candidates.into_iter().enumerate() {
+            if attempt > 0 {
+                tokio::time::sleep(sprayer.delay).await;
+            }
+            let (credential, client) = &sprayer.credentials[index];
+            let result = match self.send(client, test_url, None).await {
+                Ok(response) => spray::classify(response.status().as_u16()),
+                Err(e) => Attempt::Halt(e.to_string()),
+            };
+            match result {
+                Attempt::Accepted => {
+                    if first_pass {
+                        info!("🔓 Realm \"{}\" on {} accepts {}", realm, host_key(test_url), credential.user);
+                        *outcome = Some(Outcome::Accepted(index));
+                    }
+                    return Some(credential.clone());
+                }
+                Attempt::Refused => {}
+                Attempt::Halt(reason) => {
+                    if first_pass {
+                        warn!("🔐 Stopped spraying realm \"{}\" on {} after {} attempts: {}", realm, host_key(test_url), attempt + 1, reason);
+                        *outcome = Some(Outcome::Stopped);
+                    }
+                    return None;
+                }
+            }
+        }
+        if first_pass {
+            *outcome = Some(Outcome::Exhausted);
+        }
+        None
+    }
+
+    /// Keep a finding's request and response in the HAR and WARC captures,
+    /// and print them under `-vvv --dump matches`
+    fn archive(&self, sent: Option<&har::Sent>, received: har::Received, truncated: bool) {
+        let state = &self.state;
+        if let (Some(dump::Sample::Matches), Some(sent)) = (self.config.dump, sent) {
+            let answer = dump::Answer::Head { version: received.version, status: received.status, headers: received.headers };
+            trace!("🔎 {}\n{}", state.redactor.redact(&sent.url), dump::render(sent, answer, &state.redactor));
+        }
+        if let (Some(recorder), Some(sent)) = (&state.har, sent) {
+            recorder.record(sent, received);
+        }
+        if let Some(warc) = &state.warc {
+            if let Err(e) = warc.record(sent, received, truncated, &state.redactor) {
+                warn!("❌ Failed to write WARC record for {}: {}", state.redactor.redact(received.final_url), e);
+            }
+        }
+    }
+
+    /// Write a finding to the scan's sinks (the terminal skips a duplicate
+    /// page), with the start of its body under `-v`, and add it to the results
+    async fn record(&self, mut finding: Finding, first_sighting: bool, snippet: Option<String>) {
+        let state = &self.state;
+        (finding.score, finding.severity) = self.scorer.score(&finding);
+        state.plugins.on_finding(&mut finding);
+        // Record found path in the shared result list
+        state.hits.fetch_add(1, Ordering::Relaxed);
+        state.metrics.finding(finding.status);
+        state.sinks.record(&Recorded {
+            finding: &finding,
+            redactor: &state.redactor,
+            first_sighting,
+            snippet: snippet.as_deref(),
+            agent: None,
+        });
+        if let Some(journal) = &state.journal {
+            journal.finding(&finding, &state.redactor);
+        }
+        if let Some(chat) = &state.chat {
+            chat.finding(chat::Hit::new(&finding, &state.redactor));
+        }
+        if let Some(stream) = &state.stream {
+            if !stream.send(finding.clone()).await {
+                state.stop("the findings stream was dropped".to_string());
+            }
+        }
+        self.found_paths.lock().await.push(finding);
+    }
+
+    /// Probe one directory for exposed VCS metadata and dotfiles, reporting
+    /// only responses whose content carries the file's signature
+    async fn check_exposures(&self) {
+        let config = &self.config;
+        let state = &self.state;
+        let base = self.base_url.trim_end_matches('/');
+
+        for check in exposure::CHECKS {
+            if state.stopped() {
+                break;
+            }
+            let url = format!("{}/{}", base, check.path);
+            self.state.progress.inc(1);
+            if let Err(reason) = self.scope.check(&url) {
+                debug!("🚧 Skipping {}: {}", state.redactor.redact(&url), reason);
+                continue;
+            }
+            if state.host_dead(&host_key(&url)) {
+                state.skipped.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+
+            let response = match self.send(&self.client, &url, None).await {
+                Ok(response) if response.status().is_success() => response,
+                Ok(response) => {
+                    trace!("{} not exposed ({})", state.redactor.redact(&url), response.status().as_u16());
+                    continue;
+                }
+                Err(e) => {
+                    state.errors.fetch_add(1, Ordering::Relaxed);
+                    let message = format!("{} - {}", url, e);
+                    warn!("❌ Error checking: {}", state.redactor.redact(&message));
+                    continue;
+                }
+            };
+            let status = response.status().as_u16();
+            let headers = response.headers().clone();
+            let version = response.version();
+            let final_url = response.url().to_string();
+            let sent = response.extensions().get::<har::Sent>().cloned();
+            let (body, truncated) = read_body(config, response).await.unwrap_or_default();
+            if !check.matches(&body) {
+                debug!("🧱 {} answered without a {} signature", state.redactor.redact(&url), check.name);
+                continue;
+            }
+
+            let mut finding = Finding {
+                url,
+                payload: None,
+                status,
+                size: Some(body.len() as u64),
+                severity: Default::default(),
+                score: 0,
+                content_type: headers.get(reqwest::header::CONTENT_TYPE).and_then(|ct| ct.to_str().ok()).map(str::to_string),
+                tags: vec![check.tag()],
+                body_file: None,
+                screenshot: None,
+                secrets: Vec::new(),
+                variants: Vec::new(),
+                simhash: None,
+                duplicates: 0,
+                verdict: None,
+                notes: Vec::new(),
+                title: None,
+                etag: header_text(&headers, reqwest::header::ETAG),
+                last_modified: header_text(&headers, reqwest::header::LAST_MODIFIED),
+                header_issues: Vec::new(),
+                methods: Vec::new(),
+                credentials: None,
+            };
+            let exchange = plugin::Exchange { headers: &headers, body: &body };
+            state.plugins.on_response(&exchange, &mut finding);
+            if !state.plugins.matches(&exchange, &finding) {
+                trace!("{} filtered by a plugin", state.redactor.redact(&finding.url));
+                continue;
+            }
+            if let Some(dir) = &config.store_responses {
+                match store::store_response(dir, &finding.url, status, &headers, &body, config.store_headers, state) {
+                    Ok(name) => finding.body_file = Some(name),
+                    Err(e) => warn!("❌ Failed to store response for {}: {}", state.redactor.redact(&finding.url), e),
+                }
+            }
+
+            let received = har::Received { status, version, headers: &headers, body: &body, final_url: &final_url };
+            self.archive(sent.as_ref(), received, truncated);
+            let snippet = config.snippet_bytes.map(|max| review::snippet(&body, finding.content_type.as_deref(), max));
+            self.record(finding, true, snippet).await;
+            state.check_limits(config);
+        }
+    }
+}
+
+/// Scan a batch of wordlist entries and collect every matching path
+pub async fn scan_entries(
+    config: Arc<FuzzerConfig>,
+    client: Client,
+    entries: Entries,
+    state: Arc<ScanState>,
+) -> Result<Vec<Finding>, CrawlerError> {
+    // Scope rules checked before every request
+    let scope = Arc::new(scope::Scope::new(&config.scope_include, &config.scope_exclude, &config.blocklist)?);
+
+    // Directory for matched response bodies
+    if let Some(dir) = &config.store_responses {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    // Alternate client for differential scans
+    let differential = match &config.differential {
+        Some(Differential::Personas(primary, alternate)) => {
+            let alt_config = FuzzerConfig {
+                persona: Some(alternate.clone()),
+                ..(*config).clone()
+            };
+            Some(Arc::new(DifferentialClient {
+                primary_label: primary.clone(),
+                label: alternate.clone(),
+                client: build_client(&alt_config)?,
+            }))
+        }
+        Some(Differential::Auth) => {
+            // Same request with every session credential stripped
+            let alt_config = FuzzerConfig {
+                cookie: None,
+                headers: config.headers.iter()
+                    .filter(|(name, _)| !is_session_header(name))
+                    .cloned()
+                    .collect(),
+                ..(*config).clone()
+            };
+            Some(Arc::new(DifferentialClient {
+                primary_label: "authenticated".to_string(),
+                label: "unauthenticated".to_string(),
+                client: build_client(&alt_config)?,
+            }))
+        }
+        None => None,
+    };
+
+    // Same requests again from an untrusted origin
+    let cors = match &config.cors_origin {
+        Some(origin) => {
+            let mut headers = config.headers.clone();
+            headers.push(("Origin".to_string(), origin.clone()));
+            let cors_config = FuzzerConfig { headers, ..(*config).clone() };
+            Some(Arc::new(CorsClient { origin: origin.clone(), client: build_client(&cors_config)? }))
+        }
+        None => None,
+    };
+
+    let redirect_client = match config.open_redirects {
+        true => Some(crate::client_builder(&config)?.redirect(reqwest::redirect::Policy::none()).build()?),
+        false => None,
+    };
+
+    let method_configs = config.probe_methods.iter()
+        .filter_map(|method| reqwest::Method::from_bytes(method.as_bytes()).ok())
+        .map(|method| FuzzerConfig { method, request_template: None, ..(*config).clone() })
+        .collect();
+
+    let sprayer = match config.spray_credentials.is_empty() {
+        true => None,
+        false => Some(Arc::new(spray::Sprayer::new(&config)?)),
+    };
+
+    // A resumed scan reports what its earlier run found along with the rest
+    let carried_over = state.checkpoint.as_ref().map(|checkpoint| checkpoint.findings.clone()).unwrap_or_default();
+    let worker = Worker {
+        config: Arc::clone(&config),
+        client,
+        state,
+        scope,
+        differential,
+        cors,
+        redirect_client,
+        method_configs: Arc::new(method_configs),
+        sprayer,
+        scorer: Arc::new(severity::Scorer::new(&config.severity_rules)?),
+        found_paths: Arc::new(Mutex::new(carried_over)),
+        base_url: config.url.clone(),
+        baseline: None,
+        discovered_dirs: Arc::new(Mutex::new(Vec::new())),
+    };
+
+    let entries = Arc::new(entries);
+    let mut frontier = Frontier::new(config.recursion_strategy);
+    // An address range puts every host and port at the top level
+    let roots = match config.targets.is_empty() {
+        true => vec![config.url.clone()],
+        false => config.targets.clone(),
+    };
+    frontier.extend(roots.into_iter().map(|url| Pending { url, depth: 0, score: 1.0 }).collect());
+    // ... along with the directories an interrupted run had queued
+    if let Some(checkpoint) = &worker.state.checkpoint {
+        frontier.extend(
+            checkpoint.directories.iter()
+                .map(|(url, depth, score)| Pending { url: url.clone(), depth: *depth, score: *score })
+                .collect(),
+        );
+    }
+    let mut visited = HashSet::new();
+    let mut scanned_per_level: HashMap<usize, usize> = HashMap::new();
+
+    // Fuzz the target, then every directory discovered beneath it
+    while let Some(Pending { url: base_url, depth, .. }) = frontier.pop() {
+        if !visited.insert(base_url.trim_end_matches('/').to_string()) {
+            continue;
+        }
+
+        // A stop condition leaves the rest of the frontier for a later scan
+        if worker.state.stopped() {
+            if depth > 0 || !config.targets.is_empty() {
+                worker.state.unexplored.lock().unwrap().push(base_url);
+            }
+            continue;
+        }
+
+        // Depth and breadth limits leave directories on the frontier unscanned
+        let scanned = scanned_per_level.entry(depth).or_default();
+        let over_breadth = depth > 0 && config.max_dirs_per_level.is_some_and(|max| *scanned >= max);
+        if depth > config.recursion_depth || over_breadth {
+            info!("⏸️  Leaving {} unexplored (depth {})", base_url, depth);
+            worker.state.unexplored.lock().unwrap().push(base_url);
+            continue;
+        }
+        *scanned += 1;
+
+        if depth > 0 {
+            worker.state.progress.inc_length(entries.count_urls(&base_url, &config.extensions));
+            if !config.silent {
+                term::err!("📂 Recursing into {} (depth {})", base_url.cyan(), depth);
+            }
+        }
+
+        // Each directory gets its own not-found fingerprint
+        let baseline = if config.auto_calibrate {
+            worker.calibrate(&base_url).await.map(Arc::new)
+        } else {
+            None
+        };
+        if let Some(baseline) = &baseline {
+            info!("🧪 Calibrated {}: {}", base_url, baseline.describe());
+        }
+
+        let dir_worker = Worker {
+            base_url,
+            baseline,
+            ..worker.clone()
+        };
+        if config.vcs_checks {
+            dir_worker.state.progress.inc_length(exposure::CHECKS.len() as u64);
+            dir_worker.check_exposures().await;
+        }
+        scan_directory(&dir_worker, &entries).await?;
+
+        let new_dirs = std::mem::take(&mut *worker.discovered_dirs.lock().await);
+        if let Some(journal) = &worker.state.journal {
+            for (url, score) in &new_dirs {
+                journal.directory(url, depth + 1, *score);
+            }
+        }
+        frontier.extend(
+            new_dirs.into_iter()
+                .map(|(url, score)| Pending { url, depth: depth + 1, score })
+                .collect(),
+        );
+    }
+
+    let findings = std::mem::take(&mut *worker.found_paths.lock().await);
+    Ok(findings)
+}
+
+/// The URLs one wordlist entry is requested at beneath a directory: with
+/// each extension, or filled into the request template along with the
+/// payload it was filled with
+pub fn candidates(config: &FuzzerConfig, base_url: &str, path: &str) -> Vec<(String, Option<String>)> {
+    match &config.request_template {
+        Some(template) => RequestTemplate::payloads(path, &config.extensions)
+            .into_iter()
+            .map(|payload| (template.url_for(&payload), Some(payload)))
+            .collect(),
+        // Bucket candidates are complete URLs already
+        None if config.buckets => vec![(path.to_string(), None)],
+        None => generate_urls(base_url, path, &config.extensions)
+            .into_iter()
+            .map(|url| (url, None))
+            .collect(),
+    }
+}
+
+/// Fuzz every wordlist entry beneath one directory
+async fn scan_directory(worker: &Worker, entries: &Arc<Entries>) -> Result<(), CrawlerError> {
+    // Concurrency management, unless the targets file limits this target
+    let threads = targets::override_for(&worker.config.target_overrides, &worker.base_url)
+        .and_then(|target| target.threads)
+        .unwrap_or(worker.config.threads);
+    let semaphore = Arc::new(Semaphore::new(threads));
+    // Finished tasks are reaped as we go: with lazily generated combinations
+    // the entries can far outnumber what's worth keeping handles for
+    let mut tasks = task::JoinSet::new();
+
+    // Process entries with extension support
+    for path in entries.iter() {
+        // Entries an interrupted run of this scan finished are only counted
+        if worker.state.checkpoint.as_ref().is_some_and(|checkpoint| checkpoint.is_done(&worker.base_url, &path)) {
+            worker.state.progress.inc(candidates(&worker.config, &worker.base_url, &path).len() as u64);
+            continue;
+        }
+        let worker = worker.clone();
+        let permit = Arc::clone(&semaphore).acquire_owned().await;
+        if worker.state.stopped() {
+            break;
+        }
+
+        tasks.spawn(async move {
+            let _permit = permit;
+
+            for (test_url, payload) in candidates(&worker.config, &worker.base_url, &path) {
+                if worker.state.stopped() {
+                    return;
+                }
+                // Aborted mid-request or mid-body, the entry is left unfinished
+                tokio::select! {
+                    _ = worker.probe(&test_url, payload.as_deref()) => {}
+                    _ = worker.state.cancellation.cancelled() => return,
+                }
+                worker.state.check_limits(&worker.config);
+                worker.state.progress.inc(1);
+            }
+            if let Some(journal) = &worker.state.journal {
+                journal.entry_done(&worker.base_url, &path);
+            }
+        });
+        while let Some(finished) = tasks.try_join_next() {
+            finished?;
+        }
+    }
+
+    // Wait for all tasks to complete
+    while let Some(finished) = tasks.join_next().await {
+        finished?;
+    }
+
+    Ok(())
+}
+
+/// Re-request every finding one at a time from a fresh client, tagging the
+/// ones that no longer answer with the status recorded during the scan.
+/// Returns how many failed to reproduce.
+pub async fn reconfirm_findings(
+    config: &FuzzerConfig,
+    state: &ScanState,
+    findings: &mut [Finding],
+) -> Result<usize, CrawlerError> {
+    // New client: empty connection pool and fresh name resolution
+    crate::dns::flush();
+    let client = build_client(config)?;
+    let delay = std::time::Duration::from_millis(config.reconfirm_delay);
+    let mut stale = 0;
+
+    for (i, finding) in findings.iter_mut().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(delay).await;
+        }
+
+        let outcome = match send_request(config, state, &client, &finding.url, finding.payload.as_deref()).await {
+            // Revalidated against `--previous`: still what it was
+            Ok(response) if response.status() == reqwest::StatusCode::NOT_MODIFIED && state.previous.is_some() => {
+                finding.status.to_string()
+            }
+            Ok(response) => response.status().as_u16().to_string(),
+            Err(e) => format!("error: {}", e),
+        };
+        if outcome == finding.status.to_string() {
+            continue;
+        }
+
+        stale += 1;
+        finding.tags.push("not-reproduced".to_string());
+        info!(
+            "🔁 {} no longer reproduces: {} → {}",
+            state.redactor.redact(&finding.url),
+            finding.status,
+            state.redactor.redact(&outcome)
+        );
+    }
+
+    Ok(stale)
+}
@@ -0,0 +1,218 @@
+use std::error::Error as _;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// What went wrong, sorted by what it means for a scan: whether the run
+/// can go on, whether the host is worth more requests, whether trying the
+/// same request again could help
+#[derive(Debug, Error)]
+pub enum CrawlerError {
+    /// Options, input files or a request that can't be built
+    #[error("{0}")]
+    Config(String),
+    #[error("Invalid URL '{url}': {reason}")]
+    Url { url: String, reason: String },
+    /// `--threads` outside `config::MIN_THREADS..=config::MAX_THREADS`
+    #[error("--threads must be between {} and {}, not {threads}", crate::config::MIN_THREADS, crate::config::MAX_THREADS)]
+    Threads { threads: usize },
+    /// Two options given together that can't work together
+    #[error("{option} can't be combined with {other}: {why}")]
+    Conflict {
+        option: &'static str,
+        other: &'static str,
+        why: &'static str,
+    },
+    #[error("Cannot use interface {name}: {reason}")]
+    Interface { name: String, reason: String },
+    /// `--source-ip` (or `--interface`) isn't an address of this host
+    #[error("Cannot send from {address}: {source}")]
+    Source {
+        address: std::net::IpAddr,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("No wordlist found. Please install SecLists or provide a custom path with -w")]
+    NoWordlist,
+    #[error("Cannot read wordlist {}: {source}", path.display())]
+    Wordlist {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("cannot resolve {host}")]
+    Dns {
+        host: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("cannot connect to {host}: {reason}")]
+    Connect {
+        host: String,
+        reason: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("TLS handshake with {host} failed: {reason}")]
+    Tls {
+        host: String,
+        reason: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    /// Given up on because the scan was cancelled
+    #[error("cancelled")]
+    Cancelled,
+    #[error("no response from {host}{}", after.map(|secs| format!(" within {}s", secs)).unwrap_or_default())]
+    Timeout { host: String, after: Option<u64> },
+    /// The connection worked but the exchange didn't: a reset mid-response,
+    /// a redirect loop, a body that broke off
+    #[error("{host}: {reason}")]
+    Http { host: String, reason: String },
+    /// Reading or writing a local file, or a socket outside the scan's requests
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A results file, checkpoint or agent message that isn't the JSON expected
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// The `--output` results database
+    #[error(transparent)]
+    Database(#[from] rusqlite::Error),
+    /// A regex from the command line or a rules file
+    #[error(transparent)]
+    Pattern(#[from] regex::Error),
+    /// A worker task that panicked or was torn down
+    #[error("worker failed: {0}")]
+    Worker(#[from] tokio::task::JoinError),
+}
+
+impl CrawlerError {
+    /// `url` couldn't be used, for `reason`
+    pub fn url(url: &str, reason: impl ToString) -> Self {
+        CrawlerError::Url { url: url.to_string(), reason: reason.to_string() }
+    }
+
+    /// Category name, as `--metrics-port` and the scan summary count errors
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CrawlerError::Config(_)
+            | CrawlerError::Url { .. }
+            | CrawlerError::Threads { .. }
+            | CrawlerError::Conflict { .. }
+            | CrawlerError::Interface { .. }
+            | CrawlerError::Source { .. }
+            | CrawlerError::Pattern(_) => "config",
+            CrawlerError::NoWordlist | CrawlerError::Wordlist { .. } => "wordlist",
+            CrawlerError::Dns { .. } => "dns",
+            CrawlerError::Connect { .. } => "connect",
+            CrawlerError::Tls { .. } => "tls",
+            CrawlerError::Timeout { .. } => "timeout",
+            CrawlerError::Cancelled => "cancelled",
+            CrawlerError::Http { .. } => "http",
+            CrawlerError::Io(_) | CrawlerError::Database(_) => "io",
+            CrawlerError::Json(_) => "json",
+            CrawlerError::Worker(_) => "worker",
+        }
+    }
+
+    /// Whether the failure says the host can't be talked to (unresolvable,
+    /// refusing, hung) rather than something about this one request
+    pub fn is_host_failure(&self) -> bool {
+        matches!(
+            self,
+            CrawlerError::Dns { .. } | CrawlerError::Connect { .. } | CrawlerError::Tls { .. } | CrawlerError::Timeout { .. }
+        )
+    }
+
+    /// Whether sending the same request again may well work: a refused or
+    /// dropped connection or a slow answer, but not a name that doesn't
+    /// resolve, a certificate that won't verify or a redirect loop
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, CrawlerError::Connect { .. } | CrawlerError::Timeout { .. })
+    }
+}
+
+impl From<reqwest::Error> for CrawlerError {
+    fn from(error: reqwest::Error) -> Self {
+        let host = error.url().map(authority).unwrap_or_default();
+        let reason = root_cause(&error);
+        let chain = chain(&error);
+        if error.is_timeout() {
+            CrawlerError::Timeout { host, after: None }
+        } else if error.is_builder() {
+            CrawlerError::Config(format!("Cannot build a request for {}: {}", host, reason))
+        } else if error.is_connect() && chain.iter().any(|message| message.starts_with("dns error")) {
+            CrawlerError::Dns { host, source: error }
+        } else if error.is_connect() && is_tls(&error, &chain) {
+            CrawlerError::Tls { host, reason, source: error }
+        } else if error.is_connect() {
+            CrawlerError::Connect { host, reason, source: error }
+        } else {
+            CrawlerError::Http { host, reason }
+        }
+    }
+}
+
+/// A message for the user, from checks that have nothing more to say than it
+impl From<String> for CrawlerError {
+    fn from(message: String) -> Self {
+        CrawlerError::Config(message)
+    }
+}
+
+impl From<&str> for CrawlerError {
+    fn from(message: &str) -> Self {
+        CrawlerError::Config(message.to_string())
+    }
+}
+
+/// `host` or `host:port`, as the user typed it in the URL
+pub fn authority(url: &reqwest::Url) -> String {
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => url.to_string(),
+    }
+}
+
+/// The message of every error in the chain, outermost first
+fn chain(error: &reqwest::Error) -> Vec<String> {
+    std::iter::successors(Some(error as &dyn std::error::Error), |&e| e.source())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// The innermost cause, which is where the useful part of reqwest's
+/// "error sending request for url (...): error trying to connect: ..." is
+fn root_cause(error: &reqwest::Error) -> String {
+    chain(error).pop().unwrap_or_default()
+}
+
+fn is_tls(error: &reqwest::Error, chain: &[String]) -> bool {
+    let native = std::iter::successors(error.source(), |&e| e.source())
+        .any(|e| e.is::<tokio_native_tls::native_tls::Error>());
+    native || chain.iter().any(|message| {
+        let message = message.to_lowercase();
+        message.contains("certificate") || message.contains("handshake") || message.contains("ssl")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn classifies_request_failures() {
+        let client = reqwest::Client::builder().timeout(std::time::Duration::from_millis(500)).build().unwrap();
+
+        // Nothing listens on the discard port
+        let refused = CrawlerError::from(client.get("http://127.0.0.1:9/").send().await.unwrap_err());
+        assert_eq!(refused.kind(), "connect");
+        assert!(refused.is_host_failure() && refused.is_retryable());
+        assert!(refused.to_string().starts_with("cannot connect to 127.0.0.1:9: "), "{}", refused);
+
+        let unresolvable = CrawlerError::from(client.get("http://nonexistent.invalid/").send().await.unwrap_err());
+        assert_eq!(unresolvable.kind(), "dns");
+        assert!(!unresolvable.is_retryable());
+        assert_eq!(unresolvable.to_string(), "cannot resolve nonexistent.invalid");
+    }
+}
@@ -0,0 +1,96 @@
+use crate::error::CrawlerError;
+use crate::ScanState;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+
+
+/// How the process ended, as its exit status for scripts and CI jobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The scan completed with findings, or another command succeeded
+    Success = 0,
+    /// The scan completed and found nothing
+    NothingFound = 1,
+    /// Bad options or input files, or anything else that failed
+    ConfigError = 2,
+    /// No request got a response
+    Unreachable = 3,
+    /// Interrupted, or cut short by a stop condition
+    Aborted = 4,
+    /// A finding violated the `--fail-on` policy
+    PolicyViolation = 5,
+}
+
+impl Outcome {
+    /// What a finished scan amounts to. Under a policy, a scan passes by
+    /// finding nothing that violates it rather than by finding something.
+    pub fn of_scan(state: &ScanState) -> Self {
+        if state.stopped() {
+            Outcome::Aborted
+        } else if state.violations.load(Ordering::Relaxed) > 0 {
+            Outcome::PolicyViolation
+        } else if state.metrics.requests() > 0 && state.metrics.responses() == 0 {
+            Outcome::Unreachable
+        } else if state.policy.is_some() || state.hits.load(Ordering::Relaxed) > 0 {
+            Outcome::Success
+        } else {
+            Outcome::NothingFound
+        }
+    }
+
+    /// What an error ending the process amounts to
+    pub fn of_error(error: &CrawlerError) -> Self {
+        match error {
+            CrawlerError::Cancelled => Outcome::Aborted,
+            e if e.is_host_failure() => Outcome::Unreachable,
+            _ => Outcome::ConfigError,
+        }
+    }
+
+    pub fn code(self) -> ExitCode {
+        ExitCode::from(self as u8)
+    }
+}
+
+/// The scan's outcome, once one has run to completion
+static SCAN_OUTCOME: OnceLock<Outcome> = OnceLock::new();
+
+pub fn record(outcome: Outcome) {
+    let _ = SCAN_OUTCOME.set(outcome);
+}
+
+/// How a run that returned without error ended: a scan's outcome, or
+/// success for every other command
+pub fn recorded() -> Outcome {
+    SCAN_OUTCOME.get().copied().unwrap_or(Outcome::Success)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_scans_and_errors_to_codes() {
+        let mut state = ScanState::default();
+        assert_eq!(Outcome::of_scan(&state), Outcome::NothingFound);
+        state.metrics.error("connect");
+        assert_eq!(Outcome::of_scan(&state), Outcome::Unreachable);
+        state.metrics.response(404);
+        assert_eq!(Outcome::of_scan(&state), Outcome::NothingFound);
+        state.hits.fetch_add(1, Ordering::Relaxed);
+        assert_eq!(Outcome::of_scan(&state), Outcome::Success);
+        state.policy = Some(crate::policy::Policy::any_finding());
+        state.violations.store(1, Ordering::Relaxed);
+        assert_eq!(Outcome::of_scan(&state), Outcome::PolicyViolation);
+        state.stop("--max-time of 60s elapsed".to_string());
+        assert_eq!(Outcome::of_scan(&state), Outcome::Aborted);
+
+        let invalid: CrawlerError = "Invalid URL format".into();
+        assert_eq!(Outcome::of_error(&invalid), Outcome::ConfigError);
+        let timeout = CrawlerError::Timeout { host: "t:80".to_string(), after: Some(10) };
+        assert_eq!(Outcome::of_error(&timeout), Outcome::Unreachable);
+        assert_eq!(Outcome::of_error(&CrawlerError::Cancelled), Outcome::Aborted);
+        assert_eq!(Outcome::Aborted.code(), ExitCode::from(4));
+    }
+}
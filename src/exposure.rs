@@ -0,0 +1,150 @@
+/// A file that should never be served, with the content signature that
+/// tells a real exposure apart from a catch-all page answering 200
+pub struct Check {
+    /// Path probed beneath each directory
+    pub path: &'static str,
+    /// Finding tag, as `exposed:<name>`
+    pub name: &'static str,
+    validate: fn(&[u8]) -> bool,
+}
+
+impl Check {
+    pub fn tag(&self) -> String {
+        format!("exposed:{}", self.name)
+    }
+
+    /// Whether a successful response really is this file
+    pub fn matches(&self, body: &[u8]) -> bool {
+        (self.validate)(body)
+    }
+}
+
+/// Probed on the target root and every discovered directory by `--vcs-checks`
+pub const CHECKS: &[Check] = &[
+    Check { path: ".git/HEAD", name: "git", validate: git_head },
+    Check { path: ".svn/entries", name: "svn", validate: svn_entries },
+    // `.hg/` itself is rarely listable; `requires` exists in every repository
+    Check { path: ".hg/requires", name: "hg", validate: hg_requires },
+    Check { path: ".env", name: "env", validate: dotenv },
+    Check { path: ".DS_Store", name: "ds_store", validate: ds_store },
+    Check { path: "docker-compose.yml", name: "docker-compose", validate: docker_compose },
+];
+
+/// Text of a body that isn't markup; error pages and SPA shells are HTML
+fn plain_text(body: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(body);
+    let trimmed = text.trim_start();
+    (!trimmed.is_empty() && !trimmed.starts_with('<')).then(|| text.into_owned())
+}
+
+/// `ref: refs/heads/main`, or a detached commit hash
+fn git_head(body: &[u8]) -> bool {
+    let Some(text) = plain_text(body) else {
+        return false;
+    };
+    let head = text.trim();
+    head.starts_with("ref: refs/") || (head.len() == 40 && head.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// A format number on the first line (`12` since Subversion 1.7, which has
+/// nothing else in the file), or the XML entries of pre-1.4 working copies
+fn svn_entries(body: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(body);
+    if text.contains("<wc-entries") {
+        return true;
+    }
+    let first = text.lines().next().unwrap_or_default().trim();
+    !first.is_empty() && first.len() <= 2 && first.chars().all(|c| c.is_ascii_digit())
+}
+
+/// One repository feature per line, including a storage format
+fn hg_requires(body: &[u8]) -> bool {
+    let Some(text) = plain_text(body) else {
+        return false;
+    };
+    let features: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    features.iter().all(|f| f.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.'))
+        && features.iter().any(|f| ["revlogv1", "store", "revlog-compression-zstd"].contains(f))
+}
+
+/// Mostly `NAME=value` lines (optionally `export`ed), comments aside
+fn dotenv(body: &[u8]) -> bool {
+    let Some(text) = plain_text(body) else {
+        return false;
+    };
+    let lines: Vec<&str> = text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+    let assignments = lines.iter()
+        .filter(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            line.split_once('=').is_some_and(|(name, _)| {
+                name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            })
+        })
+        .count();
+    assignments > 0 && assignments * 2 >= lines.len()
+}
+
+/// Finder's binary buddy allocator header
+fn ds_store(body: &[u8]) -> bool {
+    body.starts_with(b"\x00\x00\x00\x01Bud1")
+}
+
+/// A top-level `services:` mapping
+fn docker_compose(body: &[u8]) -> bool {
+    plain_text(body).is_some_and(|text| text.lines().any(|line| line.trim_end() == "services:"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(name: &str) -> &'static Check {
+        CHECKS.iter().find(|check| check.name == name).unwrap()
+    }
+
+    const SOFT_404: &[u8] = b"<!DOCTYPE html><html><body>Page not found</body></html>";
+
+    #[test]
+    fn catch_all_pages_match_nothing() {
+        for check in CHECKS {
+            assert!(!check.matches(SOFT_404), "{}", check.name);
+            assert!(!check.matches(b""), "{}", check.name);
+        }
+    }
+
+    #[test]
+    fn recognizes_git_heads() {
+        assert!(check("git").matches(b"ref: refs/heads/main\n"));
+        assert!(check("git").matches(b"4b825dc642cb6eb9a060e54bf8d69288fbee4904\n"));
+        assert!(!check("git").matches(b"ref: nothing"));
+    }
+
+    #[test]
+    fn recognizes_svn_and_hg_metadata() {
+        assert!(check("svn").matches(b"12\n"));
+        assert!(check("svn").matches(b"8\n\ndir\n1234\nhttps://svn.example.com/trunk\n"));
+        assert!(check("svn").matches(b"<?xml version=\"1.0\"?>\n<wc-entries xmlns=\"svn:\">"));
+        assert!(!check("svn").matches(b"404 not found"));
+        assert!(check("hg").matches(b"dotencode\nfncache\ngeneraldelta\nrevlogv1\nstore\n"));
+        assert!(!check("hg").matches(b"Not Found"));
+    }
+
+    #[test]
+    fn recognizes_env_files() {
+        assert!(check("env").matches(b"# local settings\nAPP_KEY=base64:abc\nexport DB_PASSWORD=hunter2\n"));
+        assert!(!check("env").matches(b"Nothing to see here.\nReally.\nkey=1"));
+        assert!(!check("env").matches(b"{\"error\":\"not found\"}"));
+    }
+
+    #[test]
+    fn recognizes_ds_store_and_compose_files() {
+        assert!(check("ds_store").matches(b"\x00\x00\x00\x01Bud1\x00\x00\x10\x00"));
+        assert!(!check("ds_store").matches(b"Bud1"));
+        assert!(check("docker-compose").matches(b"version: \"3\"\nservices:\n  db:\n    image: postgres\n"));
+        assert!(!check("docker-compose").matches(b"# services: none here\n"));
+    }
+}
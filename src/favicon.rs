@@ -0,0 +1,113 @@
+use crate::engine::{read_body, send_request};
+use crate::error::CrawlerError;
+use crate::scope::Scope;
+use crate::{FuzzerConfig, ScanState};
+use reqwest::{Client, Method};
+use serde::{Deserialize, Serialize};
+
+
+/// Favicon hashes of common products, as Shodan's `http.favicon.hash`
+/// computes them
+const KNOWN: &[(i32, &str)] = &[
+    (81586312, "Jenkins"),
+    (116323821, "Spring Boot"),
+    (-297069493, "Apache Tomcat"),
+    (-305179312, "Atlassian Confluence"),
+    (-335242539, "F5 BIG-IP"),
+    (945408572, "Fortinet FortiGate"),
+    (1278323681, "GitLab"),
+    (1768726119, "Outlook Web App"),
+];
+
+/// A target's favicon, fingerprinted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Favicon {
+    pub url: String,
+    /// MurmurHash3 of the base64-encoded icon, searchable on Shodan
+    pub hash: i32,
+    /// What the hash is known to belong to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
+}
+
+/// Fetch `/favicon.ico` at the root of `base_url` and look its hash up;
+/// `None` when the target serves no icon there or the icon is out of scope
+pub async fn fetch(config: &FuzzerConfig, state: &ScanState, client: &Client, base_url: &str) -> Result<Option<Favicon>, CrawlerError> {
+    let mut url = reqwest::Url::parse(base_url).map_err(|e| CrawlerError::url(base_url, e))?;
+    url.set_path("/favicon.ico");
+    url.set_query(None);
+    let scope = Scope::new(&config.scope_include, &config.scope_exclude, &config.blocklist)?;
+    if let Err(reason) = scope.check(url.as_str()) {
+        tracing::debug!("🚧 Not fetching {}: {}", state.redactor.redact(url.as_str()), reason);
+        return Ok(None);
+    }
+    let get = FuzzerConfig { method: Method::GET, request_template: None, ..config.clone() };
+    let response = send_request(&get, state, client, url.as_str(), None).await?;
+    // Catch-all pages answer for missing icons too
+    let is_html = response.headers().get(reqwest::header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .is_some_and(|ct| ct.contains("text/html"));
+    if !response.status().is_success() || is_html {
+        return Ok(None);
+    }
+    let (icon, _) = read_body(&get, response).await?;
+    if icon.is_empty() {
+        return Ok(None);
+    }
+    let hash = shodan_hash(&icon);
+    let product = KNOWN.iter().find(|(known, _)| *known == hash).map(|(_, product)| product.to_string());
+    Ok(Some(Favicon { url: url.to_string(), hash, product }))
+}
+
+/// Shodan's favicon hash: MurmurHash3 of the icon base64-encoded the way
+/// Python's `base64.encodebytes` does, a newline after every 76 characters
+/// and at the end
+pub fn shodan_hash(icon: &[u8]) -> i32 {
+    use base64::Engine;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(icon);
+    let mut lines = String::with_capacity(encoded.len() + encoded.len() / 76 + 1);
+    for chunk in encoded.as_bytes().chunks(76) {
+        lines.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        lines.push('\n');
+    }
+    murmur3_32(lines.as_bytes(), 0) as i32
+}
+
+/// MurmurHash3, x86 32-bit variant
+fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let mut hash = seed;
+    let mut blocks = data.chunks_exact(4);
+    for block in &mut blocks {
+        let k = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+        hash ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let k = tail.iter().rev().fold(0u32, |k, byte| (k << 8) | u32::from(*byte));
+        hash ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^ (hash >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_like_python_mmh3() {
+        assert_eq!(murmur3_32(b"", 0), 0);
+        assert_eq!(murmur3_32(b"foo", 0) as i32, -156908512);
+        assert_eq!(murmur3_32(b"Hello, world!", 1234), 0xfaf6_cdb3);
+        // 100 bytes encode to 136 characters: two lines
+        let lines = format!("{}\n{}==\n", "A".repeat(76), "A".repeat(58));
+        assert_eq!(shodan_hash(&[0u8; 100]), murmur3_32(lines.as_bytes(), 0) as i32);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::RecursionStrategy;
+use std::collections::VecDeque;
+
+/// A discovered directory waiting to be fuzzed
+#[derive(Debug, Clone)]
+pub struct Pending {
+    pub url: String,
+    pub depth: usize,
+    /// Confidence of the hit that revealed this directory
+    pub score: f64,
+}
+
+/// Directories queued for recursion, popped in the order the strategy dictates
+pub struct Frontier {
+    strategy: RecursionStrategy,
+    queue: VecDeque<Pending>,
+}
+
+impl Frontier {
+    pub fn new(strategy: RecursionStrategy) -> Self {
+        Frontier { strategy, queue: VecDeque::new() }
+    }
+
+    /// Queue the directories found while scanning one parent, in discovery order
+    pub fn extend(&mut self, dirs: Vec<Pending>) {
+        match self.strategy {
+            // Children go on top of the stack, first-discovered on the very top
+            RecursionStrategy::Dfs => {
+                for dir in dirs.into_iter().rev() {
+                    self.queue.push_front(dir);
+                }
+            }
+            RecursionStrategy::Bfs | RecursionStrategy::Priority => self.queue.extend(dirs),
+        }
+    }
+
+    /// Next directory to scan
+    pub fn pop(&mut self) -> Option<Pending> {
+        match self.strategy {
+            RecursionStrategy::Bfs | RecursionStrategy::Dfs => self.queue.pop_front(),
+            // Highest score wins; ties go to the shallowest, then oldest entry
+            RecursionStrategy::Priority => {
+                let best = self.queue.iter().enumerate().max_by(|(ia, a), (ib, b)| {
+                    a.score.total_cmp(&b.score)
+                        .then(b.depth.cmp(&a.depth))
+                        .then(ib.cmp(ia))
+                })?.0;
+                self.queue.remove(best)
+            }
+        }
+    }
+}
+
+/// How much a hit suggests the directory behind it holds more content.
+/// Plain 2xx pages rank highest, auth-protected ones next, redirects and
+/// anything else last; secrets and access-control differences add weight.
+pub fn confidence(status: u16, tags: &[String]) -> f64 {
+    let base = match status {
+        200..=299 => 1.0,
+        401 | 403 => 0.7,
+        300..=399 => 0.5,
+        _ => 0.3,
+    };
+    let bonus: f64 = tags.iter()
+        .map(|tag| {
+            if tag.starts_with("secret:") {
+                0.3
+            } else if tag == "unauthenticated-access" || tag == "differential" {
+                0.2
+            } else {
+                0.0
+            }
+        })
+        .sum();
+    base + bonus
+}
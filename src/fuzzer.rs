@@ -0,0 +1,201 @@
+//! Scans run from another program: the findings come back as a stream,
+//! as the scan makes them, rather than in a report once it's over.
+
+use crate::error::CrawlerError;
+use crate::{Finding, FuzzerConfig, ProgressEvent};
+use std::ffi::OsString;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tokio_util::sync::CancellationToken;
+
+
+/// Findings a scan may get ahead of the program taking them before its
+/// workers wait
+const BUFFER: usize = 64;
+
+/// A scan, set up with `FuzzerConfig::builder` or the options `dir_crawler
+/// scan` takes
+pub struct Fuzzer {
+    setup: Setup,
+}
+
+enum Setup {
+    Config(Box<FuzzerConfig>),
+    Args(Box<crate::cli::ScanArgs>),
+}
+
+impl Fuzzer {
+    pub fn new(config: FuzzerConfig) -> Self {
+        Fuzzer { setup: Setup::Config(Box::new(config)) }
+    }
+
+    /// A scan from `dir_crawler scan`'s arguments, without the program and
+    /// command names: `["http://example.com/", "-w", "words.txt"]`. The
+    /// `DIR_CRAWLER_*` environment variables apply as on the command line.
+    pub fn from_args<I, T>(args: I) -> Result<Self, CrawlerError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString>,
+    {
+        let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+        let args = crate::cli::ScanArgs::try_parse_env(args).map_err(|e| e.to_string())?;
+        Ok(Fuzzer { setup: Setup::Args(Box::new(args)) })
+    }
+
+    /// Start the scan in the background, silent, and stream its findings.
+    /// The workers wait whenever the stream falls `BUFFER` findings behind;
+    /// cancelling `cancel` or dropping the stream winds the scan down, and
+    /// the stream ends once the scan has.
+    pub fn run_stream(self, cancel: CancellationToken) -> Findings {
+        let (sender, receiver) = mpsc::channel(BUFFER);
+        let progress = broadcast::channel(crate::progress::CAPACITY).0;
+        let outlet = Outlet { findings: Mutex::new(Some(sender)), cancel, progress: progress.clone() };
+        let scan = match self.setup {
+            Setup::Config(config) => tokio::spawn(crate::scan_config(*config, outlet)),
+            Setup::Args(args) => tokio::spawn(crate::scan(*args, None, Vec::new(), None, Some(outlet))),
+        };
+        Findings { findings: ReceiverStream::new(receiver), scan, progress }
+    }
+}
+
+/// The findings of a scan started by `Fuzzer::run_stream`, as they're made
+pub struct Findings {
+    findings: ReceiverStream<Finding>,
+    scan: JoinHandle<Result<(), CrawlerError>>,
+    progress: broadcast::Sender<ProgressEvent>,
+}
+
+impl Findings {
+    /// Where the scan has got, a few times a second and once more at the
+    /// end; a receiver that falls behind skips to the newer events
+    pub fn progress(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.progress.subscribe()
+    }
+
+    /// Wait for the scan to end, stopping it if findings are still coming,
+    /// and return why it failed if it did
+    pub async fn finish(self) -> Result<(), CrawlerError> {
+        drop(self.findings);
+        self.scan.await?
+    }
+}
+
+impl Stream for Findings {
+    type Item = Finding;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Finding>> {
+        Pin::new(&mut self.findings).poll_next(cx)
+    }
+}
+
+/// The scan's end of `Findings`
+pub(crate) struct Outlet {
+    /// Taken when the scan ends, which closes the stream even while
+    /// something still holds the scan's state
+    findings: Mutex<Option<mpsc::Sender<Finding>>>,
+    pub cancel: CancellationToken,
+    pub progress: broadcast::Sender<ProgressEvent>,
+}
+
+impl Outlet {
+    /// Hand a finding over, waiting while the stream is behind; false once
+    /// nobody takes them any more
+    pub async fn send(&self, finding: Finding) -> bool {
+        let sender = self.findings.lock().unwrap().clone();
+        match sender {
+            Some(sender) => sender.send(finding).await.is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn close(&self) {
+        self.findings.lock().unwrap().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn streams_findings_until_the_scan_ends() {
+        // Everything but /missing is there
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let status = match String::from_utf8_lossy(&buf[..n]).contains("GET /missing ") {
+                    true => "404 Not Found",
+                    false => "200 OK",
+                };
+                let response = format!("HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok", status);
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        let wordlist = std::env::temp_dir().join(format!("dir_crawler-stream-{}.txt", std::process::id()));
+        std::fs::write(&wordlist, "admin\nmissing\nlogin\n").unwrap();
+
+        let config = FuzzerConfig::builder(base.as_str()).wordlist(&wordlist).threads(2).build().unwrap();
+        let from_args = Fuzzer::from_args([base.as_str(), "-w", wordlist.to_str().unwrap(), "-t", "2"]).unwrap();
+        for fuzzer in [Fuzzer::new(config), from_args] {
+            let mut findings = fuzzer.run_stream(CancellationToken::new());
+            let mut progress = findings.progress();
+            let mut urls = Vec::new();
+            while let Some(finding) = findings.next().await {
+                urls.push(finding.url);
+            }
+            findings.finish().await.unwrap();
+            urls.sort();
+            assert_eq!(urls, [format!("{}admin", base), format!("{}login", base)]);
+
+            let mut last = None;
+            loop {
+                match progress.try_recv() {
+                    Ok(event) => last = Some(event),
+                    Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                    Err(_) => break,
+                }
+            }
+            let last = last.unwrap();
+            assert_eq!((last.processed, last.total, last.findings), (3, 3, 2));
+        }
+
+        assert!(Fuzzer::from_args(["--threads"]).is_err());
+        std::fs::remove_file(&wordlist).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelling_drops_the_requests_in_flight() {
+        // Accepts connections and never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+        let wordlist = std::env::temp_dir().join(format!("dir_crawler-cancel-{}.txt", std::process::id()));
+        std::fs::write(&wordlist, "admin\nlogin\n").unwrap();
+
+        let config = FuzzerConfig::builder(base.as_str()).wordlist(&wordlist).read_timeout(60).build().unwrap();
+        let cancel = CancellationToken::new();
+        let mut findings = Fuzzer::new(config).run_stream(cancel.clone());
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        cancel.cancel();
+        let ended = tokio::time::timeout(std::time::Duration::from_secs(5), findings.next()).await;
+        assert!(matches!(ended, Ok(None)));
+        findings.finish().await.unwrap();
+        std::fs::remove_file(&wordlist).unwrap();
+    }
+}
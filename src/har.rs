@@ -0,0 +1,352 @@
+use base64::Engine as _;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Body bytes kept per request and response by default
+pub const DEFAULT_MAX_BODY: &str = "64KB";
+
+/// A request as it went out, attached to its response so the finding it
+/// becomes can be archived with both sides
+#[derive(Debug, Clone)]
+pub struct Sent {
+    pub method: String,
+    pub url: String,
+    pub version: reqwest::Version,
+    /// Client defaults (persona, `-H`, `-b`) overlaid with the request's own
+    pub headers: HeaderMap,
+    pub body: Option<Vec<u8>>,
+    pub started_at: SystemTime,
+    pub started: Instant,
+}
+
+impl Sent {
+    pub fn new(defaults: HeaderMap, request: &reqwest::Request) -> Self {
+        let mut headers = defaults;
+        for (name, value) in request.headers() {
+            headers.insert(name, value.clone());
+        }
+        Sent {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            version: request.version(),
+            headers,
+            body: request.body().and_then(|body| body.as_bytes()).map(<[u8]>::to_vec),
+            started_at: SystemTime::now(),
+            started: Instant::now(),
+        }
+    }
+
+    /// `GET /path?query HTTP/1.1` and, when the request sets none, the
+    /// `Host` header it went out with, each ending in CRLF
+    pub fn request_line(&self) -> String {
+        let url = reqwest::Url::parse(&self.url).ok();
+        let target = url.as_ref().map_or("/".to_string(), |url| match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        });
+        let mut head = format!("{} {} {:?}\r\n", self.method, target, self.version);
+        if !self.headers.contains_key(reqwest::header::HOST) {
+            if let Some(host) = url.as_ref().and_then(|url| url.host_str()) {
+                match url.as_ref().and_then(|url| url.port()) {
+                    Some(port) => head.push_str(&format!("Host: {}:{}\r\n", host, port)),
+                    None => head.push_str(&format!("Host: {}\r\n", host)),
+                }
+            }
+        }
+        head
+    }
+}
+
+/// The response side of an entry
+#[derive(Clone, Copy)]
+pub struct Received<'a> {
+    pub status: u16,
+    pub version: reqwest::Version,
+    pub headers: &'a HeaderMap,
+    pub body: &'a [u8],
+    /// Where redirects ended up, when they were followed
+    pub final_url: &'a str,
+}
+
+/// HTTP Archive 1.2 log of every finding's request and response
+pub struct Recorder {
+    max_body: usize,
+    entries: Mutex<Vec<Entry>>,
+}
+
+#[derive(Serialize)]
+struct Har<'a> {
+    log: Log<'a>,
+}
+
+#[derive(Serialize)]
+struct Log<'a> {
+    version: &'static str,
+    creator: Creator,
+    entries: &'a [Entry],
+}
+
+#[derive(Serialize)]
+struct Creator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    started_date_time: String,
+    time: f64,
+    request: Request,
+    response: Response,
+    cache: Cache,
+    timings: Timings,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    http_version: String,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    query_string: Vec<NameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    post_data: Option<PostData>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PostData {
+    mime_type: String,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    status: u16,
+    status_text: String,
+    http_version: String,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    content: Content,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    body_size: i64,
+    /// The URL redirects ended at, which this response came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    size: u64,
+    mime_type: String,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Serialize)]
+struct NameValue {
+    name: String,
+    value: String,
+}
+
+#[derive(Serialize)]
+struct Cache {}
+
+#[derive(Serialize)]
+struct Timings {
+    send: f64,
+    wait: f64,
+    receive: f64,
+}
+
+impl Recorder {
+    /// Keep at most `max_body` bytes of each body (`0` = all of it)
+    pub fn new(max_body: u64) -> Self {
+        Recorder {
+            max_body: if max_body == 0 { usize::MAX } else { max_body as usize },
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn record(&self, sent: &Sent, received: Received) {
+        let millis = sent.started.elapsed().as_secs_f64() * 1_000.0;
+        let (text, encoding, comment) = self.capture(received.body);
+        let request_body = sent.body.as_deref().map(|body| {
+            let (text, _, comment) = self.capture(body);
+            PostData { mime_type: mime_type(&sent.headers), text, comment }
+        });
+        let query_string = reqwest::Url::parse(&sent.url)
+            .map(|url| url.query_pairs().map(|(name, value)| NameValue { name: name.into(), value: value.into() }).collect())
+            .unwrap_or_default();
+        let redirected = (received.final_url != sent.url).then(|| format!("after redirects to {}", received.final_url));
+
+        let entry = Entry {
+            started_date_time: crate::workspace::format_iso8601(
+                sent.started_at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64),
+            ),
+            time: millis,
+            request: Request {
+                method: sent.method.clone(),
+                url: sent.url.clone(),
+                http_version: format!("{:?}", sent.version),
+                cookies: Vec::new(),
+                headers: name_values(&sent.headers),
+                query_string,
+                body_size: sent.body.as_ref().map_or(0, |body| body.len() as i64),
+                post_data: request_body,
+                headers_size: -1,
+            },
+            response: Response {
+                status: received.status,
+                status_text: reqwest::StatusCode::from_u16(received.status).ok()
+                    .and_then(|status| status.canonical_reason())
+                    .unwrap_or_default()
+                    .to_string(),
+                http_version: format!("{:?}", received.version),
+                cookies: Vec::new(),
+                headers: name_values(received.headers),
+                content: Content {
+                    size: received.body.len() as u64,
+                    mime_type: mime_type(received.headers),
+                    text,
+                    encoding,
+                    comment,
+                },
+                redirect_url: received.headers.get(reqwest::header::LOCATION)
+                    .map(|location| String::from_utf8_lossy(location.as_bytes()).into_owned())
+                    .unwrap_or_default(),
+                headers_size: -1,
+                body_size: received.body.len() as i64,
+                comment: redirected,
+            },
+            cache: Cache {},
+            // Only the total is measured; it is all counted as waiting
+            timings: Timings { send: 0.0, wait: millis, receive: 0.0 },
+        };
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// The archive as JSON, entries in the order their requests started
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.sort_by(|a, b| a.started_date_time.cmp(&b.started_date_time));
+        let har = Har {
+            log: Log {
+                version: "1.2",
+                creator: Creator { name: "dir_crawler", version: env!("CARGO_PKG_VERSION") },
+                entries: &entries,
+            },
+        };
+        serde_json::to_string_pretty(&har)
+    }
+
+    pub fn count(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// A body cut to the size limit: text as is, anything else base64
+    /// encoded, with a comment when bytes were left out
+    fn capture(&self, body: &[u8]) -> (String, Option<&'static str>, Option<String>) {
+        let kept = &body[..body.len().min(self.max_body)];
+        let comment = (kept.len() < body.len()).then(|| format!("truncated to {} of {} bytes", kept.len(), body.len()));
+        let (text, encoding) = match std::str::from_utf8(kept) {
+            Ok(text) => (text.to_string(), None),
+            // The cut landed inside a multi-byte character
+            Err(e) if e.error_len().is_none() => (String::from_utf8_lossy(&kept[..e.valid_up_to()]).into_owned(), None),
+            Err(_) => (base64::engine::general_purpose::STANDARD.encode(kept), Some("base64")),
+        };
+        (text, encoding, comment)
+    }
+}
+
+fn name_values(headers: &HeaderMap) -> Vec<NameValue> {
+    headers.iter()
+        .map(|(name, value)| NameValue {
+            name: name.to_string(),
+            value: String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        })
+        .collect()
+}
+
+fn mime_type(headers: &HeaderMap) -> String {
+    headers.get(reqwest::header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, CONTENT_TYPE};
+
+    fn sent(body: Option<&[u8]>) -> Sent {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-role", HeaderValue::from_static("admin"));
+        Sent {
+            method: "POST".to_string(),
+            url: "http://example.com/api/users?id=1&q=a%20b".to_string(),
+            version: reqwest::Version::HTTP_11,
+            headers,
+            body: body.map(<[u8]>::to_vec),
+            started_at: UNIX_EPOCH + std::time::Duration::from_millis(1_700_000_000_123),
+            started: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn archives_both_sides_of_a_finding() {
+        let recorder = Recorder::new(1024);
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        recorder.record(&sent(Some(b"name=x")), Received {
+            status: 200,
+            version: reqwest::Version::HTTP_11,
+            headers: &headers,
+            body: br#"{"users":[]}"#,
+            final_url: "http://example.com/api/users?id=1&q=a%20b",
+        });
+
+        let har: serde_json::Value = serde_json::from_str(&recorder.to_json().unwrap()).unwrap();
+        let entry = &har["log"]["entries"][0];
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(entry["startedDateTime"], "2023-11-14T22:13:20.123Z");
+        assert_eq!(entry["request"]["method"], "POST");
+        assert_eq!(entry["request"]["httpVersion"], "HTTP/1.1");
+        assert_eq!(entry["request"]["headers"][0]["name"], "x-role");
+        assert_eq!(entry["request"]["queryString"][1]["value"], "a b");
+        assert_eq!(entry["request"]["postData"]["text"], "name=x");
+        assert_eq!(entry["response"]["statusText"], "OK");
+        assert_eq!(entry["response"]["content"]["mimeType"], "application/json");
+        assert_eq!(entry["response"]["content"]["text"], r#"{"users":[]}"#);
+        assert_eq!(entry["response"]["redirectURL"], "");
+        assert!(entry["response"].get("comment").is_none());
+    }
+
+    #[test]
+    fn caps_bodies_and_encodes_binary() {
+        let recorder = Recorder::new(4);
+        assert_eq!(recorder.capture(b"hello world"), ("hell".to_string(), None, Some("truncated to 4 of 11 bytes".to_string())));
+        assert_eq!(recorder.capture("héllo".as_bytes()).0, "h\u{e9}l");
+        assert_eq!(recorder.capture("aaé".as_bytes()).0, "aaé");
+        assert_eq!(recorder.capture("aaaé".as_bytes()).0, "aaa");
+        assert_eq!(recorder.capture(&[0x89, b'P', 0xff]), ("iVD/".to_string(), Some("base64"), None));
+        assert_eq!(Recorder::new(0).capture(&[b'x'; 100_000]).0.len(), 100_000);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::plugin::{Exchange, ScanPlugin};
+use crate::Finding;
+use reqwest::header::{HeaderMap, CONTENT_TYPE, SET_COOKIE};
+use serde::{Deserialize, Serialize};
+
+/// Shortest HSTS `max-age` not flagged as weak: 180 days
+const MIN_HSTS_MAX_AGE: u64 = 180 * 24 * 3600;
+
+/// A missing or weak security header on a finding
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HeaderIssue {
+    /// Stable name of the check, e.g. `csp-missing`
+    pub check: String,
+    pub detail: String,
+}
+
+impl HeaderIssue {
+    fn new(check: &str, detail: impl Into<String>) -> Self {
+        HeaderIssue { check: check.to_string(), detail: detail.into() }
+    }
+}
+
+/// Security headers a response should carry but doesn't, or carries in a
+/// form that doesn't protect much. Pages get the CSP and framing checks;
+/// every HTTPS response the HSTS check; error responses only the cookie one.
+pub fn audit(url: &str, status: u16, headers: &HeaderMap) -> Vec<HeaderIssue> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).map(str::trim);
+    let https = url.starts_with("https://");
+    let html = header(CONTENT_TYPE.as_str()).is_some_and(|ct| ct.contains("html"));
+    let mut issues = Vec::new();
+
+    if status < 400 {
+        let csp = header("content-security-policy");
+        if html {
+            match csp {
+                None if header("content-security-policy-report-only").is_some() => {
+                    issues.push(HeaderIssue::new("csp-missing", "only Content-Security-Policy-Report-Only, which enforces nothing"));
+                }
+                None => issues.push(HeaderIssue::new("csp-missing", "no Content-Security-Policy")),
+                Some(csp) => issues.extend(weak_csp(csp).map(|detail| HeaderIssue::new("csp-weak", detail))),
+            }
+
+            let frame_ancestors = csp.is_some_and(|csp| directive(csp, "frame-ancestors").is_some());
+            match header("x-frame-options") {
+                Some(value) if value.eq_ignore_ascii_case("deny") || value.eq_ignore_ascii_case("sameorigin") => {}
+                Some(value) if !frame_ancestors => {
+                    issues.push(HeaderIssue::new("frame-weak", format!("X-Frame-Options '{}' is not DENY or SAMEORIGIN", value)));
+                }
+                None if !frame_ancestors => {
+                    issues.push(HeaderIssue::new("frame-missing", "no X-Frame-Options or CSP frame-ancestors"));
+                }
+                _ => {}
+            }
+        }
+
+        if (200..300).contains(&status) && !header("x-content-type-options").is_some_and(|v| v.eq_ignore_ascii_case("nosniff")) {
+            issues.push(HeaderIssue::new("nosniff-missing", "no X-Content-Type-Options: nosniff"));
+        }
+
+        if https {
+            match header("strict-transport-security") {
+                None => issues.push(HeaderIssue::new("hsts-missing", "no Strict-Transport-Security")),
+                Some(hsts) => {
+                    let max_age = hsts.split(';')
+                        .filter_map(|part| part.trim().split_once('='))
+                        .find(|(name, _)| name.trim().eq_ignore_ascii_case("max-age"))
+                        .and_then(|(_, value)| value.trim().trim_matches('"').parse::<u64>().ok());
+                    match max_age {
+                        Some(age) if age >= MIN_HSTS_MAX_AGE => {}
+                        Some(age) => issues.push(HeaderIssue::new("hsts-weak", format!("max-age={} is under 180 days", age))),
+                        None => issues.push(HeaderIssue::new("hsts-weak", format!("no valid max-age in '{}'", hsts))),
+                    }
+                }
+            }
+        }
+    }
+
+    for cookie in headers.get_all(SET_COOKIE).iter().filter_map(|value| value.to_str().ok()) {
+        let mut parts = cookie.split(';');
+        let name = parts.next().and_then(|pair| pair.split_once('=')).map_or("?", |(name, _)| name.trim());
+        let attributes: Vec<String> = parts
+            .map(|part| part.split('=').next().unwrap_or_default().trim().to_ascii_lowercase())
+            .collect();
+        let missing: Vec<&str> = [("secure", "Secure", https), ("httponly", "HttpOnly", true), ("samesite", "SameSite", true)]
+            .into_iter()
+            .filter(|(attribute, _, required)| *required && !attributes.iter().any(|a| a == attribute))
+            .map(|(_, label, _)| label)
+            .collect();
+        if !missing.is_empty() {
+            issues.push(HeaderIssue::new("cookie-flags", format!("cookie '{}' without {}", name, missing.join(", "))));
+        }
+    }
+    issues
+}
+
+/// Findings per failed check, most common first, each with the first
+/// finding that failed it as an example
+pub fn summary(findings: &[Finding]) -> Vec<(&str, usize, &Finding, &HeaderIssue)> {
+    let mut rows: Vec<(&str, usize, &Finding, &HeaderIssue)> = Vec::new();
+    for finding in findings {
+        let mut seen = std::collections::HashSet::new();
+        for issue in &finding.header_issues {
+            // A page with three weak cookies counts once for the check
+            if !seen.insert(issue.check.as_str()) {
+                continue;
+            }
+            match rows.iter_mut().find(|row| row.0 == issue.check) {
+                Some(row) => row.1 += 1,
+                None => rows.push((&issue.check, 1, finding, issue)),
+            }
+        }
+    }
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    rows
+}
+
+/// The sources of one CSP directive
+fn directive<'a>(csp: &'a str, name: &str) -> Option<Vec<&'a str>> {
+    csp.split(';')
+        .map(|part| part.split_whitespace().collect::<Vec<_>>())
+        .find(|words| words.first().is_some_and(|first| first.eq_ignore_ascii_case(name)))
+        .map(|words| words[1..].to_vec())
+}
+
+/// What lets scripts through a policy anyway
+fn weak_csp(csp: &str) -> Option<String> {
+    let Some((name, sources)) = directive(csp, "script-src")
+        .map(|sources| ("script-src", sources))
+        .or_else(|| directive(csp, "default-src").map(|sources| ("default-src", sources)))
+    else {
+        return Some("no script-src or default-src".to_string());
+    };
+    // Nonces and hashes make browsers ignore 'unsafe-inline'
+    let pinned = sources.iter().any(|s| s.starts_with("'nonce-") || s.starts_with("'sha"));
+    let weak: Vec<&str> = sources.iter()
+        .copied()
+        .filter(|s| {
+            (*s == "'unsafe-inline'" && !pinned)
+                || *s == "'unsafe-eval'"
+                || matches!(*s, "*" | "data:" | "http:" | "https:")
+        })
+        .collect();
+    (!weak.is_empty()).then(|| format!("{} allows {}", name, weak.join(" ")))
+}
+
+/// `--audit-headers`: missing and weak security headers on every finding
+pub struct HeaderAudit;
+
+impl ScanPlugin for HeaderAudit {
+    fn name(&self) -> &'static str {
+        "header-audit"
+    }
+
+    fn on_response(&self, exchange: &Exchange, finding: &mut Finding) {
+        finding.header_issues = audit(&finding.url, finding.status, exchange.headers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn checks(issues: &[HeaderIssue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.check.as_str()).collect()
+    }
+
+    #[test]
+    fn flags_missing_headers_and_cookie_flags() {
+        let bare = headers(&[("content-type", "text/html"), ("set-cookie", "session=abc; Path=/; HttpOnly")]);
+        let issues = audit("https://t/admin", 200, &bare);
+        assert_eq!(checks(&issues), ["csp-missing", "frame-missing", "nosniff-missing", "hsts-missing", "cookie-flags"]);
+        assert_eq!(issues[4].detail, "cookie 'session' without Secure, SameSite");
+
+        // Plain HTTP has no HSTS or Secure to ask for; errors only get the cookie check
+        assert_eq!(checks(&audit("http://t/admin", 200, &bare)), ["csp-missing", "frame-missing", "nosniff-missing", "cookie-flags"]);
+        assert_eq!(checks(&audit("https://t/missing", 404, &bare)), ["cookie-flags"]);
+    }
+
+    #[test]
+    fn flags_weak_values() {
+        let weak = headers(&[
+            ("content-type", "text/html; charset=utf-8"),
+            ("content-security-policy", "default-src 'self'; script-src 'self' 'unsafe-inline' https:"),
+            ("x-frame-options", "ALLOW-FROM https://partner.example"),
+            ("x-content-type-options", "nosniff"),
+            ("strict-transport-security", "max-age=86400; includeSubDomains"),
+        ]);
+        let issues = audit("https://t/", 200, &weak);
+        assert_eq!(checks(&issues), ["csp-weak", "frame-weak", "hsts-weak"]);
+        assert_eq!(issues[0].detail, "script-src allows 'unsafe-inline' https:");
+
+        let strong = headers(&[
+            ("content-type", "text/html"),
+            ("content-security-policy", "script-src 'nonce-r4nd' 'unsafe-inline'; frame-ancestors 'none'"),
+            ("x-content-type-options", "nosniff"),
+            ("strict-transport-security", "max-age=31536000"),
+            ("set-cookie", "id=1; Secure; HttpOnly; SameSite=Lax"),
+        ]);
+        assert!(audit("https://t/", 200, &strong).is_empty());
+    }
+
+    #[test]
+    fn summarizes_by_check() {
+        let finding = |url: &str, checks: &[&str]| -> Finding {
+            let issues: Vec<_> = checks.iter().map(|check| serde_json::json!({ "check": check, "detail": url })).collect();
+            serde_json::from_value(serde_json::json!({ "url": url, "status": 200, "header_issues": issues })).unwrap()
+        };
+        let findings = [
+            finding("http://t/a", &["csp-missing", "cookie-flags", "cookie-flags"]),
+            finding("http://t/b", &["cookie-flags"]),
+            finding("http://t/c", &[]),
+        ];
+        let rows: Vec<_> = summary(&findings).into_iter().map(|(check, count, example, _)| (check, count, example.url.as_str())).collect();
+        assert_eq!(rows, [("cookie-flags", 2, "http://t/a"), ("csp-missing", 1, "http://t/a")]);
+    }
+}
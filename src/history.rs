@@ -0,0 +1,105 @@
+use crate::error::CrawlerError;
+use crate::output::ScanReport;
+use crate::term;
+use crate::workspace::{self, ScanSummary, Workspace};
+use colored::*;
+use std::path::Path;
+
+
+/// Every workspace under `root` that holds a finished scan, newest first
+fn past_scans(root: &Path) -> Vec<(Workspace, ScanSummary)> {
+    let Ok(dirs) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+
+    let mut scans: Vec<_> = dirs
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let ws = Workspace {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                dir: entry.path(),
+            };
+            ws.read_summary().map(|summary| (ws, summary))
+        })
+        .collect();
+    scans.sort_by_key(|(_, summary)| std::cmp::Reverse(summary.started_at));
+    scans
+}
+
+/// Print one line per past scan: id, date, target, hits and duration
+pub fn list(root: &Path) {
+    let scans = past_scans(root);
+    if scans.is_empty() {
+        term::out!("📭 No past scans in {}", root.display());
+        return;
+    }
+
+    term::out!(
+        "{:<40} {:<20} {:>6} {:>9}  {}",
+        "ID".bold(),
+        "DATE (UTC)".bold(),
+        "HITS".bold(),
+        "DURATION".bold(),
+        "TARGET".bold()
+    );
+    for (_, summary) in scans {
+        term::out!(
+            "{:<40} {:<20} {:>6} {:>8.1}s  {}",
+            summary.name.cyan(),
+            workspace::format_datetime(summary.started_at),
+            summary.hits.to_string().green(),
+            summary.duration_secs,
+            summary.target
+        );
+    }
+}
+
+/// Print a past scan's summary and, when its results are readable, its findings.
+/// `id` may be a full workspace name or a unique prefix of one.
+pub fn show(root: &Path, id: &str) -> Result<(), CrawlerError> {
+    let scans = past_scans(root);
+    let exact = scans.iter().find(|(_, s)| s.name == id);
+    let (ws, summary) = match exact {
+        Some(scan) => scan,
+        None => {
+            let matching: Vec<_> = scans.iter().filter(|(_, s)| s.name.starts_with(id)).collect();
+            match matching[..] {
+                [scan] => scan,
+                [] => return Err(format!("No past scan '{}' in {}", id, root.display()).into()),
+                _ => return Err(format!("'{}' matches {} scans; use a longer ID", id, matching.len()).into()),
+            }
+        }
+    };
+
+    term::out!("🗂️  Scan: {}", summary.name.cyan());
+    term::out!("🌐 Target: {}", summary.target.green());
+    term::out!("🆔 Scan ID: {}", summary.scan_id);
+    term::out!("📅 Started: {} UTC", workspace::format_datetime(summary.started_at));
+    term::out!("⏱️  Duration: {:.2} seconds", summary.duration_secs);
+    term::out!("🎯 Hits: {} | ❌ Errors: {}", summary.hits.to_string().green(), summary.errors);
+    term::out!("📁 Workspace: {}", ws.dir.display().to_string().blue());
+
+    // Findings are only listed from plain JSON results or a results
+    // database; encrypted or Markdown reports are left for the user to open
+    let results = ws.dir.join(crate::output::OutputFormat::Json.default_file_name());
+    let database = ws.dir.join(crate::output::OutputFormat::Sqlite.default_file_name());
+    let findings = std::fs::read(&results)
+        .ok()
+        .and_then(|data| serde_json::from_slice::<ScanReport>(&data).ok())
+        .map(|report| report.findings)
+        .or_else(|| crate::sqlite::findings(&database, &summary.scan_id).ok().flatten());
+    if let Some(findings) = findings {
+        if !findings.is_empty() {
+            term::err!("\n🎉 Found Paths:");
+            for finding in &findings {
+                if finding.tags.is_empty() {
+                    term::out!("{} (Status: {})", finding.url, finding.status);
+                } else {
+                    term::out!("{} (Status: {}) [{}]", finding.url, finding.status, finding.tags.join(", "));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
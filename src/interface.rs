@@ -0,0 +1,155 @@
+//! The local address requests go out from, for `--source-ip` and
+//! `--interface` on hosts with more than one way out.
+
+use crate::dns::AddressFamily;
+use crate::error::CrawlerError;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+
+/// The address of interface `name` to send from: of `family` when one is
+/// chosen, else of the family the target resolved to (`target`), and
+/// globally routable before link-local. A link-local IPv6 address is never
+/// picked: it can't be bound without its scope.
+pub fn address(name: &str, family: AddressFamily, target: &[IpAddr]) -> Result<IpAddr, CrawlerError> {
+    let fail = |reason: String| CrawlerError::Interface { name: name.to_string(), reason };
+    let addresses = addresses(name).map_err(|e| fail(e.to_string()))?;
+    if addresses.is_empty() {
+        return Err(fail("no such interface, or it has no IP address".to_string()));
+    }
+    pick(&addresses, family, target).map_err(fail)
+}
+
+fn pick(addresses: &[IpAddr], family: AddressFamily, target: &[IpAddr]) -> Result<IpAddr, String> {
+    let describe = |ipv6: bool| if ipv6 { "IPv6" } else { "IPv4" };
+    let usable: Vec<IpAddr> = addresses.iter()
+        .copied()
+        .filter(|address| !(address.is_ipv6() && is_link_local(address)))
+        .collect();
+    let has = |ipv6: bool| usable.iter().any(|address| address.is_ipv6() == ipv6);
+    let ipv6 = match family {
+        AddressFamily::Ipv4 => false,
+        AddressFamily::Ipv6 => true,
+        // The first of the target's addresses this interface can reach
+        AddressFamily::Any => match target.iter().find(|address| has(address.is_ipv6())) {
+            Some(address) => address.is_ipv6(),
+            None if !target.is_empty() => {
+                return Err(format!("no {} address to reach the target from", describe(target[0].is_ipv6())));
+            }
+            None if has(false) && has(true) => {
+                return Err("it has IPv4 and IPv6 addresses and the target's family is unknown; pick one with --ipv4-only or --ipv6-only".to_string());
+            }
+            None => has(true),
+        },
+    };
+    usable.iter()
+        .copied()
+        .filter(|address| address.is_ipv6() == ipv6)
+        .min_by_key(is_link_local)
+        .ok_or_else(|| match addresses.iter().any(|address| address.is_ipv6() == ipv6) {
+            true => format!("it has only a link-local {} address, which can't be bound without its scope", describe(ipv6)),
+            false => format!("no {} address", describe(ipv6)),
+        })
+}
+
+/// Fail now, rather than on every request, when `address` isn't one of
+/// this host's
+pub fn check(address: IpAddr) -> Result<(), CrawlerError> {
+    UdpSocket::bind(SocketAddr::new(address, 0))
+        .map(drop)
+        .map_err(|source| CrawlerError::Source { address, source })
+}
+
+fn is_link_local(address: &IpAddr) -> bool {
+    match address {
+        IpAddr::V4(v4) => v4.is_link_local(),
+        IpAddr::V6(v6) => v6.segments()[0] & 0xffc0 == 0xfe80,
+    }
+}
+
+/// Every IP address of interface `name`
+#[cfg(unix)]
+fn addresses(name: &str) -> std::io::Result<Vec<IpAddr>> {
+    use std::ffi::CStr;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    let mut head: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: getifaddrs fills `head` with a list we free below
+    if unsafe { libc::getifaddrs(&mut head) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut addresses = Vec::new();
+    let mut cursor = head;
+    while !cursor.is_null() {
+        // SAFETY: every node of the list, its name and its address stay
+        // valid until freeifaddrs, and the address is read as the type its
+        // family says it is
+        unsafe {
+            let entry = &*cursor;
+            cursor = entry.ifa_next;
+            if entry.ifa_addr.is_null() || CStr::from_ptr(entry.ifa_name).to_bytes() != name.as_bytes() {
+                continue;
+            }
+            match i32::from((*entry.ifa_addr).sa_family) {
+                libc::AF_INET => {
+                    let address = &*(entry.ifa_addr as *const libc::sockaddr_in);
+                    addresses.push(IpAddr::V4(Ipv4Addr::from(u32::from_be(address.sin_addr.s_addr))));
+                }
+                libc::AF_INET6 => {
+                    let address = &*(entry.ifa_addr as *const libc::sockaddr_in6);
+                    addresses.push(IpAddr::V6(Ipv6Addr::from(address.sin6_addr.s6_addr)));
+                }
+                _ => {}
+            }
+        }
+    }
+    // SAFETY: `head` came from getifaddrs and nothing borrowed from it is left
+    unsafe { libc::freeifaddrs(head) };
+    Ok(addresses)
+}
+
+#[cfg(not(unix))]
+fn addresses(_name: &str) -> std::io::Result<Vec<IpAddr>> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "--interface is only supported on Unix; use --source-ip"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_an_address_of_the_interface() {
+        // Loopback is `lo` on Linux and `lo0` on the BSDs and macOS
+        let (name, loopback) = ["lo", "lo0"].iter()
+            .find_map(|name| address(name, AddressFamily::Ipv4, &[]).ok().map(|loopback| (*name, loopback)))
+            .expect("a loopback interface");
+        assert!(loopback.is_loopback());
+        assert!(check(loopback).is_ok());
+        assert!(matches!(address(name, AddressFamily::Any, &[loopback]), Ok(IpAddr::V4(_))));
+
+        assert!(matches!(address("no-such-interface0", AddressFamily::Any, &[]), Err(CrawlerError::Interface { .. })));
+        // TEST-NET-3, not an address of any host running the tests
+        assert!(matches!(check("203.0.113.7".parse().unwrap()), Err(CrawlerError::Source { .. })));
+    }
+
+    #[test]
+    fn picks_the_family_the_target_resolved_to() {
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+        let dual = [ip("192.0.2.10"), ip("fe80::1"), ip("2001:db8::10")];
+        assert_eq!(pick(&dual, AddressFamily::Any, &[ip("2001:db8::80")]), Ok(ip("2001:db8::10")));
+        assert_eq!(pick(&dual, AddressFamily::Any, &[ip("198.51.100.80")]), Ok(ip("192.0.2.10")));
+        // The first of the target's families the interface has
+        assert_eq!(pick(&[ip("192.0.2.10")], AddressFamily::Any, &[ip("2001:db8::80"), ip("198.51.100.80")]), Ok(ip("192.0.2.10")));
+        assert!(pick(&[ip("192.0.2.10")], AddressFamily::Any, &[ip("2001:db8::80")]).is_err());
+        // No quiet preference when the target's family isn't known
+        assert!(pick(&dual, AddressFamily::Any, &[]).is_err());
+        assert_eq!(pick(&[ip("169.254.0.5"), ip("192.0.2.10")], AddressFamily::Any, &[]), Ok(ip("192.0.2.10")));
+    }
+
+    #[test]
+    fn never_binds_a_link_local_ipv6_address() {
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+        let link_local = [ip("192.0.2.10"), ip("fe80::1")];
+        assert!(pick(&link_local, AddressFamily::Ipv6, &[]).unwrap_err().contains("link-local"));
+        assert!(pick(&link_local, AddressFamily::Any, &[ip("2001:db8::80")]).is_err());
+        assert_eq!(pick(&link_local, AddressFamily::Any, &[]), Ok(ip("192.0.2.10")));
+    }
+}
@@ -0,0 +1,2887 @@
+use cli::{Commands, HistoryCommand, ReportCommand, WordlistsCommand};
+use colored::*;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use reqwest::{Client, Method, Url};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use serde::{Deserialize, Serialize};
+pub use error::CrawlerError;
+use wordlist::Entries;
+
+pub use config::FuzzerConfigBuilder;
+pub use dns::AddressFamily;
+pub use fuzzer::{Findings, Fuzzer};
+pub use progress::ProgressEvent;
+pub use secrets::Severity;
+pub use tokio_util::sync::CancellationToken;
+
+mod term;
+mod apispec;
+mod audit;
+mod bandwidth;
+mod bench;
+mod buckets;
+mod calibrate;
+mod chat;
+mod cli;
+mod conditional;
+mod config;
+mod cors;
+mod crypto;
+mod csrf;
+mod distributed;
+mod dryrun;
+mod dns;
+mod dump;
+mod engine;
+mod error;
+mod exit;
+mod exposure;
+mod favicon;
+mod frontier;
+mod fuzzer;
+mod har;
+mod headers;
+mod history;
+mod interface;
+mod logging;
+mod methods;
+mod metrics;
+mod mutate;
+mod nmap;
+mod notify;
+mod output;
+mod persona;
+mod plugin;
+mod policy;
+mod preflight;
+mod progress;
+mod redirect;
+mod redact;
+mod replay;
+mod resume;
+mod review;
+mod sarif;
+mod scope;
+mod screenshot;
+mod script;
+mod secrets;
+mod session;
+mod severity;
+mod signing;
+mod spray;
+mod stealth;
+mod simhash;
+mod sink;
+mod sqlite;
+mod store;
+mod subdomains;
+mod targets;
+mod template;
+mod throttle;
+mod waf;
+mod warc;
+mod wordlist;
+mod wordgen;
+mod workspace;
+
+/// Generate URLs with extensions
+fn generate_urls(base_url: &str, path: &str, extensions: &[String]) -> HashSet<String> {
+    let mut urls = HashSet::new();
+
+    // Normalize base URL to ensure consistent trailing slash
+    let normalized_base_url = base_url.trim_end_matches('/') // Remove any trailing slashes
+        .to_owned() + "/"; // Add a single trailing slash
+
+    // Entries may be subpaths (`admin/config`) with stray slashes, and
+    // characters like `#` and `?` that would end the path early
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let normalized_path = encode_path(&segments.join("/"));
+
+    // Add base path variations
+    urls.insert(format!("{}{}", normalized_base_url, normalized_path));
+
+    // Add URLs with extensions
+    if !extensions.is_empty() {
+        for ext in extensions {
+            urls.insert(format!(
+                "{}{}.{}",
+                normalized_base_url,
+                normalized_path,
+                ext.trim_matches('.')
+            ));
+        }
+    }
+
+    urls
+}
+
+/// Percent-encode what can't appear as-is in a URL path (spaces, `#`, `?`,
+/// `%` on its own, non-ASCII, ...), leaving `/` and existing `%XX` escapes
+fn encode_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut encoded = String::with_capacity(path.len());
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b'!' | b'$' | b'&'
+            | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' | b':' | b'@' => encoded.push(b as char),
+            b'%' if bytes.get(i + 1..i + 3).is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) => encoded.push('%'),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+/// Exact number of requests `generate_urls` produces for a wordlist under one directory
+fn count_urls(base_url: &str, entries: &[String], extensions: &[String]) -> u64 {
+    entries
+        .iter()
+        .map(|entry| generate_urls(base_url, entry, extensions).len() as u64)
+        .sum()
+}
+
+/// A single matched path reported by a scan
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Finding {
+    pub url: String,
+    /// Value substituted for the request template's FUZZ marker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<String>,
+    pub status: u16,
+    /// Bytes of body read, up to `--max-body-size`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Likely importance, from `score`
+    #[serde(default)]
+    pub severity: secrets::Severity,
+    /// Triage score from the severity rules; higher is more interesting
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub score: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_file: Option<String>,
+    /// Screenshot file name in the `--screenshots` directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<secrets::SecretMatch>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<Variant>,
+    /// Fuzzy fingerprint of the body, used by `--unique`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simhash: Option<u64>,
+    /// Near-identical findings collapsed into this one
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub duplicates: usize,
+    /// Triage verdict from `review`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<review::Verdict>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    /// `<title>` of an HTML page, from `--titles`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// `Last-Modified` header as the server sent it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// Missing or weak security headers, from `--audit-headers`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub header_issues: Vec<headers::HeaderIssue>,
+    /// How the URL answered the methods of `--probe-methods`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<methods::MethodResponse>,
+    /// Credential a Basic auth prompt accepted, from `--spray-basic-auth`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials: Option<spray::Credential>,
+}
+
+impl Finding {
+    /// URL as shown to the user, qualified by the payload when it isn't part of the URL
+    fn label(&self) -> String {
+        display_url(&self.url, self.payload.as_deref())
+    }
+
+    /// Unix time the server says the resource last changed
+    fn modified(&self) -> Option<u64> {
+        self.last_modified.as_deref().and_then(workspace::parse_http_date)
+    }
+}
+
+/// Name a probe by its URL, adding the template payload when only headers or body carry it
+fn display_url(url: &str, payload: Option<&str>) -> String {
+    match payload {
+        Some(payload) if !template::shown_in(url, payload) => format!("{} [{}]", url, template::describe(payload)),
+        _ => url.to_string(),
+    }
+}
+
+fn is_zero<T: Default + PartialEq>(n: &T) -> bool {
+    *n == T::default()
+}
+
+/// How a candidate responded under one side of a differential scan
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Variant {
+    pub label: String,
+    pub status: u16,
+    pub size: u64,
+    /// Where the request ended up when redirects moved it elsewhere
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>,
+}
+
+/// Enhanced fuzzer configuration to include extensions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzerConfig {
+    url: String,
+    /// Every base URL to fuzz when the target was an address range or Nmap
+    /// results; empty for just `url`
+    targets: Vec<String>,
+    /// Web services Nmap found behind `targets`, for the report
+    services: Vec<nmap::Service>,
+    /// Threads, delays and headers set for single targets by `--targets`
+    target_overrides: Vec<targets::TargetOverride>,
+    wordlist: PathBuf,
+    /// Wordlist entries skipped before scanning
+    wordlist_offset: usize,
+    /// Most wordlist entries scanned after the offset
+    wordlist_limit: Option<usize>,
+    /// Scan the wordlist in random order
+    shuffle: bool,
+    /// Client fingerprint and request jitter of `--stealth`
+    stealth: Option<stealth::Fingerprint>,
+    /// Variants generated for each wordlist entry
+    mutations: Option<mutate::Mutations>,
+    /// Crawl the target first and add the words it uses to the wordlist
+    learn: bool,
+    /// Probe for Swagger/OpenAPI and GraphQL endpoints and scan what they list
+    api_discovery: bool,
+    /// Query parameter whose value is fuzzed, with reflection and change detection
+    fuzz_param: Option<String>,
+    /// Wordlists for FUZZ2, FUZZ3, ... (FUZZ1 takes the main wordlist)
+    position_wordlists: Vec<PathBuf>,
+    /// How position wordlists are combined
+    fuzz_mode: template::Mode,
+    /// Wordlist entries are keywords for cloud storage bucket names
+    buckets: bool,
+    threads: usize,
+    silent: bool,
+    status_codes: Vec<u16>,
+    /// Overall cap on a single request in seconds, if any
+    timeout: Option<u64>,
+    /// Seconds allowed to establish a connection
+    connect_timeout: u64,
+    /// Seconds a server may stay silent while we wait for or read its response
+    read_timeout: u64,
+    /// Times a request that was refused or timed out is sent again
+    retries: u32,
+    /// Stop reading a response body after this many bytes, if limited
+    max_body_size: Option<u64>,
+    /// Cap on requests in flight to one host; `None` leaves it to `threads`
+    max_connections_per_host: Option<usize>,
+    /// Response bytes per second the whole scan may download, if capped
+    max_bandwidth: Option<u64>,
+    /// Seconds an idle pooled connection is kept open
+    pool_idle_timeout: u64,
+    /// TCP keepalive interval in seconds (0 disables it)
+    tcp_keepalive: u64,
+    #[serde(with = "method_serde")]
+    method: Method,
+    /// Raw request whose FUZZ marker is filled with each wordlist entry
+    request_template: Option<template::RequestTemplate>,
+    extensions: Vec<String>,
+    detect_language: bool,
+    scan_id: String,
+    request_id_header: Option<String>,
+    scope_include: Vec<String>,
+    scope_exclude: Vec<String>,
+    blocklist: Vec<String>,
+    adaptive_throttle: bool,
+    output: Option<PathBuf>,
+    format: output::OutputFormat,
+    /// Status ranges `--format urls` lists; empty for every finding
+    urls_status: Vec<(u16, u16)>,
+    /// Only report responses with one of these media types, if any are given
+    match_content_types: Vec<String>,
+    /// Never report responses with one of these media types
+    filter_content_types: Vec<String>,
+    store_responses: Option<PathBuf>,
+    store_headers: bool,
+    /// HTTP Archive of every finding's request and response
+    har: Option<PathBuf>,
+    /// Body bytes kept per HAR entry (`0` = all)
+    har_max_body: u64,
+    /// WARC capture of every finding, gzipped per record for `.gz` paths
+    warc: Option<PathBuf>,
+    sign_key: Option<PathBuf>,
+    detect_secrets: bool,
+    secret_rules: Vec<String>,
+    /// Pull the `<title>` out of HTML findings
+    extract_titles: bool,
+    /// Check findings for missing or weak security headers
+    audit_headers: bool,
+    /// Origin each finding is requested again from, to check its CORS policy
+    cors_origin: Option<String>,
+    /// Test redirecting findings for parameters that set their target
+    open_redirects: bool,
+    /// Methods each finding is requested again with
+    probe_methods: Vec<String>,
+    /// Credentials tried against Basic auth prompts
+    spray_credentials: Vec<spray::Credential>,
+    /// Seconds between two attempts against the same realm
+    spray_delay: u64,
+    /// Directory for headless Chromium screenshots of findings
+    screenshots: Option<PathBuf>,
+    /// Body bytes previewed under each hit, with `-v`
+    snippet_bytes: Option<usize>,
+    /// Requests printed in full, with `-vvv`
+    dump: Option<dump::Sample>,
+    /// Order findings are listed and reported in
+    sort: output::SortOrder,
+    /// Rhai script hooked into requests, matching and findings
+    script: Option<PathBuf>,
+    /// Extra `field|regex|points` rules scoring findings' severity
+    severity_rules: Vec<String>,
+    persona: Option<String>,
+    headers: Vec<(String, String)>,
+    cookie: Option<String>,
+    /// Host header presented instead of the URL's host
+    host_header: Option<String>,
+    /// Hostnames pinned to fixed addresses, like curl's `--resolve`
+    resolve: Vec<(String, SocketAddr)>,
+    /// Connect over IPv4 or IPv6 only, or whichever answers first
+    address_family: dns::AddressFamily,
+    /// Local address requests go out from, if not the system's choice
+    source_ip: Option<IpAddr>,
+    /// DNS server or DoH URL used instead of the system resolver
+    dns_resolver: Option<String>,
+    differential: Option<Differential>,
+    recursion_depth: usize,
+    recursion_strategy: RecursionStrategy,
+    /// Directories scanned per recursion level before the rest are left on the frontier
+    max_dirs_per_level: Option<usize>,
+    auto_calibrate: bool,
+    unique: bool,
+    /// Probe every directory for exposed VCS metadata and dotfiles
+    vcs_checks: bool,
+    /// Check the target answers, and how fast, before scanning
+    preflight: bool,
+    /// The URL was typed without a scheme: try HTTPS, then HTTP
+    detect_scheme: bool,
+    /// Look for a WAF or CDN in front of the target before scanning
+    waf_check: bool,
+    /// Switch to stealthier settings when one is found
+    waf_adapt: bool,
+    /// Hash each target's favicon and name the product it belongs to
+    favicon: bool,
+    /// Re-request every finding slowly from a fresh client before reporting
+    reconfirm: bool,
+    /// Pause between re-confirmation requests, in milliseconds
+    reconfirm_delay: u64,
+    /// Stop the scan after this many seconds
+    max_time: Option<u64>,
+    /// Stop the scan once this many requests have failed
+    max_errors: Option<u64>,
+    /// Stop the scan once this many findings are recorded
+    max_findings: Option<u64>,
+    /// Consecutive failed requests after which a host's remaining queue is skipped
+    host_error_threshold: Option<u32>,
+    /// List the requests the scan would send instead of sending them
+    dry_run: bool,
+    /// File the dry-run list is written to instead of stdout
+    dry_run_file: Option<PathBuf>,
+    /// Desktop notification when the scan ends
+    notify: bool,
+    /// JUnit XML of the `--fail-on` policy check, for CI dashboards
+    junit: Option<PathBuf>,
+}
+
+impl Default for FuzzerConfig {
+    /// The settings a bare `dir_crawler <url>` scans with
+    fn default() -> Self {
+        FuzzerConfig {
+            url: String::new(),
+            targets: Vec::new(),
+            services: Vec::new(),
+            target_overrides: Vec::new(),
+            wordlist: PathBuf::new(),
+            wordlist_offset: 0,
+            wordlist_limit: None,
+            shuffle: false,
+            stealth: None,
+            mutations: None,
+            learn: false,
+            api_discovery: false,
+            fuzz_param: None,
+            position_wordlists: Vec::new(),
+            fuzz_mode: template::Mode::Clusterbomb,
+            buckets: false,
+            threads: 20,
+            silent: false,
+            status_codes: vec![200, 204, 301, 302, 307, 401, 403],
+            timeout: None,
+            connect_timeout: 5,
+            read_timeout: 10,
+            retries: 0,
+            max_body_size: Some(1024 * 1024),
+            max_connections_per_host: None,
+            max_bandwidth: None,
+            pool_idle_timeout: 90,
+            tcp_keepalive: 60,
+            method: Method::GET,
+            request_template: None,
+            extensions: Vec::new(),
+            detect_language: false,
+            scan_id: generate_scan_id(),
+            request_id_header: None,
+            scope_include: Vec::new(),
+            scope_exclude: Vec::new(),
+            blocklist: Vec::new(),
+            adaptive_throttle: true,
+            output: None,
+            format: output::OutputFormat::Json,
+            urls_status: Vec::new(),
+            match_content_types: Vec::new(),
+            filter_content_types: Vec::new(),
+            store_responses: None,
+            store_headers: false,
+            har: None,
+            har_max_body: 64 << 10,
+            warc: None,
+            sign_key: None,
+            detect_secrets: false,
+            secret_rules: Vec::new(),
+            extract_titles: false,
+            audit_headers: false,
+            cors_origin: None,
+            open_redirects: false,
+            probe_methods: Vec::new(),
+            spray_credentials: Vec::new(),
+            spray_delay: 1,
+            screenshots: None,
+            snippet_bytes: None,
+            dump: None,
+            sort: output::SortOrder::Severity,
+            script: None,
+            severity_rules: Vec::new(),
+            persona: None,
+            headers: Vec::new(),
+            cookie: None,
+            host_header: None,
+            resolve: Vec::new(),
+            address_family: dns::AddressFamily::Any,
+            source_ip: None,
+            dns_resolver: None,
+            differential: None,
+            recursion_depth: 0,
+            recursion_strategy: RecursionStrategy::Bfs,
+            max_dirs_per_level: None,
+            auto_calibrate: false,
+            unique: false,
+            vcs_checks: false,
+            preflight: true,
+            detect_scheme: false,
+            waf_check: false,
+            favicon: false,
+            waf_adapt: false,
+            reconfirm: false,
+            reconfirm_delay: 1000,
+            max_time: None,
+            max_errors: None,
+            max_findings: None,
+            host_error_threshold: Some(20),
+            dry_run: false,
+            dry_run_file: None,
+            notify: false,
+            junit: None,
+        }
+    }
+}
+
+/// Runtime state shared by every worker in a scan
+#[derive(Default)]
+struct ScanState {
+    request_seq: AtomicU64,
+    /// Requests answered so far, for sampling `--dump`
+    dump_seq: AtomicU64,
+    audit_log: Option<audit::AuditLog>,
+    redactor: redact::Redactor,
+    throttle: throttle::Throttle,
+    cipher: Option<crypto::OutputCipher>,
+    /// (status, simhash) of every distinct page printed so far under `--unique`
+    seen_pages: std::sync::Mutex<Vec<(u16, u64)>>,
+    /// Requests that failed outright (connection, timeout, ...)
+    errors: AtomicU64,
+    /// Findings recorded so far
+    hits: AtomicU64,
+    /// Discovered directories left unscanned by the depth or breadth limits
+    unexplored: std::sync::Mutex<Vec<String>>,
+    /// Directory this scan's files are organized under, if any
+    workspace: Option<workspace::Workspace>,
+    /// Login session from `--login-config`, refreshed when it expires
+    session: Option<session::Session>,
+    /// Where fuzzed requests get their anti-CSRF token from
+    csrf: Option<csrf::CsrfSource>,
+    /// Where findings are written as they are recorded
+    sinks: sink::Sinks,
+    /// Per-host request slots enforcing `--max-connections-per-host`
+    host_slots: std::sync::Mutex<HashMap<String, Arc<tokio::sync::Semaphore>>>,
+    /// Why the scan was cut short by a stop condition, once one is hit
+    stop_reason: std::sync::OnceLock<String>,
+    /// Cancelled to abort the scan, dropping the requests in flight
+    cancellation: CancellationToken,
+    /// Consecutive failed requests per host, reset by any response
+    host_failures: std::sync::Mutex<HashMap<String, u32>>,
+    /// Hosts given up on by the circuit breaker
+    dead_hosts: std::sync::Mutex<HashSet<String>>,
+    /// Address each host last answered from
+    addresses: std::sync::Mutex<HashMap<String, IpAddr>>,
+    /// Requests never sent because their host was given up on
+    skipped: AtomicU64,
+    /// Counters served by `--metrics-port`
+    metrics: metrics::Metrics,
+    /// URLs tried out of those known, published to the progress bar
+    progress: progress::Progress,
+    /// Keys of every request sent, so no URL is requested twice
+    requested: std::sync::Mutex<HashSet<u64>>,
+    /// Requests never sent because an identical one already was
+    duplicates: AtomicU64,
+    /// Request/response archive for `--har`
+    har: Option<har::Recorder>,
+    /// Response capture streamed to `--warc`
+    warc: Option<warc::Writer>,
+    /// Slack and Discord updates
+    chat: Option<chat::Chat>,
+    /// Analysis passes run on requests, responses and findings
+    plugins: plugin::Plugins,
+    /// Findings of `--previous` to revalidate
+    previous: Option<conditional::Previous>,
+    /// Findings carried over from `--previous` because they answered 304
+    unchanged: AtomicU64,
+    /// Download budget for `--max-bandwidth`
+    bandwidth: Option<Arc<bandwidth::Limiter>>,
+    /// What findings must not match under `--fail-on` or `--ci`
+    policy: Option<policy::Policy>,
+    /// Findings that violated the policy
+    violations: AtomicU64,
+    /// Progress kept in the workspace for `resume`
+    journal: Option<resume::Journal>,
+    /// What an interrupted run of this scan got through, when resuming it
+    checkpoint: Option<resume::Checkpoint>,
+    /// Where findings go when a program running the scan streams them
+    stream: Option<fuzzer::Outlet>,
+}
+
+impl ScanState {
+    /// Note the address `host` answered from, returning the one it answered
+    /// from before
+    fn connected(&self, host: &str, address: IpAddr) -> Option<IpAddr> {
+        self.addresses.lock().unwrap().insert(host.to_string(), address)
+    }
+
+    /// Remember a page fingerprint, returning false if a near-identical page was already seen
+    fn first_sighting(&self, status: u16, hash: u64) -> bool {
+        let mut seen = self.seen_pages.lock().unwrap();
+        let duplicate = seen.iter().any(|(seen_status, seen_hash)| {
+            *seen_status == status && simhash::distance(*seen_hash, hash) <= simhash::MAX_DISTANCE
+        });
+        if !duplicate {
+            seen.push((status, hash));
+        }
+        !duplicate
+    }
+
+    /// Claim a request, returning false (and counting it) if an identical
+    /// one was already sent this scan
+    fn first_request(&self, key: u64) -> bool {
+        let first = self.requested.lock().unwrap().insert(key);
+        if !first {
+            self.duplicates.fetch_add(1, Ordering::Relaxed);
+        }
+        first
+    }
+
+    /// Semaphore bounding concurrent requests to one host
+    fn host_slot(&self, host: &str, limit: usize) -> Arc<tokio::sync::Semaphore> {
+        let mut slots = self.host_slots.lock().unwrap();
+        Arc::clone(slots.entry(host.to_string())
+            .or_insert_with(|| Arc::new(tokio::sync::Semaphore::new(limit))))
+    }
+
+    /// Ask every worker to wind down; only the first reason is kept
+    fn stop(&self, reason: String) {
+        if self.stop_reason.set(reason).is_ok() {
+            if let Some(reason) = self.stop_reason.get() {
+                tracing::info!("🛑 Stopping scan: {}", reason);
+            }
+        }
+    }
+
+    /// Stop at once: the requests in flight are dropped rather than
+    /// awaited, and what was found so far is written out
+    fn abort(&self, reason: String) {
+        self.stop(reason);
+        self.cancellation.cancel();
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop_reason.get().is_some()
+    }
+
+    /// Stop the scan if the error or finding count reached its limit
+    fn check_limits(&self, config: &FuzzerConfig) {
+        let errors = self.errors.load(Ordering::Relaxed);
+        let hits = self.hits.load(Ordering::Relaxed);
+        if let Some(max) = config.max_errors.filter(|max| errors >= *max) {
+            self.stop(format!("{} errors reached --max-errors {}", errors, max));
+        } else if let Some(max) = config.max_findings.filter(|max| hits >= *max) {
+            self.stop(format!("{} findings reached --max-findings {}", hits, max));
+        }
+    }
+
+    fn host_dead(&self, host: &str) -> bool {
+        self.dead_hosts.lock().unwrap().contains(host)
+    }
+
+    /// Track a host's run of failures, returning the run length when it
+    /// just reached the threshold and the host is now given up on
+    fn record_host_outcome(&self, host: &str, failed: bool, threshold: Option<u32>) -> Option<u32> {
+        let mut failures = self.host_failures.lock().unwrap();
+        if !failed {
+            failures.remove(host);
+            return None;
+        }
+        let run = failures.entry(host.to_string()).or_default();
+        *run += 1;
+        let threshold = threshold?;
+        (*run >= threshold && self.dead_hosts.lock().unwrap().insert(host.to_string())).then_some(*run)
+    }
+
+    /// Allocate the next sequential request ID for this scan
+    fn next_request_id(&self, scan_id: &str) -> String {
+        let seq = self.request_seq.fetch_add(1, Ordering::Relaxed) + 1;
+        format!("{}-{:06}", scan_id, seq)
+    }
+}
+
+/// Short identifier distinguishing this scan's requests in server logs
+fn generate_scan_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    format!("{:08x}", (millis as u32) ^ std::process::id())
+}
+
+/// What a differential scan compares each candidate against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum Differential {
+    /// Same request under two personas (primary, alternate)
+    Personas(String, String),
+    /// With and without the configured session (cookies and auth headers)
+    Auth,
+}
+
+/// Order in which discovered directories are recursed into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum RecursionStrategy {
+    /// Every directory at one depth before going deeper
+    Bfs,
+    /// Follow one branch all the way down before the next
+    Dfs,
+    /// Most promising directory first, by the confidence of its hit
+    Priority,
+}
+
+/// Header names treated as session credentials for `--differential auth`
+fn is_session_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name == "cookie"
+        || ["auth", "token", "session", "api-key", "apikey"].iter().any(|marker| name.contains(marker))
+}
+
+/// Serialize request methods by name so configs can be shipped to agents
+mod method_serde {
+    use reqwest::Method;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(method: &Method, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(method.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Method, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Method::from_bytes(name.as_bytes()).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Reduce an HTML document to its visible text
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    let mut skip_until: Option<&str> = None;
+    let lower = html.to_ascii_lowercase();
+
+    let mut i = 0;
+    while i < html.len() {
+        // Drop script and style blocks entirely
+        if let Some(end) = skip_until {
+            match lower[i..].find(end) {
+                Some(pos) => {
+                    i += pos + end.len();
+                    skip_until = None;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
+        let ch = html[i..].chars().next().unwrap();
+        if ch == '<' {
+            if lower[i..].starts_with("<script") {
+                skip_until = Some("</script>");
+            } else if lower[i..].starts_with("<style") {
+                skip_until = Some("</style>");
+            }
+            in_tag = true;
+        } else if ch == '>' {
+            in_tag = false;
+            text.push(' ');
+        } else if !in_tag {
+            text.push(ch);
+        }
+        i += ch.len_utf8();
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Detect the natural language of an HTML body as an ISO 639-3 code
+fn detect_language(html: &str) -> Option<String> {
+    let text = strip_html(html);
+    let info = whatlang::detect(&text)?;
+    if info.is_reliable() {
+        Some(info.lang().code().to_string())
+    } else {
+        None
+    }
+}
+
+/// On stderr with the rest of the startup info, so stdout carries only results
+fn print_banner() {
+    let banner = r#"
+██████╗ ██╗██████╗          ██████╗██████╗  █████╗ ██╗    ██╗██╗     ███████╗██████╗
+██╔══██╗██║██╔══██╗        ██╔════╝██╔══██╗██╔══██╗██║    ██║██║     ██╔════╝██╔══██╗
+██║  ██║██║██████╔╝        ██║     ██████╔╝███████║██║ █╗ ██║██║     █████╗  ██████╔╝
+██║  ██║██║██╔══██╗        ██║     ██╔══██╗██╔══██║██║███╗██║██║     ██╔══╝  ██╔══██╗
+██████╔╝██║██║  ██║███████╗╚██████╗██║  ██║██║  ██║╚███╔███╔╝███████╗███████╗██║  ██║
+╚═════╝ ╚═╝╚═╝  ╚═╝╚══════╝ ╚═════╝╚═╝  ╚═╝╚═╝  ╚═╝ ╚══╝╚══╝ ╚══════╝╚══════╝╚═╝  ╚═╝
+"#;
+
+    term::err!("{}", banner.bright_magenta());
+    term::err!("{}", "           [ Sylar's Dir_Crawler ]".yellow());
+    term::err!("{}", "   🕵️‍♂️ 🌐 Network Directory Exploration Tool 🔍".truecolor(50, 150, 250));
+    term::err!("{}", "~".repeat(65).truecolor(100, 100, 100));
+    term::err!("{}", "   Empowering Cybersecurity Professionals with Precise Scanning".bright_white());
+    term::err!("{}", "=".repeat(65).truecolor(80, 80, 80));
+}
+
+/// Check a target URL. One typed without a scheme gets `https://` until
+/// the scan has probed the target.
+fn validate_url(url: &str) -> Result<String, CrawlerError> {
+    let invalid = |reason: &str| CrawlerError::Url { url: url.to_string(), reason: reason.to_string() };
+    let full_url = match has_scheme(url) {
+        true => url.to_string(),
+        false => format!("https://{}", url),
+    };
+    let parsed_url = Url::parse(&full_url).map_err(|e| invalid(&e.to_string()))?;
+
+    match parsed_url.scheme() {
+        "http" | "https" => Ok(full_url),
+        _ => Err(invalid("only http:// and https:// URLs can be scanned")),
+    }
+}
+
+/// Whether a target was typed with its scheme; one without is scanned over
+/// whichever of HTTPS and HTTP `preflight::detect_scheme` finds answering
+fn has_scheme(url: &str) -> bool {
+    // `://` further on, as in `example.com/?next=http://x`, is no scheme
+    url.split_once("://").is_some_and(|(scheme, _)| {
+        let mut chars = scheme.chars();
+        chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Headers sent with every request: persona defaults, then user headers and cookies
+fn default_headers(config: &FuzzerConfig) -> reqwest::header::HeaderMap {
+    // Present a coherent browser/bot identity when a persona is selected
+    let mut headers = match &config.stealth {
+        Some(fingerprint) => fingerprint.headers(),
+        None => config.persona.as_deref()
+            .and_then(persona::find)
+            .map(|persona| persona.header_map())
+            .unwrap_or_default(),
+    };
+
+    // User-supplied headers and session cookies override persona defaults
+    for (name, value) in &config.headers {
+        if let (Ok(name), Ok(value)) = (
+            reqwest::header::HeaderName::from_bytes(name.as_bytes()),
+            reqwest::header::HeaderValue::from_str(value),
+        ) {
+            headers.insert(name, value);
+        }
+    }
+    if let Some(cookie) = &config.cookie {
+        if let Ok(value) = reqwest::header::HeaderValue::from_str(cookie) {
+            headers.insert(reqwest::header::COOKIE, value);
+        }
+    }
+    if let Some(host) = &config.host_header {
+        if let Ok(value) = reqwest::header::HeaderValue::from_str(host) {
+            headers.insert(reqwest::header::HOST, value);
+        }
+    }
+
+    match &config.stealth {
+        Some(fingerprint) => fingerprint.reorder(headers),
+        None => headers,
+    }
+}
+
+/// Parse a curl-style `host:port:address` pin (IPv6 addresses may be bracketed)
+fn parse_resolve(spec: &str) -> Result<(String, SocketAddr), String> {
+    let invalid = || format!("Invalid --resolve '{}'. Use host:port:address", spec);
+    let mut parts = spec.splitn(3, ':');
+    let (Some(host), Some(port), Some(address)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let port: u16 = port.parse().map_err(|_| invalid())?;
+    let ip: IpAddr = address.trim_matches(['[', ']']).parse().map_err(|_| invalid())?;
+    Ok((host.to_string(), SocketAddr::new(ip, port)))
+}
+
+/// The addresses `url`'s host resolves to the way the scan will resolve
+/// it: pinned with `--resolve`, else through the chosen resolver or the
+/// system's. Empty when it doesn't resolve.
+async fn target_addresses(url: &str, resolve: &[(String, SocketAddr)], dns_resolver: Option<&str>) -> Result<Vec<IpAddr>, CrawlerError> {
+    let Some(host) = Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string)) else {
+        return Ok(Vec::new());
+    };
+    if let Ok(ip) = host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        return Ok(vec![ip]);
+    }
+    let pinned: Vec<IpAddr> = resolve.iter().filter(|(name, _)| *name == host).map(|(_, address)| address.ip()).collect();
+    if !pinned.is_empty() {
+        return Ok(pinned);
+    }
+    Ok(match dns_resolver {
+        Some(spec) => dns::shared(spec)?.lookup(&host).await.unwrap_or_default(),
+        None => tokio::net::lookup_host((host.as_str(), 0))
+            .await
+            .map(|addresses| addresses.map(|address| address.ip()).collect())
+            .unwrap_or_default(),
+    })
+}
+
+/// Parse a byte size such as `512`, `64KB` or `1MB` (binary units)
+fn parse_size(spec: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid size '{}'. Use a number with an optional B, KB, MB or GB suffix", spec);
+    let upper = spec.trim().to_ascii_uppercase();
+    let digits = upper.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let multiplier: u64 = match upper[digits.len()..].trim() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        _ => return Err(invalid()),
+    };
+    let value: u64 = digits.trim().parse().map_err(|_| invalid())?;
+    value.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+/// Parse a duration such as `90`, `45s`, `30m` or `2h` into seconds
+fn parse_duration(spec: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid duration '{}'. Use a number with an optional s, m or h suffix", spec);
+    let spec = spec.trim().to_ascii_lowercase();
+    let (digits, multiplier) = match spec.strip_suffix(['s', 'm', 'h']) {
+        Some(digits) if spec.ends_with('m') => (digits, 60),
+        Some(digits) if spec.ends_with('h') => (digits, 3_600),
+        Some(digits) => (digits, 1),
+        None => (spec.as_str(), 1),
+    };
+    let value: u64 = digits.trim().parse().map_err(|_| invalid())?;
+    value.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+/// Parse statuses and status classes such as `2xx,3xx,401` into inclusive ranges
+fn parse_status_filter(spec: &str) -> Result<Vec<(u16, u16)>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let invalid = || format!("Invalid status '{}'. Use codes like 401 or classes like 2xx", item);
+            match item.to_ascii_lowercase().strip_suffix("xx") {
+                Some(class) => class.parse::<u16>().ok()
+                    .filter(|class| (1..=5).contains(class))
+                    .map(|class| (class * 100, class * 100 + 99))
+                    .ok_or_else(invalid),
+                None => item.parse::<u16>().ok()
+                    .filter(|code| (100..=599).contains(code))
+                    .map(|code| (code, code))
+                    .ok_or_else(invalid),
+            }
+        })
+        .collect()
+}
+
+/// Parse media types such as `application/json,image/*` for content-type matching
+fn parse_content_types(spec: &str) -> Result<Vec<String>, String> {
+    spec.split(',')
+        .map(|item| item.trim().to_ascii_lowercase())
+        .filter(|item| !item.is_empty())
+        .map(|item| match item.split_once('/') {
+            Some((kind, subtype)) if !kind.is_empty() && !subtype.is_empty() && !item.contains(';') => Ok(item),
+            _ => Err(format!("Invalid content type '{}'. Use media types like application/json or image/*", item)),
+        })
+        .collect()
+}
+
+/// Whether a Content-Type header names one of the media types (`type/*`
+/// covering a whole family); parameters like `charset` are ignored
+fn content_type_matches(types: &[String], header: Option<&str>) -> bool {
+    let Some(media) = header.and_then(|h| h.split(';').next()).map(|m| m.trim().to_ascii_lowercase()) else {
+        return false;
+    };
+    types.iter().any(|wanted| match wanted.strip_suffix("/*") {
+        Some(kind) => media.split_once('/').is_some_and(|(k, _)| k == kind),
+        None => *wanted == media,
+    })
+}
+
+/// Redirects followed per request, matching reqwest's default policy
+const MAX_REDIRECTS: usize = 10;
+
+/// Build the shared HTTP client for a scan
+fn build_client(config: &FuzzerConfig) -> Result<Client, CrawlerError> {
+    Ok(client_builder(config)?.build()?)
+}
+
+/// The scan's client settings, for callers that adjust them before building
+fn client_builder(config: &FuzzerConfig) -> Result<reqwest::ClientBuilder, CrawlerError> {
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(config.connect_timeout))
+        .default_headers(default_headers(config))
+        // Keep one idle connection per worker so high thread counts reuse
+        // sockets instead of churning through ephemeral ports
+        .pool_max_idle_per_host(config.max_connections_per_host.unwrap_or(config.threads).max(1))
+        .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout))
+        .tcp_nodelay(true)
+        .tcp_keepalive((config.tcp_keepalive > 0).then(|| Duration::from_secs(config.tcp_keepalive)));
+    if let Some(timeout) = config.timeout {
+        builder = builder.timeout(Duration::from_secs(timeout));
+    }
+    if config.stealth.as_ref().is_some_and(|fingerprint| fingerprint.title_case) {
+        builder = builder.http1_title_case_headers();
+    }
+
+    // Redirects are requests too: never follow one out of scope or onto a blocklisted path
+    let scope = scope::Scope::new(&config.scope_include, &config.scope_exclude, &config.blocklist)?;
+    builder = builder.redirect(reqwest::redirect::Policy::custom(move |attempt| {
+        if !scope.allows(attempt.url().as_str()) {
+            tracing::debug!("🚧 Not following redirect to {}", attempt.url());
+            attempt.stop()
+        } else if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    }));
+
+    apply_network(builder, config)
+}
+
+/// How every client of a scan reaches the target: resolver, source address
+/// and address family, and pinned addresses
+fn apply_network(mut builder: reqwest::ClientBuilder, config: &FuzzerConfig) -> Result<reqwest::ClientBuilder, CrawlerError> {
+    // Every client shares one in-process DNS cache when a resolver is chosen
+    if let Some(spec) = &config.dns_resolver {
+        builder = builder.dns_resolver(Arc::new(dns::shared(spec)?));
+    }
+
+    // Bound to one address, hyper only connects to targets of its family
+    if let Some(address) = config.source_ip.or(config.address_family.local_address()) {
+        builder = builder.local_address(address);
+    }
+
+    // Connect to pinned addresses while keeping the hostname for SNI and certificates
+    for (host, address) in &config.resolve {
+        builder = builder.resolve(host, *address);
+    }
+
+    Ok(builder)
+}
+
+/// Enhanced directory fuzzing function with thread-safe result collection
+async fn fuzz_directory(
+    config: Arc<FuzzerConfig>,
+    state: Arc<ScanState>,
+    agents: Vec<String>,
+    agent_secret: String,
+    chunk_size: usize,
+) -> Result<(), CrawlerError> {
+    let start_time = Instant::now();
+    let started_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    // Enhanced HTTP client configuration
+    let mut config = config;
+    let mut client = build_client(&config)?;
+
+    let typed_url = config.url.clone();
+
+    // A URL typed without a scheme gets the one the target answers on
+    if config.detect_scheme && !config.dry_run {
+        if let Some(url) = preflight::detect_scheme(&config, &state, &client).await? {
+            if !config.silent {
+                term::err!("🔎 Scheme detected: {}", state.redactor.redact(&url).cyan());
+            }
+            config = Arc::new(FuzzerConfig { url, ..(*config).clone() });
+        }
+    }
+
+    // A typo'd host should fail now, not after a full run of timeouts
+    let single_target = config.targets.is_empty() && !config.buckets;
+    if config.preflight && single_target && !config.dry_run {
+        if let Some(liveness) = preflight::probe(&config, &state, &client).await? {
+            if !config.silent {
+                term::err!(
+                    "📡 Target answered {} in {}ms",
+                    liveness.status.to_string().cyan(),
+                    liveness.latency.as_millis()
+                );
+                if liveness.failed > 0 {
+                    term::err!("⚠️  {} of the pre-scan requests got no response; consider --retries", liveness.failed);
+                }
+                if liveness.threads > config.threads {
+                    term::err!(
+                        "💡 At {}ms per response, -t {} would make about {} requests/s",
+                        liveness.latency.as_millis(),
+                        liveness.threads,
+                        preflight::TARGET_RATE
+                    );
+                }
+            }
+            if let Some(upgrade) = liveness.upgrade {
+                match config.request_template.is_none() {
+                    true => {
+                        if !config.silent {
+                            term::err!("🔒 Redirected to HTTPS; scanning {}", state.redactor.redact(&upgrade).cyan());
+                        }
+                        config = Arc::new(FuzzerConfig { url: upgrade, ..(*config).clone() });
+                    }
+                    false if !config.silent => {
+                        term::err!("💡 The target redirects to {}", state.redactor.redact(&upgrade).cyan());
+                    }
+                    false => {}
+                }
+            }
+        }
+    }
+
+    if config.url != typed_url {
+        let report = output::ScanReport::new(&config, started_at, 0.0, &[]);
+        state.sinks.retarget(&report, &state.redactor)?;
+    }
+
+    // Getting banned minutes into a scan is avoidable: look for a WAF first
+    if config.waf_check {
+        match waf::preflight(&config, &state, &client).await {
+            Ok(detections) if detections.is_empty() => {
+                if !config.silent {
+                    term::err!("🛡️  No WAF or CDN detected");
+                }
+            }
+            Ok(detections) => {
+                for detection in &detections {
+                    tracing::warn!("WAF/CDN detected: {} ({})", detection.name, detection.evidence);
+                    if !config.silent {
+                        term::err!("🛡️  {} detected ({})", detection.name.yellow(), detection.evidence);
+                    }
+                }
+                match waf::stealthier(&config) {
+                    Some((adjusted, changes)) if config.waf_adapt => {
+                        config = Arc::new(adjusted);
+                        client = build_client(&config)?;
+                        if !config.silent {
+                            term::err!("🐢 Applied stealthier settings: {}", changes.join(", ").cyan());
+                        }
+                    }
+                    Some((_, changes)) if !config.silent => {
+                        term::err!("💡 Consider stealthier settings: {} (or --waf-adapt to apply them)", changes.join(" ").cyan());
+                    }
+                    _ => {}
+                }
+            }
+            Err(e) => {
+                let message = state.redactor.redact(&e.to_string()).into_owned();
+                tracing::warn!("WAF pre-flight failed: {}", message);
+                if !config.silent {
+                    term::err!("⚠️  WAF pre-flight failed: {}", message);
+                }
+            }
+        }
+    }
+
+    // The product behind the target, from its favicon
+    let mut favicons = Vec::new();
+    if config.favicon {
+        let roots = match config.targets.is_empty() {
+            true => vec![config.url.clone()],
+            false => config.targets.clone(),
+        };
+        for root in &roots {
+            match favicon::fetch(&config, &state, &client, root).await {
+                Ok(Some(icon)) => {
+                    if !config.silent {
+                        let product = match &icon.product {
+                            Some(product) => product.green().to_string(),
+                            None => "unknown product".dimmed().to_string(),
+                        };
+                        term::err!("🖼️  {}: http.favicon.hash:{} ({})", state.redactor.redact(&icon.url), icon.hash.to_string().cyan(), product);
+                    }
+                    favicons.push(icon);
+                }
+                Ok(None) => tracing::debug!("No favicon at {}", state.redactor.redact(root)),
+                Err(e) => tracing::warn!("Favicon fetch failed for {}: {}", state.redactor.redact(root), e),
+            }
+        }
+    }
+
+    // A token page that yields nothing would fail every request; find out now
+    if let Some(csrf) = &state.csrf {
+        let token = csrf.token(&config, &state, &client).await?;
+        if !config.silent {
+            term::err!("🎫 CSRF token from {} ({} chars)", state.redactor.redact(csrf.url()).cyan(), token.value.len());
+        }
+    }
+
+    // Read wordlist, keeping only this session's slice of it
+    let mut words: Vec<String> = wordlist::load(&config.wordlist)
+        .map_err(|source| CrawlerError::Wordlist { path: config.wordlist.clone(), source })?
+        .into_iter()
+        .skip(config.wordlist_offset)
+        .take(config.wordlist_limit.unwrap_or(usize::MAX))
+        .collect();
+    let sliced = words.len();
+    // Words the target itself uses, ahead of mutation so they get variants too
+    if config.learn {
+        let learned = wordgen::learn(
+            &config,
+            &state,
+            &client,
+            wordgen::DEFAULT_DEPTH,
+            wordgen::DEFAULT_MAX_PAGES,
+            wordgen::DEFAULT_MIN_LENGTH,
+        ).await?;
+        let known: HashSet<String> = words.iter().cloned().collect();
+        let added: Vec<String> = learned.words.into_iter().filter(|word| !known.contains(word)).collect();
+        if !config.silent {
+            term::err!("🧠 Learned {} new words from {} pages", added.len().to_string().cyan(), learned.pages);
+        }
+        words.extend(added);
+    }
+    // Bucket keywords become every permuted name in every provider's URL format
+    if config.buckets {
+        let defaults = buckets::default_mutations();
+        words = buckets::candidates(&words, config.mutations.as_ref().unwrap_or(&defaults));
+    } else if let Some(mutations) = &config.mutations {
+        words = mutations.expand(words);
+    }
+    // Endpoints from published API descriptions are exact, so they skip mutation
+    if config.api_discovery {
+        let discovery = apispec::discover(&config, &state, &client).await?;
+        if !config.silent {
+            for (url, paths) in &discovery.specs {
+                term::err!("📜 API description: {} ({} paths)", state.redactor.redact(url).cyan(), paths);
+            }
+            for (url, types) in &discovery.graphql {
+                term::err!("🧬 GraphQL introspection enabled: {} ({} types)", state.redactor.redact(url).cyan(), types);
+            }
+            if discovery.specs.is_empty() && discovery.graphql.is_empty() {
+                term::err!("📜 No API descriptions or GraphQL endpoints found");
+            }
+        }
+        let known: HashSet<String> = words.iter().cloned().collect();
+        words.extend(discovery.paths.into_iter().filter(|path| !known.contains(path)));
+    }
+
+    // Words that can't form a valid header would only fail to send
+    let valid = |valid: bool, word: &str| {
+        if !valid {
+            tracing::debug!("Skipping {:?}: not valid in a header", word);
+        }
+        valid
+    };
+
+    // Multi-position templates fuzz combinations of one list per position;
+    // a single list feeds every position
+    let mut entries = match config.request_template.as_ref() {
+        Some(template) if template.positions > 1 => {
+            let mut lists = vec![words];
+            for path in &config.position_wordlists {
+                lists.push(wordlist::load(path).map_err(|source| CrawlerError::Wordlist { path: path.clone(), source })?);
+            }
+            while lists.len() < template.positions {
+                lists.push(lists[0].clone());
+            }
+            match config.fuzz_mode {
+                template::Mode::Pitchfork => {
+                    let mut combos = template::pitchfork(&lists);
+                    combos.retain(|payload| valid(template.valid_payload(payload), payload));
+                    Entries::List(combos)
+                }
+                template::Mode::Clusterbomb => {
+                    for (i, list) in lists.iter_mut().enumerate() {
+                        list.retain(|word| valid(template.valid_at(i + 1, word), word));
+                    }
+                    Entries::Combinations(template::Clusterbomb::new(lists))
+                }
+            }
+        }
+        Some(template) => {
+            words.retain(|word| valid(template.valid_payload(word), word));
+            Entries::List(words)
+        }
+        None => Entries::List(words),
+    };
+    if config.shuffle {
+        entries.shuffle();
+    }
+
+    // Detailed startup information
+    if !config.silent {
+        term::err!("🔍 Directory Fuzzing Initiated");
+        if config.buckets {
+            term::err!("🪣 Buckets: {} candidate URLs across AWS S3, Google Cloud Storage and Azure Blob", entries.len().to_string().green());
+        } else if config.targets.len() > 1 {
+            term::err!("🌐 Targets: {} base URLs ({} first)", config.targets.len().to_string().green(), config.url.green());
+        } else {
+            term::err!("🌐 Target URL: {}", config.url.green());
+        }
+        term::err!("📋 Wordlist: {}", config.wordlist.display().to_string().blue());
+        if config.wordlist_offset > 0 || config.wordlist_limit.is_some() {
+            term::err!(
+                "✂️  Entries: {}–{}",
+                config.wordlist_offset + 1,
+                config.wordlist_offset + sliced
+            );
+        }
+        if let Entries::Combinations(combos) = &entries {
+            term::err!("🧮 Combinations: {} (clusterbomb, generated as scanned)", combos.len().to_string().yellow());
+        }
+        if let Some(mutations) = &config.mutations {
+            term::err!("🧬 Mutations: {}", mutations.describe().magenta());
+        }
+        if config.shuffle {
+            term::err!("🔀 Order: shuffled");
+        }
+        term::err!("🧵 Threads: {}", config.threads.to_string().yellow());
+        if let Some(rate) = config.max_bandwidth {
+            term::err!("📶 Bandwidth: at most {}", bandwidth::describe(rate).yellow());
+        }
+        term::err!("🚀 Request Method: {}", format!("{}", config.method).cyan());
+        if let Some(address) = config.source_ip {
+            term::err!("🔌 Source Address: {}", address.to_string().cyan());
+        }
+
+        // Show extensions if provided
+        if !config.extensions.is_empty() {
+            term::err!("🔗 Extensions: {}", config.extensions.join(", ").magenta());
+        }
+
+        // Show the client identity in use
+        match &config.differential {
+            Some(Differential::Personas(primary, alternate)) => {
+                term::err!("↔️  Differential: {} vs {}", primary.cyan(), alternate.cyan());
+            }
+            Some(Differential::Auth) => {
+                term::err!("↔️  Differential: {} vs {}", "authenticated".cyan(), "unauthenticated".cyan());
+            }
+            None => {}
+        }
+        if let Some(persona) = &config.persona {
+            term::err!("🎭 Persona: {}", persona.cyan());
+        }
+        if let Some(host) = &config.host_header {
+            term::err!("🏷️  Host Header: {}", host.cyan());
+        }
+        if let Some(resolver) = &config.dns_resolver {
+            term::err!("🧭 DNS Resolver: {}", resolver.cyan());
+        }
+        for (host, address) in &config.resolve {
+            term::err!("📌 Resolve: {} → {}", host.cyan(), address.ip().to_string().yellow());
+        }
+
+        // Show request ID stamping for server-side log correlation
+        if let Some(header) = &config.request_id_header {
+            term::err!("🆔 Request IDs: {} ({}-NNNNNN)", header.cyan(), config.scan_id);
+        }
+
+        // Show recursion and soft-404 handling
+        if config.recursion_depth > 0 {
+            term::err!("📂 Recursion Depth: {}", config.recursion_depth.to_string().yellow());
+        }
+        if config.recursion_depth > 0 && config.recursion_strategy != RecursionStrategy::Bfs {
+            term::err!("🧭 Recursion Strategy: {}", format!("{:?}", config.recursion_strategy).to_lowercase().yellow());
+        }
+        if let Some(max) = config.max_dirs_per_level {
+            term::err!("📂 Max Directories per Level: {}", max.to_string().yellow());
+        }
+        if config.auto_calibrate {
+            term::err!("🧪 Soft-404 calibration: per directory");
+        }
+        if config.vcs_checks {
+            let paths: Vec<&str> = exposure::CHECKS.iter().map(|check| check.path).collect();
+            term::err!("🕳️  Exposure checks: {}", paths.join(", ").magenta());
+        }
+
+        // Show where this scan's files are collected
+        if let Some(ws) = &state.workspace {
+            term::err!("🗂️  Workspace: {}", ws.dir.display().to_string().blue());
+        }
+
+        // Show remote agents when distributing the scan
+        if !agents.is_empty() {
+            term::err!("🛰️  Agents: {}", agents.join(", ").magenta());
+        }
+
+    }
+
+    // Exact request count for the top-level directory; recursion extends it as it goes
+    let total_paths = entries.count_urls(&config.url, &config.extensions) * config.targets.len().max(1) as u64;
+    if !config.silent {
+        term::err!("📊 Total Paths to Check: {}", total_paths.to_string().cyan());
+    }
+
+    // Dry run: list what would be requested and stop short of sending it
+    if config.dry_run {
+        let plan = match &config.dry_run_file {
+            Some(path) => {
+                let mut file = std::io::BufWriter::new(std::fs::File::create(path)
+                    .map_err(|e| format!("Cannot write dry run to {}: {}", path.display(), e))?);
+                dryrun::write(&config, &entries, &mut file)?
+            }
+            None => dryrun::write(&config, &entries, &mut std::io::stdout().lock())?,
+        };
+        let destination = config.dry_run_file.as_ref().map(|path| format!(" to {}", path.display())).unwrap_or_default();
+        term::err!("🧪 Dry run: {} requests listed{}, none sent", plan.requests.to_string().cyan(), destination);
+        if plan.duplicates > 0 {
+            term::err!("♻️  {} duplicates of earlier requests left out", plan.duplicates.to_string().yellow());
+        }
+        if plan.out_of_scope > 0 {
+            term::err!("🚧 {} more fall outside the scope and would be skipped", plan.out_of_scope.to_string().yellow());
+        }
+        if config.recursion_depth > 0 || config.auto_calibrate {
+            term::err!("ℹ️  Calibration and recursion add requests that depend on the target's responses");
+        }
+        return Ok(());
+    }
+
+    // Silent mode keeps the terminal free for result lines
+    let progress_bar = if config.silent {
+        ProgressBar::with_draw_target(Some(total_paths), ProgressDrawTarget::hidden())
+    } else {
+        ProgressBar::new(total_paths)
+    };
+    let progress_style = ProgressStyle::default_bar()
+        .template(&format!(
+            "{{spinner}} {}[{}] {{pos}}/{{len}} {{msg}}",
+            if term::emoji(term::Stream::Stderr) { "🕵️ " } else { "" },
+            if term::color(term::Stream::Stderr) { "{bar:40.cyan/blue}" } else { "{bar:40}" },
+        ))
+        .unwrap()
+        .progress_chars("#>-");
+    progress_bar.set_style(progress_style);
+    // The workers only count; the bar is drawn from the events
+    let drawing = progress::draw(progress_bar.clone(), state.progress.subscribe());
+    let ticker = progress::start(Arc::clone(&state), total_paths);
+
+    // Wall-clock limit: flag the stop and let in-flight requests drain
+    let timer = config.max_time.map(|secs| {
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            state.stop(format!("--max-time of {}s elapsed", secs));
+        })
+    });
+
+    // However the scan is cancelled, the report says it was
+    let watch = {
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            state.cancellation.cancelled().await;
+            state.stop("cancelled".to_string());
+        })
+    };
+
+    // Scan locally or shard the wordlist across remote agents
+    let found_paths = if agents.is_empty() {
+        engine::scan_entries(Arc::clone(&config), client, entries, Arc::clone(&state)).await
+    } else {
+        distributed::run_coordinator(
+            Arc::clone(&config),
+            &agents,
+            entries,
+            chunk_size,
+            Arc::clone(&state),
+            &agent_secret,
+        ).await
+    };
+
+    if let Some(timer) = timer {
+        timer.abort();
+    }
+    watch.abort();
+    ticker.abort();
+    drawing.abort();
+    let found_paths = found_paths?;
+    progress::show(&progress_bar, &state.progress.finish(&state));
+    if let Some(reason) = state.stop_reason.get() {
+        progress_bar.abandon();
+        if !config.silent {
+            term::err!("\n🛑 Scan stopped early: {}", reason.yellow());
+        }
+    }
+
+    // Collapse catch-all pages that serve the same content for every path
+    let found_paths = if config.unique {
+        let mut sorted = found_paths;
+        sorted.sort_by(|a, b| a.url.len().cmp(&b.url.len()).then_with(|| a.url.cmp(&b.url)));
+        simhash::collapse(sorted)
+    } else {
+        found_paths
+    };
+
+    // Make sure every finding still reproduces before it is reported
+    let mut found_paths = found_paths;
+    let cancelled = state.cancellation.is_cancelled();
+    if config.reconfirm && !found_paths.is_empty() && !cancelled {
+        if !config.silent {
+            term::err!(
+                "\n🔁 Re-confirming {} findings ({} ms apart)...",
+                found_paths.len(),
+                config.reconfirm_delay
+            );
+        }
+        let stale = engine::reconfirm_findings(&config, &state, &mut found_paths).await?;
+        if !config.silent {
+            if stale > 0 {
+                term::err!("⚠️  {} findings no longer reproduce (tagged not-reproduced)", stale.to_string().yellow());
+            } else {
+                term::err!("✅ All findings reproduced");
+            }
+        }
+    }
+
+    // A look at every page that answered, for visual triage
+    if let Some(dir) = config.screenshots.as_ref().filter(|_| !cancelled) {
+        let wanted = found_paths.iter().filter(|finding| screenshot::wanted(finding)).count();
+        if wanted > 0 {
+            if !config.silent {
+                term::err!("\n📸 Taking {} screenshots...", wanted);
+            }
+            let taken = screenshot::capture(&config, dir, &mut found_paths).await?;
+            if !config.silent {
+                term::err!("📸 {} screenshots in {}", taken, dir.display());
+            }
+        }
+    }
+
+    // Comprehensive results display
+    let elapsed_time = start_time.elapsed();
+
+    // Most severe first, here and in every report; or most recently
+    // changed first, undated findings last and by severity
+    found_paths.sort_by_key(|finding| std::cmp::Reverse((finding.severity, finding.score)));
+    if config.sort == output::SortOrder::Modified {
+        found_paths.sort_by_key(|finding| std::cmp::Reverse(finding.modified()));
+    }
+    state.plugins.on_scan_end(&found_paths);
+
+    let mut sorted_paths: Vec<_> = found_paths.iter()
+        .map(|finding| {
+            // Further normalize URL for display
+            let label = finding.label();
+            let normalized_url = label.trim_end_matches("//");
+            (state.redactor.redact(normalized_url).into_owned(), finding.status, &finding.tags, finding.duplicates, finding.severity, finding.modified())
+        })
+        .collect();
+    match config.sort {
+        output::SortOrder::Severity => {
+            sorted_paths.sort_by(|a, b| b.4.cmp(&a.4).then_with(|| a.0.cmp(&b.0)));
+            sorted_paths.dedup_by(|a, b| a.0 == b.0);
+        }
+        output::SortOrder::Modified => {
+            let mut seen = HashSet::new();
+            sorted_paths.retain(|path| seen.insert(path.0.clone()));
+        }
+    }
+
+    if config.silent {
+        // Bare "status url" lines for pipelines, unless results already go to
+        // a file, stdout or a stream
+        if config.output.is_none() && config.format == output::OutputFormat::Json && state.stream.is_none() {
+            for (path, status, _, _, _, _) in &sorted_paths {
+                term::out!("{} {}", status, path);
+            }
+        }
+    } else if sorted_paths.is_empty() {
+        term::err!("🚫 No paths found. Possible reasons:");
+        term::err!("   - Incorrect URL");
+        term::err!("   - Firewall/Security blocking requests");
+        term::err!("   - Server not responding");
+        term::err!("   - Wordlist or extensions don't match server paths");
+    } else {
+        // The heading is decoration: only the finding lines reach a pipe
+        term::err!("\n🎉 Found Paths:");
+        for (path, status, tags, duplicates, severity, modified) in sorted_paths {
+            let status_color = match status {
+                200..=299 => path.green(),
+                300..=399 => path.yellow(),
+                400..=599 => path.red(),
+                _ => path.white(),
+            };
+            let mut suffix = if duplicates > 0 {
+                format!(" (+{} similar)", duplicates).dimmed().to_string()
+            } else {
+                String::new()
+            };
+            if let Some(modified) = modified.filter(|_| config.sort == output::SortOrder::Modified) {
+                suffix.push_str(&format!(" modified {}", workspace::format_datetime(modified)).dimmed().to_string());
+            }
+            // Padded before coloring, which would hide it from the width
+            let pad = " ".repeat(8usize.saturating_sub(severity.to_string().len()));
+            let severity = format!("{}{}", pad, engine::severity_color(severity));
+            if tags.is_empty() {
+                term::out!("{} {} (Status: {}){}", severity, status_color, status, suffix);
+            } else {
+                term::out!("{} {} (Status: {}) [{}]{}", severity, status_color, status, tags.join(", "), suffix);
+            }
+        }
+    }
+
+    // Findings that fail the build, on stderr so piped result lines stay clean
+    if let Some(policy) = &state.policy {
+        let violations: Vec<&Finding> = found_paths.iter().filter(|finding| policy.violated_by(finding)).collect();
+        state.violations.store(violations.len() as u64, Ordering::Relaxed);
+        if violations.is_empty() {
+            term::err!("✅ No findings violate the policy ({})", policy.source);
+        } else {
+            term::err!("❌ {} findings violate the policy ({}):", violations.len(), policy.source);
+            for finding in &violations {
+                term::err!("   {} {}", finding.status, state.redactor.redact(&finding.label()));
+            }
+        }
+        if let Some(path) = &config.junit {
+            let xml = policy::junit(policy, &found_paths, &config.url, elapsed_time.as_secs_f64());
+            std::fs::write(path, state.redactor.redact(&xml).as_bytes())?;
+            if !config.silent {
+                term::err!("🧪 JUnit report written to {}", path.display().to_string().blue());
+            }
+        }
+    }
+
+    // Summaries of the findings go with the diagnostics, on stderr
+    let audit = headers::summary(&found_paths);
+    if !audit.is_empty() && !config.silent {
+        term::err!("\n🛡️  Security header audit:");
+        for (check, count, example, issue) in audit {
+            term::err!(
+                "   {:<16} {:>5}  {}",
+                check,
+                count,
+                format!("e.g. {}: {}", state.redactor.redact(&example.label()), issue.detail).dimmed()
+            );
+        }
+    }
+
+    // Show what a truncated recursive scan never got to
+    let unexplored = std::mem::take(&mut *state.unexplored.lock().unwrap());
+    if !unexplored.is_empty() && !config.silent {
+        term::err!("\n🧭 Unexplored frontier ({} directories):", unexplored.len());
+        for dir in &unexplored {
+            term::err!("   {}", state.redactor.redact(dir).dimmed());
+        }
+    }
+
+    // The finished report: to stdout for md, curl, urls and sarif without
+    // an output file, otherwise over the copy kept current while scanning
+    let mut report = output::ScanReport::new(&config, started_at, elapsed_time.as_secs_f64(), &found_paths);
+    report.unexplored = unexplored;
+    report.stopped = state.stop_reason.get().cloned();
+    report.favicons = favicons;
+    state.sinks.finish(&report, &state.redactor)?;
+
+    // Persist results, encrypted at rest when a key is configured
+    if let Some(path) = &config.output {
+        if !config.silent {
+            let note = if state.cipher.is_some() { " (encrypted)" } else { "" };
+            term::err!("\n💾 Results written to {}{}", path.display().to_string().blue(), note);
+        }
+
+        // Detached signature for chain of custody
+        if let Some(key) = &config.sign_key {
+            let sig_path = signing::sign_report(path, key)?;
+            if !config.silent {
+                term::err!("🔏 Report signed: {}", sig_path.display().to_string().blue());
+            }
+        }
+    }
+
+    // Detailed timing and performance information
+    if !config.silent {
+        let skipped = state.skipped.load(Ordering::Relaxed);
+        if skipped > 0 {
+            let hosts: Vec<String> = state.dead_hosts.lock().unwrap().iter().cloned().collect();
+            term::err!("\n⚡ Skipped {} requests to unresponsive hosts: {}", skipped, hosts.join(", ").red());
+        }
+
+        let errors = state.metrics.errors();
+        if !errors.is_empty() {
+            let by_kind: Vec<String> = errors.iter().map(|(kind, n)| format!("{} {}", n, kind)).collect();
+            term::err!("\n❌ Failed requests: {}", by_kind.join(", ").red());
+        }
+
+        let duplicates = state.duplicates.load(Ordering::Relaxed);
+        if duplicates > 0 {
+            term::err!("\n♻️  Skipped {} duplicate requests", duplicates);
+        }
+
+        let unchanged = state.unchanged.load(Ordering::Relaxed);
+        if unchanged > 0 {
+            term::err!("\n💤 {} findings unchanged since the previous scan (304 Not Modified)", unchanged);
+        }
+
+        let redactions = state.redactor.count();
+        if redactions > 0 {
+            term::err!("\n🔒 Redacted {} sensitive values from output", redactions);
+        }
+
+        term::err!(
+            "\n⏱️  Total Scan Time: {:.2} seconds 🕒",
+            elapsed_time.as_secs_f64()
+        );
+    }
+
+    // Evidence archive, redacted and encrypted like the results
+    if let (Some(path), Some(recorder)) = (&config.har, &state.har) {
+        let archive = recorder.to_json()?;
+        let archive = state.redactor.redact(&archive);
+        crypto::write_file(path, archive.as_bytes(), state.cipher.as_ref())?;
+        if !config.silent {
+            let note = if state.cipher.is_some() { " (encrypted)" } else { "" };
+            term::err!("🗃️  HAR with {} entries written to {}{}", recorder.count(), path.display().to_string().blue(), note);
+        }
+    }
+
+    // Completion summary for the team channel
+    if let Some(chat) = &state.chat {
+        let mut by_severity: Vec<(secrets::Severity, usize)> = Vec::new();
+        for finding in &found_paths {
+            match by_severity.iter_mut().find(|(severity, _)| *severity == finding.severity) {
+                Some((_, n)) => *n += 1,
+                None => by_severity.push((finding.severity, 1)),
+            }
+        }
+        by_severity.sort_by_key(|(severity, _)| std::cmp::Reverse(*severity));
+        chat.finish(chat::Summary {
+            findings: found_paths.len(),
+            by_severity,
+            elapsed: elapsed_time,
+            stopped: state.stop_reason.get().cloned(),
+        }).await;
+    }
+
+    if let (Some(path), Some(warc)) = (&config.warc, &state.warc) {
+        if !config.silent {
+            term::err!("🗄️  WARC with {} responses written to {}", warc.count(), path.display().to_string().blue());
+        }
+    }
+
+    // Leave a summary behind so the workspace can be listed later
+    if let Some(ws) = &state.workspace {
+        ws.write_summary(&workspace::ScanSummary {
+            name: ws.name.clone(),
+            target: config.url.clone(),
+            scan_id: config.scan_id.clone(),
+            started_at,
+            duration_secs: elapsed_time.as_secs_f64(),
+            hits: found_paths.len(),
+            errors: state.errors.load(Ordering::Relaxed),
+        })?;
+    }
+
+    progress_bar.finish_with_message(term::plain(term::Stream::Stderr, "🔍 Directory Fuzzing Complete!").into_owned());
+    Ok(())
+}
+
+/// `-H 'Name: value'` arguments as name/value pairs
+fn parse_headers(values: &[String]) -> Result<Vec<(String, String)>, String> {
+    values.iter()
+        .map(|header| {
+            header.split_once(':')
+                .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                .ok_or_else(|| format!("Invalid header '{}'. Use 'Name: value'", header))
+        })
+        .collect()
+}
+
+/// Shared secret for distributed scans, from `--agent-secret` or the environment
+fn agent_secret(args: &cli::ScanArgs) -> Result<String, String> {
+    args.agent_secret
+        .clone()
+        .filter(|secret| !secret.is_empty())
+        .ok_or_else(|| format!(
+            "Distributed scans need a shared secret: pass --agent-secret or set {} on the coordinator and every agent",
+            distributed::SECRET_ENV
+        ))
+}
+
+/// The `dir_crawler` command line: run the command given, and the exit
+/// status its outcome calls for
+pub async fn main() -> std::process::ExitCode {
+    match run().await {
+        Ok(()) => exit::recorded().code(),
+        Err(e) => {
+            term::err!("Error: {}", e);
+            exit::Outcome::of_error(&e).code()
+        }
+    }
+}
+
+async fn run() -> Result<(), CrawlerError> {
+    // Subcommands, with the legacy flat command line running as `scan`
+    let command_line: Vec<String> = std::env::args_os().skip(1).map(|arg| arg.to_string_lossy().into_owned()).collect();
+    let parsed = cli::Cli::try_parse_env(std::env::args_os()).unwrap_or_else(|e| e.exit());
+    term::init(parsed.no_color, parsed.no_emoji);
+    if let Some(dir) = &parsed.generate_man {
+        return Ok(cli::man_pages(dir.as_deref().map(Path::new))?);
+    }
+    let Some(command) = parsed.into_command() else {
+        return Err("No command given; see dir_crawler --help".into());
+    };
+    match command {
+        command @ (Commands::Scan(_) | Commands::Vhost(_)) => start_scan(command, command_line, None).await,
+
+        // Subdomain enumeration: resolve every candidate name and exit
+        Commands::Dns(dns) => {
+            let wordlist = dns.wordlist.map(PathBuf::from)
+                .or_else(wordlist::find_subdomains)
+                .ok_or("No subdomain wordlist found. Install SecLists (dir_crawler wordlists install seclists) or pass -w")?;
+            let options = subdomains::Options {
+                domain: dns.domain.trim_end_matches('.').to_lowercase(),
+                wordlist,
+                threads: dns.threads.max(1),
+                resolver: dns.resolver,
+                output: dns.output.map(PathBuf::from),
+            };
+            subdomains::run(&options).await
+        }
+
+        // Results files: convert, verify or decrypt one and exit
+        Commands::Report { command } => match command {
+            ReportCommand::Convert { results, format, output } => {
+                let data = std::fs::read(&results).map_err(|e| format!("Cannot read {}: {}", results, e))?;
+                let report: output::ScanReport = serde_json::from_slice(&data)
+                    .map_err(|e| format!("{} is not a JSON results file: {}", results, e))?;
+                let config = FuzzerConfig { format: output::OutputFormat::parse(&format)?, ..Default::default() };
+                if config.format == output::OutputFormat::Sqlite {
+                    let path = output.ok_or("Converting to sqlite needs --output for the database")?;
+                    return sqlite::store(&PathBuf::from(path), &report, &redact::Redactor::default());
+                }
+                let text = report.render(&config);
+                match output {
+                    Some(path) => crypto::write_file(&PathBuf::from(path), term::strip_ansi(&text).as_bytes(), None)?,
+                    None => println!("{}", text),
+                }
+                Ok(())
+            }
+            ReportCommand::Verify { results, key } => verify_report(&results, key.as_deref()),
+            ReportCommand::Decrypt { file, key_file, output } => {
+                let cipher = crypto::OutputCipher::from_key_file(&PathBuf::from(key_file))?;
+                decrypt_file(&file, &cipher, output.as_deref())
+            }
+        },
+
+        // Resume mode: run an interrupted workspace scan's command line again,
+        // skipping what it got through
+        Commands::Resume(resume) => {
+            let checkpoint = resume::Checkpoint::load(&workspace::expand_root(&resume.root), &resume.id)?;
+            let recorded = checkpoint.command.clone();
+            let args = std::iter::once("dir_crawler".to_string()).chain(recorded.iter().cloned());
+            let command = cli::Cli::try_parse_env(args)
+                .map_err(|e| format!("Cannot re-run the command recorded in {}: {}", checkpoint.workspace.name, e))?
+                .into_command()
+                .ok_or_else(|| format!("No command recorded in {}", checkpoint.workspace.name))?;
+            start_scan(command, recorded, Some(checkpoint)).await
+        }
+
+        // Wordlist management: install or list sources and exit
+        Commands::Wordlists { command } => match command {
+            WordlistsCommand::Install { dir, .. } => wordlist::install_seclists(&workspace::expand_root(&dir)),
+            WordlistsCommand::List { cache } => {
+                wordlist::list(&workspace::expand_root(&cache));
+                Ok(())
+            }
+        },
+
+        // History mode: browse past workspaces and exit
+        Commands::History(history) => {
+            let root = workspace::expand_root(&history.root);
+            match history.command {
+                Some(HistoryCommand::Show { id }) => history::show(&root, &id),
+                None => {
+                    history::list(&root);
+                    Ok(())
+                }
+            }
+        }
+
+        // Replay mode: re-check a past scan's findings and exit
+        Commands::Replay(replay) => {
+            let threads = replay.threads.parse::<usize>().ok().filter(|n| *n > 0)
+                .ok_or("--threads must be a positive number")?;
+            let verdicts = replay.verdict.as_deref().unwrap_or_default().split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| review::Verdict::parse(name)
+                    .ok_or_else(|| format!("Unknown verdict '{}'. Use interesting, false-positive or done", name)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let min_severity = secrets::Severity::parse(&replay.min_severity)
+                .ok_or_else(|| format!("Unknown severity '{}'", replay.min_severity))?;
+            let config = FuzzerConfig {
+                headers: parse_headers(&replay.header)?,
+                cookie: replay.cookie,
+                ..Default::default()
+            };
+            let options = replay::Options {
+                results: PathBuf::from(replay.results),
+                output: replay.output.map(PathBuf::from),
+                threads,
+                statuses: parse_status_filter(replay.status.as_deref().unwrap_or_default())?,
+                verdicts,
+                min_severity,
+            };
+            replay::run(config, &options).await
+        }
+
+        // Review mode: triage a past scan's findings and exit
+        Commands::Review(review) => {
+            let preview_lines = review.preview_lines.parse().map_err(|_| "--preview-lines must be a number")?;
+            let config = FuzzerConfig {
+                headers: parse_headers(&review.header)?,
+                cookie: review.cookie,
+                ..Default::default()
+            };
+            let options = review::Options {
+                results: PathBuf::from(review.results),
+                output: review.output.map(PathBuf::from),
+                all: review.all,
+                preview_lines,
+            };
+            review::run(&config, &options).await
+        }
+
+        // Target-derived wordlist: crawl, print or save, and exit
+        Commands::Wordgen(wordgen) => {
+            let url = validate_url(&wordgen.url)?;
+            let config = FuzzerConfig { url, ..Default::default() };
+            let client = build_client(&config)?;
+            let state = ScanState::default();
+            let learned = wordgen::learn(&config, &state, &client, wordgen.depth, wordgen.max_pages, wordgen.min_length).await?;
+            let list = learned.words.join("\n");
+            match wordgen.output {
+                Some(path) => std::fs::write(path, list + "\n")?,
+                None => println!("{}", list),
+            }
+            term::err!("🧠 Learned {} words from {} pages", learned.words.len(), learned.pages);
+            Ok(())
+        }
+
+        // Benchmark mode: measure throughput against a local server and exit
+        Commands::Bench(bench) => {
+            let requests = bench.requests.parse::<usize>().ok().filter(|n| *n > 0)
+                .ok_or("--requests must be a positive number")?;
+            let threads = bench.threads.split(',')
+                .map(|n| n.trim().parse::<usize>().ok().filter(|n| *n > 0))
+                .collect::<Option<Vec<_>>>()
+                .ok_or("--threads must be a comma-separated list of positive numbers")?;
+            let latency = bench.latency.parse::<u64>().map_err(|_| "--latency must be a number of milliseconds")?;
+            bench::run(&bench::Options { requests, threads, latency: Duration::from_millis(latency) }).await
+        }
+
+        // Shell completions: print the script and exit
+        Commands::Completions { shell } => {
+            use std::io::Write;
+            std::io::stdout().write_all(&cli::completions(shell))?;
+            Ok(())
+        }
+    }
+}
+
+/// Run `scan` or `vhost`. `command_line` is kept in the workspace so
+/// `resume` can run it again; `checkpoint` is what an earlier run of it got
+/// through.
+async fn start_scan(
+    command: Commands,
+    command_line: Vec<String>,
+    checkpoint: Option<resume::Checkpoint>,
+) -> Result<(), CrawlerError> {
+    match command {
+        Commands::Scan(args) => scan(args, None, command_line, checkpoint, None).await,
+        Commands::Vhost(vhost) => {
+            let args = vhost.scan;
+            let given = [
+                ("--fuzz-header", args.fuzz_header.is_some()),
+                ("--fuzz-param", args.fuzz_param.is_some()),
+                ("--request", args.request.is_some()),
+                ("--mode", args.mode.is_some()),
+                ("--nmap-xml", args.nmap_xml.is_some()),
+                ("--targets", args.targets.is_some()),
+            ];
+            if let Some((flag, _)) = given.iter().find(|(_, given)| *given) {
+                return Err(format!("vhost fuzzes the Host header of one URL; {} can't be used with it", flag).into());
+            }
+            let url = validate_url(args.url.as_ref().ok_or("vhost needs a target URL")?)?;
+            let domain = match vhost.domain {
+                Some(domain) => domain.trim_matches('.').to_lowercase(),
+                None => Url::parse(&url).map_err(|e| CrawlerError::url(&url, e))?.host_str()
+                    .filter(|host| host.trim_matches(['[', ']']).parse::<IpAddr>().is_err())
+                    .map(str::to_string)
+                    .ok_or("The URL's host is an IP address; pass --domain for the names to try")?,
+            };
+            scan(args, Some(format!("Host: FUZZ.{}", domain)), command_line, checkpoint, None).await
+        }
+        _ => Err("Only scan and vhost command lines can be resumed".into()),
+    }
+}
+
+/// Run a scan set up through `FuzzerConfig::builder`: the scan alone,
+/// without the command line's workspace, login, chat or agents
+async fn scan_config(config: FuzzerConfig, stream: fuzzer::Outlet) -> Result<(), CrawlerError> {
+    config.validate()?;
+    let redactor = redact::Redactor::default();
+    let state = Arc::new(ScanState {
+        sinks: sink::Sinks::for_scan(&config, &redactor, None)?,
+        plugins: plugin::Plugins::builtin(&config)?,
+        throttle: targets::throttle(&config.target_overrides),
+        redactor,
+        cancellation: stream.cancel.clone(),
+        progress: progress::Progress::to(stream.progress.clone()),
+        stream: Some(stream),
+        ..Default::default()
+    });
+    let result = fuzz_directory(Arc::new(config), Arc::clone(&state), Vec::new(), String::new(), 1).await;
+    if let Some(stream) = &state.stream {
+        stream.close();
+    }
+    result
+}
+
+/// Check a signed report against its `.sig`, and against `key` when given
+fn verify_report(report: &str, key: Option<&str>) -> Result<(), CrawlerError> {
+    let trusted = key.map(|path| signing::load_public_key(&PathBuf::from(path))).transpose()?;
+    let public_key = signing::verify_report(&PathBuf::from(report), trusted.as_ref())?;
+    term::out!("✅ Signature valid for {}", report.green());
+    term::out!("🔑 Signer public key: {} ({})", public_key.cyan(), signing::fingerprint(&public_key));
+    if trusted.is_none() {
+        term::err!(
+            "⚠️  {}",
+            "No public key given to check against: this only proves the report matches the key inside its .sig file. Check the fingerprint against the signer's.".yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Recover a file written with `--encrypt-output`, to `output` or stdout
+fn decrypt_file(path: &str, cipher: &crypto::OutputCipher, output: Option<&str>) -> Result<(), CrawlerError> {
+    let plaintext = cipher.decrypt(&std::fs::read(path)?)?;
+    match output {
+        Some(out) => crypto::write_file(&PathBuf::from(out), &plaintext, None)?,
+        None => {
+            use std::io::Write;
+            std::io::stdout().write_all(&plaintext)?;
+        }
+    }
+    Ok(())
+}
+
+/// One scan, of paths or of the header in `fuzz_header` (which stands in
+/// for `--fuzz-header`)
+async fn scan(
+    args: cli::ScanArgs,
+    fuzz_header: Option<String>,
+    command_line: Vec<String>,
+    checkpoint: Option<resume::Checkpoint>,
+    stream: Option<fuzzer::Outlet>,
+) -> Result<(), CrawlerError> {
+    // Modes whose stdout is data (a decrypted file) or a short answer run
+    // before the banner so it never mixes into their output.
+    // List personas and exit
+    if args.list_personas {
+        for persona in persona::PERSONAS {
+            term::out!("🎭 {:<14} {}", persona.name.cyan(), persona.description);
+        }
+        return Ok(());
+    }
+
+    // Verify mode: check a signed report and exit
+    if let Some(report) = args.verify_report.as_ref() {
+        return verify_report(report, args.verify_key.as_deref());
+    }
+
+    // Encryption key for results at rest
+    let cipher = args.key_file.as_ref()
+        .map(|path| crypto::OutputCipher::from_key_file(&PathBuf::from(path)))
+        .transpose()?;
+
+    // Decrypt mode: recover a previously encrypted results file
+    if let Some(path) = args.decrypt.as_ref() {
+        let cipher = cipher.ok_or("--decrypt requires --key-file")?;
+        return decrypt_file(path, &cipher, args.output.as_deref());
+    }
+
+    // Results going to stdout (md, ndjson or curl without --output or a
+    // workspace) must not mix with the banner, progress bar and status lines
+    let ci = args.ci;
+    if ci {
+        colored::control::set_override(false);
+    }
+    let silent = args.silent
+        || ci
+        || stream.is_some()
+        || (args.format != "json"
+            && args.output.is_none()
+            && args.workspace.is_none());
+
+    // Silent mode starts straight into output, no banner
+    if !silent && !args.no_banner {
+        print_banner();
+    }
+
+    // Per-scan workspace that results, logs and stored responses land in
+    let workspace = match args.workspace.as_ref() {
+        // A resumed scan goes on in the workspace it was interrupted in
+        Some(_) if checkpoint.is_some() => checkpoint.as_ref().map(|checkpoint| checkpoint.workspace.clone()),
+        Some(name) => {
+            let target = args.url.as_ref()
+                .map(|url| validate_url(url))
+                .transpose()?
+                .unwrap_or_default();
+            Some(workspace::Workspace::create(&workspace::expand_root(&args.workspace_root), name, &target)?)
+        }
+        None => None,
+    };
+    let place = |given: Option<&String>, default: Option<&str>| match &workspace {
+        Some(ws) => ws.place(given, default),
+        None => given.map(PathBuf::from),
+    };
+
+    // Diagnostics to stderr by -v count, and to --log-file if given; a
+    // program streaming the findings has its own logging
+    if stream.is_none() {
+        logging::init(args.verbose, place(args.log_file.as_ref(), Some("scan.log")).as_deref())?;
+    }
+
+    let threads = args.threads;
+
+    // Audit log of every request sent
+    let audit_log = place(args.audit_log.as_ref(), Some("audit.log"))
+        .map(|path| audit::AuditLog::create(&path))
+        .transpose()?;
+
+    let cipher = cipher.filter(|_| args.encrypt_output);
+
+    // Sensitive value redaction for everything written or printed
+    let redactor = redact::Redactor::new(&args.redact.clone())?;
+
+    // Agent mode: serve work units from a coordinator instead of scanning locally
+    if let Some(listen) = args.agent_listen.as_ref() {
+        return distributed::run_agent(listen, threads, agent_secret(&args)?, args.redact.clone()).await;
+    }
+
+    // Raw request template; a positional URL overrides its origin
+    let request_template = match args.request.as_ref() {
+        Some(path) => {
+            let raw = std::fs::read_to_string(path)
+                .map_err(|e| format!("Cannot read request template {}: {}", path, e))?;
+            let origin = args.url.as_ref().map(|url| validate_url(url)).transpose()?;
+            Some(template::RequestTemplate::parse(&raw, origin.as_deref())?)
+        }
+        None => None,
+    };
+
+    // Header and parameter fuzzing: the URL stays fixed and the wordlist
+    // fills one header or one query parameter
+    let fuzz_header = fuzz_header.as_ref().or(args.fuzz_header.as_ref());
+    let fuzz_param = args.fuzz_param.as_ref();
+    let fixed_url = fuzz_header.is_some() || fuzz_param.is_some();
+    let request_template = match request_template {
+        Some(_) if fixed_url => {
+            return Err("--fuzz-header and --fuzz-param can't be combined with --request; put the marker in the template instead".into())
+        }
+        None if fixed_url => {
+            if fuzz_header.is_some() && fuzz_param.is_some() {
+                return Err("Use either --fuzz-header or --fuzz-param, not both".into());
+            }
+            let url = validate_url(args.url.as_ref().ok_or("URL is required")?)?;
+            let method = match args.method.to_uppercase().as_str() {
+                "POST" => "POST",
+                _ => "GET",
+            };
+            Some(match (fuzz_header, fuzz_param) {
+                (Some(header), _) => template::RequestTemplate::for_header(&url, method, header)?,
+                (_, Some(param)) => template::RequestTemplate::for_param(&url, method, param)?,
+                _ => unreachable!("fixed-URL mode needs a header or parameter"),
+            })
+        }
+        template => template,
+    };
+
+    // Bucket enumeration builds its own URLs; a target URL only seeds --learn
+    let buckets = args.mode.as_ref().is_some_and(|mode| mode == "buckets");
+
+    // Web services from an Nmap scan, each scanned as its own target
+    let mut services = match args.nmap_xml.as_ref() {
+        Some(path) => {
+            let services = nmap::load(&PathBuf::from(path))?;
+            if services.is_empty() {
+                return Err(format!("No open HTTP(S) ports in {}", path).into());
+            }
+            if !silent {
+                let hosts: HashSet<&str> = services.iter().map(|service| service.host.as_str()).collect();
+                term::err!("🗺️  {} web services on {} hosts from {}", services.len().to_string().green(), hosts.len(), path.blue());
+            }
+            services
+        }
+        None => Vec::new(),
+    };
+
+    // Targets listed in a file, some scanned with their own settings
+    let (listed_targets, target_overrides) = match args.targets.as_ref() {
+        Some(path) => {
+            let (targets, overrides) = targets::load(&PathBuf::from(path))?;
+            if !silent {
+                term::err!("🎯 {} targets from {} ({} with their own settings)", targets.len().to_string().green(), path.blue(), overrides.len());
+            }
+            (Some(targets), overrides)
+        }
+        None => (None, Vec::new()),
+    };
+
+    // Address ranges expand into one base URL per host and port
+    let range_targets = match (&request_template, args.url.as_ref()) {
+        _ if !services.is_empty() => Some(services.iter().map(|service| service.url.clone()).collect()),
+        _ if listed_targets.is_some() => listed_targets,
+        (None, Some(url)) if !buckets => targets::expand(url)?,
+        _ => None,
+    };
+
+    // Validate and process URL
+    let validated_url = match (&request_template, args.url.as_ref()) {
+        (Some(template), _) => template.base_url(),
+        (None, _) if range_targets.is_some() => range_targets.as_ref().map(|targets| targets[0].clone()).unwrap_or_default(),
+        (None, Some(url)) => validate_url(url)?,
+        (None, None) if buckets && !args.learn => buckets::TARGET.to_string(),
+        (None, None) => return Err("URL is required".into()),
+    };
+    let address_family = match (args.ipv4_only, args.ipv6_only) {
+        (true, _) => dns::AddressFamily::Ipv4,
+        (_, true) => dns::AddressFamily::Ipv6,
+        _ => dns::AddressFamily::Any,
+    };
+    let detect_scheme = request_template.is_none()
+        && range_targets.is_none()
+        && args.url.as_ref().is_some_and(|url| !has_scheme(url));
+
+    // Anti-CSRF token fetched ahead of templated requests
+    let csrf = match args.csrf_url.as_ref() {
+        Some(page) => {
+            if !request_template.as_ref().is_some_and(csrf::has_placeholder) {
+                return Err(format!("--csrf-url needs a --request template with {} in a header or the body", csrf::PLACEHOLDER).into());
+            }
+            let extractor = match (args.csrf_regex.as_ref(), args.csrf_selector.as_ref()) {
+                (Some(pattern), _) => csrf::Extractor::regex(pattern)?,
+                (_, Some(css)) => csrf::Extractor::selector(css)?,
+                _ => return Err("--csrf-url needs --csrf-regex or --csrf-selector to find the token".into()),
+            };
+            let url = reqwest::Url::parse(&validated_url)
+                .map_err(|e| CrawlerError::url(&validated_url, e))?
+                .join(page)
+                .map_err(|e| format!("Invalid --csrf-url {}: {}", page, e))?;
+            let refresh_every = args.csrf_refresh.unwrap_or(0);
+            Some(csrf::CsrfSource::new(url.to_string(), extractor, refresh_every))
+        }
+        None => None,
+    };
+
+    // Process extensions
+    let extensions: Vec<String> = args.extensions.as_ref()
+        .map(|ext|
+            ext.split(',')
+                .map(|e| e.trim().to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect()
+        )
+        .unwrap_or_default();
+
+    // Remote wordlists are downloaded once and reused from the cache
+    let mut given = Vec::new();
+    for (i, spec) in args.wordlist.clone().into_iter().enumerate() {
+        if !wordlist::is_remote(&spec) {
+            given.push(PathBuf::from(spec));
+            continue;
+        }
+        let cache = &args.wordlist_cache;
+        let expected = args.wordlist_sha256.as_deref().filter(|_| i == 0);
+        let (path, downloaded) = wordlist::fetch(&spec, &workspace::expand_root(cache), expected)
+            .await
+            .map_err(|e| format!("Cannot fetch wordlist {}: {}", spec, e))?;
+        if downloaded && !silent {
+            term::err!("📥 Downloaded wordlist to {}", path.display().to_string().blue());
+        }
+        given.push(path);
+    }
+    let mut given = given.into_iter();
+
+    // Flexible wordlist selection, falling back to the embedded list
+    let wordlist = given.next()
+        .or_else(wordlist::find_default)
+        .or_else(|| wordlist::builtin_available().then(|| PathBuf::from(wordlist::BUILTIN)))
+        .ok_or(CrawlerError::NoWordlist)?;
+
+    // Further -w lists feed FUZZ2, FUZZ3, ... of a multi-position template
+    let position_wordlists: Vec<PathBuf> = given.collect();
+    let positions = request_template.as_ref().map_or(0, |template| template.positions);
+    if positions > 1 {
+        if !position_wordlists.is_empty() && position_wordlists.len() + 1 != positions {
+            return Err(format!(
+                "Request template has {} positions but {} wordlists were given; pass one -w per position, or a single -w for all",
+                positions,
+                position_wordlists.len() + 1
+            ).into());
+        }
+    } else if !position_wordlists.is_empty() {
+        return Err("Several -w wordlists need a request template with FUZZ1..FUZZn markers".into());
+    }
+    let fuzz_mode = match args.mode.as_deref() {
+        Some("pitchfork") => template::Mode::Pitchfork,
+        _ => template::Mode::Clusterbomb,
+    };
+
+    // Parse status codes with intelligent defaults
+     let status_codes = args.status_codes.as_ref()
+        .map(|codes|
+            codes.split(',')
+                .filter_map(|c| c.parse().ok())
+                .collect()
+        )
+        // Header and parameter fuzzing look for any change from the baseline, errors
+        // included; bucket enumeration classifies every response itself
+        .unwrap_or_else(|| match fixed_url || buckets {
+            true => Vec::new(),
+            false => vec![200, 204, 301, 302, 307, 401, 403],
+        });
+
+    // Parse request method; a request template brings its own
+    let method = match &request_template {
+        Some(template) => Method::from_bytes(template.method.as_bytes())
+            .map_err(|_| format!("Invalid method '{}' in the request template", template.method))?,
+        None => match args.method.to_uppercase().as_str() {
+            "POST" => Method::POST,
+            _ => Method::GET,
+        },
+    };
+
+    // User-supplied secret detection rules
+    let secret_rules = match args.secret_rules.as_ref() {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read secret rules {}: {}", path, e))?
+            .lines()
+            .map(String::from)
+            .collect(),
+        None => Vec::new(),
+    };
+    let severity_rules: Vec<String> = match args.severity_rules.as_ref() {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read severity rules {}: {}", path, e))?
+            .lines()
+            .map(String::from)
+            .collect(),
+        None => Vec::new(),
+    };
+    // Fail on a bad rule now rather than once the scan has started
+    severity::Scorer::new(&severity_rules)?;
+
+    // Validate the requested persona up front
+    let persona = match args.persona.as_ref() {
+        Some(name) => Some(
+            persona::find(name)
+                .ok_or_else(|| format!("Unknown persona '{}'. Available: {}", name, persona::names()))?
+                .name
+                .to_string(),
+        ),
+        None => None,
+    };
+
+    // Custom headers ("Name: value") and session cookies
+    let headers = parse_headers(&args.header.clone())?;
+    let cookie = args.cookie.clone();
+
+    // Custom DNS upstream, checked before any request is made
+    let dns_resolver = args.dns_resolver.clone();
+    if let Some(spec) = &dns_resolver {
+        dns::Upstream::parse(spec)?;
+    }
+
+    // Word variants, from a rules file or the built-in sets
+    let mutations = match (
+        args.mutation_rules.as_ref(),
+        args.mutations.as_ref(),
+    ) {
+        (Some(path), _) => Some(mutate::Mutations::load(&PathBuf::from(path))?),
+        (None, Some(kinds)) => Some(mutate::Mutations::from_kinds(kinds)?),
+        (None, None) => None,
+    };
+
+    let max_time = args.max_time.as_ref()
+        .map(|spec| parse_duration(spec))
+        .transpose()?
+        .filter(|secs| *secs > 0);
+
+    let max_body_size = Some(parse_size(&args.max_body_size)?).filter(|size| *size > 0);
+    // A glance at each hit's body when running with -v
+    let snippet_bytes = match args.verbose {
+        0 => None,
+        _ => {
+            let spec = args.preview_bytes.as_deref().unwrap_or(review::DEFAULT_SNIPPET_BYTES);
+            Some(parse_size(spec)? as usize).filter(|bytes| *bytes > 0)
+        }
+    };
+    // Whole exchanges for a sample of requests at -vvv
+    let dump = match args.verbose {
+        0..=2 => None,
+        _ => Some(args.dump.as_deref().unwrap_or(dump::DEFAULT_SAMPLE).parse()?),
+    };
+    let probe_methods = match args.probe_methods.as_ref() {
+        Some(list) => methods::parse(list)?,
+        None => Vec::new(),
+    };
+    let spray_credentials = match args.spray_basic_auth.as_ref() {
+        Some(path) => spray::load(&PathBuf::from(path))?,
+        None => Vec::new(),
+    };
+    let screenshots = args.screenshots.as_ref().map(PathBuf::from);
+    if screenshots.is_some() {
+        screenshot::check()?;
+    }
+    let max_bandwidth = args.max_bandwidth.as_ref().map(|rate| bandwidth::parse(rate)).transpose()?;
+    // Build-breaking findings for CI
+    let policy = match args.fail_on.as_ref() {
+        Some(expression) => Some(policy::Policy::parse(expression)?),
+        None if ci => Some(policy::Policy::any_finding()),
+        None => None,
+    };
+    if policy.is_none() && args.junit.is_some() {
+        return Err("--junit reports on a policy: add --fail-on or --ci".into());
+    }
+    let har_max_body = parse_size(&args.har_max_body)?;
+
+    // Origin testing: fixed addresses and an overridden Host header
+    let resolve = args.resolve.clone()
+        .iter()
+        .map(|spec| parse_resolve(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // An interface sends from the family the target answers on
+    let source_ip = match (args.source_ip, args.interface.as_ref()) {
+        (Some(address), _) => Some(address),
+        (None, Some(name)) => {
+            let target = match address_family {
+                dns::AddressFamily::Any => target_addresses(&validated_url, &resolve, dns_resolver.as_deref()).await?,
+                _ => Vec::new(),
+            };
+            Some(interface::address(name, address_family, &target)?)
+        }
+        (None, None) => None,
+    };
+
+    // Differential scanning between two personas or auth states
+    let differential = match args.differential.as_deref() {
+        Some("auth") => {
+            if cookie.is_none() && !headers.iter().any(|(name, _)| is_session_header(name)) {
+                return Err("--differential auth needs a session via --cookie or an auth header (-H)".into());
+            }
+            Some(Differential::Auth)
+        }
+        Some(spec) => {
+            let list = spec.strip_prefix("personas=")
+                .ok_or_else(|| format!("Unknown differential mode '{}'. Use personas=<a>,<b> or auth", spec))?;
+            let names: Vec<&str> = list.split(',').map(str::trim).collect();
+            let [first, second] = names[..] else {
+                return Err("Differential personas need exactly two names, e.g. personas=desktop,mobile".into());
+            };
+            let resolve = |name: &str| persona::find(name)
+                .map(|p| p.name.to_string())
+                .ok_or_else(|| format!("Unknown persona '{}'. Available: {}", name, persona::names()));
+            Some(Differential::Personas(resolve(first)?, resolve(second)?))
+        }
+        None => None,
+    };
+
+    // The first differential persona drives the primary client
+    let persona = match &differential {
+        Some(Differential::Personas(primary, _)) => Some(primary.clone()),
+        _ => persona,
+    };
+
+    // Recursion order through discovered directories
+    let recursion_strategy = match args.recursion_strategy.as_str() {
+        "dfs" => RecursionStrategy::Dfs,
+        "priority" => RecursionStrategy::Priority,
+        "bfs" => RecursionStrategy::Bfs,
+        other => {
+            return Err(format!("Unknown recursion strategy '{}'. Use bfs, dfs or priority", other).into());
+        }
+    };
+
+    // Layout of the results file
+    let format = output::OutputFormat::parse(&args.format)?;
+    let sort = match args.sort.as_str() {
+        "severity" => output::SortOrder::Severity,
+        "modified" => output::SortOrder::Modified,
+        other => return Err(format!("Unknown sort order '{}'. Use severity or modified", other).into()),
+    };
+    let urls_status = match args.urls_status.as_ref() {
+        Some(_) if format != output::OutputFormat::Urls => return Err("--urls-status only applies to --format urls".into()),
+        Some(list) => parse_status_filter(list)?,
+        None => Vec::new(),
+    };
+
+    // Hosts refusing connections would only trip the circuit breaker one by one
+    let targets = match range_targets {
+        Some(targets) if args.alive_check => {
+            let timeout = args.connect_timeout;
+            let expanded = targets.len();
+            let open = targets::alive(targets, Duration::from_secs(timeout)).await;
+            if !silent {
+                term::err!("📡 {} of {} targets accept connections", open.len().to_string().green(), expanded);
+            }
+            if open.is_empty() {
+                return Err("No target accepts connections".into());
+            }
+            services.retain(|service| open.contains(&service.url));
+            open
+        }
+        targets => targets.unwrap_or_default(),
+    };
+
+    // Create comprehensive fuzzer configuration
+    let mut config = FuzzerConfig {
+        url: targets.first().cloned().unwrap_or(validated_url),
+        targets,
+        services,
+        target_overrides,
+        wordlist,
+        wordlist_offset: args.wordlist_offset.unwrap_or(0),
+        wordlist_limit: args.wordlist_limit,
+        shuffle: args.shuffle,
+        stealth: None,
+        learn: args.learn,
+        api_discovery: args.api_discovery,
+        position_wordlists,
+        fuzz_mode,
+        buckets,
+        mutations,
+        threads,
+        silent,
+        status_codes,
+        timeout: args.timeout,
+        connect_timeout: args.connect_timeout,
+        read_timeout: args.read_timeout,
+        retries: args.retries,
+        max_body_size,
+        max_connections_per_host: args.max_connections_per_host.filter(|m| *m > 0),
+        max_bandwidth,
+        pool_idle_timeout: args.pool_idle_timeout,
+        tcp_keepalive: args.tcp_keepalive,
+        method,
+        request_template,
+        extensions,
+        detect_language: args.detect_language,
+        scan_id: generate_scan_id(),
+        request_id_header: args.request_id_header.clone(),
+        scope_include: args.scope_include.clone(),
+        scope_exclude: args.scope_exclude.clone(),
+        blocklist: args.blocklist.clone(),
+        adaptive_throttle: !args.no_adaptive_throttle,
+        output: place(args.output.as_ref(), Some(format.default_file_name())),
+        format,
+        urls_status,
+        match_content_types: args.match_content_type.as_ref()
+            .map(|types| parse_content_types(types))
+            .transpose()?
+            .unwrap_or_default(),
+        filter_content_types: args.filter_content_type.as_ref()
+            .map(|types| parse_content_types(types))
+            .transpose()?
+            .unwrap_or_default(),
+        store_responses: place(args.store_responses.as_ref(), None),
+        store_headers: args.store_headers,
+        har: place(args.har.as_ref(), None),
+        har_max_body,
+        warc: place(args.warc.as_ref(), None),
+        sign_key: args.sign_report.as_ref().map(PathBuf::from),
+        detect_secrets: args.detect_secrets || args.secret_rules.is_some(),
+        secret_rules,
+        extract_titles: args.titles,
+        audit_headers: args.audit_headers,
+        cors_origin: args.cors.clone(),
+        open_redirects: args.open_redirects,
+        probe_methods,
+        spray_credentials,
+        screenshots,
+        spray_delay: parse_duration(args.spray_delay.as_deref().unwrap_or(spray::DEFAULT_DELAY))?,
+        snippet_bytes,
+        dump,
+        sort,
+        script: args.script.as_ref().map(PathBuf::from),
+        severity_rules,
+        persona,
+        headers,
+        cookie,
+        host_header: args.host_header.clone(),
+        resolve,
+        address_family,
+        source_ip,
+        dns_resolver,
+        differential,
+        recursion_depth: if args.recursive && !fixed_url && !buckets {
+            args.depth
+        } else {
+            0
+        },
+        recursion_strategy,
+        max_dirs_per_level: args.max_dirs_per_level,
+        // Header and parameter fuzzing are only meaningful against the unmodified response
+        auto_calibrate: (args.auto_calibrate || fixed_url) && !buckets,
+        fuzz_param: fuzz_param.cloned(),
+        unique: args.unique,
+        vcs_checks: args.vcs_checks,
+        preflight: !args.no_preflight,
+        detect_scheme,
+        waf_check: args.waf_check || args.waf_adapt,
+        favicon: args.favicon,
+        waf_adapt: args.waf_adapt,
+        reconfirm: args.reconfirm,
+        reconfirm_delay: args.reconfirm_delay,
+        max_time,
+        max_errors: args.max_errors.filter(|m| *m > 0),
+        max_findings: args.max_findings.filter(|m| *m > 0),
+        host_error_threshold: Some(args.host_error_threshold).filter(|t| *t > 0),
+        dry_run: args.dry_run.is_some(),
+        dry_run_file: args.dry_run.as_ref().filter(|file| !file.is_empty()).map(PathBuf::from),
+        notify: args.notify,
+        junit: place(args.junit.as_ref(), None),
+    };
+    if args.stealth {
+        let changes = stealth::apply(&mut config);
+        if !silent {
+            term::err!("🥷 Stealth: {}", changes.join(", ").cyan());
+        }
+    }
+    config.validate()?;
+    let config = Arc::new(config);
+    // Findings written as they're found, so none are lost if the scan dies
+    let sinks = sink::Sinks::for_scan(&config, &redactor, cipher.as_ref())?;
+
+    // Team chat updates; nothing is announced for a dry run
+    let mut destinations = Vec::new();
+    for (id, given, destination) in [
+        ("slack-webhook", &args.slack_webhook, chat::Destination::SlackWebhook as fn(String) -> chat::Destination),
+        ("discord-webhook", &args.discord_webhook, chat::Destination::DiscordWebhook),
+    ] {
+        if let Some(url) = given {
+            reqwest::Url::parse(url).map_err(|e| format!("Invalid --{} '{}': {}", id, url, e))?;
+            destinations.push(destination(url.clone()));
+        }
+    }
+    if let Some(channel) = args.slack_channel.as_ref() {
+        let token = args.slack_token.as_ref()
+            .cloned()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| format!("--slack-channel needs a bot token: pass --slack-token or set {}", chat::SLACK_TOKEN_ENV))?;
+        destinations.push(chat::Destination::SlackApi { token, channel: channel.clone() });
+    }
+    let chat_severity = secrets::Severity::parse(&args.chat_severity)
+        .ok_or_else(|| format!("Unknown severity '{}' for --chat-severity", args.chat_severity))?;
+    let chat = (!destinations.is_empty() && !config.dry_run)
+        .then(|| chat::Chat::start(destinations, chat_severity, &redactor.redact(&config.url), &config.scan_id));
+
+    // Log in up front; the session is refreshed whenever it expires mid-scan
+    let session = match args.login_config.as_ref() {
+        Some(path) => {
+            let session = session::Session::start(session::LoginConfig::load(&PathBuf::from(path))?, &config).await?;
+            if !config.silent {
+                term::err!("🔐 Logged in via {}", redactor.redact(session.url()).cyan());
+            }
+            Some(session)
+        }
+        None => None,
+    };
+
+    // WARC capture, opened up front so a bad path fails before scanning
+    // Validators from an earlier scan, so unchanged findings cost a 304
+    let previous = match args.previous.as_ref() {
+        Some(path) => {
+            let previous = conditional::Previous::load(&PathBuf::from(path))?;
+            if !config.silent {
+                term::err!("🔁 Revalidating {} findings from {}", previous.len(), path.cyan());
+            }
+            Some(previous)
+        }
+        None => None,
+    };
+
+    let warc = match &config.warc {
+        Some(path) => Some(warc::Writer::create(path, &config.url, &config.scan_id)
+            .map_err(|e| format!("Cannot create WARC file {}: {}", path.display(), e))?),
+        None => None,
+    };
+
+    // Workspace scans keep their progress so `resume` can pick them up;
+    // distributed scans are tracked by their agents' work units instead
+    let journal = match &workspace {
+        Some(ws) if !config.dry_run && args.agents.is_none() => {
+            Some(resume::Journal::open(ws, &command_line, checkpoint.is_some())?)
+        }
+        _ => None,
+    };
+    if let Some(checkpoint) = &checkpoint {
+        if args.agents.is_some() {
+            return Err("Scans distributed with --agents can't be resumed".into());
+        }
+        if !config.silent {
+            term::err!(
+                "⏯️  Resuming {}: {} entries done, {} findings carried over",
+                checkpoint.workspace.name.cyan(),
+                checkpoint.entries_done().to_string().yellow(),
+                checkpoint.findings.len().to_string().green()
+            );
+        }
+    }
+
+    let state = Arc::new(ScanState {
+        hits: AtomicU64::new(checkpoint.as_ref().map_or(0, |checkpoint| checkpoint.findings.len() as u64)),
+        journal,
+        checkpoint,
+        audit_log,
+        redactor,
+        cipher,
+        workspace,
+        sinks,
+        session,
+        csrf,
+        har: config.har.as_ref().map(|_| har::Recorder::new(config.har_max_body)),
+        warc,
+        chat,
+        plugins: plugin::Plugins::builtin(&config)?,
+        previous,
+        throttle: targets::throttle(&config.target_overrides),
+        bandwidth: config.max_bandwidth.map(|rate| Arc::new(bandwidth::Limiter::new(rate))),
+        policy,
+        cancellation: stream.as_ref().map(|stream| stream.cancel.clone()).unwrap_or_default(),
+        progress: stream.as_ref().map(|stream| progress::Progress::to(stream.progress.clone())).unwrap_or_default(),
+        stream,
+        ..Default::default()
+    });
+
+    // Live counters for Prometheus, up before the first request
+    if let Some(port) = args.metrics_port {
+        if port == 0 {
+            return Err("Invalid --metrics-port '0'".into());
+        }
+        metrics::serve(port, Arc::clone(&state)).await?;
+        if !config.silent {
+            term::err!("📈 Metrics: {}", format!("http://0.0.0.0:{}/metrics", port).cyan());
+        }
+    }
+
+    // Remote agents for distributed scanning
+    let agents: Vec<String> = args.agents.as_ref()
+        .map(|list|
+            list.split(',')
+                .map(|a| a.trim().to_string())
+                .filter(|a| !a.is_empty())
+                .collect()
+        )
+        .unwrap_or_default();
+    let agent_secret = match agents.is_empty() {
+        true => String::new(),
+        false => agent_secret(&args)?,
+    };
+    let chunk_size = Some(args.chunk_size).filter(|c| *c > 0).unwrap_or(500);
+
+    // Run directory fuzzing, ending with the outcome's exit status. Ctrl-C
+    // aborts the scan, which still writes out what it found; a second one
+    // quits on the spot.
+    let started = Instant::now();
+    let target = state.redactor.redact(&config.url).into_owned();
+    let interrupt = state.stream.is_none().then(|| {
+        let (state, target, notify) = (Arc::clone(&state), target.clone(), config.notify);
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+            term::err!("\n🛑 Interrupted: dropping the requests in flight and writing the results (Ctrl-C again to quit now)");
+            state.abort("interrupted".to_string());
+            if tokio::signal::ctrl_c().await.is_ok() {
+                if notify {
+                    notify::send(&target, notify::Outcome::Aborted, state.hits.load(Ordering::Relaxed), started.elapsed());
+                }
+                std::process::exit(exit::Outcome::Aborted as i32);
+            }
+        })
+    });
+    let result = fuzz_directory(Arc::clone(&config), Arc::clone(&state), agents, agent_secret, chunk_size).await;
+    if let Some(interrupt) = interrupt {
+        interrupt.abort();
+    }
+    if let Some(stream) = &state.stream {
+        stream.close();
+    }
+    // A dry run sends nothing, so it only fails on errors
+    if result.is_ok() && !config.dry_run {
+        exit::record(exit::Outcome::of_scan(&state));
+    }
+    if !config.notify {
+        return result;
+    }
+
+    // ... and tell the desktop how it ended
+    let error = result.as_ref().err().map(|e| state.redactor.redact(&e.to_string()).into_owned());
+    let outcome = match (&error, state.stop_reason.get()) {
+        _ if state.cancellation.is_cancelled() => notify::Outcome::Aborted,
+        (Some(error), _) => notify::Outcome::Failed(error),
+        (None, Some(reason)) => notify::Outcome::Stopped(reason),
+        (None, None) => notify::Outcome::Finished,
+    };
+    notify::send(&target, outcome, state.hits.load(Ordering::Relaxed), started.elapsed());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_a_scheme_only_at_the_start() {
+        assert!(has_scheme("http://x"));
+        assert!(has_scheme("svn+ssh://x/repo"));
+        assert!(!has_scheme("example.com/?next=http://x"));
+        assert!(!has_scheme("example.com"));
+        assert!(!has_scheme("://x"));
+        assert_eq!(validate_url("example.com/?next=http://x").unwrap(), "https://example.com/?next=http://x");
+        assert_eq!(validate_url("http://x").unwrap(), "http://x");
+    }
+
+    #[test]
+    fn generates_encoded_urls_for_awkward_entries() {
+        let urls = |path: &str, extensions: &[&str]| {
+            let extensions: Vec<String> = extensions.iter().map(|e| e.to_string()).collect();
+            let mut urls: Vec<String> = generate_urls("http://example.com//", path, &extensions).into_iter().collect();
+            urls.sort();
+            urls
+        };
+        assert_eq!(urls("/admin/", &["php"]), ["http://example.com/admin", "http://example.com/admin.php"]);
+        assert_eq!(urls("admin//config", &[]), ["http://example.com/admin/config"]);
+        assert_eq!(urls("my file#1?.bak", &[]), ["http://example.com/my%20file%231%3F.bak"]);
+        assert_eq!(urls("café/ü", &[]), ["http://example.com/caf%C3%A9/%C3%BC"]);
+        assert_eq!(urls("%2e%2e;/100%", &[]), ["http://example.com/%2e%2e;/100%25"]);
+        assert_eq!(urls("a+b(1)@x:y", &[]), ["http://example.com/a+b(1)@x:y"]);
+    }
+
+    #[test]
+    fn parses_resolve_pins() {
+        assert_eq!(
+            parse_resolve("example.com:443:10.0.0.5").unwrap(),
+            ("example.com".to_string(), "10.0.0.5:443".parse().unwrap())
+        );
+        assert_eq!(
+            parse_resolve("example.com:80:[2001:db8::1]").unwrap().1,
+            "[2001:db8::1]:80".parse().unwrap()
+        );
+        assert_eq!(parse_resolve("example.com:80:2001:db8::1").unwrap().1.port(), 80);
+        assert!(parse_resolve("example.com:443").is_err());
+        assert!(parse_resolve("example.com:https:10.0.0.5").is_err());
+        assert!(parse_resolve("example.com:443:origin.internal").is_err());
+    }
+
+    #[test]
+    fn parses_sizes_in_binary_units() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("0").unwrap(), 0);
+        assert_eq!(parse_size("64KB").unwrap(), 64 * 1024);
+        assert_eq!(parse_size(" 1mb ").unwrap(), 1 << 20);
+        assert_eq!(parse_size("2 G").unwrap(), 2 << 30);
+        assert!(parse_size("1TB").is_err());
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("-1KB").is_err());
+        assert!(parse_size("99999999999999GB").is_err());
+    }
+
+    #[test]
+    fn matches_content_types_by_media_type() {
+        let types = parse_content_types("Application/JSON, image/*").unwrap();
+        assert!(content_type_matches(&types, Some("application/json; charset=utf-8")));
+        assert!(content_type_matches(&types, Some("IMAGE/png")));
+        assert!(!content_type_matches(&types, Some("application/problem+json")));
+        assert!(!content_type_matches(&types, Some("text/html")));
+        assert!(!content_type_matches(&types, None));
+        assert!(parse_content_types("json").is_err());
+        assert!(parse_content_types("text/html; charset=utf-8").is_err());
+    }
+
+    #[test]
+    fn parses_status_classes_and_codes() {
+        assert_eq!(parse_status_filter("2xx, 3XX,401").unwrap(), [(200, 299), (300, 399), (401, 401)]);
+        assert!(parse_status_filter("6xx").is_err());
+        assert!(parse_status_filter("99").is_err());
+        assert!(parse_status_filter("2x").is_err());
+    }
+
+    #[test]
+    fn parses_durations_into_seconds() {
+        assert_eq!(parse_duration("90").unwrap(), 90);
+        assert_eq!(parse_duration("45s").unwrap(), 45);
+        assert_eq!(parse_duration("30m").unwrap(), 1_800);
+        assert_eq!(parse_duration("2H").unwrap(), 7_200);
+        assert!(parse_duration("1d").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("1.5h").is_err());
+        assert!(parse_duration(&format!("{}h", u64::MAX)).is_err());
+    }
+}
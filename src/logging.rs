@@ -0,0 +1,57 @@
+use crate::error::CrawlerError;
+use crate::term;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::prelude::*;
+
+
+/// Console level for a `-v` count: silent by default, then info, debug, trace
+fn console_level(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::ERROR,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Install the global tracing subscriber.
+///
+/// Diagnostics go to stderr at the level picked by `-v/-vv/-vvv`, without
+/// colors or emoji when stderr doesn't show them. When a log file is given
+/// it always records at least debug level, so worker errors and filter
+/// decisions can be inspected after a quiet run. Only this crate's events
+/// are kept; HTTP library internals stay out.
+pub fn init(verbosity: u8, log_file: Option<&Path>) -> Result<(), CrawlerError> {
+    let console_level = console_level(verbosity);
+    let console = tracing_subscriber::fmt::layer()
+        .with_writer(|| term::Writer(term::Stream::Stderr))
+        .with_ansi(term::color(term::Stream::Stderr))
+        .without_time()
+        .with_target(false)
+        .with_filter(Targets::new().with_target("dir_crawler", console_level));
+
+    let file = match log_file {
+        Some(path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)
+                .map_err(|e| format!("Cannot open log file {}: {}", path.display(), e))?;
+            let level = console_level.max(LevelFilter::DEBUG);
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(Mutex::new(file))
+                    .with_ansi(false)
+                    .with_filter(Targets::new().with_target("dir_crawler", level)),
+            )
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(console)
+        .with(file)
+        .try_init()
+        .map_err(|e| format!("Cannot set up logging: {}", e))?;
+    Ok(())
+}
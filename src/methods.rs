@@ -0,0 +1,82 @@
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Methods `--probe-methods` tries without a list; PUT and DELETE can change
+/// the target, so they are only sent when asked for by name
+pub const DEFAULT_METHODS: &str = "OPTIONS,TRACE";
+
+/// How a finding answered another method
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MethodResponse {
+    pub method: String,
+    pub status: u16,
+    /// The `Allow` header, listing what the server says it accepts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow: Option<String>,
+}
+
+impl MethodResponse {
+    pub fn new(method: &Method, response: &reqwest::Response) -> Self {
+        MethodResponse {
+            method: method.to_string(),
+            status: response.status().as_u16(),
+            allow: response.headers().get(reqwest::header::ALLOW)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty()),
+        }
+    }
+}
+
+impl std::fmt::Display for MethodResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.method, self.status)?;
+        match &self.allow {
+            Some(allow) => write!(f, " (Allow: {})", allow),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A comma-separated method list, upper-cased; GET is what the scan already sent
+pub fn parse(list: &str) -> Result<Vec<String>, String> {
+    let mut methods: Vec<String> = Vec::new();
+    for method in list.split(',').map(|m| m.trim().to_uppercase()).filter(|m| !m.is_empty()) {
+        Method::from_bytes(method.as_bytes()).map_err(|_| format!("Invalid HTTP method '{}'", method))?;
+        if method != "GET" && !methods.contains(&method) {
+            methods.push(method);
+        }
+    }
+    if methods.is_empty() {
+        return Err(format!("No methods to probe in '{}'", list));
+    }
+    Ok(methods)
+}
+
+/// `method:<M>` for each method that succeeded, or answered differently
+/// from `status` without simply refusing the method (405, 501)
+pub fn tags(status: u16, responses: &[MethodResponse]) -> Vec<String> {
+    responses.iter()
+        .filter(|r| (200..300).contains(&r.status) || (r.status != status && !matches!(r.status, 405 | 501)))
+        .map(|r| format!("method:{}", r.method))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lists_and_tags_notable_methods() {
+        assert_eq!(parse("options, put,GET,Put").unwrap(), ["OPTIONS", "PUT"]);
+        assert!(parse("GET").is_err());
+        assert!(parse("DE LETE").is_err());
+
+        let response = |method: &str, status: u16| MethodResponse { method: method.to_string(), status, allow: None };
+        let responses = [response("OPTIONS", 200), response("PUT", 405), response("DELETE", 500), response("TRACE", 403)];
+        assert_eq!(tags(403, &responses), ["method:OPTIONS", "method:DELETE"]);
+
+        let options = MethodResponse { allow: Some("GET, HEAD, PUT".to_string()), ..response("OPTIONS", 204) };
+        assert_eq!(options.to_string(), "OPTIONS: 204 (Allow: GET, HEAD, PUT)");
+    }
+}
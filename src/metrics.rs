@@ -0,0 +1,211 @@
+use crate::error::CrawlerError;
+use crate::ScanState;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::debug;
+
+
+/// Window the request rate gauge averages over
+const RATE_WINDOW: usize = 10;
+
+/// Largest request head read from a scraper
+const MAX_REQUEST: usize = 8 * 1024;
+
+/// Live scan counters, exposed in Prometheus text format by `--metrics-port`
+#[derive(Default)]
+pub struct Metrics {
+    requests: AtomicU64,
+    /// Responses per status class (index 1 for 1xx ... 5xx)
+    responses: [AtomicU64; 6],
+    errors: Mutex<BTreeMap<&'static str, u64>>,
+    findings: [AtomicU64; 6],
+    /// Request count sampled once a second, newest last
+    samples: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+fn class(status: u16) -> usize {
+    (status as usize / 100).min(5)
+}
+
+impl Metrics {
+    pub fn response(&self, status: u16) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.responses[class(status)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A request that failed without a response, by kind (`timeout`, `connect`, ...)
+    pub fn error(&self, kind: &'static str) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        *self.errors.lock().unwrap().entry(kind).or_default() += 1;
+    }
+
+    /// Failed requests (retries included) per kind, most frequent first
+    pub fn errors(&self) -> Vec<(&'static str, u64)> {
+        let mut errors: Vec<_> = self.errors.lock().unwrap().iter().map(|(kind, n)| (*kind, *n)).collect();
+        errors.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+        errors
+    }
+
+    /// Requests sent, answered or not
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Requests that got a response, whatever its status
+    pub fn responses(&self) -> u64 {
+        self.responses.iter().map(|count| count.load(Ordering::Relaxed)).sum()
+    }
+
+    pub fn finding(&self, status: u16) {
+        self.findings[class(status)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn sample(&self) {
+        let mut samples = self.samples.lock().unwrap();
+        samples.push_back((Instant::now(), self.requests.load(Ordering::Relaxed)));
+        while samples.len() > RATE_WINDOW + 1 {
+            samples.pop_front();
+        }
+    }
+
+    /// Requests per second over the last few samples
+    fn rate(&self) -> f64 {
+        let samples = self.samples.lock().unwrap();
+        match (samples.front(), samples.back()) {
+            (Some((start, first)), Some((end, last))) if end > start => {
+                (last - first) as f64 / end.duration_since(*start).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// All metrics in the Prometheus text exposition format
+pub fn render(state: &ScanState) -> String {
+    let metrics = &state.metrics;
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, values: Vec<(String, String)>| {
+        let _ = writeln!(out, "# HELP dir_crawler_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE dir_crawler_{} {}", name, kind);
+        for (labels, value) in values {
+            let _ = writeln!(out, "dir_crawler_{}{} {}", name, labels, value);
+        }
+    };
+    let by_class = |counters: &[AtomicU64; 6]| {
+        (1..=5)
+            .map(|c| (format!("{{class=\"{}xx\"}}", c), counters[c].load(Ordering::Relaxed).to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    metric("requests_total", "counter", "Requests sent, answered or not.",
+        vec![(String::new(), metrics.requests.load(Ordering::Relaxed).to_string())]);
+    metric("responses_total", "counter", "Responses received by status class.", by_class(&metrics.responses));
+    let errors = metrics.errors.lock().unwrap()
+        .iter()
+        .map(|(kind, n)| (format!("{{type=\"{}\"}}", kind), n.to_string()))
+        .collect();
+    metric("errors_total", "counter", "Requests that got no response, by failure type.", errors);
+    metric("findings_total", "counter", "Findings recorded by status class.", by_class(&metrics.findings));
+    metric("skipped_requests_total", "counter", "Requests never sent because their host was given up on.",
+        vec![(String::new(), state.skipped.load(Ordering::Relaxed).to_string())]);
+    metric("requests_per_second", "gauge", "Request rate over the last 10 seconds.",
+        vec![(String::new(), format!("{:.2}", metrics.rate()))]);
+    metric("queue_depth", "gauge", "Requests still queued in the scan.",
+        vec![(String::new(), state.progress.queued().to_string())]);
+    out
+}
+
+/// Serve `/metrics` on `port` for as long as the scan runs. Binding happens
+/// before returning so a taken port fails the scan up front.
+pub async fn serve(port: u16, state: Arc<ScanState>) -> Result<(), CrawlerError> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .map_err(|e| format!("Cannot listen for metrics on port {}: {}", port, e))?;
+
+    let sampled = Arc::clone(&state);
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            tick.tick().await;
+            sampled.metrics.sample();
+        }
+    });
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let state = Arc::clone(&state);
+            tokio::spawn(async move {
+                if let Err(e) = answer(stream, &state).await {
+                    debug!("Metrics request failed: {}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+async fn answer(mut stream: TcpStream, state: &ScanState) -> Result<(), CrawlerError> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST {
+        let n = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+            .await
+            .map_err(|_| "metrics request timed out")??;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    let line = String::from_utf8_lossy(&head);
+    let mut parts = line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(path)) if path == "/metrics" || path.starts_with("/metrics?") => ("200 OK", render(state)),
+        _ => ("404 Not Found", "Not found; metrics are at /metrics\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_counters_by_class_and_type() {
+        let state = ScanState::default();
+        state.metrics.response(200);
+        state.metrics.response(404);
+        state.metrics.response(404);
+        state.metrics.error("timeout");
+        state.metrics.finding(200);
+        state.progress.inc_length(10);
+        state.progress.inc(4);
+
+        let text = render(&state);
+        assert!(text.contains("# TYPE dir_crawler_requests_total counter\ndir_crawler_requests_total 4\n"));
+        assert!(text.contains("dir_crawler_responses_total{class=\"4xx\"} 2\n"));
+        assert!(text.contains("dir_crawler_errors_total{type=\"timeout\"} 1\n"));
+        assert!(text.contains("dir_crawler_findings_total{class=\"2xx\"} 1\n"));
+        assert!(text.contains("dir_crawler_queue_depth 6\n"));
+    }
+
+    #[test]
+    fn rate_averages_over_the_window() {
+        let metrics = Metrics::default();
+        let start = Instant::now();
+        metrics.samples.lock().unwrap().extend([(start, 0), (start + Duration::from_secs(2), 50)]);
+        assert_eq!(metrics.rate(), 25.0);
+        assert_eq!(Metrics::default().rate(), 0.0);
+    }
+}
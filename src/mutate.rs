@@ -0,0 +1,232 @@
+use crate::error::CrawlerError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+
+/// Prefixes used when `--mutations` asks for them without a rules file
+const DEFAULT_PREFIXES: &[&str] = &["_", ".", "~"];
+/// Suffixes used when `--mutations` asks for them without a rules file
+const DEFAULT_SUFFIXES: &[&str] = &[
+    "1", "2", "2024", "2025", "_old", "-old", "_bak", "_backup", "_new", "-dev", "_dev", "-test",
+];
+/// Word separators swapped for one another (and dropped) by delimiter mutations
+const DEFAULT_DELIMITERS: &[&str] = &["-", "_", "."];
+
+/// Which variants of each wordlist entry are generated
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Mutations {
+    /// lower, UPPER, Capitalized and TitleCased forms
+    pub case: bool,
+    pub prefixes: Vec<String>,
+    pub suffixes: Vec<String>,
+    /// Separators swapped for one another; empty disables swapping
+    pub delimiters: Vec<String>,
+}
+
+impl Mutations {
+    /// Built-in rules for a comma-separated list of kinds:
+    /// `case`, `prefix`, `suffix`, `delimiter`, or `all`
+    pub fn from_kinds(kinds: &str) -> Result<Self, String> {
+        let mut mutations = Mutations::default();
+        let owned = |list: &[&str]| list.iter().map(|s| s.to_string()).collect();
+        for kind in kinds.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+            match kind {
+                "case" => mutations.case = true,
+                "prefix" => mutations.prefixes = owned(DEFAULT_PREFIXES),
+                "suffix" => mutations.suffixes = owned(DEFAULT_SUFFIXES),
+                "delimiter" => mutations.delimiters = owned(DEFAULT_DELIMITERS),
+                "all" => {
+                    mutations.case = true;
+                    mutations.prefixes = owned(DEFAULT_PREFIXES);
+                    mutations.suffixes = owned(DEFAULT_SUFFIXES);
+                    mutations.delimiters = owned(DEFAULT_DELIMITERS);
+                }
+                other => {
+                    return Err(format!(
+                        "Unknown mutation '{}'. Use case, prefix, suffix, delimiter or all",
+                        other
+                    ))
+                }
+            }
+        }
+        Ok(mutations)
+    }
+
+    /// Load rules from a file, one per line:
+    ///
+    /// ```text
+    /// case
+    /// prefix _ . ~
+    /// suffix _old -dev 2024
+    /// delimiter - _ .
+    /// ```
+    ///
+    /// Blank lines and `#` comments are ignored; repeated rules accumulate.
+    pub fn load(path: &Path) -> Result<Self, CrawlerError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read mutation rules {}: {}", path.display(), e))?;
+        let mut mutations = Mutations::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let mut words = line.split_whitespace();
+            let Some(rule) = words.next() else {
+                continue;
+            };
+            let values = words.map(str::to_string);
+            match rule {
+                "case" => mutations.case = true,
+                "prefix" => mutations.prefixes.extend(values),
+                "suffix" => mutations.suffixes.extend(values),
+                "delimiter" => mutations.delimiters.extend(values),
+                other => {
+                    return Err(format!("{}:{}: unknown mutation rule '{}'", path.display(), number + 1, other).into())
+                }
+            }
+        }
+        Ok(mutations)
+    }
+
+    /// Short description for the startup banner
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.case {
+            parts.push("case".to_string());
+        }
+        if !self.prefixes.is_empty() {
+            parts.push(format!("{} prefixes", self.prefixes.len()));
+        }
+        if !self.suffixes.is_empty() {
+            parts.push(format!("{} suffixes", self.suffixes.len()));
+        }
+        if !self.delimiters.is_empty() {
+            parts.push(format!("delimiters {}", self.delimiters.join(" ")));
+        }
+        parts.join(", ")
+    }
+
+    /// The word with every separator swapped for each other one, and dropped
+    fn delimiter_swaps(&self, word: &str) -> Vec<String> {
+        let Some(found) = self.delimiters.iter().find(|d| word.contains(d.as_str())) else {
+            return Vec::new();
+        };
+        self.delimiters.iter()
+            .filter(|d| *d != found)
+            .map(|d| word.replace(found.as_str(), d))
+            .chain(std::iter::once(word.replace(found.as_str(), "")))
+            .collect()
+    }
+
+    /// Capitalization variants: lower, UPPER, Capitalized and TitleCased at separators
+    fn case_variants(word: &str) -> Vec<String> {
+        let capitalize = |part: &str| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+                None => String::new(),
+            }
+        };
+        let title: String = word
+            .split_inclusive(['-', '_', '.', ' '])
+            .map(capitalize)
+            .collect();
+        vec![word.to_lowercase(), word.to_uppercase(), capitalize(word), title]
+    }
+
+    /// Every variant of one wordlist entry, the entry itself first
+    pub fn apply(&self, word: &str) -> Vec<String> {
+        let word = word.trim_matches('/');
+        let mut core = vec![word.to_string()];
+        core.extend(self.delimiter_swaps(word));
+
+        let mut variants = core.clone();
+        if self.case {
+            variants.extend(core.iter().flat_map(|w| Self::case_variants(w)));
+        }
+        for base in &core {
+            variants.extend(self.prefixes.iter().map(|prefix| format!("{}{}", prefix, base)));
+            variants.extend(self.suffixes.iter().map(|suffix| format!("{}{}", base, suffix)));
+        }
+
+        let mut seen = HashSet::new();
+        variants.retain(|v| !v.is_empty() && seen.insert(v.clone()));
+        variants
+    }
+
+    /// Expand a whole wordlist, keeping each entry's variants together
+    pub fn expand(&self, entries: Vec<String>) -> Vec<String> {
+        let mut seen = HashSet::new();
+        entries.iter()
+            .flat_map(|entry| self.apply(entry))
+            .filter(|variant| seen.insert(variant.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn no_rules_keep_the_word_alone() {
+        assert_eq!(Mutations::default().apply("/admin/"), ["admin"]);
+    }
+
+    #[test]
+    fn case_variants_include_title_case_at_separators() {
+        let mutations = Mutations { case: true, ..Mutations::default() };
+        assert_eq!(mutations.apply("api-key"), ["api-key", "API-KEY", "Api-key", "Api-Key"]);
+    }
+
+    #[test]
+    fn delimiters_are_swapped_and_dropped() {
+        let mutations = Mutations::from_kinds("delimiter").unwrap();
+        assert_eq!(mutations.apply("old-site"), ["old-site", "old_site", "old.site", "oldsite"]);
+        assert_eq!(mutations.apply("admin"), ["admin"]);
+    }
+
+    #[test]
+    fn affixes_apply_to_every_delimiter_variant() {
+        let mutations = Mutations {
+            prefixes: strings(&["_"]),
+            suffixes: strings(&["_old"]),
+            delimiters: strings(&["-", "_"]),
+            ..Mutations::default()
+        };
+        assert_eq!(
+            mutations.apply("a-b"),
+            ["a-b", "a_b", "ab", "_a-b", "a-b_old", "_a_b", "a_b_old", "_ab", "ab_old"]
+        );
+    }
+
+    #[test]
+    fn expand_deduplicates_across_entries() {
+        let mutations = Mutations { case: true, ..Mutations::default() };
+        let expanded = mutations.expand(strings(&["Admin", "admin"]));
+        assert_eq!(expanded, ["Admin", "admin", "ADMIN"]);
+    }
+
+    #[test]
+    fn kinds_and_rule_files_parse() {
+        let all = Mutations::from_kinds("all").unwrap();
+        assert!(all.case && !all.prefixes.is_empty() && !all.suffixes.is_empty() && !all.delimiters.is_empty());
+        assert!(Mutations::from_kinds("case,bogus").is_err());
+
+        let path = std::env::temp_dir().join(format!("dir_crawler-test-{}.rules", std::process::id()));
+        std::fs::write(&path, "# team rules\ncase\nprefix _ .\nsuffix _old\nsuffix -dev # staging\n\n").unwrap();
+        let loaded = Mutations::load(&path).unwrap();
+        assert!(loaded.case);
+        assert_eq!(loaded.prefixes, ["_", "."]);
+        assert_eq!(loaded.suffixes, ["_old", "-dev"]);
+        assert_eq!(loaded.describe(), "case, 2 prefixes, 2 suffixes");
+
+        std::fs::write(&path, "case\nreverse\n").unwrap();
+        let err = Mutations::load(&path).unwrap_err().to_string();
+        let _ = std::fs::remove_file(&path);
+        assert!(err.ends_with(":2: unknown mutation rule 'reverse'"), "{}", err);
+    }
+}
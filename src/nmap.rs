@@ -0,0 +1,171 @@
+use crate::error::CrawlerError;
+use roxmltree::{Document, Node, ParsingOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+
+/// An open web port from an Nmap scan, carried into the report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Service {
+    /// Base URL the port is scanned through
+    pub url: String,
+    /// Hostname given to Nmap, else the address it scanned
+    pub host: String,
+    pub port: u16,
+    /// Nmap's service name: `http`, `https`, `http-proxy`, ...
+    pub service: String,
+    /// Product and version Nmap fingerprinted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
+}
+
+impl Service {
+    /// `http-proxy (Squid 4.10)`
+    pub fn describe(&self) -> String {
+        match &self.product {
+            Some(product) => format!("{} ({})", self.service, product),
+            None => self.service.clone(),
+        }
+    }
+}
+
+pub fn load(path: &Path) -> Result<Vec<Service>, CrawlerError> {
+    let xml = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read Nmap results {}: {}", path.display(), e))?;
+    parse(&xml).map_err(|e| format!("Invalid Nmap XML {}: {}", path.display(), e).into())
+}
+
+/// Open HTTP(S) ports of hosts that were up, in scan order, one per URL
+pub fn parse(xml: &str) -> Result<Vec<Service>, String> {
+    // Nmap writes a `<!DOCTYPE nmaprun>` line
+    let options = ParsingOptions { allow_dtd: true, ..ParsingOptions::default() };
+    let document = Document::parse_with_options(xml, options).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    if !root.has_tag_name("nmaprun") {
+        return Err("not an Nmap XML report (run nmap with -oX)".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    let mut services = Vec::new();
+    for host in root.children().filter(|n| n.has_tag_name("host")) {
+        let up = child(host, "status").is_none_or(|status| status.attribute("state") == Some("up"));
+        let Some(name) = host_name(host).filter(|_| up) else {
+            continue;
+        };
+        let ports = child(host, "ports").into_iter().flat_map(|ports| ports.children());
+        for port in ports.filter(|n| n.has_tag_name("port") && n.attribute("protocol") == Some("tcp")) {
+            let open = child(port, "state").is_some_and(|state| state.attribute("state") == Some("open"));
+            let Some(number) = port.attribute("portid").and_then(|p| p.parse::<u16>().ok()).filter(|_| open) else {
+                continue;
+            };
+            let Some(service) = child(port, "service") else {
+                continue;
+            };
+            let service_name = service.attribute("name").unwrap_or_default();
+            if !service_name.contains("http") {
+                continue;
+            }
+            let tls = service_name.contains("https")
+                || service.attribute("tunnel") == Some("ssl")
+                || service_name.starts_with("ssl/");
+            let url = base_url(&name, number, tls);
+            if !seen.insert(url.clone()) {
+                continue;
+            }
+            let product: Vec<&str> = ["product", "version"].iter().filter_map(|a| service.attribute(*a)).collect();
+            services.push(Service {
+                url,
+                host: name.clone(),
+                port: number,
+                service: service_name.to_string(),
+                product: (!product.is_empty()).then(|| product.join(" ")),
+            });
+        }
+    }
+    Ok(services)
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(tag))
+}
+
+/// The name the user scanned (virtual hosts depend on it), else the address
+fn host_name(host: Node) -> Option<String> {
+    let user = child(host, "hostnames")
+        .and_then(|names| names.children().find(|n| n.has_tag_name("hostname") && n.attribute("type") == Some("user")))
+        .and_then(|n| n.attribute("name"));
+    if let Some(name) = user {
+        return Some(name.to_string());
+    }
+    let address = host.children()
+        .filter(|n| n.has_tag_name("address"))
+        .find(|n| matches!(n.attribute("addrtype"), Some("ipv4") | Some("ipv6")))?;
+    let addr = address.attribute("addr")?;
+    Some(match address.attribute("addrtype") {
+        Some("ipv6") => format!("[{}]", addr),
+        _ => addr.to_string(),
+    })
+}
+
+fn base_url(host: &str, port: u16, tls: bool) -> String {
+    match (tls, port) {
+        (false, 80) => format!("http://{}/", host),
+        (true, 443) => format!("https://{}/", host),
+        (false, port) => format!("http://{}:{}/", host, port),
+        (true, port) => format!("https://{}:{}/", host, port),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCAN: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE nmaprun>
+<nmaprun scanner="nmap" args="nmap -sV -oX scan.xml 10.0.0.0/29 intranet.example">
+<host><status state="up" reason="syn-ack"/>
+<address addr="10.0.0.5" addrtype="ipv4"/>
+<address addr="00:11:22:33:44:55" addrtype="mac"/>
+<hostnames><hostname name="ws5.lan" type="PTR"/></hostnames>
+<ports>
+<port protocol="tcp" portid="22"><state state="open"/><service name="ssh" product="OpenSSH"/></port>
+<port protocol="tcp" portid="80"><state state="open"/><service name="http" product="nginx" version="1.18.0"/></port>
+<port protocol="tcp" portid="443"><state state="open"/><service name="http" tunnel="ssl" product="nginx"/></port>
+<port protocol="tcp" portid="8080"><state state="filtered"/><service name="http-proxy"/></port>
+<port protocol="tcp" portid="8443"><state state="open"/><service name="https-alt"/></port>
+</ports></host>
+<host><status state="up"/>
+<address addr="10.0.0.9" addrtype="ipv4"/>
+<hostnames><hostname name="intranet.example" type="user"/></hostnames>
+<ports><port protocol="tcp" portid="3128"><state state="open"/><service name="http-proxy" product="Squid http proxy" version="4.10"/></port></ports>
+</host>
+<host><status state="down"/><address addr="10.0.0.6" addrtype="ipv4"/>
+<ports><port protocol="tcp" portid="80"><state state="open"/><service name="http"/></port></ports></host>
+<host><status state="up"/><address addr="fe80::1" addrtype="ipv6"/>
+<ports><port protocol="tcp" portid="8000"><state state="open"/><service name="http-alt"/></port></ports></host>
+</nmaprun>"#;
+
+    #[test]
+    fn keeps_open_web_ports_of_live_hosts() {
+        let services = parse(SCAN).unwrap();
+        let urls: Vec<&str> = services.iter().map(|s| s.url.as_str()).collect();
+        assert_eq!(urls, [
+            "http://10.0.0.5/",
+            "https://10.0.0.5/",
+            "https://10.0.0.5:8443/",
+            "http://intranet.example:3128/",
+            "http://[fe80::1]:8000/",
+        ]);
+        assert_eq!(services[0].describe(), "http (nginx 1.18.0)");
+        assert_eq!(services[3].describe(), "http-proxy (Squid http proxy 4.10)");
+        assert_eq!(services[4].describe(), "http-alt");
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(parse("<html><body/></html>").is_err());
+        assert!(parse("not xml at all").is_err());
+        assert!(parse("<nmaprun></nmaprun>").unwrap().is_empty());
+    }
+}
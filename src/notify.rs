@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+/// How a scan ended, for the completion notification
+pub enum Outcome<'a> {
+    Finished,
+    /// Cut short by `--max-time`, `--max-errors` or `--max-findings`
+    Stopped(&'a str),
+    /// Interrupted with Ctrl-C
+    Aborted,
+    Failed(&'a str),
+}
+
+/// Notification title and text for a scan that ended
+fn message(target: &str, outcome: &Outcome, findings: u64, elapsed: Duration) -> (String, String) {
+    let summary = match outcome {
+        Outcome::Finished => "Scan finished",
+        Outcome::Stopped(_) => "Scan stopped early",
+        Outcome::Aborted => "Scan aborted",
+        Outcome::Failed(_) => "Scan failed",
+    };
+    let plural = if findings == 1 { "" } else { "s" };
+    let mut body = format!("{}\n{} finding{} in {}", target, findings, plural, format_elapsed(elapsed));
+    match outcome {
+        Outcome::Stopped(reason) | Outcome::Failed(reason) => body.push_str(&format!("\n{}", reason)),
+        Outcome::Finished | Outcome::Aborted => {}
+    }
+    (format!("dir_crawler: {}", summary), body)
+}
+
+/// `42s`, `3m 05s` or `1h 02m`
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3_599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3_600, secs % 3_600 / 60),
+    }
+}
+
+/// Show a desktop notification that the scan ended. Failing to reach a
+/// notification service (SSH session, no desktop) is only logged.
+pub fn send(target: &str, outcome: Outcome, findings: u64, elapsed: Duration) {
+    let (summary, body) = message(target, &outcome, findings, elapsed);
+    let shown = notify_rust::Notification::new()
+        .appname("dir_crawler")
+        .summary(&summary)
+        .body(&body)
+        .show()
+        .map(|_| ());
+    if let Err(e) = shown {
+        tracing::warn!("Desktop notification failed: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_how_the_scan_ended() {
+        let (summary, body) = message("http://example.com", &Outcome::Finished, 3, Duration::from_secs(185));
+        assert_eq!(summary, "dir_crawler: Scan finished");
+        assert_eq!(body, "http://example.com\n3 findings in 3m 05s");
+
+        let (summary, body) = message("http://example.com", &Outcome::Stopped("max time of 30s reached"), 1, Duration::from_secs(30));
+        assert_eq!(summary, "dir_crawler: Scan stopped early");
+        assert_eq!(body, "http://example.com\n1 finding in 30s\nmax time of 30s reached");
+
+        assert_eq!(message("t", &Outcome::Aborted, 0, Duration::from_secs(7_380)).1, "t\n0 findings in 2h 03m");
+    }
+}
@@ -0,0 +1,443 @@
+use crate::crypto::{self, OutputCipher};
+use crate::error::CrawlerError;
+use crate::redact::Redactor;
+use crate::secrets::Severity;
+use crate::{Finding, FuzzerConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+
+/// Layout of the results written by `--output`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputFormat {
+    Json,
+    Markdown,
+    /// One JSON object per finding, streamed as the scan runs
+    Ndjson,
+    /// One spreadsheet row per finding, streamed as the scan runs
+    Csv,
+    /// Shell script with a curl command reproducing each finding
+    Curl,
+    /// Deduplicated hit URLs, one per line, for httpx, nuclei or katana
+    Urls,
+    /// SARIF 2.1.0 log for code scanning and security dashboards
+    Sarif,
+    /// SQLite database of scans, targets and findings
+    Sqlite,
+}
+
+impl OutputFormat {
+    /// `--format` by name
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "json" => Ok(OutputFormat::Json),
+            "md" | "markdown" => Ok(OutputFormat::Markdown),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "csv" => Ok(OutputFormat::Csv),
+            "curl" => Ok(OutputFormat::Curl),
+            "urls" => Ok(OutputFormat::Urls),
+            "sarif" => Ok(OutputFormat::Sarif),
+            "sqlite" => Ok(OutputFormat::Sqlite),
+            other => Err(format!("Unknown output format '{}'. Use json, md, ndjson, csv, curl, urls, sarif or sqlite", other)),
+        }
+    }
+
+    /// File name used when a workspace picks the output path
+    pub fn default_file_name(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "results.json",
+            OutputFormat::Markdown => "report.md",
+            OutputFormat::Ndjson => "findings.ndjson",
+            OutputFormat::Csv => "findings.csv",
+            OutputFormat::Curl => "reproduce.sh",
+            OutputFormat::Urls => "urls.txt",
+            OutputFormat::Sarif => "results.sarif",
+            OutputFormat::Sqlite => "results.db",
+        }
+    }
+
+    /// Written a finding at a time as the scan runs, rather than rendered
+    /// from the finished report
+    pub fn is_streamed(self) -> bool {
+        matches!(self, OutputFormat::Ndjson | OutputFormat::Csv)
+    }
+}
+
+/// Order findings are listed and reported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortOrder {
+    /// Most severe first
+    Severity,
+    /// Most recent `Last-Modified` first, for incident-driven hunting
+    Modified,
+}
+
+/// Results file written by `--output`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScanReport {
+    pub target: String,
+    /// The scheme the scan picked by probing, for a target typed without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_scheme: Option<String>,
+    pub scan_id: String,
+    pub wordlist: String,
+    pub method: String,
+    pub extensions: Vec<String>,
+    pub started_at: u64,
+    pub duration_secs: f64,
+    /// SHA-256 of scan inputs (e.g. the wordlist) for chain of custody
+    #[serde(default)]
+    pub input_hashes: BTreeMap<String, String>,
+    pub findings: Vec<Finding>,
+    /// Web services the targets came from, with Nmap's fingerprint
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub services: Vec<crate::nmap::Service>,
+    /// Directories discovered but left unscanned by recursion limits
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unexplored: Vec<String>,
+    /// Stop condition that ended the scan early, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopped: Option<String>,
+    /// Targets' favicon hashes and the products they identify
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub favicons: Vec<crate::favicon::Favicon>,
+    /// Directory of the `--screenshots` each finding names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshots: Option<String>,
+}
+
+impl ScanReport {
+    pub fn new(config: &FuzzerConfig, started_at: u64, duration_secs: f64, findings: &[Finding]) -> Self {
+        let mut input_hashes = BTreeMap::new();
+        if let Ok(hash) = crate::wordlist::sha256(&config.wordlist) {
+            input_hashes.insert(format!("wordlist:{}", config.wordlist.display()), hash);
+        }
+
+        ScanReport {
+            target: config.url.clone(),
+            detected_scheme: config.detect_scheme
+                .then(|| reqwest::Url::parse(&config.url).ok().map(|url| url.scheme().to_string()))
+                .flatten(),
+            scan_id: config.scan_id.clone(),
+            wordlist: config.wordlist.display().to_string(),
+            method: config.method.to_string(),
+            extensions: config.extensions.clone(),
+            started_at,
+            duration_secs,
+            input_hashes,
+            findings: findings.to_vec(),
+            services: config.services.clone(),
+            unexplored: Vec::new(),
+            stopped: None,
+            favicons: Vec::new(),
+            screenshots: config.screenshots.as_ref().map(|dir| dir.display().to_string()),
+        }
+    }
+
+    /// The report in the configured format
+    pub fn render(&self, config: &FuzzerConfig) -> String {
+        match config.format {
+            // A database isn't text; `sqlite::store` writes one from the report
+            OutputFormat::Json | OutputFormat::Sqlite => serde_json::to_string_pretty(self).unwrap_or_default(),
+            OutputFormat::Markdown => self.to_markdown(),
+            OutputFormat::Curl => self.to_curl(config),
+            OutputFormat::Urls => self.to_urls(&config.urls_status),
+            OutputFormat::Sarif => crate::sarif::render(self),
+            OutputFormat::Ndjson => self.findings.iter()
+                .filter_map(|finding| serde_json::to_string(finding).ok())
+                .map(|json| json + "\n")
+                .collect(),
+            OutputFormat::Csv => std::iter::once(CSV_HEADER.to_string())
+                .chain(self.findings.iter().map(csv_row))
+                .collect(),
+        }
+    }
+
+    /// Write the report in the configured format with redaction applied, encrypting if requested
+    pub fn write(
+        &self,
+        path: &Path,
+        config: &FuzzerConfig,
+        redactor: &Redactor,
+        cipher: Option<&OutputCipher>,
+    ) -> Result<(), CrawlerError> {
+        // Whatever a finding picked up from the target, the file holds no escape codes
+        let text = self.render(config);
+        crypto::write_file(path, crate::term::strip_ansi(&redactor.redact(&text)).as_bytes(), cipher)
+    }
+
+    /// Render one curl command per finding that replays the scan's request:
+    /// method, persona and custom headers, session cookies, and the
+    /// request template's headers and body with the finding's payload
+    pub fn to_curl(&self, config: &FuzzerConfig) -> String {
+        let mut flags = Vec::new();
+        if config.method != reqwest::Method::GET {
+            flags.push(format!("-X {}", config.method));
+        }
+        for (name, value) in &crate::default_headers(config) {
+            let header = format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes()));
+            flags.push(format!("-H {}", shell_quote(&header)));
+        }
+        flags.push(format!("--connect-timeout {}", config.connect_timeout));
+        if let Some(timeout) = config.timeout {
+            flags.push(format!("--max-time {}", timeout));
+        }
+        for (host, address) in &config.resolve {
+            let ip = match address.ip() {
+                std::net::IpAddr::V6(ip) => format!("[{}]", ip),
+                ip => ip.to_string(),
+            };
+            flags.push(format!("--resolve {}", shell_quote(&format!("{}:{}:{}", host, address.port(), ip))));
+        }
+
+        let mut script = format!("#!/bin/sh\n# Reproduce findings from scan {} against {}\n", self.scan_id, self.target);
+        for finding in &self.findings {
+            let mut finding_flags = flags.clone();
+            if let (Some(template), Some(payload)) = (&config.request_template, &finding.payload) {
+                for (name, value) in template.headers_for(payload) {
+                    finding_flags.push(format!("-H {}", shell_quote(&format!("{}: {}", name, value))));
+                }
+                if let Some(body) = template.body_for(payload) {
+                    finding_flags.push(format!("--data-raw {}", shell_quote(&body)));
+                }
+            }
+
+            let _ = write!(script, "\n# {} (Status: {})", finding.label(), finding.status);
+            if !finding.tags.is_empty() {
+                let _ = write!(script, " [{}]", finding.tags.join(", "));
+            }
+            let _ = writeln!(script, "\ncurl -i {} {}", finding_flags.join(" "), shell_quote(&finding.url));
+        }
+        script
+    }
+
+    /// Every distinct finding URL in report order, one per line, keeping
+    /// only statuses within `statuses` (inclusive ranges) when any are given
+    pub fn to_urls(&self, statuses: &[(u16, u16)]) -> String {
+        let mut seen = std::collections::HashSet::new();
+        self.findings.iter()
+            .filter(|finding| statuses.is_empty() || statuses.iter().any(|(low, high)| (*low..=*high).contains(&finding.status)))
+            .filter(|finding| seen.insert(finding.url.as_str()))
+            .map(|finding| format!("{}\n", finding.url))
+            .collect()
+    }
+
+    /// Render the report as Markdown: scan parameters, summary stats and a findings table
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let extensions = if self.extensions.is_empty() {
+            "–".to_string()
+        } else {
+            self.extensions.join(", ")
+        };
+
+        let _ = writeln!(md, "# Dir_Crawler Scan Report\n");
+        let _ = writeln!(md, "| Parameter | Value |");
+        let _ = writeln!(md, "|---|---|");
+        let _ = writeln!(md, "| Target | `{}` |", escape_cell(&self.target));
+        if let Some(scheme) = &self.detected_scheme {
+            let _ = writeln!(md, "| Scheme | {} (detected) |", scheme);
+        }
+        let _ = writeln!(md, "| Scan ID | `{}` |", self.scan_id);
+        let _ = writeln!(md, "| Started | {} UTC |", crate::workspace::format_datetime(self.started_at));
+        let _ = writeln!(md, "| Duration | {:.2} s |", self.duration_secs);
+        if let Some(reason) = &self.stopped {
+            let _ = writeln!(md, "| Stopped early | {} |", escape_cell(reason));
+        }
+        let _ = writeln!(md, "| Wordlist | `{}` |", escape_cell(&self.wordlist));
+        let _ = writeln!(md, "| Method | {} |", self.method);
+        let _ = writeln!(md, "| Extensions | {} |", escape_cell(&extensions));
+
+        // Findings per severity, status class and tag
+        let mut severities: BTreeMap<std::cmp::Reverse<Severity>, usize> = BTreeMap::new();
+        let mut classes: BTreeMap<String, usize> = BTreeMap::new();
+        let mut tags: BTreeMap<&str, usize> = BTreeMap::new();
+        for finding in &self.findings {
+            *severities.entry(std::cmp::Reverse(finding.severity)).or_default() += 1;
+            *classes.entry(format!("{}xx", finding.status / 100)).or_default() += 1;
+            for tag in &finding.tags {
+                *tags.entry(tag.as_str()).or_default() += 1;
+            }
+        }
+
+        let _ = writeln!(md, "\n## Summary\n");
+        let _ = writeln!(md, "- **Findings:** {}", self.findings.len());
+        if !severities.is_empty() {
+            let breakdown: Vec<String> = severities.iter().map(|(severity, n)| format!("{}: {}", severity.0, n)).collect();
+            let _ = writeln!(md, "- **By severity:** {}", breakdown.join(" · "));
+        }
+        if !classes.is_empty() {
+            let breakdown: Vec<String> = classes.iter().map(|(class, n)| format!("{}: {}", class, n)).collect();
+            let _ = writeln!(md, "- **By status:** {}", breakdown.join(" · "));
+        }
+        if !tags.is_empty() {
+            let breakdown: Vec<String> = tags.iter().map(|(tag, n)| format!("`{}` ({})", tag, n)).collect();
+            let _ = writeln!(md, "- **Tags:** {}", breakdown.join(", "));
+        }
+        if !self.unexplored.is_empty() {
+            let _ = writeln!(md, "- **Unexplored directories:** {}", self.unexplored.len());
+        }
+        for icon in &self.favicons {
+            let product = icon.product.as_deref().unwrap_or("unknown product");
+            let _ = writeln!(md, "- **Favicon:** `{}` `http.favicon.hash:{}` ({})", escape_cell(&icon.url), icon.hash, product);
+        }
+
+        if !self.findings.is_empty() {
+            let _ = writeln!(md, "\n## Findings\n");
+            let _ = writeln!(md, "| # | Severity | Status | URL | Tags |");
+            let _ = writeln!(md, "|---|---|---|---|---|");
+            for (i, finding) in self.findings.iter().enumerate() {
+                let mut notes = finding.tags.join(", ");
+                if finding.duplicates > 0 {
+                    if !notes.is_empty() {
+                        notes.push_str(", ");
+                    }
+                    notes.push_str(&format!("+{} similar", finding.duplicates));
+                }
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | `{}` | {} |",
+                    i + 1,
+                    finding.severity,
+                    finding.status,
+                    escape_cell(&finding.label()),
+                    escape_cell(&notes)
+                );
+            }
+        }
+
+        let audit = crate::headers::summary(&self.findings);
+        if !audit.is_empty() {
+            let _ = writeln!(md, "\n## Security Header Audit\n");
+            let _ = writeln!(md, "| Check | Findings | Example |");
+            let _ = writeln!(md, "|---|---|---|");
+            for (check, count, example, issue) in audit {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | `{}`: {} |",
+                    check,
+                    count,
+                    escape_cell(&example.label()),
+                    escape_cell(&issue.detail)
+                );
+            }
+        }
+
+        if !self.services.is_empty() {
+            let _ = writeln!(md, "\n## Services\n");
+            let _ = writeln!(md, "| URL | Port | Service | Findings |");
+            let _ = writeln!(md, "|---|---|---|---|");
+            for service in &self.services {
+                let hits = self.findings.iter().filter(|finding| finding.url.starts_with(&service.url)).count();
+                let _ = writeln!(
+                    md,
+                    "| `{}` | {} | {} | {} |",
+                    escape_cell(&service.url),
+                    service.port,
+                    escape_cell(&service.describe()),
+                    hits
+                );
+            }
+        }
+
+        let shots: Vec<&Finding> = self.findings.iter().filter(|finding| finding.screenshot.is_some()).collect();
+        if let (Some(dir), false) = (&self.screenshots, shots.is_empty()) {
+            let _ = writeln!(md, "\n## Screenshots\n");
+            for finding in shots {
+                let path = format!("{}/{}", dir.trim_end_matches('/'), finding.screenshot.as_deref().unwrap_or_default());
+                let _ = writeln!(
+                    md,
+                    "<a href=\"{0}\"><img src=\"{0}\" width=\"320\" alt=\"{1}\"></a>\n\n`{1}` ({2})\n",
+                    path,
+                    finding.label().replace('"', "&quot;"),
+                    finding.status
+                );
+            }
+        }
+
+        if !self.unexplored.is_empty() {
+            let _ = writeln!(md, "\n## Unexplored Directories\n");
+            for dir in &self.unexplored {
+                let _ = writeln!(md, "- `{}`", dir);
+            }
+        }
+
+        md
+    }
+}
+
+/// First line of `--format csv`
+pub const CSV_HEADER: &str = "severity,status,url,payload,size,content_type,title,tags\n";
+
+/// One finding as a CSV line
+pub fn csv_row(finding: &Finding) -> String {
+    let fields = [
+        finding.severity.to_string(),
+        finding.status.to_string(),
+        finding.url.clone(),
+        finding.payload.clone().unwrap_or_default(),
+        finding.size.map(|size| size.to_string()).unwrap_or_default(),
+        finding.content_type.clone().unwrap_or_default(),
+        finding.title.clone().unwrap_or_default(),
+        finding.tags.join(";"),
+    ];
+    let cells: Vec<String> = fields.iter().map(|field| csv_cell(field)).collect();
+    cells.join(",") + "\n"
+}
+
+/// Quote a CSV cell when it needs it, and keep a target's page title from
+/// running as a spreadsheet formula
+fn csv_cell(value: &str) -> String {
+    let value = match value.starts_with(['=', '+', '-', '@']) {
+        true => format!("'{}", value),
+        false => value.to_string(),
+    };
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value,
+    }
+}
+
+/// Single-quote a value for POSIX shells
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Keep a value from breaking out of its Markdown table cell
+fn escape_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn finding(url: &str, status: u16) -> Finding {
+        serde_json::from_value(json!({ "url": url, "status": status })).unwrap()
+    }
+
+    #[test]
+    fn url_lists_are_deduplicated_and_filtered() {
+        let findings = [
+            finding("http://t/admin", 301),
+            finding("http://t/login", 200),
+            finding("http://t/admin", 301),
+            finding("http://t/secret", 403),
+        ];
+        let report = ScanReport::new(&FuzzerConfig::default(), 0, 0.0, &findings);
+        assert_eq!(report.to_urls(&[]), "http://t/admin\nhttp://t/login\nhttp://t/secret\n");
+        assert_eq!(report.to_urls(&[(200, 299), (403, 403)]), "http://t/login\nhttp://t/secret\n");
+        assert_eq!(report.to_urls(&[(500, 599)]), "");
+    }
+
+    #[test]
+    fn csv_cells_are_quoted_and_defused() {
+        let mut hit = finding("http://t/a,b", 200);
+        hit.title = Some("=HYPERLINK(\"http://evil\")".to_string());
+        hit.tags = vec!["backup".to_string(), "git".to_string()];
+        assert_eq!(csv_row(&hit), "info,200,\"http://t/a,b\",,,,\"'=HYPERLINK(\"\"http://evil\"\")\",backup;git\n");
+    }
+}
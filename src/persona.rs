@@ -0,0 +1,85 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+/// A coherent set of client headers impersonating a browser, bot, or tool
+pub struct Persona {
+    pub name: &'static str,
+    pub description: &'static str,
+    headers: &'static [(&'static str, &'static str)],
+}
+
+pub const PERSONAS: &[Persona] = &[
+    Persona {
+        name: "chrome",
+        description: "Desktop Chrome on Windows",
+        headers: &[
+            ("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36"),
+            ("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8"),
+            ("accept-language", "en-US,en;q=0.9"),
+            ("sec-ch-ua", "\"Chromium\";v=\"124\", \"Google Chrome\";v=\"124\", \"Not-A.Brand\";v=\"99\""),
+            ("sec-ch-ua-mobile", "?0"),
+            ("sec-ch-ua-platform", "\"Windows\""),
+            ("upgrade-insecure-requests", "1"),
+        ],
+    },
+    Persona {
+        name: "mobile-safari",
+        description: "Safari on iPhone",
+        headers: &[
+            ("user-agent", "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1"),
+            ("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"),
+            ("accept-language", "en-US,en;q=0.9"),
+        ],
+    },
+    Persona {
+        name: "googlebot",
+        description: "Googlebot smartphone crawler",
+        headers: &[
+            ("user-agent", "Mozilla/5.0 (Linux; Android 6.0.1; Nexus 5X Build/MMB29P) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)"),
+            ("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"),
+            ("from", "googlebot(at)googlebot.com"),
+        ],
+    },
+    Persona {
+        name: "curl",
+        description: "curl command-line client",
+        headers: &[
+            ("user-agent", "curl/8.5.0"),
+            ("accept", "*/*"),
+        ],
+    },
+];
+
+/// Aliases accepted on the command line for common personas
+fn canonical_name(name: &str) -> &str {
+    match name {
+        "desktop" => "chrome",
+        "mobile" | "iphone" | "safari" => "mobile-safari",
+        "bot" => "googlebot",
+        other => other,
+    }
+}
+
+/// Look up a persona by name or alias
+pub fn find(name: &str) -> Option<&'static Persona> {
+    let name = canonical_name(name.trim());
+    PERSONAS.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+/// Comma-separated list of persona names for help and error messages
+pub fn names() -> String {
+    PERSONAS.iter().map(|p| p.name).collect::<Vec<_>>().join(", ")
+}
+
+impl Persona {
+    /// Headers to install as the client's defaults
+    pub fn header_map(&self) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in self.headers {
+            map.insert(
+                HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            );
+        }
+        map
+    }
+}
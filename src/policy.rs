@@ -0,0 +1,281 @@
+use crate::secrets::Severity;
+use crate::Finding;
+use regex::Regex;
+
+/// Fields a `--fail-on` condition can test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Status,
+    Size,
+    Severity,
+    Path,
+    Url,
+    Tag,
+    ContentType,
+    Title,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "status" => Field::Status,
+            "size" => Field::Size,
+            "severity" => Field::Severity,
+            "path" => Field::Path,
+            "url" => Field::Url,
+            "tag" => Field::Tag,
+            "content-type" => Field::ContentType,
+            "title" => Field::Title,
+            _ => return None,
+        })
+    }
+
+    fn numeric(self) -> bool {
+        matches!(self, Field::Status | Field::Size | Field::Severity)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Matches,
+    NotMatches,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// Longest first, so `>=` isn't read as `>`
+const OPS: &[(&str, Op)] = &[
+    ("~=", Op::Matches),
+    ("!~", Op::NotMatches),
+    ("!=", Op::Ne),
+    (">=", Op::Ge),
+    ("<=", Op::Le),
+    ("=", Op::Eq),
+    (">", Op::Gt),
+    ("<", Op::Lt),
+];
+
+/// What a condition compares against
+#[derive(Debug, Clone)]
+enum Value {
+    /// An inclusive range: one number, a status class like `2xx`, or a severity
+    Range(u64, u64),
+    Text(String),
+    Pattern(Regex),
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    field: Field,
+    op: Op,
+    value: Value,
+}
+
+/// A `--fail-on` expression: conditions on a finding's fields joined by `&&`
+/// and `||` (`&&` binding tighter), such as
+/// `status=200 && path~=/\.git|/backup || severity>=high`
+#[derive(Debug, Clone)]
+pub struct Policy {
+    pub source: String,
+    any: Vec<Vec<Condition>>,
+}
+
+impl Policy {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let any = source.split("||")
+            .map(|all| all.split("&&").map(|condition| Condition::parse(condition.trim())).collect())
+            .collect::<Result<Vec<Vec<_>>, _>>()
+            .map_err(|e| format!("Invalid --fail-on '{}': {}", source, e))?;
+        Ok(Policy { source: source.trim().to_string(), any })
+    }
+
+    /// Fails on every finding, for `--ci` without `--fail-on`
+    pub fn any_finding() -> Self {
+        Policy { source: "any finding".to_string(), any: vec![Vec::new()] }
+    }
+
+    pub fn violated_by(&self, finding: &Finding) -> bool {
+        self.any.iter().any(|all| all.iter().all(|condition| condition.holds(finding)))
+    }
+}
+
+impl Condition {
+    fn parse(text: &str) -> Result<Self, String> {
+        let name_len = text.find(|c: char| !(c.is_ascii_alphabetic() || c == '-')).unwrap_or(text.len());
+        let name = &text[..name_len];
+        let field = Field::parse(name).ok_or_else(|| match name {
+            "" => format!("expected a condition like status=200, got '{}'", text),
+            _ => format!("unknown field '{}' (status, size, severity, path, url, tag, content-type or title)", name),
+        })?;
+        let rest = text[name_len..].trim_start();
+        let (symbol, op) = OPS.iter()
+            .find(|(symbol, _)| rest.starts_with(symbol))
+            .copied()
+            .ok_or_else(|| format!("expected =, !=, ~=, !~, <, <=, > or >= after '{}'", name))?;
+        let raw = rest[symbol.len()..].trim();
+        let raw = raw.strip_prefix('"').and_then(|r| r.strip_suffix('"'))
+            .or_else(|| raw.strip_prefix('\'').and_then(|r| r.strip_suffix('\'')))
+            .unwrap_or(raw);
+        if raw.is_empty() {
+            return Err(format!("'{}' has no value", text));
+        }
+
+        let value = match op {
+            Op::Matches | Op::NotMatches => Value::Pattern(Regex::new(raw).map_err(|e| format!("bad pattern '{}': {}", raw, e))?),
+            _ if field.numeric() => {
+                let range = match field {
+                    Field::Severity => Severity::parse(raw).map(|s| (s as u64, s as u64)),
+                    Field::Status => match raw.to_ascii_lowercase().strip_suffix("xx") {
+                        Some(class) => class.parse::<u64>().ok().map(|class| (class * 100, class * 100 + 99)),
+                        None => raw.parse().ok().map(|code| (code, code)),
+                    },
+                    _ => raw.parse().ok().map(|n| (n, n)),
+                };
+                let (low, high) = range.ok_or_else(|| format!("'{}' is not a valid {}", raw, name))?;
+                Value::Range(low, high)
+            }
+            Op::Eq | Op::Ne => Value::Text(raw.to_string()),
+            _ => return Err(format!("{} can't be compared with {}", name, symbol)),
+        };
+        Ok(Condition { field, op, value })
+    }
+
+    fn holds(&self, finding: &Finding) -> bool {
+        match &self.value {
+            Value::Range(low, high) => {
+                let Some(actual) = number(self.field, finding) else {
+                    return self.op == Op::Ne;
+                };
+                match self.op {
+                    Op::Eq => (*low..=*high).contains(&actual),
+                    Op::Ne => !(*low..=*high).contains(&actual),
+                    Op::Gt => actual > *high,
+                    Op::Ge => actual >= *low,
+                    Op::Lt => actual < *low,
+                    Op::Le => actual <= *high,
+                    Op::Matches | Op::NotMatches => false,
+                }
+            }
+            Value::Text(expected) => {
+                let found = texts(self.field, finding).iter().any(|text| text == expected);
+                found == (self.op == Op::Eq)
+            }
+            Value::Pattern(pattern) => {
+                let found = texts(self.field, finding).iter().any(|text| pattern.is_match(text));
+                found == (self.op == Op::Matches)
+            }
+        }
+    }
+}
+
+fn number(field: Field, finding: &Finding) -> Option<u64> {
+    match field {
+        Field::Status => Some(u64::from(finding.status)),
+        Field::Size => finding.size,
+        Field::Severity => Some(finding.severity as u64),
+        _ => None,
+    }
+}
+
+/// The values a text condition looks at: none when the finding lacks the
+/// field, one per tag for `tag`
+fn texts(field: Field, finding: &Finding) -> Vec<String> {
+    match field {
+        Field::Status => vec![finding.status.to_string()],
+        Field::Size => finding.size.map(|size| size.to_string()).into_iter().collect(),
+        Field::Severity => vec![finding.severity.to_string()],
+        Field::Url => vec![finding.url.clone()],
+        Field::Path => vec![reqwest::Url::parse(&finding.url).map(|url| url.path().to_string()).unwrap_or_default()],
+        Field::Tag => finding.tags.clone(),
+        Field::ContentType => finding.content_type.iter().cloned().collect(),
+        Field::Title => finding.title.iter().cloned().collect(),
+    }
+}
+
+/// JUnit XML for CI dashboards: one test case per finding, failed when it
+/// violates the policy (or a single passing case when nothing was found)
+pub fn junit(policy: &Policy, findings: &[Finding], target: &str, elapsed: f64) -> String {
+    let failures = findings.iter().filter(|finding| policy.violated_by(finding)).count();
+    let tests = findings.len().max(1);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"dir_crawler\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        tests, failures, elapsed
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" time=\"{:.3}\">\n",
+        escape(target), tests, failures, elapsed
+    ));
+    xml.push_str(&format!(
+        "    <properties>\n      <property name=\"fail-on\" value=\"{}\"/>\n    </properties>\n",
+        escape(&policy.source)
+    ));
+    if findings.is_empty() {
+        xml.push_str("    <testcase classname=\"dir_crawler\" name=\"no findings\"/>\n");
+    }
+    for finding in findings {
+        let name = escape(&finding.label());
+        if policy.violated_by(finding) {
+            let mut detail = format!("status {}, severity {}", finding.status, finding.severity);
+            if !finding.tags.is_empty() {
+                detail.push_str(&format!(", tags {}", finding.tags.join(", ")));
+            }
+            xml.push_str(&format!(
+                "    <testcase classname=\"dir_crawler\" name=\"{}\">\n      <failure type=\"policy\" message=\"violates --fail-on\">{}</failure>\n    </testcase>\n",
+                name,
+                escape(&detail)
+            ));
+        } else {
+            xml.push_str(&format!("    <testcase classname=\"dir_crawler\" name=\"{}\"/>\n", name));
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(url: &str, status: u16, tags: &[&str]) -> Finding {
+        serde_json::from_value(serde_json::json!({ "url": url, "status": status, "size": 120, "severity": "high", "tags": tags })).unwrap()
+    }
+
+    #[test]
+    fn evaluates_policies_and_writes_junit() {
+        let policy = Policy::parse(r"status=200 && path~=/\.git|/backup || tag = 'exposed:env'").unwrap();
+        assert!(policy.violated_by(&finding("http://t/.git/HEAD", 200, &[])));
+        assert!(policy.violated_by(&finding("http://t/old/backup.zip", 200, &[])));
+        assert!(!policy.violated_by(&finding("http://t/.git/HEAD", 403, &[])));
+        assert!(policy.violated_by(&finding("http://t/app/.env", 403, &["exposed:env"])));
+
+        let classes = Policy::parse("status!=4xx && severity>=medium && size<=1000").unwrap();
+        assert!(classes.violated_by(&finding("http://t/admin", 302, &[])));
+        assert!(!classes.violated_by(&finding("http://t/admin", 404, &[])));
+        assert!(Policy::any_finding().violated_by(&finding("http://t/", 404, &[])));
+
+        assert!(Policy::parse("code=200").unwrap_err().contains("unknown field 'code'"));
+        assert!(Policy::parse("path>3").unwrap_err().contains("can't be compared"));
+        assert!(Policy::parse("severity=urgent").is_err());
+        assert!(Policy::parse("status=200 &&").is_err());
+
+        let findings = [finding("http://t/.git/HEAD", 200, &["exposed:git"]), finding("http://t/a?x=<1>", 301, &[])];
+        let xml = junit(&policy, &findings, "http://t/", 1.5);
+        assert!(xml.contains("tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<failure type=\"policy\" message=\"violates --fail-on\">status 200, severity high, tags exposed:git</failure>"));
+        assert!(xml.contains("name=\"http://t/a?x=&lt;1&gt;\"/>"));
+        assert!(junit(&policy, &[], "http://t/", 0.0).contains("name=\"no findings\""));
+    }
+}
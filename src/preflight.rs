@@ -0,0 +1,225 @@
+//! A few requests to the target before the scan sends thousands: which
+//! scheme it answers on, whether it answers at all, how fast, and whether
+//! it moves plain HTTP to HTTPS.
+
+use crate::engine::send_request;
+use crate::error::CrawlerError;
+use crate::scope::Scope;
+use crate::{FuzzerConfig, ScanState};
+use reqwest::{Client, Method, Url};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Requests sent, one after the other
+const PROBES: usize = 3;
+
+/// Request rate a suggested thread count aims for
+pub const TARGET_RATE: f64 = 50.0;
+
+/// Range of suggested thread counts
+const SUGGESTED_THREADS: (usize, usize) = (10, 100);
+
+/// What the probe found
+#[derive(Debug)]
+pub struct Liveness {
+    /// Status of the last answer
+    pub status: u16,
+    /// Median time to the response head
+    pub latency: Duration,
+    /// Probes that got no response
+    pub failed: usize,
+    /// The target URL over HTTPS, when plain HTTP redirects there
+    pub upgrade: Option<String>,
+    /// Threads that reach `TARGET_RATE` at this latency
+    pub threads: usize,
+}
+
+/// Send `PROBES` GETs to the target URL. Fails with the first error when
+/// the host can't be reached and the last when none is answered; `None`
+/// when the target URL is out of scope or the scan was cancelled meanwhile.
+pub async fn probe(config: &FuzzerConfig, state: &ScanState, client: &Client) -> Result<Option<Liveness>, CrawlerError> {
+    let scope = Scope::new(&config.scope_include, &config.scope_exclude, &config.blocklist)?;
+    if let Err(reason) = scope.check(&config.url) {
+        debug!("🚧 Not probing {}: {}", state.redactor.redact(&config.url), reason);
+        return Ok(None);
+    }
+    let get = FuzzerConfig { method: Method::GET, request_template: None, ..config.clone() };
+    let mut latencies = Vec::new();
+    let mut failure = None;
+    let mut last = None;
+    for _ in 0..PROBES {
+        let started = Instant::now();
+        match send_request(&get, state, client, &config.url, None).await {
+            Ok(response) => {
+                latencies.push(started.elapsed());
+                last = Some((response.status().as_u16(), response.url().clone()));
+            }
+            Err(CrawlerError::Cancelled) => return Ok(None),
+            // Asking again would only wait as long again
+            Err(e) if e.is_host_failure() && latencies.is_empty() => return Err(e),
+            Err(e) => failure = Some(e),
+        }
+    }
+    let (status, landed) = match last {
+        Some(last) => last,
+        None => return Err(failure.expect("a probe that isn't answered fails")),
+    };
+
+    latencies.sort();
+    let latency = latencies[latencies.len() / 2];
+    let threads = (TARGET_RATE * latency.as_secs_f64()).ceil() as usize;
+    Ok(Some(Liveness {
+        status,
+        latency,
+        failed: PROBES - latencies.len(),
+        upgrade: upgrade(&config.url, &landed),
+        threads: threads.clamp(SUGGESTED_THREADS.0, SUGGESTED_THREADS.1),
+    }))
+}
+
+/// The target URL with the scheme it answers on, for a URL typed without
+/// one (`validate_url` made it `https://`): HTTPS unless it redirects to
+/// plain HTTP on the same host, or nothing answers it there and HTTP does.
+/// A scheme out of scope isn't tried. `None` when the scan was cancelled
+/// meanwhile or neither scheme is in scope.
+pub async fn detect_scheme(config: &FuzzerConfig, state: &ScanState, client: &Client) -> Result<Option<String>, CrawlerError> {
+    let get = FuzzerConfig { method: Method::GET, request_template: None, ..config.clone() };
+    let scope = Scope::new(&config.scope_include, &config.scope_exclude, &config.blocklist)?;
+    let in_scope = |url: &str| match scope.check(url) {
+        Ok(()) => true,
+        Err(reason) => {
+            debug!("🚧 Not trying {}: {}", state.redactor.redact(url), reason);
+            false
+        }
+    };
+
+    let https = config.url.clone();
+    // A port typed with the host stays
+    let port = Url::parse(&https).ok().and_then(|url| url.port());
+    let http = with_scheme(&https, "http", port).unwrap_or_default();
+    if !in_scope(&https) {
+        return match in_scope(&http) {
+            true => Ok(Some(http)),
+            false => Ok(None),
+        };
+    }
+
+    let https_failure = match send_request(&get, state, client, &https, None).await {
+        Ok(response) => return Ok(Some(downgrade(&https, response.url()).filter(|url| scope.allows(url)).unwrap_or(https))),
+        Err(CrawlerError::Cancelled) => return Ok(None),
+        // Neither scheme helps a name that doesn't resolve
+        Err(e @ CrawlerError::Dns { .. }) => return Err(e),
+        Err(e) => e,
+    };
+    if !in_scope(&http) {
+        return Err(https_failure);
+    }
+    debug!("No answer over HTTPS ({}), trying HTTP", https_failure);
+    match send_request(&get, state, client, &http, None).await {
+        Ok(response) => Ok(Some(upgrade(&http, response.url()).filter(|url| scope.allows(url)).unwrap_or(http))),
+        Err(CrawlerError::Cancelled) => Ok(None),
+        Err(_) => Err(https_failure),
+    }
+}
+
+/// `url` over plain HTTP when asking for it over HTTPS ended up there on
+/// the same host
+fn downgrade(url: &str, landed: &Url) -> Option<String> {
+    let same_host = Url::parse(url).ok()?.host_str() == landed.host_str();
+    (same_host && landed.scheme() == "http").then(|| with_scheme(url, "http", landed.port()))?
+}
+
+/// `url` over HTTPS when asking for it over HTTP ended up at HTTPS on the
+/// same host, with the port the redirect went to
+fn upgrade(url: &str, landed: &Url) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let same_host = parsed.host_str() == landed.host_str();
+    (parsed.scheme() == "http" && landed.scheme() == "https" && same_host)
+        .then(|| with_scheme(url, "https", landed.port()))?
+}
+
+/// `url` with another scheme and port (`None` for the scheme's default)
+fn with_scheme(url: &str, scheme: &str, port: Option<u16>) -> Option<String> {
+    let mut url = Url::parse(url).ok()?;
+    url.set_scheme(scheme).ok()?;
+    url.set_port(port).ok()?;
+    Some(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn falls_back_to_http_when_https_gets_no_answer() {
+        // Plain HTTP only: a TLS handshake gets an HTTP response
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            }
+        });
+
+        let wordlist = std::env::temp_dir().join(format!("dir_crawler-scheme-{}.txt", std::process::id()));
+        std::fs::write(&wordlist, "admin\n").unwrap();
+        let config = FuzzerConfig::builder(host.as_str()).wordlist(&wordlist).build().unwrap();
+        std::fs::remove_file(&wordlist).unwrap();
+        assert_eq!(config.url, format!("https://{}", host));
+        let client = crate::build_client(&config).unwrap();
+        let detected = detect_scheme(&config, &ScanState::default(), &client).await.unwrap();
+        assert_eq!(detected, Some(format!("http://{}/", host)));
+    }
+
+    #[tokio::test]
+    async fn does_not_probe_a_target_out_of_scope() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/admin", listener.local_addr().unwrap());
+        let wordlist = std::env::temp_dir().join(format!("dir_crawler-probe-scope-{}.txt", std::process::id()));
+        std::fs::write(&wordlist, "admin\n").unwrap();
+        let config = FuzzerConfig::builder(url.as_str()).wordlist(&wordlist).build().unwrap();
+        std::fs::remove_file(&wordlist).unwrap();
+        let config = FuzzerConfig { scope_exclude: vec!["*/admin*".to_string()], ..config };
+        let client = crate::build_client(&config).unwrap();
+
+        assert!(probe(&config, &ScanState::default(), &client).await.unwrap().is_none());
+        let accepted = tokio::time::timeout(Duration::from_millis(100), listener.accept()).await;
+        assert!(accepted.is_err(), "an out-of-scope target was probed");
+    }
+
+    #[tokio::test]
+    async fn does_not_try_a_scheme_out_of_scope() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = listener.local_addr().unwrap().to_string();
+        let wordlist = std::env::temp_dir().join(format!("dir_crawler-scheme-scope-{}.txt", std::process::id()));
+        std::fs::write(&wordlist, "admin\n").unwrap();
+        let config = FuzzerConfig::builder(host.as_str()).wordlist(&wordlist).build().unwrap();
+        std::fs::remove_file(&wordlist).unwrap();
+        let client = crate::build_client(&config).unwrap();
+        let state = ScanState::default();
+
+        // Only plain HTTP is in scope: it's picked without a request
+        let http_only = FuzzerConfig { scope_include: vec!["http://*".to_string()], ..config.clone() };
+        assert_eq!(detect_scheme(&http_only, &state, &client).await.unwrap(), Some(format!("http://{}/", host)));
+        let neither = FuzzerConfig { scope_exclude: vec!["*".to_string()], ..config };
+        assert_eq!(detect_scheme(&neither, &state, &client).await.unwrap(), None);
+        let accepted = tokio::time::timeout(Duration::from_millis(100), listener.accept()).await;
+        assert!(accepted.is_err(), "a scheme out of scope was tried");
+    }
+
+    #[test]
+    fn changes_scheme_only_on_the_same_host() {
+        let landed = |url: &str| Url::parse(url).unwrap();
+        assert_eq!(downgrade("https://t/", &landed("http://t/")).as_deref(), Some("http://t/"));
+        assert_eq!(downgrade("https://t/", &landed("http://www.t/")), None);
+        assert_eq!(upgrade("http://t/admin/", &landed("https://t/login")).as_deref(), Some("https://t/admin/"));
+        assert_eq!(upgrade("http://t:8080/", &landed("https://t:8443/")).as_deref(), Some("https://t:8443/"));
+        assert_eq!(upgrade("http://t/", &landed("https://www.t/")), None);
+        assert_eq!(upgrade("http://t/", &landed("http://t/login")), None);
+        assert_eq!(upgrade("https://t/", &landed("https://t/")), None);
+    }
+}
@@ -0,0 +1,184 @@
+//! How far a scan has got, published as events rather than drawn by the
+//! workers: they only bump counters, and a ticker turns those into a
+//! `ProgressEvent` for the progress bar and any program running the scan.
+
+use crate::ScanState;
+use indicatif::{HumanDuration, ProgressBar};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// How often an event is published
+const TICK: Duration = Duration::from_millis(250);
+
+/// Ticks the rate is averaged over
+const RATE_WINDOW: usize = 20;
+
+/// Events a subscriber may fall behind by before it misses some
+pub const CAPACITY: usize = 16;
+
+/// One look at a running scan
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    /// URLs tried (or skipped) so far
+    pub processed: u64,
+    /// URLs the scan knows it will try, growing as directories are found
+    pub total: u64,
+    pub findings: u64,
+    /// Requests that got no response
+    pub errors: u64,
+    /// URLs per second over the last few seconds
+    pub rate: f64,
+    /// Time left at that rate, once there is one
+    pub eta: Option<Duration>,
+    pub elapsed: Duration,
+}
+
+/// The counters the workers bump, and where events about them go
+pub struct Progress {
+    processed: AtomicU64,
+    total: AtomicU64,
+    started: OnceLock<Instant>,
+    events: broadcast::Sender<ProgressEvent>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress::to(broadcast::channel(CAPACITY).0)
+    }
+}
+
+impl Progress {
+    /// Publishing on a channel made beforehand, so subscribers can be
+    /// there from the first event
+    pub fn to(events: broadcast::Sender<ProgressEvent>) -> Self {
+        Progress { processed: AtomicU64::new(0), total: AtomicU64::new(0), started: OnceLock::new(), events }
+    }
+
+    pub fn inc(&self, urls: u64) {
+        self.processed.fetch_add(urls, Ordering::Relaxed);
+    }
+
+    pub fn inc_length(&self, urls: u64) {
+        self.total.fetch_add(urls, Ordering::Relaxed);
+    }
+
+    /// URLs still to try, for the queue depth metric
+    pub fn queued(&self) -> u64 {
+        self.total.load(Ordering::Relaxed).saturating_sub(self.processed.load(Ordering::Relaxed))
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.events.subscribe()
+    }
+
+    /// Where things stand, at `rate` URLs per second
+    fn snapshot(&self, state: &ScanState, rate: f64) -> ProgressEvent {
+        let processed = self.processed.load(Ordering::Relaxed);
+        let total = self.total.load(Ordering::Relaxed).max(processed);
+        ProgressEvent {
+            processed,
+            total,
+            findings: state.hits.load(Ordering::Relaxed),
+            errors: state.errors.load(Ordering::Relaxed),
+            rate,
+            eta: (rate > 0.0).then(|| Duration::from_secs_f64((total - processed) as f64 / rate)),
+            elapsed: self.started.get().map(Instant::elapsed).unwrap_or_default(),
+        }
+    }
+
+    /// The last event of a scan, once its workers are done, with the rate
+    /// over the whole scan
+    pub fn finish(&self, state: &ScanState) -> ProgressEvent {
+        let elapsed = self.started.get().map(Instant::elapsed).unwrap_or_default().as_secs_f64();
+        let rate = match elapsed > 0.0 {
+            true => self.processed.load(Ordering::Relaxed) as f64 / elapsed,
+            false => 0.0,
+        };
+        let event = self.snapshot(state, rate);
+        let _ = self.events.send(event.clone());
+        event
+    }
+}
+
+/// Start the scan's clock with `total` URLs known, and publish an event
+/// every `TICK` until the returned task is aborted
+pub fn start(state: Arc<ScanState>, total: u64) -> JoinHandle<()> {
+    let progress = &state.progress;
+    progress.inc_length(total);
+    let _ = progress.started.set(Instant::now());
+    tokio::spawn(async move {
+        let progress = &state.progress;
+        let mut samples = VecDeque::with_capacity(RATE_WINDOW + 1);
+        let mut tick = tokio::time::interval(TICK);
+        loop {
+            tick.tick().await;
+            samples.push_back((Instant::now(), progress.processed.load(Ordering::Relaxed)));
+            if samples.len() > RATE_WINDOW {
+                samples.pop_front();
+            }
+            let rate = match (samples.front(), samples.back()) {
+                (Some((start, first)), Some((end, last))) if end > start => {
+                    (last - first) as f64 / end.duration_since(*start).as_secs_f64()
+                }
+                _ => 0.0,
+            };
+            // Nobody subscribed is no reason to stop
+            let _ = progress.events.send(progress.snapshot(&state, rate));
+        }
+    })
+}
+
+/// Draw the events on `bar` until the returned task is aborted
+pub fn draw(bar: ProgressBar, mut events: broadcast::Receiver<ProgressEvent>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => show(&bar, &event),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+pub fn show(bar: &ProgressBar, event: &ProgressEvent) {
+    bar.set_length(event.total);
+    bar.set_position(event.processed);
+    let eta = event.eta.map(|eta| HumanDuration(eta).to_string()).unwrap_or_else(|| "-".to_string());
+    bar.set_message(format!(
+        "{:.0}/s ({}) errors: {} | findings: {}",
+        event.rate, eta, event.errors, event.findings
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn publishes_what_the_workers_count() {
+        let state = Arc::new(ScanState::default());
+        let mut events = state.progress.subscribe();
+        let ticker = start(Arc::clone(&state), 10);
+        state.progress.inc(4);
+        state.progress.inc_length(2);
+        state.hits.fetch_add(1, Ordering::Relaxed);
+
+        // The first tick may have gone out before the counts changed
+        let event = loop {
+            let event = events.recv().await.unwrap();
+            if event.processed == 4 {
+                break event;
+            }
+        };
+        ticker.abort();
+        assert_eq!((event.total, event.findings, event.errors), (12, 1, 0));
+        assert_eq!(state.progress.queued(), 8);
+        assert!(state.progress.finish(&state).rate > 0.0);
+    }
+}
@@ -0,0 +1,44 @@
+use regex::Regex;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Masks sensitive values (e.g. `password=...`) before anything is persisted or printed
+#[derive(Debug, Default)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+    redactions: AtomicUsize,
+}
+
+impl Redactor {
+    /// Compile `--redact` patterns. Each pattern marks where a secret starts;
+    /// the value following it (up to a delimiter) is replaced.
+    pub fn new(patterns: &[String]) -> Result<Self, regex::Error> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Regex::new(&format!(r#"(?P<key>{})[^&\s"'<>;,]*"#, pattern)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Redactor {
+            patterns,
+            redactions: AtomicUsize::new(0),
+        })
+    }
+
+    /// Redact every configured pattern in `text`, counting each replacement
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut result = Cow::Borrowed(text);
+        for re in &self.patterns {
+            let hits = re.find_iter(&result).count();
+            if hits > 0 {
+                self.redactions.fetch_add(hits, Ordering::Relaxed);
+                result = Cow::Owned(re.replace_all(&result, "${key}[REDACTED]").into_owned());
+            }
+        }
+        result
+    }
+
+    /// Number of values masked so far
+    pub fn count(&self) -> usize {
+        self.redactions.load(Ordering::Relaxed)
+    }
+}
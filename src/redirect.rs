@@ -0,0 +1,76 @@
+use reqwest::Url;
+
+/// Host the payloads point at; a `Location` on it means the redirect
+/// target came from the request
+const HOST: &str = "evil.example";
+
+/// Absolute, scheme-relative and backslash forms, the last two slipping
+/// past checks that only look for `http`
+pub const PAYLOADS: &[&str] = &["https://evil.example/", "//evil.example/", "/\\evil.example/"];
+
+/// Parameter names redirect targets are commonly read from
+const COMMON_PARAMS: &[&str] = &[
+    "next", "url", "redirect", "redirect_uri", "redirect_url", "return", "returnTo", "return_to", "returnUrl",
+    "continue", "dest", "destination", "goto", "target", "to", "out", "redir", "forward", "callback", "r", "u",
+];
+
+/// Parameters worth steering a redirect with: those already in the
+/// finding's URL and in where it redirected to, then the common names
+pub fn params(url: &str, landed: Option<&str>) -> Vec<String> {
+    let base = Url::parse(url).ok();
+    let landed = landed.and_then(|landed| match &base {
+        Some(base) => base.join(landed).ok(),
+        None => Url::parse(landed).ok(),
+    });
+    let mut params: Vec<String> = Vec::new();
+    let seen = base.iter().chain(landed.iter()).flat_map(|url| url.query_pairs().map(|(name, _)| name.into_owned()));
+    for name in seen.chain(COMMON_PARAMS.iter().map(|name| name.to_string())) {
+        if !params.contains(&name) {
+            params.push(name);
+        }
+    }
+    params
+}
+
+/// The URL with each of `params` set to `payload`, replacing values it already had
+pub fn with_params(url: &str, params: &[String], payload: &str) -> Option<String> {
+    let mut url = Url::parse(url).ok()?;
+    let kept: Vec<(String, String)> = url.query_pairs()
+        .filter(|(name, _)| !params.iter().any(|param| param == name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(kept)
+        .extend_pairs(params.iter().map(|param| (param, payload)));
+    Some(url.to_string())
+}
+
+/// Whether a `Location` sent for `url` leads to the payload host, resolved
+/// the way a browser would
+pub fn off_site(url: &str, location: &str) -> bool {
+    Url::parse(url)
+        .and_then(|url| url.join(location.trim()))
+        .is_ok_and(|target| target.host_str().is_some_and(|host| host.eq_ignore_ascii_case(HOST)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_candidates_and_spots_off_site_locations() {
+        let params = params("http://t/login?lang=en", Some("/sso?service=%2Fhome"));
+        assert_eq!(params[..3], ["lang", "service", "next"]);
+        assert_eq!(params.iter().filter(|param| *param == "url").count(), 1);
+
+        let url = with_params("http://t/login?lang=en&next=/home", &["next".to_string(), "url".to_string()], "//evil.example/").unwrap();
+        assert_eq!(url, "http://t/login?lang=en&next=%2F%2Fevil.example%2F&url=%2F%2Fevil.example%2F");
+
+        assert!(off_site("http://t/login", "https://evil.example/"));
+        assert!(off_site("http://t/login", "//evil.example/"));
+        assert!(off_site("http://t/login", "/\\evil.example/"));
+        assert!(!off_site("http://t/login", "/home?next=https://evil.example/"));
+        assert!(!off_site("http://t/login", "https://evil.example.t/"));
+    }
+}
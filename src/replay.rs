@@ -0,0 +1,271 @@
+use crate::engine::{read_body, send_request};
+use crate::error::CrawlerError;
+use crate::output::ScanReport;
+use crate::review::Verdict;
+use crate::secrets::Severity;
+use crate::{Finding, FuzzerConfig, ScanState};
+use crate::term;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+
+/// Findings re-requested at once by default
+pub const DEFAULT_THREADS: &str = "10";
+
+/// A body this much larger or smaller (as a fraction) than it was counts as changed
+const SIZE_TOLERANCE: f64 = 0.05;
+
+pub struct Options {
+    pub results: PathBuf,
+    /// Write the comparison as JSON here
+    pub output: Option<PathBuf>,
+    pub threads: usize,
+    /// Only findings with these statuses (inclusive ranges), when any are given
+    pub statuses: Vec<(u16, u16)>,
+    /// Only findings with these verdicts from `review`, when any are given
+    pub verdicts: Vec<Verdict>,
+    pub min_severity: Severity,
+}
+
+/// What became of a finding since the scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// Same status, similar size
+    Present,
+    /// Still answering, with another status or size
+    Changed,
+    /// Now 404 or 410
+    Gone,
+    /// The request failed, so nothing is known
+    Failed,
+    /// Sent through a request template, which results don't keep
+    Skipped,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Outcome::Present => "present",
+            Outcome::Changed => "changed",
+            Outcome::Gone => "gone",
+            Outcome::Failed => "failed",
+            Outcome::Skipped => "skipped",
+        })
+    }
+}
+
+/// One finding re-requested
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replayed {
+    pub url: String,
+    pub outcome: Outcome,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub now_status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub now_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Comparison written by `replay --output`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub target: String,
+    /// Scan the findings came from
+    pub scan_id: String,
+    pub replayed_at: u64,
+    pub findings: Vec<Replayed>,
+}
+
+/// Whether a finding passes the status, verdict and severity filters
+fn selected(options: &Options, finding: &Finding) -> bool {
+    (options.statuses.is_empty() || options.statuses.iter().any(|(low, high)| (*low..=*high).contains(&finding.status)))
+        && (options.verdicts.is_empty() || finding.verdict.is_some_and(|v| options.verdicts.contains(&v)))
+        && finding.severity >= options.min_severity
+}
+
+/// Compare what a URL answers now with what the scan saw
+fn classify(status: u16, size: Option<u64>, now_status: u16, now_size: u64) -> Outcome {
+    if now_status != status && matches!(now_status, 404 | 410) {
+        return Outcome::Gone;
+    }
+    let resized = size.is_some_and(|size| {
+        let larger = size.max(now_size) as f64;
+        (size.abs_diff(now_size) as f64) > larger * SIZE_TOLERANCE
+    });
+    match now_status != status || resized {
+        true => Outcome::Changed,
+        false => Outcome::Present,
+    }
+}
+
+/// Re-request every selected finding from a results file and report which
+/// are still there, which changed and which disappeared
+pub async fn run(config: FuzzerConfig, options: &Options) -> Result<(), CrawlerError> {
+    let data = std::fs::read(&options.results)
+        .map_err(|e| format!("Cannot read {}: {}", options.results.display(), e))?;
+    let report: ScanReport = serde_json::from_slice(&data).map_err(|_| {
+        format!("{} is not a JSON results file (decrypt encrypted results with --decrypt first)", options.results.display())
+    })?;
+    let method = report.method.parse().map_err(|_| format!("Invalid method '{}' in {}", report.method, options.results.display()))?;
+    let config = Arc::new(FuzzerConfig { method, ..config });
+
+    let findings: Vec<Finding> = report.findings.iter().filter(|f| selected(options, f)).cloned().collect();
+    if findings.is_empty() {
+        term::out!("📭 No findings in {} match the filters", options.results.display());
+        return Ok(());
+    }
+    term::out!("🔁 Replaying {} of {} findings from {}\n", findings.len(), report.findings.len(), report.target.green());
+
+    let client = crate::build_client(&config)?;
+    let state = Arc::new(ScanState::default());
+    let permits = Arc::new(Semaphore::new(options.threads));
+    let mut tasks = JoinSet::new();
+    for (i, finding) in findings.into_iter().enumerate() {
+        let (config, state, client, permits) = (config.clone(), state.clone(), client.clone(), permits.clone());
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            (i, replay(&config, &state, &client, finding).await)
+        });
+    }
+    let mut replayed = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        replayed.push(result?);
+    }
+    replayed.sort_by_key(|(i, _)| *i);
+    let replayed: Vec<Replayed> = replayed.into_iter().map(|(_, r)| r).collect();
+
+    for r in &replayed {
+        term::out!("{}", line(r));
+    }
+    let count = |outcome| replayed.iter().filter(|r| r.outcome == outcome).count();
+    term::out!(
+        "\n✅ {} present · 🔀 {} changed · 🗑️  {} gone · ❌ {} failed{}",
+        count(Outcome::Present),
+        count(Outcome::Changed),
+        count(Outcome::Gone),
+        count(Outcome::Failed),
+        match count(Outcome::Skipped) {
+            0 => String::new(),
+            n => format!(" · ⏭️  {} skipped (request templates)", n),
+        }
+    );
+
+    if let Some(path) = &options.output {
+        let report = ReplayReport {
+            target: report.target,
+            scan_id: report.scan_id,
+            replayed_at: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            findings: replayed,
+        };
+        crate::crypto::write_file(path, serde_json::to_string_pretty(&report)?.as_bytes(), None)?;
+        term::out!("💾 Replay results saved to: {}", path.display().to_string().blue());
+    }
+    Ok(())
+}
+
+async fn replay(config: &FuzzerConfig, state: &ScanState, client: &reqwest::Client, finding: Finding) -> Replayed {
+    let mut replayed = Replayed {
+        url: finding.label(),
+        outcome: Outcome::Skipped,
+        status: finding.status,
+        size: finding.size,
+        now_status: None,
+        now_size: None,
+        error: None,
+    };
+    if finding.payload.is_some() {
+        return replayed;
+    }
+
+    let response = match send_request(config, state, client, &finding.url, None).await {
+        Ok(response) => response,
+        Err(e) => {
+            replayed.outcome = Outcome::Failed;
+            replayed.error = Some(e.to_string());
+            return replayed;
+        }
+    };
+    let now_status = response.status().as_u16();
+    match read_body(config, response).await {
+        Ok((body, _)) => {
+            replayed.outcome = classify(finding.status, finding.size, now_status, body.len() as u64);
+            replayed.now_status = Some(now_status);
+            replayed.now_size = Some(body.len() as u64);
+        }
+        Err(e) => {
+            replayed.outcome = Outcome::Failed;
+            replayed.error = Some(e.to_string());
+        }
+    }
+    replayed
+}
+
+/// `  changed  200 → 403   1532 B → 210 B  http://…`
+fn line(r: &Replayed) -> String {
+    let outcome = format!("{:>8}", r.outcome.to_string());
+    let outcome = match r.outcome {
+        Outcome::Present => outcome.green(),
+        Outcome::Changed => outcome.yellow(),
+        Outcome::Gone => outcome.red(),
+        Outcome::Failed => outcome.red().bold(),
+        Outcome::Skipped => outcome.dimmed(),
+    };
+    let size = |size: Option<u64>| size.map_or("?".to_string(), |s| format!("{} B", s));
+    let change = match (r.now_status, &r.error) {
+        (Some(now), _) => format!("{} → {}  {} → {}", r.status, now, size(r.size), size(r.now_size)),
+        (None, Some(error)) => format!("{} → {}", r.status, error),
+        (None, None) => r.status.to_string(),
+    };
+    format!("{} {}  {}", outcome, r.url, change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_status_and_size_changes() {
+        assert_eq!(classify(200, Some(1000), 200, 1030), Outcome::Present);
+        assert_eq!(classify(200, Some(1000), 200, 1200), Outcome::Changed);
+        assert_eq!(classify(200, None, 200, 5), Outcome::Present);
+        assert_eq!(classify(200, Some(1000), 403, 1000), Outcome::Changed);
+        assert_eq!(classify(200, Some(1000), 404, 300), Outcome::Gone);
+        assert_eq!(classify(301, None, 410, 0), Outcome::Gone);
+        // Findings from a scan matching 404s are still there when they 404
+        assert_eq!(classify(404, Some(120), 404, 120), Outcome::Present);
+    }
+
+    #[test]
+    fn filters_by_status_verdict_and_severity() {
+        let finding = |value: serde_json::Value| serde_json::from_value::<Finding>(value).unwrap();
+        let options = Options {
+            results: PathBuf::new(),
+            output: None,
+            threads: 1,
+            statuses: vec![(200, 299)],
+            verdicts: vec![Verdict::Interesting],
+            min_severity: Severity::Medium,
+        };
+        assert!(selected(&options, &finding(serde_json::json!({
+            "url": "http://t/a", "status": 200, "severity": "high", "verdict": "interesting"
+        }))));
+        assert!(!selected(&options, &finding(serde_json::json!({
+            "url": "http://t/a", "status": 200, "severity": "high"
+        }))));
+        assert!(!selected(&options, &finding(serde_json::json!({
+            "url": "http://t/a", "status": 403, "severity": "high", "verdict": "interesting"
+        }))));
+        assert!(!selected(&options, &finding(serde_json::json!({
+            "url": "http://t/a", "status": 200, "severity": "low", "verdict": "interesting"
+        }))));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::error::CrawlerError;
+use crate::redact::Redactor;
+use crate::workspace::Workspace;
+use crate::Finding;
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+
+/// Command line of the scan running in a workspace, as `resume` runs it again
+const COMMAND_FILE: &str = "command.json";
+
+/// Progress of the scan running in a workspace, one tab-separated record per line:
+/// `done  base-url  entry`, `dir  depth  score  url` or `finding  json`
+const CHECKPOINT_FILE: &str = "checkpoint.log";
+
+/// What a workspace scan records as it goes, so an interrupted run can be
+/// picked up later. Lines are flushed as they are written, so a Ctrl-C or a
+/// crash loses at most the entries still in flight.
+pub struct Journal {
+    writer: Mutex<LineWriter<std::fs::File>>,
+}
+
+impl Journal {
+    /// Record the command line and start the checkpoint, continuing the
+    /// existing one when resuming
+    pub fn open(ws: &Workspace, command_line: &[String], resuming: bool) -> Result<Self, CrawlerError> {
+        std::fs::write(ws.dir.join(COMMAND_FILE), serde_json::to_string_pretty(command_line)?)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resuming)
+            .truncate(!resuming)
+            .open(ws.dir.join(CHECKPOINT_FILE))?;
+        let journal = Journal { writer: Mutex::new(LineWriter::new(file)) };
+        // Start on a line of its own after whatever the interruption cut short
+        let path = ws.dir.join(CHECKPOINT_FILE);
+        if resuming && std::fs::read(path)?.last().is_some_and(|byte| *byte != b'\n') {
+            journal.write("");
+        }
+        Ok(journal)
+    }
+
+    /// Every URL of `entry` beneath `base_url` has been requested
+    pub fn entry_done(&self, base_url: &str, entry: &str) {
+        self.write(&format!("done\t{}\t{}", base_url, entry.replace('\n', " ")));
+    }
+
+    /// A directory was queued for scanning at `depth`
+    pub fn directory(&self, url: &str, depth: usize, score: f64) {
+        self.write(&format!("dir\t{}\t{}\t{}", depth, score, url));
+    }
+
+    pub fn finding(&self, finding: &Finding, redactor: &Redactor) {
+        if let Ok(json) = serde_json::to_string(finding) {
+            self.write(&format!("finding\t{}", redactor.redact(&json)));
+        }
+    }
+
+    fn write(&self, line: &str) {
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writeln!(writer, "{}", line);
+        }
+    }
+}
+
+/// How far an interrupted workspace scan got
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub workspace: Workspace,
+    /// The command line it was started with
+    pub command: Vec<String>,
+    /// (base URL, entry) pairs it finished
+    done: HashSet<(String, String)>,
+    /// Directories it queued, with their depth and confidence
+    pub directories: Vec<(String, usize, f64)>,
+    pub findings: Vec<Finding>,
+}
+
+impl Checkpoint {
+    /// The checkpoint of the workspace named `id` (or a unique prefix of
+    /// one) under `root`
+    pub fn load(root: &Path, id: &str) -> Result<Self, CrawlerError> {
+        let resumable: Vec<Workspace> = std::fs::read_dir(root)
+            .map_err(|e| format!("Cannot read workspace root {}: {}", root.display(), e))?
+            .filter_map(Result::ok)
+            .map(|entry| Workspace {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                dir: entry.path(),
+            })
+            .filter(|ws| ws.dir.join(COMMAND_FILE).is_file())
+            .collect();
+        let workspace = match resumable.iter().find(|ws| ws.name == id) {
+            Some(ws) => ws.clone(),
+            None => {
+                let matching: Vec<&Workspace> = resumable.iter().filter(|ws| ws.name.starts_with(id)).collect();
+                match matching[..] {
+                    [ws] => ws.clone(),
+                    [] => return Err(format!("No resumable scan '{}' in {} (only scans run with --workspace can be resumed)", id, root.display()).into()),
+                    _ => return Err(format!("'{}' matches {} scans; use a longer ID", id, matching.len()).into()),
+                }
+            }
+        };
+
+        let command: Vec<String> = serde_json::from_slice(&std::fs::read(workspace.dir.join(COMMAND_FILE))?)
+            .map_err(|e| format!("Unreadable {} in {}: {}", COMMAND_FILE, workspace.dir.display(), e))?;
+        let mut checkpoint = Checkpoint { workspace, command, done: HashSet::new(), directories: Vec::new(), findings: Vec::new() };
+        let log = std::fs::read_to_string(checkpoint.workspace.dir.join(CHECKPOINT_FILE)).unwrap_or_default();
+        checkpoint.replay(&log);
+        Ok(checkpoint)
+    }
+
+    /// Take in the checkpoint log. A line cut short by the interruption
+    /// fails to parse and is skipped.
+    fn replay(&mut self, log: &str) {
+        let mut seen_findings = HashSet::new();
+        for (kind, record) in log.lines().filter_map(|line| line.split_once('\t')) {
+            match kind {
+                "done" => {
+                    if let Some((base_url, entry)) = record.split_once('\t') {
+                        self.done.insert((base_url.to_string(), entry.to_string()));
+                    }
+                }
+                "dir" => {
+                    let fields: Vec<&str> = record.splitn(3, '\t').collect();
+                    if let [depth, score, url] = fields[..] {
+                        if let (Ok(depth), Ok(score)) = (depth.parse(), score.parse()) {
+                            self.directories.push((url.to_string(), depth, score));
+                        }
+                    }
+                }
+                "finding" => {
+                    if let Ok(finding) = serde_json::from_str::<Finding>(record) {
+                        if seen_findings.insert((finding.url.clone(), finding.payload.clone())) {
+                            self.findings.push(finding);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether the earlier run already requested every URL of `entry` beneath `base_url`
+    pub fn is_done(&self, base_url: &str, entry: &str) -> bool {
+        self.done.contains(&(base_url.to_string(), entry.to_string()))
+    }
+
+    pub fn entries_done(&self) -> usize {
+        self.done.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_and_reloads_progress() {
+        let root = std::env::temp_dir().join(format!("dir_crawler-resume-{}", std::process::id()));
+        let ws = Workspace::create(&root, "example.com-1", "http://example.com/").unwrap();
+        let command = vec!["scan".to_string(), "http://example.com/".to_string(), "--workspace".to_string()];
+        let journal = Journal::open(&ws, &command, false).unwrap();
+        journal.entry_done("http://example.com/", "admin");
+        journal.directory("http://example.com/admin/", 1, 0.9);
+        let finding: Finding = serde_json::from_value(serde_json::json!({ "url": "http://example.com/admin/", "status": 301 })).unwrap();
+        journal.finding(&finding, &Redactor::default());
+        journal.finding(&finding, &Redactor::default());
+        drop(journal);
+        // An interrupted write leaves half a line behind
+        let mut file = OpenOptions::new().append(true).open(ws.dir.join(CHECKPOINT_FILE)).unwrap();
+        file.write_all(b"finding\t{\"url\":\"http://exa").unwrap();
+
+        let checkpoint = Checkpoint::load(&root, "example").unwrap();
+        assert_eq!(checkpoint.command, command);
+        assert!(checkpoint.is_done("http://example.com/", "admin"));
+        assert!(!checkpoint.is_done("http://example.com/admin/", "admin"));
+        assert_eq!(checkpoint.directories, [("http://example.com/admin/".to_string(), 1, 0.9)]);
+        assert_eq!(checkpoint.findings.len(), 1);
+        assert!(Checkpoint::load(&root, "other").is_err());
+
+        // Resuming appends to the checkpoint rather than starting it over
+        Journal::open(&ws, &command, true).unwrap().entry_done("http://example.com/", "login");
+        assert_eq!(Checkpoint::load(&root, "example.com-1").unwrap().entries_done(), 2);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
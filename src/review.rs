@@ -0,0 +1,343 @@
+use crate::engine::{read_body, send_request, severity_color};
+use crate::error::CrawlerError;
+use crate::output::ScanReport;
+use crate::{Finding, FuzzerConfig, ScanState};
+use crate::term;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+
+/// Body lines shown under each finding by default
+pub const DEFAULT_PREVIEW_LINES: &str = "15";
+
+/// Longest preview line shown before it is cut off
+const PREVIEW_WIDTH: usize = 160;
+
+/// Body bytes shown under each hit by default with `-v`
+pub const DEFAULT_SNIPPET_BYTES: &str = "200";
+
+/// How a finding was judged during review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verdict {
+    Interesting,
+    FalsePositive,
+    Done,
+}
+
+impl Verdict {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "interesting" => Some(Verdict::Interesting),
+            "false-positive" => Some(Verdict::FalsePositive),
+            "done" => Some(Verdict::Done),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Verdict::Interesting => "interesting",
+            Verdict::FalsePositive => "false-positive",
+            Verdict::Done => "done",
+        })
+    }
+}
+
+pub struct Options {
+    pub results: PathBuf,
+    /// Where the reviewed results go; the results file itself when `None`
+    pub output: Option<PathBuf>,
+    /// Also step through findings that already have a verdict
+    pub all: bool,
+    pub preview_lines: usize,
+}
+
+/// One keystroke (or note) typed at the review prompt
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    Verdict(Verdict),
+    Note(String),
+    Refetch,
+    Skip,
+    Back,
+    Quit,
+}
+
+impl Action {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        Some(match word.to_lowercase().as_str() {
+            "i" | "interesting" => Action::Verdict(Verdict::Interesting),
+            "f" | "fp" | "false-positive" => Action::Verdict(Verdict::FalsePositive),
+            "d" | "done" => Action::Verdict(Verdict::Done),
+            "n" | "note" if !rest.trim().is_empty() => Action::Note(rest.trim().to_string()),
+            "r" | "refetch" => Action::Refetch,
+            "" | "s" | "skip" => Action::Skip,
+            "b" | "back" => Action::Back,
+            "q" | "quit" => Action::Quit,
+            _ => return None,
+        })
+    }
+}
+
+/// Step through a results file's findings, fetching each one live, and
+/// record verdicts and notes. The file is saved after every change, so
+/// quitting (or Ctrl-C) at any point keeps the work done so far.
+pub async fn run(config: &FuzzerConfig, options: &Options) -> Result<(), CrawlerError> {
+    let data = std::fs::read(&options.results)
+        .map_err(|e| format!("Cannot read {}: {}", options.results.display(), e))?;
+    let mut report: ScanReport = serde_json::from_slice(&data).map_err(|_| {
+        format!("{} is not a JSON results file (decrypt encrypted results with --decrypt first)", options.results.display())
+    })?;
+    let output = options.output.clone().unwrap_or_else(|| options.results.clone());
+
+    let queue: Vec<usize> = (0..report.findings.len())
+        .filter(|&i| options.all || report.findings[i].verdict.is_none())
+        .collect();
+    if queue.is_empty() {
+        term::out!("📭 Nothing to review in {}", options.results.display());
+        return Ok(());
+    }
+    term::out!("🔎 Reviewing {} of {} findings from {}", queue.len(), report.findings.len(), report.target.green());
+    term::out!("   [i]nteresting  [f]alse positive  [d]one  n <note>  [r]efetch  [s]kip  [b]ack  [q]uit\n");
+
+    let client = crate::build_client(config)?;
+    let state = ScanState::default();
+    let mut stdin = std::io::stdin().lock();
+    let mut position = 0;
+    let mut fetch = true;
+    while position < queue.len() {
+        let finding = &mut report.findings[queue[position]];
+        if fetch {
+            term::out!("{}", heading(finding, position + 1, queue.len()));
+            term::out!("{}", live(config, &state, &client, finding, options.preview_lines).await);
+            for note in &finding.notes {
+                term::out!("  📝 {}", note);
+            }
+        }
+
+        print!("{} ", term::plain(term::Stream::Stdout, &"review>".bold().to_string()));
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            break;
+        }
+        fetch = true;
+        match Action::parse(&line) {
+            Some(Action::Verdict(verdict)) => {
+                finding.verdict = Some(verdict);
+                save(&report, &output)?;
+                position += 1;
+            }
+            Some(Action::Note(note)) => {
+                term::out!("  📝 {}", note);
+                finding.notes.push(note);
+                save(&report, &output)?;
+                fetch = false;
+            }
+            Some(Action::Refetch) => {}
+            Some(Action::Skip) => position += 1,
+            Some(Action::Back) => position = position.saturating_sub(1),
+            Some(Action::Quit) => break,
+            None => {
+                term::out!("   [i]nteresting  [f]alse positive  [d]one  n <note>  [r]efetch  [s]kip  [b]ack  [q]uit");
+                fetch = false;
+            }
+        }
+        if fetch {
+            term::out!();
+        }
+    }
+
+    let judged = |verdict| report.findings.iter().filter(|f| f.verdict == Some(verdict)).count();
+    term::out!(
+        "\n✅ {} interesting, {} false positives, {} done, {} unreviewed",
+        judged(Verdict::Interesting),
+        judged(Verdict::FalsePositive),
+        judged(Verdict::Done),
+        report.findings.iter().filter(|f| f.verdict.is_none()).count()
+    );
+    if output.exists() {
+        term::out!("💾 Results saved to: {}", output.display().to_string().blue());
+    }
+    Ok(())
+}
+
+fn save(report: &ScanReport, path: &std::path::Path) -> Result<(), CrawlerError> {
+    crate::crypto::write_file(path, serde_json::to_string_pretty(report)?.as_bytes(), None)
+}
+
+/// `[3/12] high http://… (Status: 200) [tags] {verdict}`
+fn heading(finding: &Finding, n: usize, total: usize) -> String {
+    let mut line = format!("[{}/{}] {} {} (Status: {})", n, total, severity_color(finding.severity), finding.label().bold(), finding.status);
+    if !finding.tags.is_empty() {
+        line.push_str(&format!(" [{}]", finding.tags.join(", ")));
+    }
+    if let Some(verdict) = finding.verdict {
+        line.push_str(&format!(" {{{}}}", verdict));
+    }
+    line
+}
+
+/// What the finding's URL answers now: status (flagged when it changed
+/// since the scan), size, content type and the start of the body
+async fn live(config: &FuzzerConfig, state: &ScanState, client: &reqwest::Client, finding: &Finding, lines: usize) -> String {
+    let response = match send_request(config, state, client, &finding.url, finding.payload.as_deref()).await {
+        Ok(response) => response,
+        Err(e) => return format!("  ❌ {}", e).red().to_string(),
+    };
+    let status = response.status().as_u16();
+    let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .unwrap_or("no content type")
+        .to_string();
+    let (body, truncated) = match read_body(config, response).await {
+        Ok(body) => body,
+        Err(e) => return format!("  ❌ {}", e).red().to_string(),
+    };
+
+    let status = match status == finding.status {
+        true => status.to_string().green(),
+        false => format!("{} (was {})", status, finding.status).yellow(),
+    };
+    let size = match truncated {
+        true => format!("{}+ bytes", body.len()),
+        false => format!("{} bytes", body.len()),
+    };
+    let mut out = format!("  ↳ now {} · {} · {}", status, size, content_type);
+    for line in preview(&body, lines) {
+        out.push_str(&format!("\n  │ {}", line.dimmed()));
+    }
+    out
+}
+
+/// The first `lines` non-blank lines of a body, shortened to fit a terminal;
+/// binary bodies are described rather than shown
+fn preview(body: &[u8], lines: usize) -> Vec<String> {
+    let Ok(text) = std::str::from_utf8(body) else {
+        return vec![format!("({} bytes of binary content)", body.len())];
+    };
+    if text.contains('\0') {
+        return vec![format!("({} bytes of binary content)", body.len())];
+    }
+    let mut shown: Vec<String> = text.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .take(lines + 1)
+        .map(|line| {
+            let line: String = line.chars().filter(|c| !c.is_control() || *c == '\t').collect();
+            match line.chars().count() > PREVIEW_WIDTH {
+                true => format!("{}…", line.chars().take(PREVIEW_WIDTH).collect::<String>()),
+                false => line,
+            }
+        })
+        .collect();
+    if shown.len() > lines {
+        shown.truncate(lines);
+        shown.push("…".to_string());
+    }
+    shown
+}
+
+/// The start of a body on one line for `-v`: the visible text of HTML
+/// pages (no markup, scripts or styles), other text as is, whitespace
+/// collapsed and cut after about `max_bytes`
+pub fn snippet(body: &[u8], content_type: Option<&str>, max_bytes: usize) -> String {
+    let text = match std::str::from_utf8(body) {
+        Ok(text) => text,
+        // The body was cut inside a multi-byte character
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&body[..e.valid_up_to()]).unwrap_or_default(),
+        Err(_) => return format!("({} bytes of binary content)", body.len()),
+    };
+    if text.contains('\0') {
+        return format!("({} bytes of binary content)", body.len());
+    }
+    let text = match content_type.is_some_and(|ct| ct.contains("html")) {
+        true => visible_text(text),
+        false => text.to_string(),
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &text[..end])
+}
+
+/// Text a browser would show for an HTML page
+fn visible_text(page: &str) -> String {
+    let document = scraper::Html::parse_document(page);
+    let mut text = String::new();
+    for node in document.root_element().descendants() {
+        let Some(chunk) = node.value().as_text() else {
+            continue;
+        };
+        let hidden = node.parent()
+            .and_then(|parent| parent.value().as_element().map(|element| element.name().to_string()))
+            .is_some_and(|name| matches!(name.as_str(), "script" | "style" | "noscript" | "template" | "title"));
+        if !hidden {
+            text.push_str(chunk);
+            text.push(' ');
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_prompt_actions() {
+        assert_eq!(Action::parse("i\n"), Some(Action::Verdict(Verdict::Interesting)));
+        assert_eq!(Action::parse("FP"), Some(Action::Verdict(Verdict::FalsePositive)));
+        assert_eq!(Action::parse("done"), Some(Action::Verdict(Verdict::Done)));
+        assert_eq!(Action::parse("n  creds in   config "), Some(Action::Note("creds in   config".to_string())));
+        assert_eq!(Action::parse("n"), None);
+        assert_eq!(Action::parse("\n"), Some(Action::Skip));
+        assert_eq!(Action::parse("b"), Some(Action::Back));
+        assert_eq!(Action::parse("q"), Some(Action::Quit));
+        assert_eq!(Action::parse("x"), None);
+    }
+
+    #[test]
+    fn previews_text_and_describes_binary() {
+        let body = b"<html>\n\n  <title>Admin</title>\r\n<p>one</p>\n<p>two</p>\n";
+        assert_eq!(preview(body, 2), ["<html>", "  <title>Admin</title>", "…"]);
+        assert_eq!(preview(body, 4).len(), 4);
+        assert_eq!(preview(&"x".repeat(500).into_bytes(), 1)[0].chars().count(), PREVIEW_WIDTH + 1);
+        assert_eq!(preview(&[0x89, b'P', b'N', b'G', 0, 0xff], 5), ["(6 bytes of binary content)"]);
+    }
+
+    #[test]
+    fn snippets_show_visible_text() {
+        let page = b"<html><head><title>Admin</title><style>p{color:red}</style></head>\n<body><script>var x=1;</script>\n  <h1>Admin &amp; Ops</h1><p>Welcome   back</p></body></html>";
+        assert_eq!(snippet(page, Some("text/html; charset=utf-8"), 200), "Admin & Ops Welcome back");
+        assert_eq!(snippet(page, Some("text/html"), 8), "Admin & …");
+        assert_eq!(snippet(br#"{"users": []}"#, Some("application/json"), 200), r#"{"users": []}"#);
+        assert_eq!(snippet("añb".as_bytes(), None, 2), "a…");
+        assert_eq!(snippet(&[0x89, b'P', 0xff], None, 200), "(3 bytes of binary content)");
+    }
+
+    #[test]
+    fn verdicts_and_notes_round_trip() {
+        let finding: Finding = serde_json::from_value(serde_json::json!({
+            "url": "http://t/admin", "status": 200, "verdict": "false-positive", "notes": ["login page"]
+        })).unwrap();
+        assert_eq!(finding.verdict, Some(Verdict::FalsePositive));
+        let json = serde_json::to_value(&finding).unwrap();
+        assert_eq!(json["verdict"], "false-positive");
+        assert_eq!(json["notes"][0], "login page");
+        let unreviewed: Finding = serde_json::from_value(serde_json::json!({ "url": "http://t/", "status": 200 })).unwrap();
+        assert!(serde_json::to_value(&unreviewed).unwrap().get("verdict").is_none());
+    }
+}
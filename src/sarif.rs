@@ -0,0 +1,183 @@
+use crate::output::ScanReport;
+use crate::secrets::Severity;
+use crate::Finding;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+
+/// Kinds of issue a finding is reported as: rule ID, short description and
+/// GitHub `security-severity` (0-10)
+const RULES: &[(&str, &str, &str)] = &[
+    ("vcs-exposure", "Version control metadata exposed", "9.0"),
+    ("secret-exposure", "Secret exposed in a response", "9.0"),
+    ("default-credentials", "Basic auth accepts a common credential", "9.5"),
+    ("public-bucket", "Cloud storage bucket publicly readable", "8.0"),
+    ("sensitive-file", "Backup, dump or configuration file exposed", "7.5"),
+    ("open-redirect", "Open redirect", "6.1"),
+    ("cors-misconfiguration", "CORS policy trusts arbitrary origins", "5.5"),
+    ("admin-interface", "Administrative interface reachable", "5.5"),
+    ("risky-http-method", "Risky HTTP method enabled", "4.0"),
+    ("security-headers", "Missing or weak security headers", "3.0"),
+    ("exposed-path", "Path discovered", "2.0"),
+];
+
+fn vcs_path() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"(?i)/\.(git|svn|hg|bzr)(/|$)").unwrap())
+}
+
+fn sensitive_path() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?i)(/\.(env|htpasswd|aws|ssh|npmrc|dockercfg)(/|$)|\.(bak|backup|old|orig|save|swp|sql|dump|zip|tar|gz|tgz|7z|rar|key|pem|p12|pfx|kdbx)$)").unwrap()
+    })
+}
+
+fn admin_path() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?i)(^|/)(admin|administrator|manager|console|phpinfo|actuator|server-status|phpmyadmin|wp-admin)(/|\.|$)").unwrap()
+    })
+}
+
+/// The rules a finding falls under, with a message for each; a finding no
+/// specific rule covers is still reported as a discovered path
+fn classify(finding: &Finding) -> Vec<(&'static str, String)> {
+    let path = reqwest::Url::parse(&finding.url).map(|url| url.path().to_string()).unwrap_or_default();
+    let tagged = |prefix: &'static str| finding.tags.iter().filter(move |tag| tag.starts_with(prefix));
+    let answered = (200..400).contains(&finding.status);
+    let mut results = Vec::new();
+
+    let vcs_exposed = tagged("exposed:").any(|tag| matches!(&tag["exposed:".len()..], "git" | "svn" | "hg"));
+    if vcs_exposed || (answered && vcs_path().is_match(&path)) {
+        results.push(("vcs-exposure", format!("Version control metadata is served at {}", finding.url)));
+    } else if tagged("exposed:").next().is_some() || (answered && sensitive_path().is_match(&path)) {
+        results.push(("sensitive-file", format!("{} serves a file that should not be public", finding.url)));
+    }
+    if !finding.secrets.is_empty() {
+        let rules: Vec<&str> = finding.secrets.iter().map(|secret| secret.rule.as_str()).collect();
+        results.push(("secret-exposure", format!("{} exposes {}", finding.url, rules.join(", "))));
+    }
+    if let Some(credential) = &finding.credentials {
+        results.push(("default-credentials", format!("{} accepts the Basic auth login '{}'", finding.url, credential.user)));
+    }
+    if tagged("bucket:").any(|tag| tag.ends_with(":public")) {
+        results.push(("public-bucket", format!("{} lists its contents to anyone", finding.url)));
+    }
+    for tag in tagged("open-redirect:") {
+        results.push(("open-redirect", format!("{} redirects off-site through the '{}' parameter", finding.url, &tag["open-redirect:".len()..])));
+    }
+    for tag in tagged("cors:").filter(|tag| tag.as_str() != "cors:wildcard") {
+        results.push(("cors-misconfiguration", format!("{} answers cross-origin requests ({})", finding.url, tag)));
+    }
+    if answered && admin_path().is_match(&path) {
+        results.push(("admin-interface", format!("An administrative interface answers at {}", finding.url)));
+    }
+    let methods: Vec<&str> = tagged("method:").map(|tag| &tag["method:".len()..]).collect();
+    if !methods.is_empty() {
+        results.push(("risky-http-method", format!("{} accepts {}", finding.url, methods.join(", "))));
+    }
+    if !finding.header_issues.is_empty() {
+        let checks: Vec<&str> = finding.header_issues.iter().map(|issue| issue.check.as_str()).collect();
+        results.push(("security-headers", format!("{} has header issues: {}", finding.url, checks.join(", "))));
+    }
+    if results.is_empty() {
+        results.push(("exposed-path", format!("{} answered {}", finding.url, finding.status)));
+    }
+    results
+}
+
+/// SARIF's result levels, from the finding's own severity
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Info => "note",
+    }
+}
+
+/// The report as a SARIF 2.1.0 log for code scanning and security
+/// dashboards. Each finding becomes one result per rule it falls under,
+/// located at its URL.
+pub fn render(report: &ScanReport) -> String {
+    let rules: Vec<Value> = RULES.iter()
+        .map(|(id, description, security_severity)| json!({
+            "id": id,
+            "shortDescription": { "text": description },
+            "properties": { "security-severity": security_severity, "tags": ["security"] },
+        }))
+        .collect();
+
+    let mut results = Vec::new();
+    for finding in &report.findings {
+        for (rule, message) in classify(finding) {
+            let rule_index = RULES.iter().position(|(id, _, _)| *id == rule).unwrap_or_default();
+            let fingerprint = crate::signing::sha256_hex(format!("{} {}", rule, finding.label()).as_bytes());
+            results.push(json!({
+                "ruleId": rule,
+                "ruleIndex": rule_index,
+                "level": level(finding.severity),
+                "message": { "text": message },
+                "locations": [{ "physicalLocation": { "artifactLocation": { "uri": finding.url } } }],
+                "partialFingerprints": { "dirCrawler/v1": fingerprint },
+                "webRequest": { "method": report.method, "target": finding.url },
+                "webResponse": { "statusCode": finding.status },
+                "properties": { "severity": finding.severity, "tags": finding.tags },
+            }));
+        }
+    }
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": { "driver": {
+                "name": "dir_crawler",
+                "version": env!("CARGO_PKG_VERSION"),
+                "rules": rules,
+            } },
+            "automationDetails": { "id": format!("dir_crawler/{}", report.scan_id) },
+            "properties": { "target": report.target },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(value: Value) -> Finding {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn maps_findings_to_rules() {
+        let rules = |value: Value| classify(&finding(value)).into_iter().map(|(rule, _)| rule).collect::<Vec<_>>();
+        assert_eq!(rules(json!({ "url": "http://t/.git/HEAD", "status": 200 })), ["vcs-exposure"]);
+        assert_eq!(rules(json!({ "url": "http://t/app/", "status": 200, "tags": ["exposed:git"] })), ["vcs-exposure"]);
+        assert_eq!(rules(json!({ "url": "http://t/site.zip", "status": 200 })), ["sensitive-file"]);
+        assert_eq!(rules(json!({ "url": "http://t/.git/HEAD", "status": 404 })), ["exposed-path"]);
+        assert_eq!(
+            rules(json!({
+                "url": "http://t/admin/", "status": 401,
+                "tags": ["basic-auth:admin", "method:TRACE"],
+                "credentials": { "user": "admin", "password": "admin" },
+            })),
+            ["default-credentials", "risky-http-method"]
+        );
+
+        let mut report: ScanReport = serde_json::from_value(json!({
+            "target": "http://t/", "scan_id": "s1", "wordlist": "w", "method": "GET", "extensions": [],
+            "started_at": 0, "duration_secs": 1.0, "findings": [],
+        })).unwrap();
+        report.findings = vec![finding(json!({ "url": "http://t/.env", "status": 200, "severity": "critical" }))];
+        let log: Value = serde_json::from_str(&render(&report)).unwrap();
+        let result = &log["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "sensitive-file");
+        assert_eq!(result["level"], "error");
+        assert_eq!(log["runs"][0]["tool"]["driver"]["rules"][result["ruleIndex"].as_u64().unwrap() as usize]["id"], "sensitive-file");
+        assert_eq!(result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "http://t/.env");
+    }
+}
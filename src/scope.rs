@@ -0,0 +1,142 @@
+use regex::Regex;
+use reqwest::Url;
+
+/// Paths that are never requested, whatever the include/exclude patterns say.
+/// Hitting these mid-engagement can kill the session or destroy data, so
+/// compounds like `/delete-account` and `/logout_all` count too.
+const HARD_BLOCKLIST: &str =
+    r"(?i)/(logout|log-out|log_out|signout|sign-out|sign_out|delete|destroy|remove|drop|reset|unsubscribe)([/.?_-]|$)";
+
+/// Scope rules enforced on every URL before it is requested
+#[derive(Debug)]
+pub struct Scope {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    blocklist: Vec<Regex>,
+    hard_blocklist: Regex,
+}
+
+impl Scope {
+    /// Compile scope patterns. Patterns prefixed with `re:` are regular
+    /// expressions; anything else is a glob matched against the full URL.
+    pub fn new(include: &[String], exclude: &[String], blocklist: &[String]) -> Result<Self, regex::Error> {
+        Ok(Scope {
+            include: compile_patterns(include)?,
+            exclude: compile_patterns(exclude)?,
+            blocklist: compile_patterns(blocklist)?,
+            hard_blocklist: Regex::new(HARD_BLOCKLIST)?,
+        })
+    }
+
+    /// Check a URL against the scope, returning why it was rejected
+    pub fn check(&self, url: &str) -> Result<(), &'static str> {
+        // The built-in blocklist only looks at the path so hostnames can't trip it
+        let path = Url::parse(url).map(|u| u.path().to_string()).unwrap_or_default();
+        if self.hard_blocklist.is_match(&path) || self.blocklist.iter().any(|re| re.is_match(url)) {
+            return Err("blocklisted");
+        }
+        if !self.include.is_empty() && !self.include.iter().any(|re| re.is_match(url)) {
+            return Err("not in include scope");
+        }
+        if self.exclude.iter().any(|re| re.is_match(url)) {
+            return Err("excluded by scope");
+        }
+        Ok(())
+    }
+
+    pub fn allows(&self, url: &str) -> bool {
+        self.check(url).is_ok()
+    }
+}
+
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Regex>, regex::Error> {
+    patterns
+        .iter()
+        .map(|pattern| match pattern.strip_prefix("re:") {
+            Some(re) => Regex::new(re),
+            None => Regex::new(&glob_to_regex(pattern)),
+        })
+        .collect()
+}
+
+/// Translate a `*`/`?` glob into an anchored regular expression
+fn glob_to_regex(glob: &str) -> String {
+    let mut re = String::from("^");
+    for ch in glob.chars() {
+        match ch {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            _ => re.push_str(&regex::escape(&ch.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(include: &[&str], exclude: &[&str], blocklist: &[&str]) -> Scope {
+        let owned = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        Scope::new(&owned(include), &owned(exclude), &owned(blocklist)).unwrap()
+    }
+
+    #[test]
+    fn globs_match_the_whole_url() {
+        let scope = scope(&["https://example.com/*"], &["*/static/*"], &[]);
+        assert!(scope.allows("https://example.com/admin"));
+        assert_eq!(scope.check("https://other.com/admin"), Err("not in include scope"));
+        assert_eq!(scope.check("https://example.com/static/app.js"), Err("excluded by scope"));
+    }
+
+    #[test]
+    fn question_mark_matches_one_character() {
+        let scope = scope(&["https://example.com/v?/*"], &[], &[]);
+        assert!(scope.allows("https://example.com/v1/users"));
+        assert!(!scope.allows("https://example.com/v10/users"));
+    }
+
+    #[test]
+    fn regex_patterns_are_unanchored() {
+        let scope = scope(&[], &["re:\\.(png|jpg)$"], &[]);
+        assert!(scope.allows("https://example.com/logo.svg"));
+        assert!(!scope.allows("https://example.com/logo.png"));
+    }
+
+    #[test]
+    fn empty_include_allows_everything() {
+        assert!(scope(&[], &[], &[]).allows("https://anything.example/path"));
+    }
+
+    #[test]
+    fn hard_blocklist_catches_destructive_paths_and_compounds() {
+        let scope = scope(&[], &[], &[]);
+        for url in [
+            "https://example.com/logout",
+            "https://example.com/account/delete",
+            "https://example.com/delete-account",
+            "https://example.com/logout_all",
+            "https://example.com/reset-password",
+            "https://example.com/signout?next=/",
+            "https://example.com/Destroy.php",
+        ] {
+            assert_eq!(scope.check(url), Err("blocklisted"), "{}", url);
+        }
+    }
+
+    #[test]
+    fn hard_blocklist_leaves_lookalikes_and_hosts_alone() {
+        let scope = scope(&[], &[], &[]);
+        assert!(scope.allows("https://example.com/deleted_items_archive"));
+        assert!(scope.allows("https://example.com/dropbox"));
+        assert!(scope.allows("https://logout.example.com/"));
+    }
+
+    #[test]
+    fn user_blocklist_matches_the_full_url() {
+        let scope = scope(&[], &[], &["*/billing*"]);
+        assert_eq!(scope.check("https://example.com/billing/invoices"), Err("blocklisted"));
+        assert!(scope.allows("https://example.com/users"));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{Finding, FuzzerConfig};
+use std::path::Path;
+
+/// Longest wait for one page to load and render
+#[cfg(feature = "screenshots")]
+const PAGE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
+
+/// Only pages a browser shows something for are worth a screenshot
+pub fn wanted(finding: &Finding) -> bool {
+    (200..400).contains(&finding.status) && finding.payload.is_none()
+}
+
+/// Screenshot every 2xx and 3xx finding into `dir` with a headless Chromium
+/// driven over CDP, sending the scan's headers and cookies along. Each
+/// finding records its file name; pages that fail to load are skipped.
+/// Returns how many screenshots were taken.
+#[cfg(feature = "screenshots")]
+pub async fn capture(config: &FuzzerConfig, dir: &Path, findings: &mut [Finding]) -> Result<usize, String> {
+    use chromiumoxide::browser::{Browser, BrowserConfig};
+    use chromiumoxide::cdp::browser_protocol::network::{Headers, SetExtraHttpHeadersParams};
+    use chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat;
+    use chromiumoxide::handler::viewport::Viewport;
+    use chromiumoxide::page::ScreenshotParams;
+    use futures::StreamExt;
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    let browser_config = BrowserConfig::builder()
+        .no_sandbox()
+        .arg("--ignore-certificate-errors")
+        .viewport(Viewport { width: 1280, height: 800, ..Default::default() })
+        .build()
+        .map_err(|e| format!("Cannot start Chromium for --screenshots: {}", e))?;
+    let (mut browser, mut handler) = Browser::launch(browser_config)
+        .await
+        .map_err(|e| format!("Cannot start Chromium for --screenshots: {}", e))?;
+    let events = tokio::spawn(async move { while handler.next().await.is_some() {} });
+
+    let mut extra = serde_json::Map::new();
+    for (name, value) in &config.headers {
+        extra.insert(name.clone(), value.clone().into());
+    }
+    if let Some(cookie) = &config.cookie {
+        extra.insert("Cookie".to_string(), cookie.clone().into());
+    }
+
+    let mut taken = 0;
+    for finding in findings.iter_mut().filter(|finding| wanted(finding)) {
+        let name = format!("{}.png", crate::store::url_hash(&finding.url));
+        let path = dir.join(&name);
+        let shot = async {
+            let page = browser.new_page("about:blank").await?;
+            if !extra.is_empty() {
+                page.execute(SetExtraHttpHeadersParams::new(Headers::new(serde_json::Value::Object(extra.clone())))).await?;
+            }
+            page.goto(finding.url.as_str()).await?;
+            let params = ScreenshotParams::builder().format(CaptureScreenshotFormat::Png).build();
+            let saved = page.save_screenshot(params, &path).await.map(|_| ());
+            page.close().await?;
+            saved
+        };
+        match tokio::time::timeout(PAGE_TIMEOUT, shot).await {
+            Ok(Ok(())) => {
+                finding.screenshot = Some(name);
+                taken += 1;
+            }
+            Ok(Err(e)) => tracing::debug!("📸 No screenshot of {}: {}", finding.url, e),
+            Err(_) => tracing::debug!("📸 No screenshot of {}: timed out", finding.url),
+        }
+    }
+
+    let _ = browser.close().await;
+    let _ = browser.wait().await;
+    events.abort();
+    Ok(taken)
+}
+
+/// Fail before the scan rather than after it when there's no Chromium to drive
+#[cfg(feature = "screenshots")]
+pub fn check() -> Result<(), String> {
+    chromiumoxide::browser::BrowserConfig::builder()
+        .build()
+        .map(|_| ())
+        .map_err(|e| format!("Cannot start Chromium for --screenshots: {}", e))
+}
+
+/// Why `--screenshots` is refused by builds without the feature
+#[cfg(not(feature = "screenshots"))]
+const NOT_BUILT: &str = "--screenshots needs dir_crawler built with `--features screenshots`";
+
+#[cfg(not(feature = "screenshots"))]
+pub fn check() -> Result<(), String> {
+    Err(NOT_BUILT.to_string())
+}
+
+#[cfg(not(feature = "screenshots"))]
+pub async fn capture(_config: &FuzzerConfig, _dir: &Path, _findings: &mut [Finding]) -> Result<usize, String> {
+    Err(NOT_BUILT.to_string())
+}
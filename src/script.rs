@@ -0,0 +1,248 @@
+use crate::plugin::{Exchange, ScanPlugin};
+use crate::secrets::Severity;
+use crate::Finding;
+use crate::term;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, warn};
+
+/// Operations one hook call may take before it is abandoned, so a runaway
+/// loop costs a warning rather than a stuck worker
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Functions a script may define, all optional:
+///
+/// - `on_request(request)`: `#{method, url, headers}`; return it changed
+/// - `matches(response)`: `#{url, status, size, content_type, headers, body, tags}`;
+///   return `false` to drop the response
+/// - `on_finding(finding)`: `#{url, status, size, severity, score, content_type,
+///   tags, title, notes}`; return it with changed `tags`, `severity`, `title` or `notes`
+/// - `on_scan_end(findings)`: every finding, in report order
+const HOOKS: &[&str] = &["on_request", "matches", "on_finding", "on_scan_end"];
+
+/// `--script`: a Rhai script hooked into the scan as a plugin, for the one
+/// rule of an engagement no set of flags covers
+pub struct Script {
+    name: String,
+    engine: Engine,
+    ast: AST,
+    /// Which of `HOOKS` the script defines
+    defined: Vec<&'static str>,
+    /// A failing hook is reported once; later failures only at debug level
+    warned: AtomicBool,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("Cannot read script {}: {}", path.display(), e))?;
+        let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+        Self::compile(name, &source).map_err(|e| format!("Invalid script {}: {}", path.display(), e))
+    }
+
+    fn compile(name: String, source: &str) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| term::out!("📜 {}", text));
+        engine.on_debug(|text, _, position| debug!("📜 {} {}", position, text));
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+
+        let defined: Vec<&'static str> = HOOKS.iter()
+            .copied()
+            .filter(|hook| ast.iter_functions().any(|f| f.name == *hook && f.params.len() == 1))
+            .collect();
+        if defined.is_empty() {
+            return Err(format!("defines none of {}, each taking one argument", HOOKS.join(", ")));
+        }
+        Ok(Script { name, engine, ast, defined, warned: AtomicBool::new(false) })
+    }
+
+    /// Call one hook, or `None` when the script doesn't define it or it failed
+    fn call(&self, hook: &'static str, argument: Dynamic) -> Option<Dynamic> {
+        if !self.defined.contains(&hook) {
+            return None;
+        }
+        let options = CallFnOptions::new().eval_ast(false);
+        match self.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, hook, (argument,)) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                match self.warned.swap(true, Ordering::Relaxed) {
+                    false => warn!("📜 {} failed in {}: {} (further errors are logged at debug level)", self.name, hook, e),
+                    true => debug!("📜 {} failed in {}: {}", self.name, hook, e),
+                }
+                None
+            }
+        }
+    }
+}
+
+impl ScanPlugin for Script {
+    fn name(&self) -> &'static str {
+        "script"
+    }
+
+    fn needs_body(&self) -> bool {
+        self.defined.contains(&"matches")
+    }
+
+    fn on_request(&self, request: &mut reqwest::Request) {
+        let mut headers = Map::new();
+        for (name, value) in request.headers() {
+            headers.insert(name.as_str().into(), String::from_utf8_lossy(value.as_bytes()).into_owned().into());
+        }
+        let mut map = Map::new();
+        map.insert("method".into(), request.method().to_string().into());
+        map.insert("url".into(), request.url().to_string().into());
+        map.insert("headers".into(), headers.into());
+
+        let Some(changed) = self.call("on_request", map.into()).and_then(|result| result.try_cast::<Map>()) else {
+            return;
+        };
+        if let Some(method) = text(&changed, "method").and_then(|m| m.to_uppercase().parse().ok()) {
+            *request.method_mut() = method;
+        }
+        if let Some(url) = text(&changed, "url") {
+            match reqwest::Url::parse(&url) {
+                Ok(url) => *request.url_mut() = url,
+                Err(e) => debug!("📜 {}: on_request returned an invalid URL '{}': {}", self.name, url, e),
+            }
+        }
+        if let Some(headers) = changed.get("headers").and_then(|h| h.clone().try_cast::<Map>()) {
+            request.headers_mut().clear();
+            for (name, value) in headers {
+                let name = reqwest::header::HeaderName::from_bytes(name.as_bytes());
+                let value = reqwest::header::HeaderValue::from_str(&value.to_string());
+                if let (Ok(name), Ok(value)) = (name, value) {
+                    request.headers_mut().insert(name, value);
+                }
+            }
+        }
+    }
+
+    fn matches(&self, exchange: &Exchange, finding: &Finding) -> bool {
+        if !self.defined.contains(&"matches") {
+            return true;
+        }
+        let mut headers = Map::new();
+        for (name, value) in exchange.headers {
+            headers.insert(name.as_str().into(), String::from_utf8_lossy(value.as_bytes()).into_owned().into());
+        }
+        let mut map = finding_map(finding);
+        map.insert("headers".into(), headers.into());
+        map.insert("body".into(), String::from_utf8_lossy(exchange.body).into_owned().into());
+        // A failing predicate keeps the response rather than hiding it
+        self.call("matches", map.into()).is_none_or(|result| result.as_bool().unwrap_or(true))
+    }
+
+    fn on_finding(&self, finding: &mut Finding) {
+        let Some(changed) = self.call("on_finding", finding_map(finding).into()).and_then(|result| result.try_cast::<Map>()) else {
+            return;
+        };
+        if let Some(tags) = strings(&changed, "tags") {
+            finding.tags = tags;
+        }
+        if let Some(notes) = strings(&changed, "notes") {
+            finding.notes = notes;
+        }
+        if let Some(severity) = text(&changed, "severity").and_then(|s| Severity::parse(&s)) {
+            finding.severity = severity;
+        }
+        if changed.contains_key("title") {
+            finding.title = text(&changed, "title").filter(|title| !title.is_empty());
+        }
+    }
+
+    fn on_scan_end(&self, findings: &[Finding]) {
+        let all: Array = findings.iter().map(|finding| finding_map(finding).into()).collect();
+        self.call("on_scan_end", all.into());
+    }
+}
+
+/// What scripts see of a finding
+fn finding_map(finding: &Finding) -> Map {
+    let mut map = Map::new();
+    map.insert("url".into(), finding.label().into());
+    map.insert("status".into(), (finding.status as i64).into());
+    map.insert("size".into(), finding.size.map_or(Dynamic::UNIT, |size| (size as i64).into()));
+    map.insert("severity".into(), finding.severity.to_string().into());
+    map.insert("score".into(), (finding.score as i64).into());
+    map.insert("content_type".into(), finding.content_type.clone().map_or(Dynamic::UNIT, Into::into));
+    map.insert("title".into(), finding.title.clone().map_or(Dynamic::UNIT, Into::into));
+    map.insert("tags".into(), finding.tags.iter().cloned().map(Dynamic::from).collect::<Array>().into());
+    map.insert("notes".into(), finding.notes.iter().cloned().map(Dynamic::from).collect::<Array>().into());
+    map
+}
+
+fn text(map: &Map, key: &str) -> Option<String> {
+    map.get(key).filter(|value| !value.is_unit()).map(ToString::to_string)
+}
+
+fn strings(map: &Map, key: &str) -> Option<Vec<String>> {
+    let array = map.get(key)?.clone().into_array().ok()?;
+    Some(array.into_iter().map(|value| value.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    fn finding() -> Finding {
+        serde_json::from_value(serde_json::json!({
+            "url": "http://t/admin", "status": 200, "size": 42, "tags": ["lang:eng"]
+        })).unwrap()
+    }
+
+    #[test]
+    fn hooks_filter_rewrite_and_enrich() {
+        let script = Script::compile("test.rhai".to_string(), r#"
+            fn on_request(request) {
+                request.headers["x-engagement"] = "acme-42";
+                request.url.replace("/old/", "/new/");
+                request
+            }
+            fn matches(response) {
+                !response.body.contains("Maintenance") && response.headers["server"] != "cdn"
+            }
+            fn on_finding(finding) {
+                if finding.url.ends_with("/admin") {
+                    finding.tags.push("admin-panel");
+                    finding.severity = "high";
+                    finding.notes.push(`size ${finding.size}`);
+                }
+                finding
+            }
+        "#).unwrap();
+        assert!(script.needs_body());
+
+        let mut request = reqwest::Request::new(reqwest::Method::GET, "http://t/old/x".parse().unwrap());
+        script.on_request(&mut request);
+        assert_eq!(request.url().as_str(), "http://t/new/x");
+        assert_eq!(request.headers()["x-engagement"], "acme-42");
+
+        let mut headers = HeaderMap::new();
+        headers.insert("server", HeaderValue::from_static("nginx"));
+        assert!(script.matches(&Exchange { headers: &headers, body: b"<h1>Admin</h1>" }, &finding()));
+        assert!(!script.matches(&Exchange { headers: &headers, body: b"Maintenance mode" }, &finding()));
+        headers.insert("server", HeaderValue::from_static("cdn"));
+        assert!(!script.matches(&Exchange { headers: &headers, body: b"" }, &finding()));
+
+        let mut finding = finding();
+        script.on_finding(&mut finding);
+        assert_eq!(finding.tags, ["lang:eng", "admin-panel"]);
+        assert_eq!(finding.severity, Severity::High);
+        assert_eq!(finding.notes, ["size 42"]);
+    }
+
+    #[test]
+    fn failing_hooks_leave_findings_alone() {
+        let script = Script::compile("loop.rhai".to_string(), "fn matches(r) { loop {} } fn on_finding(f) { f.nope() }").unwrap();
+        assert!(script.matches(&Exchange { headers: &HeaderMap::new(), body: b"" }, &finding()));
+        let mut finding = finding();
+        script.on_finding(&mut finding);
+        assert_eq!(finding.tags, ["lang:eng"]);
+
+        assert!(Script::compile("none.rhai".to_string(), "fn helper(a, b) { a + b }").is_err());
+        assert!(Script::compile("bad.rhai".to_string(), "fn matches(r) {").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How serious a finding or detected secret is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "info" => Some(Severity::Info),
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Built-in detectors: (name, severity, pattern)
+const BUILTIN_RULES: &[(&str, Severity, &str)] = &[
+    ("private-key", Severity::Critical, r"-----BEGIN (?:RSA |EC |DSA |OPENSSH |PGP |ENCRYPTED )?PRIVATE KEY"),
+    ("aws-access-key", Severity::Critical, r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+    ("aws-secret-key", Severity::Critical, r#"(?i)aws.{0,20}secret.{0,20}['"=:\s][0-9a-zA-Z/+]{40}\b"#),
+    ("stripe-live-key", Severity::Critical, r"\b[sr]k_live_[0-9a-zA-Z]{24,}\b"),
+    ("github-token", Severity::High, r"\bgh[pousr]_[A-Za-z0-9]{36,}\b"),
+    ("slack-token", Severity::High, r"\bxox[abprs]-[0-9A-Za-z-]{10,}\b"),
+    ("google-api-key", Severity::High, r"\bAIza[0-9A-Za-z_-]{35}\b"),
+    ("jwt", Severity::Medium, r"\beyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}"),
+    ("generic-secret", Severity::Medium, r#"(?i)\b(?:api[_-]?key|secret|passw(?:or)?d|access[_-]?token)\b\s*["']?\s*[:=]\s*["']?[A-Za-z0-9_\-/+]{12,}"#),
+    ("email", Severity::Low, r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b"),
+];
+
+/// A secret detected in a response body
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SecretMatch {
+    pub rule: String,
+    pub severity: Severity,
+    /// Masked excerpt so reports never carry the full secret
+    pub excerpt: String,
+}
+
+struct Rule {
+    name: String,
+    severity: Severity,
+    regex: Regex,
+}
+
+/// Regex-based detector for credentials and sensitive data in bodies
+pub struct SecretScanner {
+    rules: Vec<Rule>,
+}
+
+impl SecretScanner {
+    /// Build the scanner from the built-in rules plus user rules given as
+    /// `name|severity|regex` lines
+    pub fn new(extra_rules: &[String]) -> Result<Self, String> {
+        let mut rules: Vec<Rule> = BUILTIN_RULES
+            .iter()
+            .map(|(name, severity, pattern)| Rule {
+                name: name.to_string(),
+                severity: *severity,
+                regex: Regex::new(pattern).expect("built-in secret rule must compile"),
+            })
+            .collect();
+
+        for line in extra_rules {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(3, '|');
+            let (Some(name), Some(severity), Some(pattern)) = (parts.next(), parts.next(), parts.next()) else {
+                return Err(format!("Invalid secret rule (expected name|severity|regex): {}", line));
+            };
+            let severity = Severity::parse(severity)
+                .ok_or_else(|| format!("Unknown severity '{}' in secret rule {}", severity, name))?;
+            let regex = Regex::new(pattern)
+                .map_err(|e| format!("Invalid regex in secret rule {}: {}", name, e))?;
+
+            rules.push(Rule { name: name.trim().to_string(), severity, regex });
+        }
+
+        Ok(SecretScanner { rules })
+    }
+
+    /// Find every distinct secret in a body
+    pub fn scan(&self, body: &str) -> Vec<SecretMatch> {
+        let mut found: Vec<SecretMatch> = Vec::new();
+        for rule in &self.rules {
+            for m in rule.regex.find_iter(body) {
+                let excerpt = mask(m.as_str());
+                if !found.iter().any(|f| f.rule == rule.name && f.excerpt == excerpt) {
+                    found.push(SecretMatch {
+                        rule: rule.name.clone(),
+                        severity: rule.severity,
+                        excerpt,
+                    });
+                }
+            }
+        }
+        found.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        found
+    }
+}
+
+/// Keep only the first few characters of a secret
+fn mask(secret: &str) -> String {
+    let visible: String = secret.chars().take(6).collect();
+    format!("{}…", visible)
+}
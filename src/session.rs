@@ -0,0 +1,400 @@
+use crate::FuzzerConfig;
+use crate::error::CrawlerError;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, LOCATION, SET_COOKIE};
+use reqwest::{Client, Method, Response};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+
+/// How long re-logins pause after one didn't help, so pages that answer 401
+/// whatever the session don't turn every request into a login
+const REFRESH_BACKOFF: Duration = Duration::from_secs(30);
+
+/// `--login-config` file: how to log in, how to tell it worked, and which
+/// responses mean the session has expired
+///
+/// ```yaml
+/// url: https://example.com/login
+/// method: POST
+/// headers:
+///   Content-Type: application/x-www-form-urlencoded
+/// body: username=admin&password=hunter2
+/// success:
+///   status: [302]
+///   cookie: sessionid
+/// expired:
+///   status: [401, 403]
+///   redirect_contains: /login
+/// token:
+///   regex: '"access_token":"([^"]+)"'
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoginConfig {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub success: SuccessRule,
+    #[serde(default)]
+    pub expired: ExpiryRule,
+    /// Bearer-style token taken from the login response body
+    pub token: Option<TokenRule>,
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+/// What a successful login response looks like; every given condition must hold
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuccessRule {
+    /// Accepted statuses (default: any 2xx or 3xx)
+    #[serde(default)]
+    pub status: Vec<u16>,
+    /// Text the response body must contain
+    pub contains: Option<String>,
+    /// Cookie the response must set
+    pub cookie: Option<String>,
+}
+
+/// Responses that mean the session is gone; any one condition triggers a re-login
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExpiryRule {
+    pub status: Vec<u16>,
+    /// Text in the URL a request was redirected to, e.g. `/login`
+    pub redirect_contains: Option<String>,
+}
+
+impl Default for ExpiryRule {
+    fn default() -> Self {
+        ExpiryRule { status: vec![401], redirect_contains: None }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenRule {
+    /// Regex whose first capture group is the token
+    pub regex: String,
+    #[serde(default = "default_token_header")]
+    pub header: String,
+    #[serde(default = "default_token_prefix")]
+    pub prefix: String,
+}
+
+fn default_token_header() -> String {
+    "Authorization".to_string()
+}
+
+fn default_token_prefix() -> String {
+    "Bearer ".to_string()
+}
+
+impl LoginConfig {
+    pub fn load(path: &Path) -> Result<Self, CrawlerError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read login config {}: {}", path.display(), e))?;
+        let config: LoginConfig = serde_yaml::from_str(&text)
+            .map_err(|e| format!("Invalid login config {}: {}", path.display(), e))?;
+        Method::from_bytes(config.method.as_bytes())
+            .map_err(|_| format!("Invalid method '{}' in login config", config.method))?;
+        if let Some(token) = &config.token {
+            Regex::new(&token.regex).map_err(|e| format!("Invalid token regex in login config: {}", e))?;
+        }
+        Ok(config)
+    }
+
+    /// Whether a response means the session has expired
+    pub fn expired(&self, status: u16, final_url: &str, headers: &HeaderMap) -> bool {
+        if self.expired.status.contains(&status) {
+            return true;
+        }
+        let Some(marker) = &self.expired.redirect_contains else {
+            return false;
+        };
+        let location = headers.get(LOCATION).and_then(|l| l.to_str().ok()).unwrap_or_default();
+        final_url.contains(marker.as_str()) || location.contains(marker.as_str())
+    }
+}
+
+/// What a login yields: cookies and optionally a token header
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+    /// `name=value; name=value`, ready for a Cookie header
+    pub cookies: String,
+    pub token: Option<(String, String)>,
+}
+
+/// `name=value` of every Set-Cookie header, attributes dropped
+pub fn cookie_pairs(headers: &HeaderMap) -> Vec<String> {
+    headers.get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|cookie| cookie.split(';').next())
+        .map(|pair| pair.trim().to_string())
+        .filter(|pair| pair.contains('='))
+        .collect()
+}
+
+/// Judge a login response and pull the credentials out of it
+pub fn credentials(config: &LoginConfig, status: u16, headers: &HeaderMap, body: &str) -> Result<Credentials, String> {
+    let success = &config.success;
+    let status_ok = match success.status.is_empty() {
+        true => (200..400).contains(&status),
+        false => success.status.contains(&status),
+    };
+    if !status_ok {
+        return Err(format!("login answered {}", status));
+    }
+    if let Some(text) = success.contains.as_ref().filter(|text| !body.contains(text.as_str())) {
+        return Err(format!("login response doesn't contain \"{}\"", text));
+    }
+
+    let pairs = cookie_pairs(headers);
+    if let Some(name) = &success.cookie {
+        if !pairs.iter().any(|pair| pair.split('=').next() == Some(name.as_str())) {
+            return Err(format!("login didn't set the {} cookie", name));
+        }
+    }
+
+    let token = match &config.token {
+        Some(rule) => {
+            let re = Regex::new(&rule.regex).map_err(|e| e.to_string())?;
+            let token = re.captures(body)
+                .and_then(|captures| captures.get(1))
+                .ok_or("login response has no token matching the token regex")?;
+            Some((rule.header.clone(), format!("{}{}", rule.prefix, token.as_str())))
+        }
+        None => None,
+    };
+
+    if pairs.is_empty() && token.is_none() {
+        return Err("login set no cookies and no token was configured".to_string());
+    }
+    Ok(Credentials { cookies: pairs.join("; "), token })
+}
+
+/// Logged-in session shared by every worker, re-established when responses
+/// show it has expired
+pub struct Session {
+    config: LoginConfig,
+    client: Client,
+    current: RwLock<Credentials>,
+    /// Bumped on every login, so concurrent workers seeing the same expiry
+    /// log in only once
+    generation: AtomicU64,
+    /// No re-logins before this, set when a fresh session was refused too
+    backoff_until: std::sync::Mutex<Option<Instant>>,
+    refreshing: tokio::sync::Mutex<()>,
+}
+
+impl Session {
+    /// Log in for the first time; failing to is fatal
+    pub async fn start(config: LoginConfig, scan: &FuzzerConfig) -> Result<Self, CrawlerError> {
+        // Redirects aren't followed so cookies set alongside one aren't lost
+        let builder = Client::builder()
+            .connect_timeout(Duration::from_secs(scan.connect_timeout))
+            .timeout(Duration::from_secs(scan.connect_timeout + scan.read_timeout))
+            .default_headers(crate::default_headers(scan))
+            .redirect(reqwest::redirect::Policy::none());
+        // The login goes out the way the scan's requests do
+        let client = crate::apply_network(builder, scan)?.build()?;
+
+        let credentials = login(&config, &client).await?;
+        Ok(Session {
+            config,
+            client,
+            current: RwLock::new(credentials),
+            generation: AtomicU64::new(0),
+            backoff_until: std::sync::Mutex::new(None),
+            refreshing: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.config.url
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Cookies and token of the current login
+    pub fn credentials(&self) -> Credentials {
+        self.current.read().unwrap().clone()
+    }
+
+    pub fn expired(&self, response: &Response) -> bool {
+        self.config.expired(response.status().as_u16(), response.url().as_str(), response.headers())
+    }
+
+    /// Log in again unless someone already did since `seen` was read, or
+    /// re-logins are backing off. Returns whether the request should be
+    /// retried with fresh credentials.
+    pub async fn refresh(&self, seen: u64) -> Result<bool, CrawlerError> {
+        let _guard = self.refreshing.lock().await;
+        if self.generation() != seen {
+            return Ok(true);
+        }
+        if self.backoff_until.lock().unwrap().is_some_and(|until| Instant::now() < until) {
+            return Ok(false);
+        }
+        let credentials = login(&self.config, &self.client).await;
+        if credentials.is_err() {
+            self.back_off();
+        }
+        *self.current.write().unwrap() = credentials?;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        info!("🔐 Session expired; logged in again");
+        Ok(true)
+    }
+
+    /// A fresh session was refused as well, so the response wasn't about the
+    /// session: hold off re-logging in for a while
+    pub fn back_off(&self) {
+        *self.backoff_until.lock().unwrap() = Some(Instant::now() + REFRESH_BACKOFF);
+    }
+}
+
+async fn login(config: &LoginConfig, client: &Client) -> Result<Credentials, CrawlerError> {
+    let method = Method::from_bytes(config.method.as_bytes())
+        .map_err(|_| format!("Invalid login method '{}'", config.method))?;
+    let mut request = client.request(method, &config.url);
+    for (name, value) in &config.headers {
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| format!("Invalid login header name '{}'", name))?;
+        let value = HeaderValue::from_str(value).map_err(|_| format!("Invalid value for login header {}", name))?;
+        request = request.header(name, value);
+    }
+    if let Some(body) = &config.body {
+        request = request.body(body.clone());
+    }
+    let response = request.send().await.map_err(|e| format!("Login request failed: {}", e))?;
+    let status = response.status().as_u16();
+    let headers = response.headers().clone();
+    let body = response.text().await.unwrap_or_default();
+    credentials(config, status, &headers, &body).map_err(|e| format!("Login failed: {}", e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(yaml: &str) -> LoginConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(*name, HeaderValue::from_static(value));
+        }
+        map
+    }
+
+    #[test]
+    fn parses_minimal_configs_with_defaults() {
+        let login = config("url: https://example.com/login\nbody: user=a&pass=b\n");
+        assert_eq!(login.method, "POST");
+        assert_eq!(login.expired.status, [401]);
+        assert!(login.success.status.is_empty());
+        assert!(serde_yaml::from_str::<LoginConfig>("url: x\npasword: typo\n").is_err());
+    }
+
+    #[test]
+    fn collects_cookies_from_successful_logins() {
+        let login = config("url: x\nsuccess:\n  status: [302]\n  cookie: sessionid\n");
+        let response = headers(&[
+            ("set-cookie", "sessionid=abc123; Path=/; HttpOnly"),
+            ("set-cookie", "csrftoken=xyz; Secure"),
+        ]);
+        let credentials = credentials(&login, 302, &response, "").unwrap();
+        assert_eq!(credentials.cookies, "sessionid=abc123; csrftoken=xyz");
+        assert!(credentials.token.is_none());
+
+        assert!(super::credentials(&login, 200, &response, "").is_err(), "wrong status");
+        let other = headers(&[("set-cookie", "tracking=1")]);
+        assert!(super::credentials(&login, 302, &other, "").is_err(), "missing session cookie");
+    }
+
+    #[test]
+    fn extracts_tokens_and_checks_body_text() {
+        let login = config("url: x\nsuccess:\n  contains: Welcome\ntoken:\n  regex: '\"access_token\":\"([^\"]+)\"'\n");
+        let body = r#"{"message":"Welcome","access_token":"eyJabc"}"#;
+        let credentials = credentials(&login, 200, &HeaderMap::new(), body).unwrap();
+        assert_eq!(credentials.token, Some(("Authorization".to_string(), "Bearer eyJabc".to_string())));
+        assert!(super::credentials(&login, 200, &HeaderMap::new(), r#"{"error":"bad password"}"#).is_err());
+    }
+
+    #[test]
+    fn logins_that_yield_nothing_fail() {
+        assert!(credentials(&config("url: x\n"), 200, &HeaderMap::new(), "ok").is_err());
+    }
+
+    #[test]
+    fn detects_expiry_by_status_or_login_redirect() {
+        let login = config("url: x\nexpired:\n  status: [401]\n  redirect_contains: /login\n");
+        assert!(login.expired(401, "https://t/admin", &HeaderMap::new()));
+        assert!(login.expired(200, "https://t/login?next=/admin", &HeaderMap::new()));
+        assert!(login.expired(302, "https://t/admin", &headers(&[("location", "/login")])));
+        assert!(!login.expired(200, "https://t/admin", &HeaderMap::new()));
+        assert!(!login.expired(403, "https://t/admin", &HeaderMap::new()));
+    }
+
+    /// A login endpoint on loopback that hands out a session cookie,
+    /// reporting the address each connection came from
+    async fn login_server() -> (LoginConfig, tokio::sync::mpsc::UnboundedReceiver<std::net::IpAddr>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let login = config(&format!("url: http://{}/login\nsuccess:\n  cookie: sid\n", listener.local_addr().unwrap()));
+        let (peers, received) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((mut socket, peer)) = listener.accept().await {
+                let _ = peers.send(peer.ip());
+                let mut buf = vec![0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket
+                    .write_all(b"HTTP/1.1 200 OK\r\nSet-Cookie: sid=1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .await;
+            }
+        });
+        (login, received)
+    }
+
+    #[tokio::test]
+    async fn logs_in_over_the_scan_address_family() {
+        let (login, mut peers) = login_server().await;
+        let ipv4 = FuzzerConfig { address_family: crate::dns::AddressFamily::Ipv4, ..FuzzerConfig::default() };
+        assert!(Session::start(login.clone(), &ipv4).await.is_ok());
+        assert!(peers.recv().await.unwrap().is_ipv4());
+
+        // An IPv6-only scan doesn't log in to an IPv4 address
+        let ipv6 = FuzzerConfig { address_family: crate::dns::AddressFamily::Ipv6, ..FuzzerConfig::default() };
+        assert!(Session::start(login, &ipv6).await.is_err());
+        assert!(peers.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn logs_in_from_the_scan_source_address() {
+        let (login, mut peers) = login_server().await;
+        // Any loopback address is this host's on Linux; elsewhere only 127.0.0.1 may be
+        let source: std::net::IpAddr = "127.0.0.2".parse().unwrap();
+        if crate::interface::check(source).is_err() {
+            return;
+        }
+        let scan = FuzzerConfig { source_ip: Some(source), ..FuzzerConfig::default() };
+        assert!(Session::start(login, &scan).await.is_ok());
+        assert_eq!(peers.recv().await, Some(source));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::secrets::Severity;
+use crate::Finding;
+use regex::Regex;
+use reqwest::Url;
+
+/// Built-in scoring, in the same `field|regex|points` format as
+/// `--severity-rules`. Every rule that matches adds its points once.
+const BUILTIN_RULES: &str = r"
+status|^2|20
+status|^5|15
+status|^40[13]$|10
+status|^3|5
+path|(?i)/\.(git|svn|hg|env|htpasswd|htaccess|ds_store|aws|ssh|npmrc|dockercfg)(/|$)|50
+path|(?i)\.(bak|backup|old|orig|save|swp|sql|dump|zip|tar|gz|tgz|7z|rar|key|pem|p12|pfx|kdbx)$|40
+path|(?i)(^|/)(backups?|dumps?|db|database)(/|$)|30
+path|(?i)(^|/)(admin|administrator|manager|config|configuration|debug|console|phpinfo|actuator|server-status|internal|private)(/|\.|$)|25
+path|(?i)(^|/)(api|graphql|swagger|openapi|login|signin|auth)(/|\.|$)|10
+content-type|^application/(zip|gzip|x-gzip|x-tar|x-7z-compressed|x-rar-compressed|sql|x-sql|octet-stream)|30
+content-type|^application/json|5
+tag|^exposed:|50
+tag|^bucket:.*:public$|50
+tag|^bucket:|20
+tag|^reflected$|15
+tag|^cors:.*-credentials$|40
+tag|^cors:(reflected|null)$|15
+tag|^open-redirect:|30
+tag|^method:(PUT|DELETE|PATCH|TRACE)$|20
+tag|^basic-auth:|60
+tag|^(status|size)-changed$|10
+";
+
+/// Scores where each severity starts
+const THRESHOLDS: &[(i64, Severity)] = &[
+    (80, Severity::Critical),
+    (55, Severity::High),
+    (35, Severity::Medium),
+    (20, Severity::Low),
+];
+
+/// Responses that differ between identities point at access control problems
+const DIVERGENT_VARIANTS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Status,
+    /// The URL's path, without query string
+    Path,
+    Url,
+    ContentType,
+    /// Any one of the finding's tags
+    Tag,
+}
+
+struct Rule {
+    field: Field,
+    regex: Regex,
+    points: i64,
+}
+
+/// Ranks findings so the likely-interesting ones come first
+pub struct Scorer {
+    rules: Vec<Rule>,
+}
+
+impl Scorer {
+    /// The built-in rules plus extra `field|regex|points` lines, where field
+    /// is `status`, `path`, `url`, `content-type` or `tag` and points may be
+    /// negative. Blank lines and `#` comments are skipped.
+    pub fn new(extra_rules: &[String]) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for line in BUILTIN_RULES.lines().map(str::to_string).chain(extra_rules.iter().cloned()) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // The regex may contain `|` itself
+            let invalid = || format!("Invalid severity rule (expected field|regex|points): {}", line);
+            let (field, rest) = line.split_once('|').ok_or_else(invalid)?;
+            let (pattern, points) = rest.rsplit_once('|').ok_or_else(invalid)?;
+            let field = match field.trim().to_lowercase().as_str() {
+                "status" => Field::Status,
+                "path" => Field::Path,
+                "url" => Field::Url,
+                "content-type" => Field::ContentType,
+                "tag" => Field::Tag,
+                other => return Err(format!("Unknown field '{}' in severity rule {}", other, line)),
+            };
+            let points = points.trim().parse().map_err(|_| format!("Invalid points '{}' in severity rule {}", points.trim(), line))?;
+            let regex = Regex::new(pattern).map_err(|e| format!("Invalid regex in severity rule {}: {}", line, e))?;
+            rules.push(Rule { field, regex, points });
+        }
+        Ok(Scorer { rules })
+    }
+
+    /// Score and severity of a finding, from its status, URL, content type,
+    /// tags, secrets and diverging variants
+    pub fn score(&self, finding: &Finding) -> (u32, Severity) {
+        let status = finding.status.to_string();
+        let path = Url::parse(&finding.url).map(|url| url.path().to_string()).unwrap_or_default();
+        let content_type = finding.content_type.as_deref().unwrap_or_default();
+
+        let mut score: i64 = self.rules.iter()
+            .filter(|rule| match rule.field {
+                Field::Status => rule.regex.is_match(&status),
+                Field::Path => rule.regex.is_match(&path),
+                Field::Url => rule.regex.is_match(&finding.url),
+                Field::ContentType => rule.regex.is_match(content_type),
+                Field::Tag => finding.tags.iter().any(|tag| rule.regex.is_match(tag)),
+            })
+            .map(|rule| rule.points)
+            .sum();
+        score += match finding.secrets.iter().map(|secret| secret.severity).max() {
+            Some(Severity::Critical) => 60,
+            Some(Severity::High) => 45,
+            Some(Severity::Medium) => 25,
+            Some(Severity::Low) => 5,
+            Some(Severity::Info) | None => 0,
+        };
+        if !finding.variants.is_empty() {
+            score += DIVERGENT_VARIANTS;
+        }
+
+        let score = score.max(0);
+        let severity = THRESHOLDS.iter()
+            .find(|(threshold, _)| score >= *threshold)
+            .map_or(Severity::Info, |(_, severity)| *severity);
+        (score as u32, severity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn score(scorer: &Scorer, finding: serde_json::Value) -> (u32, Severity) {
+        scorer.score(&serde_json::from_value(finding).unwrap())
+    }
+
+    #[test]
+    fn ranks_by_status_path_type_and_tags() {
+        let scorer = Scorer::new(&[]).unwrap();
+        assert_eq!(score(&scorer, json!({ "url": "http://t/images", "status": 301 })).1, Severity::Info);
+        assert_eq!(score(&scorer, json!({ "url": "http://t/index.html", "status": 200 })).1, Severity::Low);
+        assert_eq!(score(&scorer, json!({ "url": "http://t/admin/", "status": 403 })).1, Severity::Medium);
+        assert_eq!(
+            score(&scorer, json!({ "url": "http://t/backup/site.zip", "status": 200, "content_type": "application/zip" })),
+            (120, Severity::Critical)
+        );
+        assert_eq!(
+            score(&scorer, json!({ "url": "http://t/.git/HEAD", "status": 200, "tags": ["exposed:git"] })).1,
+            Severity::Critical
+        );
+    }
+
+    #[test]
+    fn counts_secrets_and_diverging_identities() {
+        let scorer = Scorer::new(&[]).unwrap();
+        let secret = json!({ "url": "http://t/app.js", "status": 200,
+            "secrets": [{ "rule": "aws-access-key", "severity": "critical", "excerpt": "AKIA…" }] });
+        assert_eq!(score(&scorer, secret), (80, Severity::Critical));
+        let variants = json!({ "url": "http://t/account", "status": 200,
+            "variants": [{ "label": "guest", "status": 200, "size": 10 }] });
+        assert_eq!(score(&scorer, variants).1, Severity::Medium);
+    }
+
+    #[test]
+    fn applies_user_rules() {
+        let scorer = Scorer::new(&[
+            "# our app".to_string(),
+            "path|(?i)^/internal-(tools|ops)/|40".to_string(),
+            "url|[?&]debug=1|-100".to_string(),
+        ]).unwrap();
+        assert_eq!(score(&scorer, json!({ "url": "http://t/internal-ops/x", "status": 200 })).1, Severity::High);
+        assert_eq!(score(&scorer, json!({ "url": "http://t/admin?debug=1", "status": 200 })), (0, Severity::Info));
+        assert!(Scorer::new(&["path|admin".to_string()]).is_err());
+        assert!(Scorer::new(&["body|x|10".to_string()]).is_err());
+        assert!(Scorer::new(&["path|(|10".to_string()]).is_err());
+        assert!(Scorer::new(&["path|x|ten".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::error::CrawlerError;
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+
+/// Detached signature written next to a report as `<report>.sig`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DetachedSignature {
+    pub algorithm: String,
+    pub report_sha256: String,
+    pub public_key: String,
+    pub signature: String,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, CrawlerError> {
+    if !hex.is_ascii() {
+        return Err("Non-ASCII character in hex string".into());
+    }
+    if !hex.len().is_multiple_of(2) {
+        return Err("Odd-length hex string".into());
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).map_err(|_| "Non-ASCII character in hex string")?;
+            u8::from_str_radix(pair, 16).map_err(|e| CrawlerError::Config(format!("Invalid hex string: {}", e)))
+        })
+        .collect()
+}
+
+/// SHA-256 of some bytes as lowercase hex
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// SHA-256 of a file's contents as lowercase hex
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    Ok(sha256_hex(&fs::read(path)?))
+}
+
+/// Path of the detached signature for a report
+pub fn signature_path(report: &Path) -> PathBuf {
+    let mut name = report.as_os_str().to_owned();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+/// Sign the report file as written to disk with an Ed25519 PKCS#8 PEM key
+/// (e.g. `openssl genpkey -algorithm ed25519 -out key.pem`)
+pub fn sign_report(report: &Path, key_pem: &Path) -> Result<PathBuf, CrawlerError> {
+    let pem = fs::read_to_string(key_pem)
+        .map_err(|e| format!("Cannot read signing key {}: {}", key_pem.display(), e))?;
+    let key = SigningKey::from_pkcs8_pem(&pem)
+        .map_err(|e| format!("Invalid Ed25519 signing key {}: {}", key_pem.display(), e))?;
+
+    let contents = fs::read(report)?;
+    let signature = DetachedSignature {
+        algorithm: "ed25519".to_string(),
+        report_sha256: to_hex(&Sha256::digest(&contents)),
+        public_key: to_hex(key.verifying_key().as_bytes()),
+        signature: to_hex(&key.sign(&contents).to_bytes()),
+    };
+
+    let path = signature_path(report);
+    fs::write(&path, serde_json::to_string_pretty(&signature)?)?;
+    Ok(path)
+}
+
+/// Fingerprint to compare signer keys by: SHA-256 of the raw public key
+pub fn fingerprint(public_key_hex: &str) -> String {
+    match from_hex(public_key_hex) {
+        Ok(bytes) => format!("SHA256:{}", sha256_hex(&bytes)),
+        Err(_) => "invalid key".to_string(),
+    }
+}
+
+/// Load the public key a report must be signed with: an Ed25519 SPKI PEM
+/// (`openssl pkey -pubout`) or 64 hex characters
+pub fn load_public_key(path: &Path) -> Result<VerifyingKey, CrawlerError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read public key {}: {}", path.display(), e))?;
+    if let Ok(key) = VerifyingKey::from_public_key_pem(&text) {
+        return Ok(key);
+    }
+    let bytes: [u8; 32] = from_hex(text.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("{} is not an Ed25519 public key (PEM or 64 hex characters)", path.display()))?;
+    VerifyingKey::from_bytes(&bytes)
+        .map_err(|e| format!("{} is not a valid Ed25519 public key: {}", path.display(), e).into())
+}
+
+/// Verify a report against its detached signature, returning the signer's
+/// public key. With `trusted`, the signature must also come from that key;
+/// without it anyone could re-sign a modified report with a key of their own.
+pub fn verify_report(report: &Path, trusted: Option<&VerifyingKey>) -> Result<String, CrawlerError> {
+    let sig_path = signature_path(report);
+    let detached: DetachedSignature = serde_json::from_str(
+        &fs::read_to_string(&sig_path)
+            .map_err(|e| format!("Cannot read signature {}: {}", sig_path.display(), e))?,
+    )?;
+
+    let contents = fs::read(report)?;
+    if to_hex(&Sha256::digest(&contents)) != detached.report_sha256 {
+        return Err("Report hash does not match the signature file".into());
+    }
+
+    let key_bytes: [u8; 32] = from_hex(&detached.public_key)?
+        .try_into()
+        .map_err(|_| "Malformed public key in signature file")?;
+    let sig_bytes: [u8; 64] = from_hex(&detached.signature)?
+        .try_into()
+        .map_err(|_| "Malformed signature in signature file")?;
+
+    if trusted.is_some_and(|trusted| trusted.as_bytes() != &key_bytes) {
+        return Err(format!(
+            "Report was signed by {}, not the expected key",
+            fingerprint(&detached.public_key)
+        ).into());
+    }
+
+    VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|e| format!("The signature's public key is invalid: {}", e))?
+        .verify(&contents, &Signature::from_bytes(&sig_bytes))
+        .map_err(|_| "Signature verification failed")?;
+
+    Ok(detached.public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_report(name: &str, key: &SigningKey, contents: &[u8]) -> PathBuf {
+        let report = std::env::temp_dir().join(format!("dir_crawler-test-{}-{}.json", std::process::id(), name));
+        fs::write(&report, contents).unwrap();
+        let detached = DetachedSignature {
+            algorithm: "ed25519".to_string(),
+            report_sha256: sha256_hex(contents),
+            public_key: to_hex(key.verifying_key().as_bytes()),
+            signature: to_hex(&key.sign(contents).to_bytes()),
+        };
+        fs::write(signature_path(&report), serde_json::to_string(&detached).unwrap()).unwrap();
+        report
+    }
+
+    fn cleanup(report: &Path) {
+        let _ = fs::remove_file(signature_path(report));
+        let _ = fs::remove_file(report);
+    }
+
+    #[test]
+    fn from_hex_round_trips_and_rejects_bad_input() {
+        assert_eq!(from_hex(&to_hex(&[0, 0x7f, 0xff])).unwrap(), vec![0, 0x7f, 0xff]);
+        assert_eq!(from_hex("ABcd").unwrap(), vec![0xab, 0xcd]);
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("zz").is_err());
+        // Multi-byte characters must not split mid-character and panic
+        assert!(from_hex("é1").is_err());
+        assert!(from_hex("ab€").is_err());
+    }
+
+    #[test]
+    fn verifies_untouched_reports_and_pins_the_signer() {
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+        let other = SigningKey::from_bytes(&[4u8; 32]);
+        let report = signed_report("pin", &key, b"{\"findings\":[]}");
+
+        let signer = verify_report(&report, None).unwrap();
+        assert_eq!(signer, to_hex(key.verifying_key().as_bytes()));
+        assert!(verify_report(&report, Some(&key.verifying_key())).is_ok());
+        let err = verify_report(&report, Some(&other.verifying_key())).unwrap_err();
+        assert!(err.to_string().contains(&fingerprint(&signer)));
+        cleanup(&report);
+    }
+
+    #[test]
+    fn rejects_modified_reports() {
+        let key = SigningKey::from_bytes(&[5u8; 32]);
+        let report = signed_report("modified", &key, b"{\"findings\":[]}");
+        fs::write(&report, b"{\"findings\":[1]}").unwrap();
+        assert!(verify_report(&report, None).is_err());
+        cleanup(&report);
+    }
+
+    #[test]
+    fn fingerprints_are_stable_and_flag_bad_keys() {
+        let hex = "00".repeat(32);
+        assert_eq!(fingerprint(&hex), format!("SHA256:{}", sha256_hex(&[0u8; 32])));
+        assert_eq!(fingerprint("xyz"), "invalid key");
+    }
+}
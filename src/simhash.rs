@@ -0,0 +1,122 @@
+use crate::{strip_html, Finding};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Bodies whose fingerprints differ in at most this many bits are "the same page"
+pub const MAX_DISTANCE: u32 = 3;
+
+/// 64-bit SimHash over word trigrams of a body's visible text
+pub fn simhash(body: &str) -> u64 {
+    let text = strip_html(body).to_lowercase();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut weights = [0i64; 64];
+
+    let mut add = |feature: &[&str]| {
+        let mut hasher = DefaultHasher::new();
+        feature.hash(&mut hasher);
+        let hash = hasher.finish();
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    };
+
+    if words.len() < 3 {
+        add(&words);
+    } else {
+        words.windows(3).for_each(add);
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0u64, |acc, (bit, _)| acc | (1 << bit))
+}
+
+/// Number of differing bits between two fingerprints
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Collapse findings with near-identical bodies (and the same status) into one
+/// representative carrying a count of the duplicates it stands for
+pub fn collapse(findings: Vec<Finding>) -> Vec<Finding> {
+    let mut unique: Vec<Finding> = Vec::new();
+
+    for finding in findings {
+        let Some(hash) = finding.simhash else {
+            unique.push(finding);
+            continue;
+        };
+
+        let representative = unique.iter_mut().find(|existing| {
+            existing.status == finding.status
+                && existing.simhash.is_some_and(|other| distance(hash, other) <= MAX_DISTANCE)
+        });
+
+        match representative {
+            Some(existing) => existing.duplicates += 1 + finding.duplicates,
+            None => unique.push(finding),
+        }
+    }
+
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(url: &str, status: u16, simhash: Option<u64>) -> Finding {
+        serde_json::from_value(serde_json::json!({ "url": url, "status": status, "simhash": simhash })).unwrap()
+    }
+
+    #[test]
+    fn near_identical_pages_hash_close_together() {
+        let page = |user: &str| {
+            format!(
+                "<html><body><h1>Profile</h1><p>Welcome back {}, here is your account overview with \
+                 recent orders, saved addresses, payment methods and notification settings for the \
+                 store. Contact support if anything looks wrong.</p></body></html>",
+                user
+            )
+        };
+        let a = simhash(&page("alice"));
+        let b = simhash(&page("bob"));
+        let other = simhash("<p>Index of /backup: db.sql, site.tar.gz, old configuration files and logs</p>");
+        assert_eq!(a, simhash(&page("alice")));
+        assert!(distance(a, b) < distance(a, other));
+    }
+
+    #[test]
+    fn markup_does_not_change_the_fingerprint() {
+        assert_eq!(simhash("<b>Not</b> <i>found</i> here"), simhash("not found here"));
+    }
+
+    #[test]
+    fn distance_counts_differing_bits() {
+        assert_eq!(distance(0, 0), 0);
+        assert_eq!(distance(0b1011, 0b0001), 2);
+        assert_eq!(distance(0, u64::MAX), 64);
+    }
+
+    #[test]
+    fn collapses_only_matching_status_and_close_hashes() {
+        let findings = vec![
+            finding("http://t/a", 200, Some(0b0000)),
+            finding("http://t/b", 200, Some(0b0111)),
+            finding("http://t/c", 403, Some(0b0000)),
+            finding("http://t/d", 200, Some(0b1111_0000)),
+            finding("http://t/e", 200, None),
+        ];
+        let unique = collapse(findings);
+        let urls: Vec<&str> = unique.iter().map(|f| f.url.as_str()).collect();
+        assert_eq!(urls, ["http://t/a", "http://t/c", "http://t/d", "http://t/e"]);
+        assert_eq!(unique[0].duplicates, 1);
+        assert_eq!(unique[2].duplicates, 0);
+    }
+}
@@ -0,0 +1,317 @@
+use crate::crypto::OutputCipher;
+use crate::engine::severity_color;
+use crate::error::CrawlerError;
+use crate::output::{OutputFormat, ScanReport, CSV_HEADER};
+use crate::redact::Redactor;
+use crate::{Finding, FuzzerConfig};
+use crate::term;
+use colored::*;
+use std::io::{LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+
+/// A finding as the scan records it, with what only the terminal shows
+pub struct Recorded<'a> {
+    pub finding: &'a Finding,
+    /// Masks applied to everything written
+    pub redactor: &'a Redactor,
+    /// False for a page near-identical to one already shown: it is kept,
+    /// but not printed again
+    pub first_sighting: bool,
+    /// Start of the body, shown under `-v`
+    pub snippet: Option<&'a str>,
+    /// Agent that found it, in a distributed scan
+    pub agent: Option<&'a str>,
+}
+
+/// Somewhere findings are written as the scan records them. A sink has
+/// flushed a finding by the time `finding` returns, so a crash or a kill
+/// never loses one already made.
+pub trait OutputSink: Send + Sync {
+    fn finding(&self, recorded: &Recorded) -> Result<(), CrawlerError>;
+
+    /// Write what only the finished scan knows: the final order, the
+    /// unexplored directories, why it stopped
+    fn finish(&self, _report: &ScanReport, _redactor: &Redactor) -> Result<(), CrawlerError> {
+        Ok(())
+    }
+
+    /// Take the target from `report`: the probes before the scan moved it
+    /// to another scheme, before any finding
+    fn retarget(&self, _report: &ScanReport, _redactor: &Redactor) -> Result<(), CrawlerError> {
+        Ok(())
+    }
+}
+
+/// Every sink a scan writes to
+#[derive(Default)]
+pub struct Sinks {
+    sinks: Vec<Box<dyn OutputSink>>,
+    /// A failed write is reported once, not for every finding after it
+    warned: AtomicBool,
+}
+
+impl Sinks {
+    /// The terminal unless silent, and `--output` in `--format` (stdout for
+    /// anything but json and sqlite when there's no output file)
+    pub fn for_scan(config: &FuzzerConfig, redactor: &Redactor, cipher: Option<&OutputCipher>) -> Result<Self, CrawlerError> {
+        let mut sinks: Vec<Box<dyn OutputSink>> = Vec::new();
+        if !config.silent {
+            sinks.push(Box::new(TerminalSink));
+        }
+        if config.format == OutputFormat::Sqlite {
+            if cipher.is_some() {
+                return Err("--format sqlite writes a database and cannot be combined with --encrypt-output".into());
+            }
+            let path = config.output.as_deref().ok_or("--format sqlite needs --output (or --workspace) for the database")?;
+            let targets = match config.targets.is_empty() {
+                true => vec![config.url.clone()],
+                false => config.targets.clone(),
+            };
+            let report = ScanReport::new(config, unix_now(), 0.0, &[]);
+            sinks.push(Box::new(crate::sqlite::SqliteSink::create(path, &report, &targets, redactor)?));
+        } else if config.format.is_streamed() {
+            if cipher.is_some() {
+                return Err("--format ndjson and csv stream plaintext lines and cannot be combined with --encrypt-output".into());
+            }
+            let header = (config.format == OutputFormat::Csv).then_some(CSV_HEADER);
+            sinks.push(Box::new(LineSink::create(config.format, config.output.as_deref(), header)?));
+        } else if config.output.is_some() || config.format != OutputFormat::Json {
+            sinks.push(Box::new(ReportSink::new(config, cipher.cloned())));
+        }
+        Ok(Sinks { sinks, warned: AtomicBool::new(false) })
+    }
+
+    pub fn record(&self, recorded: &Recorded) {
+        for sink in &self.sinks {
+            if let Err(e) = sink.finding(recorded) {
+                if !self.warned.swap(true, Ordering::Relaxed) {
+                    term::err!("⚠️  {}", format!("Cannot write findings: {}", e).yellow());
+                }
+            }
+        }
+    }
+
+    pub fn finish(&self, report: &ScanReport, redactor: &Redactor) -> Result<(), CrawlerError> {
+        for sink in &self.sinks {
+            sink.finish(report, redactor)?;
+        }
+        Ok(())
+    }
+
+    pub fn retarget(&self, report: &ScanReport, redactor: &Redactor) -> Result<(), CrawlerError> {
+        for sink in &self.sinks {
+            sink.retarget(report, redactor)?;
+        }
+        Ok(())
+    }
+}
+
+/// Status lines as findings come in
+struct TerminalSink;
+
+impl OutputSink for TerminalSink {
+    fn finding(&self, recorded: &Recorded) -> Result<(), CrawlerError> {
+        if !recorded.first_sighting {
+            return Ok(());
+        }
+        let (finding, redactor) = (recorded.finding, recorded.redactor);
+        let status_str = format!("{}", finding.status);
+        let output = match finding.status {
+            200..=299 => status_str.green(),
+            300..=399 => status_str.yellow(),
+            400..=599 => status_str.red(),
+            _ => status_str.white(),
+        };
+
+        let labels: Vec<&str> = finding.tags.iter().map(String::as_str).chain(recorded.agent).collect();
+        let tag_str = if labels.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", labels.join(", ")).magenta().to_string()
+        };
+
+        term::out!(
+            "🌐 Status: {} | URL: {} 📁{} ({})",
+            output,
+            redactor.redact(&finding.label()),
+            tag_str,
+            severity_color(finding.severity)
+        );
+
+        if !finding.variants.is_empty() {
+            let summary: Vec<String> = finding.variants.iter()
+                .map(|v| match &v.redirected_to {
+                    Some(target) => format!("{}: {} ({} B) → {}", v.label, v.status, v.size, target),
+                    None => format!("{}: {} ({} B)", v.label, v.status, v.size),
+                })
+                .collect();
+            term::out!("   ↔️  {}", summary.join(" | ").cyan());
+        }
+
+        if !finding.methods.is_empty() {
+            let summary: Vec<String> = finding.methods.iter().map(ToString::to_string).collect();
+            term::out!("   🔀 {}", summary.join(" | ").cyan());
+        }
+
+        if let Some(credential) = &finding.credentials {
+            term::out!("   🔓 Basic auth accepted {}", redactor.redact(&credential.to_string()).red());
+        }
+
+        if let Some(title) = &finding.title {
+            term::out!("   📄 {}", redactor.redact(title).dimmed());
+        }
+        for secret in &finding.secrets {
+            term::out!("   🔑 [{}] {}: {}", severity_color(secret.severity), secret.rule, secret.excerpt);
+        }
+        if let Some(snippet) = recorded.snippet.filter(|snippet| !snippet.is_empty()) {
+            term::out!("   💬 {}", redactor.redact(snippet).dimmed());
+        }
+        Ok(())
+    }
+}
+
+/// `--format ndjson` or `csv`: a line per finding, appended and flushed as
+/// each is recorded, into `--output` or to stdout
+struct LineSink {
+    format: OutputFormat,
+    writer: Mutex<LineWriter<Box<dyn Write + Send>>>,
+}
+
+impl LineSink {
+    /// Stream into `path` (truncating it), or to stdout when no path is
+    /// given, starting with `header`
+    fn create(format: OutputFormat, path: Option<&Path>, header: Option<&str>) -> std::io::Result<Self> {
+        let sink: Box<dyn Write + Send> = match path {
+            Some(path) => Box::new(std::fs::File::create(path)?),
+            None => Box::new(std::io::stdout()),
+        };
+        let mut writer = LineWriter::new(sink);
+        if let Some(header) = header {
+            writer.write_all(header.as_bytes())?;
+        }
+        Ok(LineSink { format, writer: Mutex::new(writer) })
+    }
+}
+
+impl OutputSink for LineSink {
+    fn finding(&self, recorded: &Recorded) -> Result<(), CrawlerError> {
+        let line = match self.format {
+            OutputFormat::Csv => crate::output::csv_row(recorded.finding),
+            _ => serde_json::to_string(recorded.finding)? + "\n",
+        };
+        let mut writer = self.writer.lock().map_err(|_| "output writer poisoned")?;
+        writer.write_all(crate::term::strip_ansi(&recorded.redactor.redact(&line)).as_bytes())?;
+        Ok(())
+    }
+}
+
+/// The formats rendered from a whole report (json, md, curl, urls, sarif).
+/// With `--output`, the file is rewritten with the findings so far after
+/// each one, marked unfinished until the scan ends; without, the report is
+/// printed once the scan is over.
+struct ReportSink {
+    config: FuzzerConfig,
+    path: Option<PathBuf>,
+    cipher: Option<OutputCipher>,
+    started: Instant,
+    /// The findings so far, and everything else the report says about the scan
+    report: Mutex<ScanReport>,
+}
+
+/// Why a report rewritten mid-scan looks short
+const UNFINISHED: &str = "unfinished: the scan was still running when this was written";
+
+impl ReportSink {
+    fn new(config: &FuzzerConfig, cipher: Option<OutputCipher>) -> Self {
+        let mut report = ScanReport::new(config, unix_now(), 0.0, &[]);
+        report.stopped = Some(UNFINISHED.to_string());
+        ReportSink {
+            config: config.clone(),
+            path: config.output.clone(),
+            cipher,
+            started: Instant::now(),
+            report: Mutex::new(report),
+        }
+    }
+}
+
+impl OutputSink for ReportSink {
+    fn finding(&self, recorded: &Recorded) -> Result<(), CrawlerError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut report = self.report.lock().map_err(|_| "report writer poisoned")?;
+        report.findings.push(recorded.finding.clone());
+        report.duration_secs = self.started.elapsed().as_secs_f64();
+        // Renamed into place, so the file is never seen half written
+        let partial = path.with_file_name(format!(
+            ".{}.partial",
+            path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default()
+        ));
+        report.write(&partial, &self.config, recorded.redactor, self.cipher.as_ref())?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    fn retarget(&self, report: &ScanReport, _redactor: &Redactor) -> Result<(), CrawlerError> {
+        let mut partial = self.report.lock().map_err(|_| "report writer poisoned")?;
+        partial.target = report.target.clone();
+        partial.detected_scheme = report.detected_scheme.clone();
+        Ok(())
+    }
+
+    fn finish(&self, report: &ScanReport, redactor: &Redactor) -> Result<(), CrawlerError> {
+        match &self.path {
+            Some(path) => report.write(path, &self.config, redactor, self.cipher.as_ref()),
+            None => {
+                let text = report.render(&self.config);
+                let text = redactor.redact(&text);
+                let text = crate::term::strip_ansi(&text);
+                // The report as data, untouched by the terminal's settings
+                match self.config.format {
+                    // Nothing but the URLs, for the next tool in the pipe
+                    OutputFormat::Urls => print!("{}", text),
+                    _ => println!("\n{}", text),
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_file_is_complete_after_every_finding() {
+        let dir = std::env::temp_dir().join(format!("dir_crawler-sink-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = FuzzerConfig { output: Some(dir.join("results.json")), silent: true, ..Default::default() };
+        let sinks = Sinks::for_scan(&config, &Redactor::default(), None).unwrap();
+        let redactor = Redactor::default();
+        for url in ["http://t/admin", "http://t/.git/HEAD"] {
+            let finding: Finding = serde_json::from_value(serde_json::json!({ "url": url, "status": 200 })).unwrap();
+            sinks.record(&Recorded { finding: &finding, redactor: &redactor, first_sighting: true, snippet: None, agent: None });
+        }
+
+        // What a kill at this point would leave behind
+        let partial: ScanReport = serde_json::from_slice(&std::fs::read(dir.join("results.json")).unwrap()).unwrap();
+        assert_eq!(partial.findings.len(), 2);
+        assert_eq!(partial.stopped.as_deref(), Some(UNFINISHED));
+
+        sinks.finish(&ScanReport::new(&config, 0, 1.0, &partial.findings), &redactor).unwrap();
+        let done: ScanReport = serde_json::from_slice(&std::fs::read(dir.join("results.json")).unwrap()).unwrap();
+        assert_eq!(done.stopped, None);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+use crate::error::CrawlerError;
+use crate::{build_client, FuzzerConfig};
+use reqwest::header::{HeaderMap, WWW_AUTHENTICATE};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Default pause between two attempts against the same realm
+pub const DEFAULT_DELAY: &str = "1s";
+
+/// One `user:password` line of a `--spray-basic-auth` file
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Credential {
+    pub user: String,
+    pub password: String,
+}
+
+impl std::fmt::Display for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.user, self.password)
+    }
+}
+
+/// `user:password` lines; blank lines and `#` comments are skipped, and the
+/// password may itself contain colons
+pub fn load(path: &Path) -> Result<Vec<Credential>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let credentials = parse(&text).map_err(|line| format!("{}:{}: expected user:password", path.display(), line))?;
+    if credentials.is_empty() {
+        return Err(format!("No credentials in {}", path.display()));
+    }
+    Ok(credentials)
+}
+
+fn parse(text: &str) -> Result<Vec<Credential>, usize> {
+    let mut credentials = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let (user, password) = line.split_once(':').ok_or(number + 1)?;
+        credentials.push(Credential { user: user.to_string(), password: password.to_string() });
+    }
+    Ok(credentials)
+}
+
+/// The realm of a `WWW-Authenticate: Basic` challenge, empty when it names none
+pub fn basic_realm(headers: &HeaderMap) -> Option<String> {
+    headers.get_all(WWW_AUTHENTICATE).iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|challenge| {
+            let (scheme, params) = challenge.trim().split_once(' ').unwrap_or((challenge.trim(), ""));
+            if !scheme.eq_ignore_ascii_case("basic") {
+                return None;
+            }
+            let realm = params.split(',')
+                .filter_map(|param| param.trim().split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("realm"))
+                .map(|(_, value)| value.trim().trim_matches('"').to_string());
+            Some(realm.unwrap_or_default())
+        })
+}
+
+/// How spraying one realm ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The credential at this index was accepted
+    Accepted(usize),
+    /// Every credential was refused
+    Exhausted,
+    /// The server answered something other than a refusal, which may be a
+    /// lockout starting, so the realm is left alone
+    Stopped,
+}
+
+/// Result of one attempt
+pub enum Attempt {
+    Accepted,
+    Refused,
+    /// Anything else: rate limited, locked, erroring
+    Halt(String),
+}
+
+/// What an attempt's status says: 401 is a plain refusal, success or a
+/// redirect means the credential got in, and anything else stops the realm
+pub fn classify(status: u16) -> Attempt {
+    match status {
+        401 => Attempt::Refused,
+        200..=399 => Attempt::Accepted,
+        status => Attempt::Halt(format!("answered {}", status)),
+    }
+}
+
+/// `--spray-basic-auth`: the credentials, one client each with its
+/// `Authorization` header, and how each realm went. Endpoints of the same
+/// host and realm share one account store, so each realm is sprayed once,
+/// one attempt at a time, and later endpoints only retry what got in.
+pub struct Sprayer {
+    pub credentials: Vec<(Credential, Client)>,
+    pub delay: std::time::Duration,
+    realms: std::sync::Mutex<HashMap<String, Arc<Mutex<Option<Outcome>>>>>,
+}
+
+impl Sprayer {
+    pub fn new(config: &FuzzerConfig) -> Result<Self, CrawlerError> {
+        let mut credentials = Vec::new();
+        for credential in &config.spray_credentials {
+            let mut headers = config.headers.clone();
+            headers.push(("Authorization".to_string(), basic_header(credential)));
+            let client = build_client(&FuzzerConfig { headers, ..config.clone() })?;
+            credentials.push((credential.clone(), client));
+        }
+        Ok(Sprayer {
+            credentials,
+            delay: std::time::Duration::from_secs(config.spray_delay),
+            realms: Default::default(),
+        })
+    }
+
+    /// The realm's outcome slot, locked while it is being sprayed
+    pub fn realm(&self, host: &str, realm: &str) -> Arc<Mutex<Option<Outcome>>> {
+        let key = format!("{} {}", host, realm);
+        Arc::clone(self.realms.lock().unwrap().entry(key).or_default())
+    }
+}
+
+fn basic_header(credential: &Credential) -> String {
+    use base64::Engine;
+    format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(credential.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn parses_credentials_and_challenges() {
+        let credentials = parse("# vendor defaults\nadmin:admin\n\ntomcat:s3cret:with:colons\r\n").unwrap();
+        assert_eq!(credentials.len(), 2);
+        assert_eq!(credentials[1].password, "s3cret:with:colons");
+        assert_eq!(parse("admin:admin\nroot\n"), Err(2));
+        assert_eq!(basic_header(&credentials[0]), "Basic YWRtaW46YWRtaW4=");
+
+        let mut headers = HeaderMap::new();
+        headers.append(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer realm=\"api\""));
+        assert_eq!(basic_realm(&headers), None);
+        headers.append(WWW_AUTHENTICATE, HeaderValue::from_static("basic realm=\"Tomcat Manager\", charset=\"UTF-8\""));
+        assert_eq!(basic_realm(&headers).as_deref(), Some("Tomcat Manager"));
+
+        assert!(matches!(classify(401), Attempt::Refused));
+        assert!(matches!(classify(302), Attempt::Accepted));
+        assert!(matches!(classify(429), Attempt::Halt(_)));
+    }
+}
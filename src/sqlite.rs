@@ -0,0 +1,296 @@
+use crate::error::CrawlerError;
+use crate::output::ScanReport;
+use crate::redact::Redactor;
+use crate::sink::{OutputSink, Recorded};
+use crate::Finding;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::path::Path;
+use std::sync::Mutex;
+
+
+/// Tables of `--format sqlite`. A database collects every scan written to
+/// it, so findings can be compared across scans with plain SQL.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS scans (
+    id INTEGER PRIMARY KEY,
+    scan_id TEXT NOT NULL UNIQUE,
+    target TEXT NOT NULL,
+    wordlist TEXT NOT NULL,
+    method TEXT NOT NULL,
+    extensions TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    duration_secs REAL NOT NULL DEFAULT 0,
+    -- 0 while the scan runs, and for good if it was killed
+    finished INTEGER NOT NULL DEFAULT 0,
+    stopped TEXT
+);
+CREATE TABLE IF NOT EXISTS targets (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS scan_targets (
+    scan INTEGER NOT NULL REFERENCES scans(id) ON DELETE CASCADE,
+    target INTEGER NOT NULL REFERENCES targets(id),
+    PRIMARY KEY (scan, target)
+);
+CREATE TABLE IF NOT EXISTS findings (
+    id INTEGER PRIMARY KEY,
+    scan INTEGER NOT NULL REFERENCES scans(id) ON DELETE CASCADE,
+    target INTEGER REFERENCES targets(id),
+    url TEXT NOT NULL,
+    payload TEXT,
+    status INTEGER NOT NULL,
+    size INTEGER,
+    severity TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    content_type TEXT,
+    title TEXT,
+    -- The whole finding as JSON, as in a results file
+    finding TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS finding_tags (
+    finding INTEGER NOT NULL REFERENCES findings(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (finding, tag)
+);
+CREATE INDEX IF NOT EXISTS findings_by_scan ON findings(scan);
+CREATE INDEX IF NOT EXISTS findings_by_target ON findings(target);
+CREATE INDEX IF NOT EXISTS findings_by_url ON findings(url);
+CREATE INDEX IF NOT EXISTS findings_by_status ON findings(status);
+CREATE INDEX IF NOT EXISTS findings_by_severity ON findings(severity);
+CREATE INDEX IF NOT EXISTS finding_tags_by_tag ON finding_tags(tag);
+";
+
+/// Open (creating if needed) a results database
+fn open(path: &Path) -> Result<Connection, CrawlerError> {
+    let conn = Connection::open(path).map_err(|e| format!("Cannot open database {}: {}", path.display(), e))?;
+    // Each commit survives the process being killed without waiting on an
+    // fsync per finding
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.pragma_update(None, "foreign_keys", true)?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+/// A scan's row in an open database, and its targets' rows
+struct Scan {
+    id: i64,
+    /// (base URL, row), longest first so a finding goes to the most specific
+    targets: Vec<(String, i64)>,
+}
+
+impl Scan {
+    /// Add `report`'s scan, or take over its row when it's already there
+    fn register(tx: &Transaction, report: &ScanReport, targets: &[String], redactor: &Redactor) -> Result<Self, CrawlerError> {
+        tx.execute(
+            "INSERT INTO scans (scan_id, target, wordlist, method, extensions, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(scan_id) DO UPDATE SET target = excluded.target, wordlist = excluded.wordlist,
+                 method = excluded.method, extensions = excluded.extensions, started_at = excluded.started_at",
+            params![
+                report.scan_id,
+                redactor.redact(&report.target),
+                redactor.redact(&report.wordlist),
+                report.method,
+                report.extensions.join(","),
+                report.started_at as i64,
+            ],
+        )?;
+        let id: i64 = tx.query_row("SELECT id FROM scans WHERE scan_id = ?1", [&report.scan_id], |row| row.get(0))?;
+
+        let mut rows = Vec::new();
+        for url in targets {
+            let url = redactor.redact(url).into_owned();
+            tx.execute("INSERT OR IGNORE INTO targets (url) VALUES (?1)", [&url])?;
+            let target: i64 = tx.query_row("SELECT id FROM targets WHERE url = ?1", [&url], |row| row.get(0))?;
+            tx.execute("INSERT OR IGNORE INTO scan_targets (scan, target) VALUES (?1, ?2)", [id, target])?;
+            rows.push((url, target));
+        }
+        rows.sort_by_key(|(url, _)| std::cmp::Reverse(url.len()));
+        Ok(Scan { id, targets: rows })
+    }
+
+    fn insert(&self, tx: &Transaction, finding: &Finding, redactor: &Redactor) -> Result<(), CrawlerError> {
+        // Redacted as a whole, like every other results file
+        let json = redactor.redact(&serde_json::to_string(finding)?).into_owned();
+        let finding: Finding = serde_json::from_str(&json)?;
+        let target = self.targets.iter().find(|(url, _)| finding.url.starts_with(url.as_str())).map(|(_, id)| *id);
+        tx.execute(
+            "INSERT INTO findings (scan, target, url, payload, status, size, severity, score, content_type, title, finding)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                self.id,
+                target,
+                finding.url,
+                finding.payload,
+                finding.status,
+                finding.size.map(|size| size as i64),
+                finding.severity.to_string(),
+                finding.score,
+                finding.content_type,
+                finding.title,
+                json,
+            ],
+        )?;
+        let row = tx.last_insert_rowid();
+        for tag in &finding.tags {
+            tx.execute("INSERT OR IGNORE INTO finding_tags (finding, tag) VALUES (?1, ?2)", params![row, tag])?;
+        }
+        Ok(())
+    }
+
+    /// Swap the findings recorded so far for the finished scan's and mark it done
+    fn complete(&self, tx: &Transaction, report: &ScanReport, redactor: &Redactor) -> Result<(), CrawlerError> {
+        tx.execute("DELETE FROM findings WHERE scan = ?1", [self.id])?;
+        for finding in &report.findings {
+            self.insert(tx, finding, redactor)?;
+        }
+        tx.execute(
+            "UPDATE scans SET duration_secs = ?1, finished = 1, stopped = ?2 WHERE id = ?3",
+            params![report.duration_secs, report.stopped.as_deref().map(|reason| redactor.redact(reason).into_owned()), self.id],
+        )?;
+        Ok(())
+    }
+}
+
+/// The base URLs a report's findings were made under
+fn report_targets(report: &ScanReport) -> Vec<String> {
+    match report.services.is_empty() {
+        true => vec![report.target.clone()],
+        false => report.services.iter().map(|service| service.url.clone()).collect(),
+    }
+}
+
+/// `--format sqlite`: each finding is committed to the database the moment
+/// it's recorded; at the end the finished scan's findings (reconfirmed,
+/// collapsed, screenshotted) take their place
+pub struct SqliteSink {
+    db: Mutex<(Connection, Scan)>,
+}
+
+impl SqliteSink {
+    /// Open `path` and add a row for the scan `report` describes, under `targets`
+    pub fn create(path: &Path, report: &ScanReport, targets: &[String], redactor: &Redactor) -> Result<Self, CrawlerError> {
+        let mut conn = open(path)?;
+        let tx = conn.transaction()?;
+        let scan = Scan::register(&tx, report, targets, redactor)?;
+        tx.execute("DELETE FROM findings WHERE scan = ?1", [scan.id])?;
+        tx.commit()?;
+        Ok(SqliteSink { db: Mutex::new((conn, scan)) })
+    }
+}
+
+impl OutputSink for SqliteSink {
+    fn finding(&self, recorded: &Recorded) -> Result<(), CrawlerError> {
+        let mut db = self.db.lock().map_err(|_| "database writer poisoned")?;
+        let (conn, scan) = &mut *db;
+        let tx = conn.transaction()?;
+        scan.insert(&tx, recorded.finding, recorded.redactor)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn finish(&self, report: &ScanReport, redactor: &Redactor) -> Result<(), CrawlerError> {
+        let mut db = self.db.lock().map_err(|_| "database writer poisoned")?;
+        let (conn, scan) = &mut *db;
+        let tx = conn.transaction()?;
+        scan.complete(&tx, report, redactor)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn retarget(&self, report: &ScanReport, redactor: &Redactor) -> Result<(), CrawlerError> {
+        let mut db = self.db.lock().map_err(|_| "database writer poisoned")?;
+        let (conn, scan) = &mut *db;
+        let tx = conn.transaction()?;
+        // Findings are filed under the target they were made beneath
+        tx.execute("DELETE FROM scan_targets WHERE scan = ?1", [scan.id])?;
+        for (_, target) in &scan.targets {
+            tx.execute(
+                "DELETE FROM targets WHERE id = ?1
+                 AND NOT EXISTS (SELECT 1 FROM scan_targets WHERE target = ?1)
+                 AND NOT EXISTS (SELECT 1 FROM findings WHERE target = ?1)",
+                [target],
+            )?;
+        }
+        *scan = Scan::register(&tx, report, &report_targets(report), redactor)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// Add a finished results file to the database at `path`
+pub fn store(path: &Path, report: &ScanReport, redactor: &Redactor) -> Result<(), CrawlerError> {
+    let mut conn = open(path)?;
+    let tx = conn.transaction()?;
+    let scan = Scan::register(&tx, report, &report_targets(report), redactor)?;
+    scan.complete(&tx, report, redactor)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// The findings stored for scan `scan_id`, in the order they were recorded,
+/// or `None` when the database doesn't hold that scan
+pub fn findings(path: &Path, scan_id: &str) -> Result<Option<Vec<Finding>>, CrawlerError> {
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let Some(scan): Option<i64> = conn
+        .query_row("SELECT id FROM scans WHERE scan_id = ?1", [scan_id], |row| row.get(0))
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    let mut statement = conn.prepare("SELECT finding FROM findings WHERE scan = ?1 ORDER BY id")?;
+    let rows = statement.query_map([scan], |row| row.get::<_, String>(0))?;
+    let mut findings = Vec::new();
+    for json in rows {
+        findings.push(serde_json::from_str(&json?)?);
+    }
+    Ok(Some(findings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FuzzerConfig;
+
+    fn finding(url: &str, status: u16, tags: &[&str]) -> Finding {
+        serde_json::from_value(serde_json::json!({ "url": url, "status": status, "tags": tags })).unwrap()
+    }
+
+    #[test]
+    fn findings_are_committed_as_recorded_and_replaced_when_done() {
+        let path = std::env::temp_dir().join(format!("dir_crawler-{}.db", std::process::id()));
+        let config = FuzzerConfig { url: "http://t/".to_string(), scan_id: "scan-1".to_string(), ..Default::default() };
+        let mut report = ScanReport::new(&config, 0, 0.0, &[]);
+        let redactor = Redactor::default();
+        let sink = SqliteSink::create(&path, &report, &["http://t/".to_string()], &redactor).unwrap();
+        let admin = finding("http://t/admin", 301, &["admin"]);
+        sink.finding(&Recorded { finding: &admin, redactor: &redactor, first_sighting: true, snippet: None, agent: None }).unwrap();
+
+        // Readable from another connection while the scan still runs
+        assert_eq!(findings(&path, "scan-1").unwrap().unwrap(), std::slice::from_ref(&admin));
+        assert_eq!(findings(&path, "scan-2").unwrap(), None);
+
+        report.findings = vec![admin, finding("http://t/.git/HEAD", 200, &["git", "vcs"])];
+        sink.finish(&report, &redactor).unwrap();
+        drop(sink);
+        assert_eq!(findings(&path, "scan-1").unwrap().unwrap(), report.findings);
+
+        let conn = Connection::open(&path).unwrap();
+        let (finished, tagged): (i64, i64) = conn
+            .query_row(
+                "SELECT finished, (SELECT COUNT(*) FROM finding_tags JOIN findings ON findings.id = finding_tags.finding
+                    JOIN targets ON targets.id = findings.target WHERE targets.url = 'http://t/')
+                 FROM scans WHERE scan_id = 'scan-1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((finished, tagged), (1, 3));
+        drop(conn);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::FuzzerConfig;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Concurrency and connections `--stealth` caps a scan at: a few requests in
+/// flight over a couple of kept-alive connections, like one browser
+const STEALTH_THREADS: usize = 3;
+const STEALTH_CONNECTIONS_PER_HOST: usize = 2;
+
+/// Shortest idle time that keeps connections open across the jittered pauses
+const STEALTH_POOL_IDLE: u64 = 90;
+
+/// Pause before every request, in milliseconds
+const JITTER_MS: (u64, u64) = (300, 1500);
+
+/// Current desktop browsers, one picked per scan
+const USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36 Edg/124.0.0.0",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:125.0) Gecko/20100101 Firefox/125.0",
+    "Mozilla/5.0 (X11; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_4_1) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4.1 Safari/605.1.15",
+];
+
+/// How a `--stealth` scan presents itself. It is drawn once per scan, so
+/// requests look like one consistent client rather than a crowd sharing an
+/// address, while two scans look like different clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub user_agent: String,
+    /// Send header names Title-Cased rather than lowercase
+    pub title_case: bool,
+    /// Seeds the order headers are sent in
+    pub order: u64,
+}
+
+impl Fingerprint {
+    pub fn random() -> Self {
+        Fingerprint {
+            user_agent: USER_AGENTS[below(USER_AGENTS.len() as u64) as usize].to_string(),
+            title_case: OsRng.next_u32().is_multiple_of(2),
+            order: OsRng.next_u64(),
+        }
+    }
+
+    /// Browser-like headers the scan's own `-H` headers are added to
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let defaults = [
+            ("user-agent", self.user_agent.as_str()),
+            ("accept", "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"),
+            ("accept-language", "en-US,en;q=0.9"),
+            ("connection", "keep-alive"),
+            ("upgrade-insecure-requests", "1"),
+        ];
+        for (name, value) in defaults {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.insert(HeaderName::from_static(name), value);
+            }
+        }
+        headers
+    }
+
+    /// The same headers in this fingerprint's order
+    pub fn reorder(&self, headers: HeaderMap) -> HeaderMap {
+        let mut entries: Vec<(Option<HeaderName>, HeaderValue)> = headers.into_iter().collect();
+        // Repeated values follow their name's first entry, which `None` marks
+        let mut grouped: Vec<(HeaderName, Vec<HeaderValue>)> = Vec::new();
+        for (name, value) in entries.drain(..) {
+            match name {
+                Some(name) => grouped.push((name, vec![value])),
+                None => {
+                    if let Some((_, values)) = grouped.last_mut() {
+                        values.push(value);
+                    }
+                }
+            }
+        }
+        let mut state = self.order | 1;
+        for i in (1..grouped.len()).rev() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            grouped.swap(i, (state % (i as u64 + 1)) as usize);
+        }
+        let mut reordered = HeaderMap::new();
+        for (name, values) in grouped {
+            for value in values {
+                reordered.append(name.clone(), value);
+            }
+        }
+        reordered
+    }
+}
+
+/// A random pause to wait before the next request
+pub fn jitter() -> Duration {
+    Duration::from_millis(JITTER_MS.0 + below(JITTER_MS.1 - JITTER_MS.0 + 1))
+}
+
+fn below(bound: u64) -> u64 {
+    OsRng.next_u64() % bound
+}
+
+/// Turn `--stealth` on: a fresh fingerprint, jittered requests in shuffled
+/// order, adaptive throttling, and concurrency capped over kept-alive
+/// connections. Settings that are already stricter are left alone; returns
+/// what changed.
+pub fn apply(config: &mut FuzzerConfig) -> Vec<String> {
+    let mut changes = vec!["random browser fingerprint".to_string(), format!("{}-{}ms jitter", JITTER_MS.0, JITTER_MS.1)];
+    config.stealth = Some(Fingerprint::random());
+    if config.threads > STEALTH_THREADS {
+        config.threads = STEALTH_THREADS;
+        changes.push(format!("-t {}", STEALTH_THREADS));
+    }
+    if config.max_connections_per_host.is_none_or(|max| max > STEALTH_CONNECTIONS_PER_HOST) {
+        config.max_connections_per_host = Some(STEALTH_CONNECTIONS_PER_HOST);
+        changes.push(format!("--max-connections-per-host {}", STEALTH_CONNECTIONS_PER_HOST));
+    }
+    if config.pool_idle_timeout < STEALTH_POOL_IDLE {
+        config.pool_idle_timeout = STEALTH_POOL_IDLE;
+        changes.push(format!("--pool-idle-timeout {}", STEALTH_POOL_IDLE));
+    }
+    if !config.shuffle {
+        config.shuffle = true;
+        changes.push("--shuffle".to_string());
+    }
+    if !config.adaptive_throttle {
+        config.adaptive_throttle = true;
+        changes.push("adaptive throttling".to_string());
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reorders_headers_consistently_and_only_tightens() {
+        let fingerprint = Fingerprint { user_agent: "UA".to_string(), title_case: false, order: 42 };
+        let mut headers = fingerprint.headers();
+        headers.append("x-trace", HeaderValue::from_static("a"));
+        headers.append("x-trace", HeaderValue::from_static("b"));
+        let reordered = fingerprint.reorder(headers.clone());
+        assert_eq!(reordered.len(), headers.len());
+        assert_eq!(reordered.get_all("x-trace").iter().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(reordered, fingerprint.reorder(headers.clone()));
+        let names = |map: &HeaderMap| map.keys().map(|name| name.to_string()).collect::<Vec<_>>();
+        let other = Fingerprint { order: 7, ..fingerprint.clone() };
+        assert_ne!(names(&reordered), names(&other.reorder(headers)));
+
+        let mut config = FuzzerConfig { threads: 50, ..FuzzerConfig::default() };
+        apply(&mut config);
+        assert_eq!(config.threads, STEALTH_THREADS);
+        assert!(config.shuffle && config.stealth.is_some());
+        let mut careful = FuzzerConfig { threads: 1, max_connections_per_host: Some(1), shuffle: true, ..FuzzerConfig::default() };
+        assert_eq!(apply(&mut careful).len(), 2);
+        assert_eq!(careful.threads, 1);
+
+        let pause = jitter();
+        assert!(pause >= Duration::from_millis(JITTER_MS.0) && pause <= Duration::from_millis(JITTER_MS.1));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::crypto;
+use crate::ScanState;
+use crate::error::CrawlerError;
+use reqwest::header::HeaderMap;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+
+/// Stable file stem for a URL: the first 16 hex digits of its SHA-256
+pub fn url_hash(url: &str) -> String {
+    let digest = Sha256::digest(url.as_bytes());
+    digest.iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Write a matched response's body (and optionally headers) into `dir`,
+/// returning the body file name recorded in the results.
+pub fn store_response(
+    dir: &Path,
+    url: &str,
+    status: u16,
+    headers: &HeaderMap,
+    body: &[u8],
+    include_headers: bool,
+    state: &ScanState,
+) -> Result<String, CrawlerError> {
+    let redactor = &state.redactor;
+    let cipher = state.cipher.as_ref();
+    let stem = url_hash(url);
+    let body_name = format!("{}.body", stem);
+
+    // Text bodies go through redaction; binary bodies are stored verbatim
+    match std::str::from_utf8(body) {
+        Ok(text) => crypto::write_file(&dir.join(&body_name), redactor.redact(text).as_bytes(), cipher)?,
+        Err(_) => crypto::write_file(&dir.join(&body_name), body, cipher)?,
+    }
+
+    if include_headers {
+        let mut dump = format!("{}\nHTTP {}\n", url, status);
+        for (name, value) in headers {
+            dump.push_str(&format!("{}: {}\n", name, String::from_utf8_lossy(value.as_bytes())));
+        }
+        crypto::write_file(
+            &dir.join(format!("{}.headers", stem)),
+            redactor.redact(&dump).as_bytes(),
+            cipher,
+        )?;
+    }
+
+    Ok(body_name)
+}
@@ -0,0 +1,142 @@
+use crate::error::CrawlerError;
+use crate::{dns, exit, term, wordlist};
+use colored::*;
+use std::collections::{BTreeSet, HashSet};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+
+/// Longest name DNS allows, and longest label within one
+const MAX_NAME: usize = 253;
+const MAX_LABEL: usize = 63;
+
+pub struct Options {
+    pub domain: String,
+    pub wordlist: PathBuf,
+    pub threads: usize,
+    /// `--resolver` spec; the system resolver when absent
+    pub resolver: Option<String>,
+    pub output: Option<PathBuf>,
+}
+
+/// Where names are looked up
+#[derive(Clone)]
+enum Lookup {
+    System,
+    Upstream(dns::DnsResolver),
+}
+
+impl Lookup {
+    /// Every address `name` resolves to, none when it doesn't exist
+    async fn addresses(&self, name: &str) -> BTreeSet<IpAddr> {
+        match self {
+            Lookup::System => tokio::net::lookup_host((name, 0))
+                .await
+                .map(|addrs| addrs.map(|addr| addr.ip()).collect())
+                .unwrap_or_default(),
+            Lookup::Upstream(resolver) => resolver.lookup(name).await.unwrap_or_default().into_iter().collect(),
+        }
+    }
+}
+
+/// Each entry as a name under `domain`, once, leaving out entries that
+/// can't form a hostname
+fn candidates(words: &[String], domain: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    words.iter()
+        .map(|word| word.trim_matches('.').to_lowercase())
+        .filter(|word| {
+            word.split('.').all(|label| {
+                (1..=MAX_LABEL).contains(&label.len())
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            })
+        })
+        .map(|word| format!("{}.{}", word, domain))
+        .filter(|name| name.len() <= MAX_NAME && seen.insert(name.clone()))
+        .collect()
+}
+
+/// Resolve every wordlist entry as a subdomain of `domain` and report the
+/// ones that exist. A wildcard record would make every name resolve, so
+/// names answered with only the wildcard's addresses aren't reported.
+pub async fn run(options: &Options) -> Result<(), CrawlerError> {
+    let words = wordlist::load(&options.wordlist)
+        .map_err(|source| CrawlerError::Wordlist { path: options.wordlist.clone(), source })?;
+    let names = candidates(&words, &options.domain);
+    let lookup = match &options.resolver {
+        Some(spec) => Lookup::Upstream(dns::shared(spec)?),
+        None => Lookup::System,
+    };
+
+    term::err!("🔍 Subdomain Enumeration Initiated");
+    term::err!("🌐 Domain: {}", options.domain.green());
+    term::err!("📋 Wordlist: {} ({} names)", options.wordlist.display().to_string().blue(), names.len().to_string().cyan());
+    term::err!("🧭 DNS Resolver: {}", options.resolver.as_deref().unwrap_or("system").cyan());
+    term::err!("🧵 Threads: {}", options.threads.to_string().yellow());
+
+    let probe = format!("{}.{}", crate::calibrate::random_segment(), options.domain);
+    let wildcard = lookup.addresses(&probe).await;
+    if !wildcard.is_empty() {
+        let addresses: Vec<String> = wildcard.iter().map(IpAddr::to_string).collect();
+        term::err!("🃏 Wildcard DNS: any name resolves to {}; only names with other addresses are reported", addresses.join(", ").yellow());
+    }
+    term::err!();
+
+    let wildcard = Arc::new(wildcard);
+    let permits = Arc::new(Semaphore::new(options.threads));
+    let mut tasks = JoinSet::new();
+    for name in names {
+        let (lookup, wildcard, permits) = (lookup.clone(), Arc::clone(&wildcard), Arc::clone(&permits));
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let addresses = lookup.addresses(&name).await;
+            let exists = !addresses.is_empty() && !addresses.is_subset(&wildcard);
+            exists.then(|| {
+                let listed: Vec<String> = addresses.iter().map(IpAddr::to_string).collect();
+                term::out!("{} {}", name.green(), listed.join(", ").dimmed());
+                name
+            })
+        });
+    }
+    let mut found = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        found.extend(result?);
+    }
+    found.sort();
+
+    term::err!("\n🎉 {} subdomains of {} found", found.len().to_string().green(), options.domain);
+    if let Some(path) = &options.output {
+        let mut list = found.join("\n");
+        list.push('\n');
+        std::fs::write(path, list).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        term::err!("💾 Names saved to: {}", path.display().to_string().blue());
+    }
+    exit::record(match found.is_empty() {
+        true => exit::Outcome::NothingFound,
+        false => exit::Outcome::Success,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_valid_unique_names() {
+        let words: Vec<String> = ["www", "API", "api", "dev.internal", ".mail.", "-bad", "has space", "under_score", ""]
+            .iter()
+            .map(|word| word.to_string())
+            .collect();
+        assert_eq!(
+            candidates(&words, "example.com"),
+            ["www.example.com", "api.example.com", "dev.internal.example.com", "mail.example.com", "under_score.example.com"]
+        );
+        assert!(candidates(&["a".repeat(64)], "example.com").is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+
+/// Most base URLs a range may expand into (a /16 on one port)
+pub const MAX_TARGETS: usize = 65_536;
+
+/// Ports served over TLS when a range doesn't name a scheme
+const TLS_PORTS: &[u16] = &[443, 8443];
+
+/// Connections attempted at once by the liveness check
+const ALIVE_CONCURRENCY: usize = 256;
+
+/// `[scheme://]a.b.c.d/prefix[:port,port,...]`, or a single address with a port list
+fn range_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^(?:(https?)://)?(\d{1,3}(?:\.\d{1,3}){3})(?:/(\d{1,2}))?(?::(\d{1,5}(?:,\d{1,5})*))?/?$").unwrap()
+    })
+}
+
+/// Base URLs for every host and port of a range like `10.10.0.0/24:80,443`,
+/// or `None` when the target is an ordinary URL. Ports default to 80;
+/// 443 and 8443 are scanned over HTTPS unless a scheme is given. Network
+/// and broadcast addresses are left out of ranges wider than /31.
+pub fn expand(spec: &str) -> Result<Option<Vec<String>>, String> {
+    let Some(captures) = range_pattern().captures(spec.trim()) else {
+        return Ok(None);
+    };
+    let prefix = captures.get(3).map(|p| p.as_str());
+    let ports = captures.get(4).map(|p| p.as_str());
+    // A lone address with a single port is just a URL
+    if prefix.is_none() && ports.is_none_or(|ports| !ports.contains(',')) {
+        return Ok(None);
+    }
+
+    let address: Ipv4Addr = captures[2].parse().map_err(|_| format!("Invalid address in {}", spec))?;
+    let prefix: u32 = match prefix {
+        Some(prefix) => prefix.parse().ok().filter(|p| *p <= 32).ok_or_else(|| format!("Invalid prefix length in {}", spec))?,
+        None => 32,
+    };
+    let ports: Vec<u16> = match ports {
+        Some(ports) => ports.split(',')
+            .map(|port| port.parse().ok().filter(|p| *p > 0).ok_or_else(|| format!("Invalid port {} in {}", port, spec)))
+            .collect::<Result<_, _>>()?,
+        None => vec![80],
+    };
+
+    let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+    let network = u32::from(address) & mask;
+    let size = 1u64 << (32 - prefix);
+    let hosts: Vec<Ipv4Addr> = match size > 2 {
+        true => (1..size - 1).map(|i| Ipv4Addr::from(network + i as u32)).collect(),
+        false => (0..size).map(|i| Ipv4Addr::from(network + i as u32)).collect(),
+    };
+    if hosts.len() * ports.len() > MAX_TARGETS {
+        return Err(format!(
+            "{} expands to {} targets; split it into ranges of at most {}",
+            spec,
+            hosts.len() * ports.len(),
+            MAX_TARGETS
+        ));
+    }
+
+    let scheme = captures.get(1).map(|s| s.as_str());
+    Ok(Some(
+        hosts.iter()
+            .flat_map(|host| ports.iter().map(move |port| base_url(scheme, *host, *port)))
+            .collect(),
+    ))
+}
+
+fn base_url(scheme: Option<&str>, host: Ipv4Addr, port: u16) -> String {
+    let scheme = scheme.unwrap_or(if TLS_PORTS.contains(&port) { "https" } else { "http" });
+    match (scheme, port) {
+        ("http", 80) | ("https", 443) => format!("{}://{}/", scheme, host),
+        _ => format!("{}://{}:{}/", scheme, host, port),
+    }
+}
+
+/// How aggressively one target of a `--targets` file is scanned, where it
+/// differs from the rest of the scan
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetOverride {
+    /// Base URL the settings apply beneath
+    pub url: String,
+    pub threads: Option<usize>,
+    /// Milliseconds between two requests to the target
+    pub delay_ms: Option<u64>,
+    /// Sent on top of the scan's `-H` headers
+    pub headers: Vec<(String, String)>,
+}
+
+/// Targets and their overrides from a file: one URL or address range per
+/// line, followed by any of `threads=N`, `delay=250ms` (or `2s`) and
+/// `header="Name: value"` (repeatable). Blank lines and `#` comments are
+/// skipped. Every target comes back, with an override only where a line set
+/// something.
+pub fn load(path: &Path) -> Result<(Vec<String>, Vec<TargetOverride>), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let (targets, overrides) = parse(&text).map_err(|e| format!("{}:{}", path.display(), e))?;
+    if targets.is_empty() {
+        return Err(format!("No targets in {}", path.display()));
+    }
+    Ok((targets, overrides))
+}
+
+fn parse(text: &str) -> Result<(Vec<String>, Vec<TargetOverride>), String> {
+    let mut targets = Vec::new();
+    let mut overrides = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fail = |message: String| format!("{}: {}", number + 1, message);
+        let mut words = split_words(line).into_iter();
+        let spec = words.next().unwrap_or_default();
+        let urls = match expand(&spec).map_err(fail)? {
+            Some(urls) => urls,
+            None => vec![crate::validate_url(&spec).map_err(|e| fail(e.to_string()))?],
+        };
+
+        let mut settings = TargetOverride::default();
+        for word in words {
+            let (key, value) = word.split_once('=').ok_or_else(|| fail(format!("expected key=value, got '{}'", word)))?;
+            match key {
+                "threads" => {
+                    let threads = value.parse().ok().filter(|threads| *threads > 0);
+                    settings.threads = Some(threads.ok_or_else(|| fail(format!("invalid thread count '{}'", value)))?);
+                }
+                "delay" => settings.delay_ms = Some(parse_millis(value).ok_or_else(|| fail(format!("invalid delay '{}'", value)))?),
+                "header" => {
+                    let (name, value) = value.split_once(':').ok_or_else(|| fail(format!("invalid header '{}'", value)))?;
+                    settings.headers.push((name.trim().to_string(), value.trim().to_string()));
+                }
+                _ => return Err(fail(format!("unknown setting '{}' (threads, delay or header)", key))),
+            }
+        }
+
+        let customized = settings != TargetOverride::default();
+        for url in urls {
+            if customized {
+                overrides.push(TargetOverride { url: url.clone(), ..settings.clone() });
+            }
+            targets.push(url);
+        }
+    }
+    Ok((targets, overrides))
+}
+
+/// Whitespace-separated words, where double quotes keep spaces in
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// `250ms`, `2s` or a bare number of milliseconds
+fn parse_millis(spec: &str) -> Option<u64> {
+    match spec.strip_suffix("ms") {
+        Some(ms) => ms.parse().ok(),
+        None => match spec.strip_suffix('s') {
+            Some(secs) => secs.parse::<u64>().ok()?.checked_mul(1000),
+            None => spec.parse().ok(),
+        },
+    }
+}
+
+/// The override of the target `url` belongs to, the most specific one when
+/// several base URLs contain it
+pub fn override_for<'a>(overrides: &'a [TargetOverride], url: &str) -> Option<&'a TargetOverride> {
+    overrides.iter()
+        .filter(|target| url.starts_with(target.url.trim_end_matches('/')))
+        .max_by_key(|target| target.url.len())
+}
+
+/// Request pacing that holds each overridden target to its delay
+pub fn throttle(overrides: &[TargetOverride]) -> crate::throttle::Throttle {
+    crate::throttle::Throttle::with_floors(overrides.iter().filter_map(|target| {
+        target.delay_ms.map(|ms| (crate::engine::host_key(&target.url), Duration::from_millis(ms)))
+    }))
+}
+
+/// The targets accepting TCP connections, in their original order
+pub async fn alive(targets: Vec<String>, timeout: Duration) -> Vec<String> {
+    let slots = Arc::new(Semaphore::new(ALIVE_CONCURRENCY));
+    let mut checks = tokio::task::JoinSet::new();
+    for (i, target) in targets.into_iter().enumerate() {
+        let slots = Arc::clone(&slots);
+        checks.spawn(async move {
+            let _slot = slots.acquire_owned().await;
+            let address = reqwest::Url::parse(&target)
+                .ok()
+                .and_then(|url| Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?)));
+            let open = match address {
+                Some(address) => matches!(tokio::time::timeout(timeout, TcpStream::connect(address)).await, Ok(Ok(_))),
+                None => false,
+            };
+            (i, target, open)
+        });
+    }
+
+    let mut open = Vec::new();
+    while let Some(Ok((i, target, is_open))) = checks.join_next().await {
+        if is_open {
+            open.push((i, target));
+        }
+    }
+    open.sort();
+    open.into_iter().map(|(_, target)| target).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_ranges_across_ports() {
+        let targets = expand("10.10.0.0/30:80,443,8080").unwrap().unwrap();
+        assert_eq!(targets, [
+            "http://10.10.0.1/", "https://10.10.0.1/", "http://10.10.0.1:8080/",
+            "http://10.10.0.2/", "https://10.10.0.2/", "http://10.10.0.2:8080/",
+        ]);
+        assert_eq!(expand("10.10.0.77/24").unwrap().unwrap().len(), 254);
+        assert_eq!(expand("10.10.0.9/31").unwrap().unwrap(), ["http://10.10.0.8/", "http://10.10.0.9/"]);
+    }
+
+    #[test]
+    fn honours_explicit_schemes_and_single_hosts() {
+        assert_eq!(
+            expand("https://192.168.1.5:8000,8443").unwrap().unwrap(),
+            ["https://192.168.1.5:8000/", "https://192.168.1.5:8443/"]
+        );
+        assert_eq!(expand("http://10.0.0.1/32:443").unwrap().unwrap(), ["http://10.0.0.1:443/"]);
+    }
+
+    #[test]
+    fn leaves_ordinary_urls_alone() {
+        for url in ["http://example.com", "10.0.0.5", "10.0.0.5:8080", "http://10.0.0.5/admin", "example.com:80,443"] {
+            assert_eq!(expand(url).unwrap(), None, "{}", url);
+        }
+    }
+
+    #[test]
+    fn reads_targets_with_overrides() {
+        let (targets, overrides) = parse(
+            "# scope\nhttps://cdn.example.com\nhttp://legacy.example.com/app threads=2 delay=500ms header=\"X-Env: legacy box\"\n\n10.0.0.0/31:8080 delay=2s\n",
+        ).unwrap();
+        assert_eq!(targets, ["https://cdn.example.com", "http://legacy.example.com/app", "http://10.0.0.0:8080/", "http://10.0.0.1:8080/"]);
+        assert_eq!(overrides.len(), 3);
+        assert_eq!(overrides[0].threads, Some(2));
+        assert_eq!(overrides[0].delay_ms, Some(500));
+        assert_eq!(overrides[0].headers, [("X-Env".to_string(), "legacy box".to_string())]);
+        assert_eq!(overrides[2].delay_ms, Some(2000));
+
+        assert_eq!(override_for(&overrides, "http://legacy.example.com/app/admin").map(|o| o.threads), Some(Some(2)));
+        assert!(override_for(&overrides, "https://cdn.example.com/admin").is_none());
+        assert_eq!(parse("http://a.example threads=0").unwrap_err(), "1: invalid thread count '0'");
+        assert!(parse("http://a.example speed=11").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_and_oversized_ranges() {
+        assert!(expand("10.0.0.0/33").is_err());
+        assert!(expand("10.0.0.0/24:0").is_err());
+        assert!(expand("10.0.0.0/24:70000").is_err());
+        assert!(expand("300.0.0.0/24").is_err());
+        assert!(expand("10.0.0.0/8").is_err());
+        assert_eq!(expand("10.0.0.0/16").unwrap().unwrap().len(), 65_534);
+    }
+}
@@ -0,0 +1,498 @@
+use crate::error::CrawlerError;
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::{Method, RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+
+/// Placeholder replaced by each wordlist entry
+pub const MARKER: &str = "FUZZ";
+
+/// Joins the values of a multi-position payload (FUZZ1, FUZZ2, ...) into one string
+pub const SEPARATOR: char = '\u{1f}';
+
+/// Headers that describe the saved connection rather than the request,
+/// and are recomputed by the HTTP client
+const CONNECTION_HEADERS: &[&str] = &["host", "content-length", "connection", "transfer-encoding"];
+
+/// Raw HTTP request (as saved from Burp) used as the template for every probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestTemplate {
+    pub method: String,
+    /// Absolute URL, possibly containing the marker
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    /// Number of numbered markers (FUZZ1..FUZZn), or 0 for a single plain FUZZ
+    #[serde(default)]
+    pub positions: usize,
+}
+
+/// How wordlists are combined across marker positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mode {
+    /// Every combination of every list (cartesian product)
+    Clusterbomb,
+    /// The n-th entry of each list together, stopping at the shortest list
+    Pitchfork,
+}
+
+/// Pair the n-th entry of each position's list, stopping at the shortest list
+pub fn pitchfork(lists: &[Vec<String>]) -> Vec<String> {
+    let sep = SEPARATOR.to_string();
+    let len = lists.iter().map(Vec::len).min().unwrap_or(0);
+    (0..len)
+        .map(|i| lists.iter().map(|list| list[i].as_str()).collect::<Vec<_>>().join(&sep))
+        .collect()
+}
+
+/// Every combination of one list per position (cartesian product), produced
+/// on demand by counting through the lists like an odometer so the product
+/// is never held in memory. The last position turns fastest.
+#[derive(Debug, Clone)]
+pub struct Clusterbomb {
+    lists: Vec<Vec<String>>,
+}
+
+impl Clusterbomb {
+    pub fn new(lists: Vec<Vec<String>>) -> Self {
+        Clusterbomb { lists }
+    }
+
+    /// Number of combinations
+    pub fn len(&self) -> u64 {
+        match self.lists.is_empty() {
+            true => 0,
+            false => self.lists.iter().fold(1u64, |n, list| n.saturating_mul(list.len() as u64)),
+        }
+    }
+
+    /// The combination at `index`, in iteration order
+    pub fn get(&self, mut index: u64) -> String {
+        let mut values = vec![""; self.lists.len()];
+        for (slot, list) in values.iter_mut().zip(&self.lists).rev() {
+            let len = list.len() as u64;
+            *slot = &list[(index % len) as usize];
+            index /= len;
+        }
+        values.join(&SEPARATOR.to_string())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.len()).map(|index| self.get(index))
+    }
+
+    /// Mutable access to each position's list, e.g. to filter or shuffle it
+    pub fn lists_mut(&mut self) -> &mut [Vec<String>] {
+        &mut self.lists
+    }
+}
+
+/// `FUZZ=value`, or `FUZZ1=a, FUZZ2=b` for a multi-position payload
+pub fn describe(payload: &str) -> String {
+    if !payload.contains(SEPARATOR) {
+        return format!("{}={}", MARKER, payload);
+    }
+    payload.split(SEPARATOR)
+        .enumerate()
+        .map(|(i, value)| format!("{}{}={}", MARKER, i + 1, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether every value of a payload already shows in a URL's path or query
+/// (a value matching the host, as in header fuzzing, doesn't count)
+pub fn shown_in(url: &str, payload: &str) -> bool {
+    let tail = match Url::parse(url) {
+        Ok(parsed) => format!("{}?{}", parsed.path(), parsed.query().unwrap_or_default()),
+        Err(_) => url.to_string(),
+    };
+    payload.split(SEPARATOR).all(|value| tail.contains(value))
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+pub fn percent_encode(value: &str) -> String {
+    value.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Highest FUZZn in a text, with n counted from 1
+fn highest_position(text: &str) -> usize {
+    text.match_indices(MARKER)
+        .filter_map(|(pos, _)| {
+            let digits: String = text[pos + MARKER.len()..].chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().ok()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+impl RequestTemplate {
+    /// Parse a raw request. The target origin comes from `origin` when given
+    /// (e.g. the positional URL), otherwise from the Host header over HTTPS.
+    pub fn parse(raw: &str, origin: Option<&str>) -> Result<Self, CrawlerError> {
+        let raw = raw.replace("\r\n", "\n");
+        let (head, body) = match raw.split_once("\n\n") {
+            Some((head, body)) => (head, Some(body)),
+            None => (raw.as_str(), None),
+        };
+
+        let mut lines = head.lines();
+        let request_line = lines.next().ok_or("Request template is empty")?;
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Err(format!("Invalid request line '{}'", request_line).into());
+        };
+        Method::from_bytes(method.as_bytes())
+            .map_err(|_| format!("Invalid method '{}' in request template", method))?;
+
+        let headers = lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split_once(':')
+                    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                    .ok_or_else(|| format!("Invalid header line '{}' in request template", line))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Absolute-form request targets carry their own origin
+        let url = if target.starts_with("http://") || target.starts_with("https://") {
+            target.to_string()
+        } else {
+            let origin = match origin {
+                Some(origin) => {
+                    let parsed = Url::parse(origin).map_err(|e| CrawlerError::url(origin, e))?;
+                    parsed.origin().ascii_serialization()
+                }
+                None => {
+                    let host = headers.iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case("host"))
+                        .map(|(_, value)| value.clone())
+                        .ok_or("Request template has no Host header; pass the target URL")?;
+                    format!("https://{}", host)
+                }
+            };
+            format!("{}{}", origin, target)
+        };
+
+        let mut template = RequestTemplate {
+            method: method.to_string(),
+            url,
+            headers,
+            body: body.map(str::to_string).filter(|b| !b.is_empty()),
+            positions: 0,
+        };
+        if !template.contains_marker() {
+            return Err(format!("Request template has no {} marker", MARKER).into());
+        }
+        template.positions = template.count_positions()?;
+        Ok(template)
+    }
+
+    /// Every piece of the request a marker can appear in
+    fn texts(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.url.as_str())
+            .chain(self.headers.iter().flat_map(|(name, value)| [name.as_str(), value.as_str()]))
+            .chain(self.body.as_deref())
+    }
+
+    /// Numbered positions used, checking they run from FUZZ1 without gaps
+    /// and aren't mixed with a plain FUZZ
+    fn count_positions(&self) -> Result<usize, CrawlerError> {
+        let positions = self.texts().map(highest_position).max().unwrap_or(0);
+        for n in 1..=positions {
+            let marker = format!("{}{}", MARKER, n);
+            if !self.texts().any(|text| text.contains(&marker)) {
+                return Err(format!("Request template uses {}{} but not {}", MARKER, positions, marker).into());
+            }
+        }
+        let plain = self.texts().any(|text| {
+            text.match_indices(MARKER)
+                .any(|(pos, _)| !text[pos + MARKER.len()..].starts_with(|c: char| c.is_ascii_digit()))
+        });
+        if positions > 0 && plain {
+            return Err(format!("Request template mixes {} with numbered {}1..{}{} markers", MARKER, MARKER, MARKER, positions).into());
+        }
+        Ok(positions)
+    }
+
+    /// Payload putting the same value in every position, e.g. for calibration
+    pub fn uniform_payload(&self, value: &str) -> String {
+        vec![value; self.positions.max(1)].join(&SEPARATOR.to_string())
+    }
+
+    /// Fill the markers in one piece of the request
+    fn substitute(&self, text: &str, payload: &str) -> String {
+        self.fill(text, payload, false)
+    }
+
+    /// Fill the markers, percent-encoding each value when `encode` is set
+    fn fill(&self, text: &str, payload: &str, encode: bool) -> String {
+        let value = |v: &str| match encode {
+            true => percent_encode(v),
+            false => v.to_string(),
+        };
+        if self.positions == 0 {
+            return text.replace(MARKER, &value(payload));
+        }
+        let values: Vec<&str> = payload.split(SEPARATOR).collect();
+        // Highest first so FUZZ1 never eats the front of FUZZ10
+        (1..=self.positions).rev().fold(text.to_string(), |text, n| {
+            text.replace(&format!("{}{}", MARKER, n), &value(values.get(n - 1).copied().unwrap_or_default()))
+        })
+    }
+
+    /// Template for header fuzzing: a fixed URL with one `Name: value`
+    /// header whose name or value holds the marker
+    pub fn for_header(url: &str, method: &str, header: &str) -> Result<Self, CrawlerError> {
+        let (name, value) = header.split_once(':')
+            .ok_or_else(|| format!("Invalid --fuzz-header '{}'. Use 'Name: value' with {} in the name or value", header, MARKER))?;
+        let mut template = RequestTemplate {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: vec![(name.trim().to_string(), value.trim().to_string())],
+            body: None,
+            positions: 0,
+        };
+        if !template.headers[0].0.contains(MARKER) && !template.headers[0].1.contains(MARKER) {
+            return Err(format!("--fuzz-header '{}' has no {} marker", header, MARKER).into());
+        }
+        template.positions = template.count_positions()?;
+        Ok(template)
+    }
+
+    /// Template for parameter fuzzing: the URL with `name` set to the
+    /// marker, replacing its value if the parameter is already there
+    pub fn for_param(url: &str, method: &str, name: &str) -> Result<Self, CrawlerError> {
+        let mut parsed = Url::parse(url).map_err(|e| CrawlerError::url(url, e))?;
+        let others: Vec<(String, String)> = parsed.query_pairs()
+            .filter(|(key, _)| key != name)
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        parsed.query_pairs_mut()
+            .clear()
+            .extend_pairs(others)
+            .append_pair(name, MARKER);
+        Ok(RequestTemplate {
+            method: method.to_string(),
+            url: parsed.to_string(),
+            headers: Vec::new(),
+            body: None,
+            positions: 0,
+        })
+    }
+
+    /// Whether `value` yields well-formed headers in position `n` (from 1),
+    /// whatever the other positions hold
+    pub fn valid_at(&self, n: usize, value: &str) -> bool {
+        let payload: Vec<&str> = (1..=self.positions.max(1))
+            .map(|i| if i == n { value } else { "x" })
+            .collect();
+        self.valid_payload(&payload.join(&SEPARATOR.to_string()))
+    }
+
+    /// Whether a payload yields well-formed headers; words that can't be a
+    /// header name (spaces, `/`, ...) would only fail to send
+    pub fn valid_payload(&self, payload: &str) -> bool {
+        self.headers_for(payload).iter().all(|(name, value)| {
+            HeaderName::from_bytes(name.as_bytes()).is_ok() && HeaderValue::from_str(value).is_ok()
+        })
+    }
+
+    fn contains_marker(&self) -> bool {
+        self.texts().any(|text| text.contains(MARKER))
+    }
+
+    /// Everything up to the marker (or the whole URL), for display and calibration
+    pub fn base_url(&self) -> String {
+        match self.url.find(MARKER) {
+            Some(pos) => self.url[..pos].to_string(),
+            None => self.url.clone(),
+        }
+    }
+
+    /// Values substituted for one wordlist entry: the word itself and one per extension
+    pub fn payloads(word: &str, extensions: &[String]) -> HashSet<String> {
+        let word = word.trim_matches('/');
+        let mut payloads = HashSet::from([word.to_string()]);
+        for ext in extensions {
+            payloads.insert(format!("{}.{}", word, ext.trim_matches('.')));
+        }
+        payloads
+    }
+
+    /// The URL for one payload. Values landing in the query string are
+    /// percent-encoded so `&`, `#` and `%` stay inside their parameter.
+    pub fn url_for(&self, payload: &str) -> String {
+        match self.url.split_once('?') {
+            Some((path, query)) => format!("{}?{}", self.substitute(path, payload), self.fill(query, payload, true)),
+            None => self.substitute(&self.url, payload),
+        }
+    }
+
+    /// Headers of the template with the payload substituted, minus connection-level
+    /// ones. A fuzzed Host is kept: that's virtual host discovery, while a fixed
+    /// one only named the origin.
+    pub fn headers_for(&self, payload: &str) -> Vec<(String, String)> {
+        self.headers.iter()
+            .filter(|(name, value)| {
+                let name = name.to_ascii_lowercase();
+                !CONNECTION_HEADERS.contains(&name.as_str()) || (name == "host" && value.contains(MARKER))
+            })
+            .map(|(name, value)| (self.substitute(name, payload), self.substitute(value, payload)))
+            .collect()
+    }
+
+    pub fn body_for(&self, payload: &str) -> Option<String> {
+        self.body.as_ref().map(|body| self.substitute(body, payload))
+    }
+
+    /// Add the template's headers and body for one payload to a request
+    pub fn apply(&self, mut request: RequestBuilder, payload: &str) -> RequestBuilder {
+        for (name, value) in self.headers_for(payload) {
+            request = request.header(name, value);
+        }
+        if let Some(body) = self.body_for(payload) {
+            request = request.body(body);
+        }
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW: &str = "POST /api/FUZZ?debug=1 HTTP/1.1\r\n\
+                       Host: example.com\r\n\
+                       Authorization: Bearer token\r\n\
+                       Content-Length: 17\r\n\
+                       \r\n\
+                       {\"name\":\"FUZZ\"}";
+
+    #[test]
+    fn parses_burp_requests_with_host_origin() {
+        let template = RequestTemplate::parse(RAW, None).unwrap();
+        assert_eq!(template.method, "POST");
+        assert_eq!(template.url, "https://example.com/api/FUZZ?debug=1");
+        assert_eq!(template.headers.len(), 3);
+        assert_eq!(template.body.as_deref(), Some("{\"name\":\"FUZZ\"}"));
+        assert_eq!(template.positions, 0);
+        assert_eq!(template.base_url(), "https://example.com/api/");
+    }
+
+    #[test]
+    fn explicit_origin_overrides_host_header() {
+        let template = RequestTemplate::parse(RAW, Some("http://staging.example.com:8080/ignored")).unwrap();
+        assert_eq!(template.url, "http://staging.example.com:8080/api/FUZZ?debug=1");
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        assert!(RequestTemplate::parse("", None).is_err());
+        assert!(RequestTemplate::parse("GET /FUZZ HTTP/1.1\n\n", None).is_err(), "no Host header");
+        assert!(RequestTemplate::parse("GET /admin HTTP/1.1\nHost: a\n", None).is_err(), "no marker");
+        assert!(RequestTemplate::parse("G@T /FUZZ HTTP/1.1\nHost: a\n", None).is_err(), "bad method");
+        assert!(RequestTemplate::parse("GET /FUZZ HTTP/1.1\nHost a\n", None).is_err(), "bad header");
+    }
+
+    #[test]
+    fn substitutes_payload_everywhere_but_connection_headers() {
+        let template = RequestTemplate::parse(RAW, None).unwrap();
+        assert_eq!(template.url_for("users"), "https://example.com/api/users?debug=1");
+        assert_eq!(template.body_for("users").as_deref(), Some("{\"name\":\"users\"}"));
+        let headers = template.headers_for("users");
+        assert!(headers.iter().all(|(name, _)| name != "Host" && name != "Content-Length"));
+        assert!(headers.contains(&("Authorization".to_string(), "Bearer token".to_string())));
+    }
+
+    #[test]
+    fn payloads_cover_each_extension() {
+        let payloads = RequestTemplate::payloads("/admin/", &["php".to_string(), ".bak".to_string()]);
+        assert_eq!(payloads, HashSet::from(["admin".to_string(), "admin.php".to_string(), "admin.bak".to_string()]));
+    }
+
+    fn lists(lists: &[&[&str]]) -> Vec<Vec<String>> {
+        lists.iter().map(|list| list.iter().map(|s| s.to_string()).collect()).collect()
+    }
+
+    #[test]
+    fn counts_numbered_positions_and_rejects_gaps_or_mixing() {
+        let template = RequestTemplate::parse("GET /FUZZ1.FUZZ2 HTTP/1.1\nHost: a\nX-User: FUZZ3\n", None).unwrap();
+        assert_eq!(template.positions, 3);
+        assert!(RequestTemplate::parse("GET /FUZZ1/FUZZ3 HTTP/1.1\nHost: a\n", None).is_err());
+        assert!(RequestTemplate::parse("GET /FUZZ1/FUZZ HTTP/1.1\nHost: a\n", None).is_err());
+    }
+
+    #[test]
+    fn fills_each_position_without_clobbering_higher_numbers() {
+        let raw = "GET /FUZZ1/FUZZ2/FUZZ3/FUZZ4/FUZZ5/FUZZ6/FUZZ7/FUZZ8/FUZZ9/FUZZ10 HTTP/1.1\nHost: a\n";
+        let template = RequestTemplate::parse(raw, None).unwrap();
+        let payload = (1..=10).map(|n| format!("v{}", n)).collect::<Vec<_>>().join(&SEPARATOR.to_string());
+        assert_eq!(template.url_for(&payload), "https://a/v1/v2/v3/v4/v5/v6/v7/v8/v9/v10");
+        assert_eq!(describe(&payload).split(", ").nth(9), Some("FUZZ10=v10"));
+        assert_eq!(describe("admin"), "FUZZ=admin");
+    }
+
+    #[test]
+    fn pitchfork_pairs_lines_up_to_the_shortest_list() {
+        let sep = SEPARATOR.to_string();
+        let paired = pitchfork(&lists(&[&["a", "b", "c"], &["1", "2"]]));
+        assert_eq!(paired, [format!("a{}1", sep), format!("b{}2", sep)]);
+    }
+
+    #[test]
+    fn clusterbomb_walks_every_combination_in_order() {
+        let sep = SEPARATOR.to_string();
+        let bomb = Clusterbomb::new(lists(&[&["a", "b"], &["1", "2", "3"]]));
+        assert_eq!(bomb.len(), 6);
+        let all: Vec<String> = bomb.iter().collect();
+        assert_eq!(all[0], format!("a{}1", sep));
+        assert_eq!(all[1], format!("a{}2", sep));
+        assert_eq!(all[5], format!("b{}3", sep));
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 6);
+        assert_eq!(bomb.get(4), all[4]);
+    }
+
+    #[test]
+    fn clusterbomb_sizes_never_overflow() {
+        let empty = Clusterbomb::new(Vec::new());
+        assert_eq!(empty.len(), 0);
+        assert_eq!(empty.iter().count(), 0);
+        assert_eq!(Clusterbomb::new(lists(&[&["a"], &[]])).len(), 0);
+        let big: Vec<String> = (0..100_000).map(|n| n.to_string()).collect();
+        assert_eq!(Clusterbomb::new(vec![big; 4]).len(), u64::MAX);
+    }
+
+    #[test]
+    fn query_payloads_are_percent_encoded() {
+        let template = RequestTemplate::for_param("http://t/invoice?id=1&lang=en", "GET", "id").unwrap();
+        assert_eq!(template.url_for("x&admin=1"), "http://t/invoice?lang=en&id=x%26admin%3D1");
+        assert_eq!(template.url_for("50%"), "http://t/invoice?lang=en&id=50%25");
+        assert_eq!(template.url_for("y#frag"), "http://t/invoice?lang=en&id=y%23frag");
+        assert_eq!(template.url_for("a-b_c.d~"), "http://t/invoice?lang=en&id=a-b_c.d~");
+    }
+
+    #[test]
+    fn for_param_adds_a_missing_parameter() {
+        let template = RequestTemplate::for_param("http://t/search", "GET", "q").unwrap();
+        assert_eq!(template.url_for("1"), "http://t/search?q=1");
+        assert!(shown_in(&template.url_for("1"), "1"));
+    }
+
+    #[test]
+    fn header_templates_fuzz_names_or_values() {
+        let value = RequestTemplate::for_header("http://t/", "GET", "X-Forwarded-For: FUZZ").unwrap();
+        assert_eq!(value.headers_for("127.0.0.1"), [("X-Forwarded-For".to_string(), "127.0.0.1".to_string())]);
+        let name = RequestTemplate::for_header("http://t/", "GET", "FUZZ: 1").unwrap();
+        assert!(name.valid_payload("X-Debug"));
+        assert!(!name.valid_payload("X Debug"));
+        assert!(!name.valid_payload("a/b"));
+        assert!(RequestTemplate::for_header("http://t/", "GET", "X-Static: 1").is_err());
+        assert!(RequestTemplate::for_header("http://t/", "GET", "no colon").is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! What the terminal gets: colors and emoji only where someone is looking.
+//!
+//! Status lines go through `term::out!` (stdout) and `term::err!` (stderr),
+//! which drop ANSI colors and emoji from a line when the stream it goes to
+//! has them turned off, so a piped `dir_crawler` prints lines a `grep` or
+//! `cut` can take apart. Diagnostics get the same treatment from
+//! [`Writer`]. Results printed for the next tool in the pipe use plain
+//! `println!`: they are data, passed on as found.
+
+use regex::Regex;
+use std::borrow::Cow;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Whether each stream shows colors and emoji; everything until `init`
+static STDOUT_COLOR: AtomicBool = AtomicBool::new(true);
+static STDOUT_EMOJI: AtomicBool = AtomicBool::new(true);
+static STDERR_COLOR: AtomicBool = AtomicBool::new(true);
+static STDERR_EMOJI: AtomicBool = AtomicBool::new(true);
+
+/// Decide what stdout and stderr show. A stream that isn't a terminal gets
+/// neither colors nor emoji; `--no-color` or `NO_COLOR` turns colors off
+/// everywhere and `--no-emoji` emoji, while `CLICOLOR_FORCE` keeps colors
+/// on in a pipe.
+pub fn init(no_color: bool, no_emoji: bool) {
+    let set = |var: &str| std::env::var_os(var).is_some_and(|value| !value.is_empty() && value != "0");
+    let no_color = no_color || set("NO_COLOR");
+    let force_color = set("CLICOLOR_FORCE");
+    for (terminal, color, emoji) in [
+        (std::io::stdout().is_terminal(), &STDOUT_COLOR, &STDOUT_EMOJI),
+        (std::io::stderr().is_terminal(), &STDERR_COLOR, &STDERR_EMOJI),
+    ] {
+        color.store(!no_color && (terminal || force_color), Ordering::Relaxed);
+        emoji.store(!no_emoji && terminal, Ordering::Relaxed);
+    }
+    // Colored strings are built before it's known where they go; the
+    // macros strip them for a stream without colors
+    colored::control::set_override(color(Stream::Stdout) || color(Stream::Stderr));
+}
+
+pub fn color(stream: Stream) -> bool {
+    match stream {
+        Stream::Stdout => STDOUT_COLOR.load(Ordering::Relaxed),
+        Stream::Stderr => STDERR_COLOR.load(Ordering::Relaxed),
+    }
+}
+
+pub fn emoji(stream: Stream) -> bool {
+    match stream {
+        Stream::Stdout => STDOUT_EMOJI.load(Ordering::Relaxed),
+        Stream::Stderr => STDERR_EMOJI.load(Ordering::Relaxed),
+    }
+}
+
+/// `text` as `stream` shows it
+pub fn plain(stream: Stream, text: &str) -> Cow<'_, str> {
+    let text = match color(stream) {
+        true => Cow::Borrowed(text),
+        false => strip_ansi(text),
+    };
+    if !emoji(stream) {
+        if let Cow::Owned(stripped) = strip_emoji(&text) {
+            return Cow::Owned(stripped);
+        }
+    }
+    text
+}
+
+/// `text` without terminal escape sequences: colors and other CSI codes,
+/// OSC sequences (titles, hyperlinks) and two-character escapes
+pub fn strip_ansi(text: &str) -> Cow<'_, str> {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    if !text.contains('\x1b') {
+        return Cow::Borrowed(text);
+    }
+    ANSI.get_or_init(|| Regex::new(r"\x1b(?:\[[0-?]*[ -/]*[@-~]|\][^\x07\x1b]*(?:\x07|\x1b\\)|[@-Z\\-_])").unwrap())
+        .replace_all(text, "")
+}
+
+/// Pictographs, and symbols asked to show as one; box drawing, arrows and
+/// bullets are plain text and stay
+fn is_emoji(c: char, next: Option<char>) -> bool {
+    is_pictograph(c) || next == Some('\u{FE0F}')
+}
+
+fn is_pictograph(c: char) -> bool {
+    matches!(c as u32, 0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2300..=0x23FF | 0x2B00..=0x2BFF)
+}
+
+/// `text` without emoji or the spaces that set them apart
+fn strip_emoji(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| is_pictograph(c) || c == '\u{FE0F}') {
+        return Cow::Borrowed(text);
+    }
+    let mut chars = text.chars().peekable();
+    let mut out = String::with_capacity(text.len());
+    while let Some(c) = chars.next() {
+        if !is_emoji(c, chars.peek().copied()) {
+            out.push(c);
+            continue;
+        }
+        // The rest of the sequence: presentation selector, skin tone,
+        // joined pictographs
+        loop {
+            match chars.peek() {
+                Some('\u{FE0F}' | '\u{1F3FB}'..='\u{1F3FF}') => {
+                    chars.next();
+                }
+                Some('\u{200D}') => {
+                    chars.next();
+                    chars.next();
+                }
+                _ => break,
+            }
+        }
+        while chars.next_if_eq(&' ').is_some() {}
+        if matches!(chars.peek(), None | Some('\n')) {
+            out.truncate(out.trim_end_matches(' ').len());
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// A stream as it shows, for tracing to write diagnostics through: each
+/// event loses its colors and emoji where `term::err!` lines would
+#[derive(Debug, Clone, Copy)]
+pub struct Writer(pub Stream);
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        let text = plain(self.0, &text);
+        match self.0 {
+            Stream::Stdout => std::io::stdout().lock().write_all(text.as_bytes())?,
+            Stream::Stderr => std::io::stderr().lock().write_all(text.as_bytes())?,
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.0 {
+            Stream::Stdout => std::io::stdout().flush(),
+            Stream::Stderr => std::io::stderr().flush(),
+        }
+    }
+}
+
+/// `println!` for a status line on stdout, as stdout shows it
+macro_rules! out {
+    () => {
+        ::std::println!()
+    };
+    ($($arg:tt)*) => {
+        ::std::println!("{}", $crate::term::plain($crate::term::Stream::Stdout, &::std::format!($($arg)*)))
+    };
+}
+pub(crate) use out;
+
+/// `eprintln!` for a status line on stderr, as stderr shows it
+macro_rules! err {
+    () => {
+        ::std::eprintln!()
+    };
+    ($($arg:tt)*) => {
+        ::std::eprintln!("{}", $crate::term::plain($crate::term::Stream::Stderr, &::std::format!($($arg)*)))
+    };
+}
+pub(crate) use err;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_colors_and_emoji() {
+        assert_eq!(strip_ansi("\x1b[32m200\x1b[0m \x1b]8;;http://t/\x1b\\link\x1b]8;;\x1b\\"), "200 link");
+        assert_eq!(strip_emoji("🌐 Status: 200 | URL: http://t/admin 📁 [admin] (HIGH)"), "Status: 200 | URL: http://t/admin [admin] (HIGH)");
+        assert_eq!(strip_emoji("   ↔️  mobile: 200 → http://t/m\n⚠️  Slow\ndone 🎉"), "   mobile: 200 → http://t/m\nSlow\ndone");
+        assert_eq!(strip_emoji("🕵️‍♂️ Agent"), "Agent");
+        assert!(matches!(strip_emoji("║ █ plain · text → …"), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Delay applied to a host after its first rate-limit response
+const INITIAL_DELAY: Duration = Duration::from_millis(250);
+/// Upper bound on the per-request delay for a single host
+const MAX_DELAY: Duration = Duration::from_secs(30);
+/// Consecutive successes required before the delay is relaxed
+const RECOVERY_STREAK: u32 = 20;
+
+#[derive(Debug)]
+struct HostThrottle {
+    delay: Duration,
+    next_allowed: Instant,
+    last_backoff: Option<Instant>,
+    streak: u32,
+    /// Slowest pace the host was configured for, which recovery stops at
+    floor: Duration,
+}
+
+/// Per-host request pacing that backs off on 429/503 and recovers on success
+#[derive(Debug, Default)]
+pub struct Throttle {
+    hosts: Mutex<HashMap<String, HostThrottle>>,
+}
+
+impl Throttle {
+    /// Pacing that starts with fixed delays for some hosts, kept however
+    /// well they respond
+    pub fn with_floors(floors: impl IntoIterator<Item = (String, Duration)>) -> Self {
+        let hosts = floors.into_iter()
+            .map(|(host, floor)| (host, HostThrottle {
+                delay: floor,
+                next_allowed: Instant::now(),
+                last_backoff: None,
+                streak: 0,
+                floor,
+            }))
+            .collect();
+        Throttle { hosts: Mutex::new(hosts) }
+    }
+
+    /// Wait until the host's current pacing allows another request
+    pub async fn wait(&self, host: &str) {
+        let sleep_for = {
+            let mut hosts = self.hosts.lock().unwrap();
+            let Some(entry) = hosts.get_mut(host) else {
+                return;
+            };
+            if entry.delay.is_zero() && entry.next_allowed <= Instant::now() {
+                return;
+            }
+
+            // Reserve the next slot so concurrent workers are spaced out
+            let now = Instant::now();
+            let slot = entry.next_allowed.max(now);
+            entry.next_allowed = slot + entry.delay;
+            slot - now
+        };
+
+        if !sleep_for.is_zero() {
+            tokio::time::sleep(sleep_for).await;
+        }
+    }
+
+    /// Feed a response back into the host's pacing. Returns a description of
+    /// the adaptation when the delay changed.
+    pub fn record(&self, host: &str, status: u16, retry_after: Option<Duration>) -> Option<String> {
+        let mut hosts = self.hosts.lock().unwrap();
+
+        if status == 429 || status == 503 {
+            let entry = hosts.entry(host.to_string()).or_insert(HostThrottle {
+                delay: Duration::ZERO,
+                next_allowed: Instant::now(),
+                last_backoff: None,
+                streak: 0,
+                floor: Duration::ZERO,
+            });
+            let previous = entry.delay;
+            entry.streak = 0;
+
+            // Requests already in flight when we backed off will also come back
+            // rate-limited; only escalate once the previous slowdown had a chance
+            let settle = previous.max(Duration::from_secs(1));
+            if entry.last_backoff.is_none_or(|at| at.elapsed() >= settle) {
+                entry.delay = (previous * 2).max(INITIAL_DELAY).min(MAX_DELAY);
+                entry.last_backoff = Some(Instant::now());
+            }
+
+            // Honor Retry-After by pausing the whole host
+            if let Some(pause) = retry_after {
+                entry.next_allowed = entry.next_allowed.max(Instant::now() + pause.min(MAX_DELAY * 4));
+            }
+
+            return (entry.delay != previous).then(|| {
+                format!(
+                    "{} returned {}; slowing to one request every {}ms{}",
+                    host,
+                    status,
+                    entry.delay.as_millis(),
+                    retry_after
+                        .map(|d| format!(" (Retry-After {}s)", d.as_secs()))
+                        .unwrap_or_default()
+                )
+            });
+        }
+
+        let entry = hosts.get_mut(host)?;
+        if entry.delay <= entry.floor {
+            return None;
+        }
+
+        entry.streak += 1;
+        if entry.streak < RECOVERY_STREAK {
+            return None;
+        }
+
+        entry.streak = 0;
+        entry.delay /= 2;
+        if entry.delay < Duration::from_millis(25) {
+            entry.delay = Duration::ZERO;
+        }
+        entry.delay = entry.delay.max(entry.floor);
+        Some(format!(
+            "{} recovered; speeding up to {}ms between requests",
+            host,
+            entry.delay.as_millis()
+        ))
+    }
+}
+
+/// Parse a `Retry-After` header given in delay-seconds
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
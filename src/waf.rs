@@ -0,0 +1,230 @@
+use crate::engine::{read_body, send_request};
+use crate::error::CrawlerError;
+use crate::scope::Scope;
+use crate::{FuzzerConfig, ScanState};
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use reqwest::{Client, Method};
+
+
+/// Query string a WAF is likely to react to but a plain web server ignores
+const SUSPICIOUS_QUERY: &str = "id=1%27%20OR%20%271%27%3D%271&q=%3Cscript%3Ealert(1)%3C%2Fscript%3E&file=..%2F..%2F..%2Fetc%2Fpasswd";
+
+/// Statuses WAFs answer blocked requests with
+const BLOCK_STATUSES: &[u16] = &[403, 406, 429, 501];
+
+/// Concurrency the stealthier settings cap a scan at
+const STEALTH_THREADS: usize = 5;
+const STEALTH_CONNECTIONS_PER_HOST: usize = 2;
+
+/// Something in a response that gives a product away
+enum Signal {
+    /// A header only this product sets
+    Header(&'static str),
+    /// A header whose value mentions the product
+    HeaderValue(&'static str, &'static str),
+    /// A cookie name prefix
+    Cookie(&'static str),
+    /// Text in a block page
+    Body(&'static str),
+}
+
+/// Known WAF and CDN fingerprints, names and values lowercase
+const SIGNATURES: &[(&str, &[Signal])] = &[
+    ("Cloudflare", &[
+        Signal::Header("cf-ray"),
+        Signal::HeaderValue("server", "cloudflare"),
+        Signal::Cookie("__cf_bm"),
+        Signal::Cookie("cf_clearance"),
+    ]),
+    ("Akamai", &[
+        Signal::HeaderValue("server", "akamaighost"),
+        Signal::Header("akamai-grn"),
+        Signal::Header("x-akamai-transformed"),
+        Signal::Body("errors.edgesuite.net"),
+    ]),
+    ("ModSecurity", &[
+        Signal::HeaderValue("server", "mod_security"),
+        Signal::Body("mod_security"),
+        Signal::Body("modsecurity"),
+    ]),
+    ("Imperva Incapsula", &[
+        Signal::Header("x-iinfo"),
+        Signal::Cookie("incap_ses_"),
+        Signal::Cookie("visid_incap_"),
+    ]),
+    ("Sucuri", &[
+        Signal::Header("x-sucuri-id"),
+        Signal::HeaderValue("server", "sucuri"),
+    ]),
+    ("AWS CloudFront", &[
+        Signal::Header("x-amz-cf-id"),
+        Signal::HeaderValue("via", "cloudfront"),
+    ]),
+    ("AWS WAF", &[Signal::Cookie("aws-waf-token")]),
+    ("F5 BIG-IP", &[Signal::Cookie("bigipserver")]),
+    ("Fastly", &[
+        Signal::Header("x-fastly-request-id"),
+        Signal::HeaderValue("x-served-by", "cache-"),
+    ]),
+];
+
+/// A product detected in front of the target, with what gave it away
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    pub name: String,
+    pub evidence: String,
+}
+
+/// Products a single response reveals
+pub fn fingerprint(headers: &HeaderMap, body: &str) -> Vec<Detection> {
+    let body = body.to_lowercase();
+    let header = |name: &str| {
+        headers.get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+    };
+    let cookies: Vec<String> = header(SET_COOKIE.as_str());
+
+    SIGNATURES.iter()
+        .filter_map(|(name, signals)| {
+            let evidence = signals.iter().find_map(|signal| match signal {
+                Signal::Header(h) => headers.contains_key(*h).then(|| format!("{} header", h)),
+                Signal::HeaderValue(h, v) => header(h).iter()
+                    .any(|value| value.contains(v))
+                    .then(|| format!("{}: {}", h, v)),
+                Signal::Cookie(prefix) => cookies.iter()
+                    .any(|cookie| cookie.starts_with(prefix))
+                    .then(|| format!("{}* cookie", prefix)),
+                Signal::Body(text) => body.contains(text).then(|| format!("\"{}\" in page", text)),
+            })?;
+            Some(Detection { name: name.to_string(), evidence })
+        })
+        .collect()
+}
+
+/// Send a benign request and a mildly suspicious one to the target and
+/// report the WAFs and CDNs they reveal. A suspicious request blocked while
+/// the benign one wasn't counts even when the product can't be named.
+/// Probes out of scope aren't sent.
+pub async fn preflight(config: &FuzzerConfig, state: &ScanState, client: &Client) -> Result<Vec<Detection>, CrawlerError> {
+    let get = FuzzerConfig { method: Method::GET, request_template: None, ..config.clone() };
+    let separator = if config.url.contains('?') { '&' } else { '?' };
+    let probes = [config.url.clone(), format!("{}{}{}", config.url, separator, SUSPICIOUS_QUERY)];
+
+    let mut detections: Vec<Detection> = Vec::new();
+    let mut statuses = Vec::new();
+    let scope = Scope::new(&config.scope_include, &config.scope_exclude, &config.blocklist)?;
+    for url in &probes {
+        if let Err(reason) = scope.check(url) {
+            tracing::debug!("🚧 Skipping WAF probe {}: {}", state.redactor.redact(url), reason);
+            continue;
+        }
+        let response = send_request(&get, state, client, url, None).await?;
+        let status = response.status().as_u16();
+        let headers = response.headers().clone();
+        let (body, _) = read_body(&get, response).await.unwrap_or_default();
+        for detection in fingerprint(&headers, &String::from_utf8_lossy(&body)) {
+            if !detections.iter().any(|known| known.name == detection.name) {
+                detections.push(detection);
+            }
+        }
+        statuses.push(status);
+    }
+
+    if let [benign, suspicious] = statuses[..] {
+        if !BLOCK_STATUSES.contains(&benign) && BLOCK_STATUSES.contains(&suspicious) {
+            detections.push(Detection {
+                name: "Request filtering".to_string(),
+                evidence: format!("suspicious probe blocked with {} (benign: {})", suspicious, benign),
+            });
+        }
+    }
+    Ok(detections)
+}
+
+/// Slower, browser-like settings less likely to get the scanner banned,
+/// with a description of each change (nothing to change yields `None`)
+pub fn stealthier(config: &FuzzerConfig) -> Option<(FuzzerConfig, Vec<String>)> {
+    let mut adjusted = config.clone();
+    let mut changes = Vec::new();
+    if adjusted.threads > STEALTH_THREADS {
+        adjusted.threads = STEALTH_THREADS;
+        changes.push(format!("-t {}", STEALTH_THREADS));
+    }
+    if adjusted.max_connections_per_host.is_none_or(|max| max > STEALTH_CONNECTIONS_PER_HOST) {
+        adjusted.max_connections_per_host = Some(STEALTH_CONNECTIONS_PER_HOST);
+        changes.push(format!("--max-connections-per-host {}", STEALTH_CONNECTIONS_PER_HOST));
+    }
+    if adjusted.persona.is_none() {
+        adjusted.persona = Some("chrome".to_string());
+        changes.push("--persona chrome".to_string());
+    }
+    if !adjusted.adaptive_throttle {
+        adjusted.adaptive_throttle = true;
+        changes.push("adaptive throttling".to_string());
+    }
+    (!changes.is_empty()).then_some((adjusted, changes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(*name, HeaderValue::from_static(value));
+        }
+        map
+    }
+
+    fn names(detections: &[Detection]) -> Vec<&str> {
+        detections.iter().map(|d| d.name.as_str()).collect()
+    }
+
+    #[test]
+    fn recognizes_cdn_headers_and_cookies() {
+        let found = fingerprint(&headers(&[("server", "cloudflare"), ("cf-ray", "8a1b2c-AMS")]), "");
+        assert_eq!(names(&found), ["Cloudflare"]);
+        assert_eq!(found[0].evidence, "cf-ray header");
+
+        let found = fingerprint(&headers(&[("set-cookie", "a=1"), ("set-cookie", "incap_ses_123=abc; path=/")]), "");
+        assert_eq!(names(&found), ["Imperva Incapsula"]);
+
+        let found = fingerprint(&headers(&[("Server", "AkamaiGHost"), ("x-amz-cf-id", "xyz")]), "");
+        assert_eq!(names(&found), ["Akamai", "AWS CloudFront"]);
+    }
+
+    #[test]
+    fn recognizes_block_pages() {
+        let page = "<h1>Not Acceptable!</h1>This error was generated by Mod_Security.";
+        assert_eq!(names(&fingerprint(&HeaderMap::new(), page)), ["ModSecurity"]);
+    }
+
+    #[test]
+    fn plain_servers_reveal_nothing() {
+        let plain = headers(&[("server", "nginx/1.25"), ("set-cookie", "session=1")]);
+        assert!(fingerprint(&plain, "<html>Welcome</html>").is_empty());
+    }
+
+    #[test]
+    fn stealthier_settings_only_tighten() {
+        let config = FuzzerConfig { threads: 50, ..FuzzerConfig::default() };
+        let (adjusted, changes) = stealthier(&config).unwrap();
+        assert_eq!(adjusted.threads, STEALTH_THREADS);
+        assert_eq!(adjusted.max_connections_per_host, Some(STEALTH_CONNECTIONS_PER_HOST));
+        assert_eq!(adjusted.persona.as_deref(), Some("chrome"));
+        assert_eq!(changes.len(), 3);
+
+        let careful = FuzzerConfig {
+            threads: 2,
+            max_connections_per_host: Some(1),
+            persona: Some("googlebot".to_string()),
+            ..FuzzerConfig::default()
+        };
+        assert!(stealthier(&careful).is_none());
+    }
+}
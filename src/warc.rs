@@ -0,0 +1,241 @@
+use crate::har::{Received, Sent};
+use crate::redact::Redactor;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// WARC 1.1 capture of every finding's request and response, appended as
+/// findings arrive. A `.gz` path gets one gzip member per record, the
+/// layout archive tools index and `zgrep` reads straight through.
+pub struct Writer {
+    file: Mutex<File>,
+    gzip: bool,
+    responses: AtomicU64,
+}
+
+impl Writer {
+    /// Create (truncating) `path` and write the `warcinfo` record describing the scan
+    pub fn create(path: &Path, target: &str, scan_id: &str) -> std::io::Result<Self> {
+        let writer = Writer {
+            file: Mutex::new(File::create(path)?),
+            gzip: path.extension().is_some_and(|ext| ext == "gz"),
+            responses: AtomicU64::new(0),
+        };
+        let info = format!(
+            "software: dir_crawler/{}\r\nformat: WARC File Format 1.1\r\nconformsTo: https://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/\r\nscan-id: {}\r\ntarget: {}\r\n",
+            env!("CARGO_PKG_VERSION"),
+            scan_id,
+            target
+        );
+        let filename = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        writer.write(&[
+            ("WARC-Type", "warcinfo".to_string()),
+            ("WARC-Record-ID", record_id()),
+            ("WARC-Date", now()),
+            ("WARC-Filename", filename),
+            ("Content-Type", "application/warc-fields".to_string()),
+        ], info.as_bytes())?;
+        Ok(writer)
+    }
+
+    /// Append a finding as a `request` record, when the request was kept,
+    /// and the `response` record it belongs to. Headers and text bodies are
+    /// redacted; a body cut short by `--max-body-size` is marked truncated.
+    pub fn record(&self, sent: Option<&Sent>, received: Received, truncated: bool, redactor: &Redactor) -> std::io::Result<()> {
+        let date = sent.map_or_else(now, |sent| iso8601(sent.started_at));
+        let target = sent.map_or(received.final_url, |sent| sent.url.as_str());
+        let response_id = record_id();
+
+        if let Some(sent) = sent {
+            let block = request_block(sent, redactor);
+            self.write(&[
+                ("WARC-Type", "request".to_string()),
+                ("WARC-Record-ID", record_id()),
+                ("WARC-Date", date.clone()),
+                ("WARC-Target-URI", target.to_string()),
+                ("WARC-Concurrent-To", response_id.clone()),
+                ("WARC-Block-Digest", digest(&block)),
+                ("Content-Type", "application/http;msgtype=request".to_string()),
+            ], &block)?;
+        }
+
+        let block = response_block(&received, redactor);
+        let mut fields = vec![
+            ("WARC-Type", "response".to_string()),
+            ("WARC-Record-ID", response_id),
+            ("WARC-Date", date),
+            ("WARC-Target-URI", target.to_string()),
+            ("WARC-Block-Digest", digest(&block)),
+            ("Content-Type", "application/http;msgtype=response".to_string()),
+        ];
+        if truncated {
+            fields.push(("WARC-Truncated", "length".to_string()));
+        }
+        self.write(&fields, &block)?;
+        self.responses.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Responses archived so far
+    pub fn count(&self) -> u64 {
+        self.responses.load(Ordering::Relaxed)
+    }
+
+    fn write(&self, fields: &[(&str, String)], block: &[u8]) -> std::io::Result<()> {
+        let mut record = b"WARC/1.1\r\n".to_vec();
+        for (name, value) in fields {
+            record.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+        }
+        record.extend_from_slice(format!("Content-Length: {}\r\n\r\n", block.len()).as_bytes());
+        record.extend_from_slice(block);
+        record.extend_from_slice(b"\r\n\r\n");
+
+        if self.gzip {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&record)?;
+            record = encoder.finish()?;
+        }
+        self.file.lock().unwrap().write_all(&record)
+    }
+}
+
+/// `GET /path?query HTTP/1.1`, headers (with Host) and body
+fn request_block(sent: &Sent, redactor: &Redactor) -> Vec<u8> {
+    http_message(sent.request_line(), &sent.headers, sent.body.as_deref().unwrap_or_default(), redactor)
+}
+
+/// `HTTP/1.1 200 OK`, headers and body
+fn response_block(received: &Received, redactor: &Redactor) -> Vec<u8> {
+    let reason = reqwest::StatusCode::from_u16(received.status).ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or_default();
+    let head = format!("{:?} {} {}\r\n", received.version, received.status, reason);
+    http_message(head, received.headers, received.body, redactor)
+}
+
+fn http_message(mut head: String, headers: &reqwest::header::HeaderMap, body: &[u8], redactor: &Redactor) -> Vec<u8> {
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, String::from_utf8_lossy(value.as_bytes())));
+    }
+    head.push_str("\r\n");
+    let mut block = redactor.redact(&head).into_owned().into_bytes();
+    match std::str::from_utf8(body) {
+        Ok(text) => block.extend_from_slice(redactor.redact(text).as_bytes()),
+        Err(_) => block.extend_from_slice(body),
+    }
+    block
+}
+
+/// `<urn:uuid:…>` from a random version 4 UUID
+fn record_id() -> String {
+    let hex = crate::crypto::random_hex(16);
+    let variant = ["8", "9", "a", "b"][usize::from_str_radix(&hex[16..17], 16).unwrap_or(0) % 4];
+    format!("<urn:uuid:{}-{}-4{}-{}{}-{}>", &hex[..8], &hex[8..12], &hex[13..16], variant, &hex[17..20], &hex[20..32])
+}
+
+fn digest(block: &[u8]) -> String {
+    let hash = Sha256::digest(block);
+    format!("sha256:{}", hash.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+fn iso8601(time: SystemTime) -> String {
+    crate::workspace::format_iso8601(time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64))
+}
+
+fn now() -> String {
+    iso8601(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::MultiGzDecoder;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use std::io::Read;
+
+    fn capture(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("dir_crawler-test-{}-{}", std::process::id(), name));
+        let writer = Writer::create(&path, "http://example.com/", "scan-1").unwrap();
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert("cookie", HeaderValue::from_static("session=hunter2"));
+        let sent = Sent {
+            method: "GET".to_string(),
+            url: "http://example.com:8080/admin?x=1".to_string(),
+            version: reqwest::Version::HTTP_11,
+            headers: request_headers,
+            body: None,
+            started_at: UNIX_EPOCH,
+            started: std::time::Instant::now(),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", HeaderValue::from_static("text/html"));
+        let received = Received {
+            status: 200,
+            version: reqwest::Version::HTTP_11,
+            headers: &headers,
+            body: b"<h1>Admin</h1>",
+            final_url: "http://example.com:8080/admin?x=1",
+        };
+        let redactor = Redactor::new(&["session=".to_string()]).unwrap();
+        writer.record(Some(&sent), received, true, &redactor).unwrap();
+        assert_eq!(writer.count(), 1);
+        drop(writer);
+
+        let data = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        match name.ends_with(".gz") {
+            true => {
+                let mut text = String::new();
+                MultiGzDecoder::new(&data[..]).read_to_string(&mut text).unwrap();
+                text
+            }
+            false => String::from_utf8(data).unwrap(),
+        }
+    }
+
+    #[test]
+    fn writes_info_request_and_response_records() {
+        let warc = capture("plain.warc");
+        let records: Vec<&str> = warc.split("WARC/1.1\r\n").skip(1).collect();
+        assert_eq!(records.len(), 3);
+        assert!(records[0].starts_with("WARC-Type: warcinfo\r\n"));
+        assert!(records[1].contains("WARC-Target-URI: http://example.com:8080/admin?x=1\r\n"));
+        assert!(records[1].contains("WARC-Date: 1970-01-01T00:00:00.000Z\r\n"));
+        assert!(records[1].contains("GET /admin?x=1 HTTP/1.1\r\nHost: example.com:8080\r\n"));
+        assert!(!records[1].contains("hunter2"));
+        assert!(records[2].contains("WARC-Truncated: length\r\n"));
+        assert!(records[2].ends_with("HTTP/1.1 200 OK\r\ncontent-type: text/html\r\n\r\n<h1>Admin</h1>\r\n\r\n"));
+
+        // Content-Length counts the block exactly
+        let (head, rest) = records[2].split_once("\r\n\r\n").unwrap();
+        let length: usize = head.lines().find_map(|l| l.strip_prefix("Content-Length: ")).unwrap().parse().unwrap();
+        assert_eq!(rest.len(), length + 4);
+
+        // Each request names the response it was sent for
+        let response_id = records[2].lines().find_map(|l| l.strip_prefix("WARC-Record-ID: ")).unwrap();
+        assert!(records[1].contains(&format!("WARC-Concurrent-To: {}\r\n", response_id)));
+    }
+
+    #[test]
+    fn gzips_each_record_separately() {
+        let warc = capture("zipped.warc.gz");
+        assert_eq!(warc.matches("WARC/1.1\r\n").count(), 3);
+    }
+
+    #[test]
+    fn record_ids_are_version_4_uuids() {
+        let id = record_id();
+        let uuid = id.strip_prefix("<urn:uuid:").and_then(|id| id.strip_suffix('>')).unwrap();
+        let groups: Vec<&str> = uuid.split('-').collect();
+        assert_eq!(groups.iter().map(|g| g.len()).collect::<Vec<_>>(), [8, 4, 4, 4, 12]);
+        assert!(groups[2].starts_with('4'));
+        assert!(matches!(&groups[3][..1], "8" | "9" | "a" | "b"));
+        assert_ne!(id, record_id());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::engine::{read_body, send_request};
+use crate::error::CrawlerError;
+use crate::scope::Scope;
+use crate::{FuzzerConfig, ScanState};
+use regex::Regex;
+use reqwest::{Client, Method, Url};
+use std::collections::{HashMap, HashSet, VecDeque};
+use tracing::debug;
+
+
+/// Links worth following: href/src/action attributes and quoted paths in scripts
+const LINK_PATTERN: &str = r#"(?i)(?:href|src|action)\s*=\s*["']([^"'#\s]+)|["'](/[A-Za-z0-9_\-./]+)["']"#;
+/// Identifier-like tokens in page text, markup and scripts
+const WORD_PATTERN: &str = r"[A-Za-z][A-Za-z0-9_\-]*[A-Za-z0-9]";
+
+/// Markup and language keywords that show up on every page and name nothing
+const STOPWORDS: &[&str] = &[
+    "html", "head", "body", "div", "span", "class", "style", "script", "type", "text", "href", "src",
+    "link", "meta", "content", "charset", "utf-8", "title", "width", "height", "name", "value", "input",
+    "form", "action", "method", "button", "label", "table", "tbody", "thead", "img", "alt", "rel",
+    "stylesheet", "javascript", "function", "return", "var", "let", "const", "this", "true", "false",
+    "null", "undefined", "new", "for", "while", "else", "the", "and", "with", "from", "that", "you",
+    "your", "are", "not", "http", "https", "www", "com", "document", "window", "typeof", "async",
+    "await", "import", "export", "default", "px", "em", "rem", "none", "block", "inline", "auto",
+];
+
+/// Links followed away from the start page, unless told otherwise
+pub const DEFAULT_DEPTH: usize = 2;
+/// Pages fetched before the crawl stops, unless told otherwise
+pub const DEFAULT_MAX_PAGES: usize = 50;
+/// Shortest word kept, unless told otherwise
+pub const DEFAULT_MIN_LENGTH: usize = 3;
+
+/// Words collected from a crawl, most useful first
+pub struct Learned {
+    pub words: Vec<String>,
+    pub pages: usize,
+}
+
+/// Content types worth mining for words: pages, scripts and plain text
+fn is_textual(content_type: &str) -> bool {
+    let content_type = content_type.to_ascii_lowercase();
+    content_type.starts_with("text/") || content_type.contains("html") || content_type.contains("javascript")
+}
+
+/// Crawl same-origin pages reachable from the target URL (breadth-first, up
+/// to `max_depth` links deep and `max_pages` fetches) and collect the words a
+/// target-specific wordlist is built from. Path segments seen in links come
+/// first, then words from page content by how often they appear.
+///
+/// The crawl is held to the scan's scope and blocklists and goes through the
+/// same request path as probes (request IDs, throttling, audit log, body cap).
+pub async fn learn(
+    config: &FuzzerConfig,
+    state: &ScanState,
+    client: &Client,
+    max_depth: usize,
+    max_pages: usize,
+    min_length: usize,
+) -> Result<Learned, CrawlerError> {
+    let link_re = Regex::new(LINK_PATTERN).expect("built-in link pattern must compile");
+    let word_re = Regex::new(WORD_PATTERN).expect("built-in word pattern must compile");
+    let stopwords: HashSet<&str> = STOPWORDS.iter().copied().collect();
+    let scope = Scope::new(&config.scope_include, &config.scope_exclude, &config.blocklist)?;
+    // Crawling only ever reads pages, whatever method the scan fuzzes with
+    let config = FuzzerConfig { method: Method::GET, request_template: None, ..config.clone() };
+
+    let start = Url::parse(&config.url).map_err(|e| CrawlerError::url(&config.url, e))?;
+    let mut queue = VecDeque::from([(start.clone(), 0usize)]);
+    let mut visited = HashSet::new();
+    let mut segments: Vec<String> = Vec::new();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut pages = 0;
+
+    let keep = |word: &str| {
+        word.len() >= min_length && word.len() <= 40 && !stopwords.contains(word.to_ascii_lowercase().as_str())
+    };
+
+    while let Some((url, depth)) = queue.pop_front() {
+        if pages >= max_pages || !visited.insert(url.as_str().to_string()) {
+            continue;
+        }
+        if let Err(reason) = scope.check(url.as_str()) {
+            debug!("🚧 Not crawling {}: {}", state.redactor.redact(url.as_str()), reason);
+            continue;
+        }
+        let Ok(response) = send_request(&config, state, client, url.as_str(), None).await else {
+            continue;
+        };
+        // Error pages are boilerplate from the server, not the application
+        if !response.status().is_success() {
+            continue;
+        }
+        let textual = response.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|ct| ct.to_str().ok())
+            .is_some_and(is_textual);
+        if !textual {
+            continue;
+        }
+        let Ok((body, _)) = read_body(&config, response).await else {
+            continue;
+        };
+        let body = String::from_utf8_lossy(&body);
+        pages += 1;
+
+        for captures in link_re.captures_iter(&body) {
+            let Some(link) = captures.get(1).or_else(|| captures.get(2)) else {
+                continue;
+            };
+            let Ok(target) = url.join(link.as_str()) else {
+                continue;
+            };
+            if target.origin() != start.origin() {
+                continue;
+            }
+            for segment in target.path_segments().into_iter().flatten() {
+                // File names contribute their stem; extensions come from -x
+                let stem = segment.split('.').next().unwrap_or_default();
+                if keep(stem) {
+                    segments.push(stem.to_string());
+                }
+            }
+            if depth < max_depth {
+                let mut next = target;
+                next.set_fragment(None);
+                queue.push_back((next, depth + 1));
+            }
+        }
+
+        for word in word_re.find_iter(&body) {
+            if keep(word.as_str()) {
+                *counts.entry(word.as_str().to_string()).or_default() += 1;
+            }
+        }
+    }
+
+    let mut by_frequency: Vec<(String, usize)> = counts.into_iter().collect();
+    by_frequency.sort_by(|(a, na), (b, nb)| nb.cmp(na).then_with(|| a.cmp(b)));
+
+    let mut seen = HashSet::new();
+    let words = segments.into_iter()
+        .chain(by_frequency.into_iter().map(|(word, _)| word))
+        .filter(|word| seen.insert(word.clone()))
+        .collect();
+    Ok(Learned { words, pages })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve fixed pages on a local port, recording every path requested
+    async fn serve(pages: &'static [(&'static str, &'static str, &'static str)]) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let log = requested.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                log.lock().unwrap().push(path.clone());
+                let response = match pages.iter().find(|(p, _, _)| *p == path) {
+                    Some((_, content_type, body)) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        content_type, body.len(), body
+                    ),
+                    None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        (base, requested)
+    }
+
+    #[test]
+    fn only_text_html_and_scripts_are_textual() {
+        assert!(is_textual("text/html; charset=utf-8"));
+        assert!(is_textual("TEXT/PLAIN"));
+        assert!(is_textual("application/javascript"));
+        assert!(is_textual("application/xhtml+xml"));
+        assert!(!is_textual("image/png"));
+        assert!(!is_textual("application/octet-stream"));
+    }
+
+    #[tokio::test]
+    async fn crawl_collects_words_and_respects_blocklists() {
+        static PAGES: &[(&str, &str, &str)] = &[
+            ("/", "text/html", r#"<a href="/dashboard/reports">Reports</a> <a href="/logout">Sign out</a>
+                <a href="/logo.png">logo</a> <script src="/static/app.js"></script> invoices invoices"#),
+            ("/static/app.js", "application/javascript", r#"fetch("/api/internal/billing")"#),
+            ("/logo.png", "image/png", "quarterly-secrets"),
+        ];
+        let (base, requested) = serve(PAGES).await;
+        let config = FuzzerConfig { url: format!("{}/", base), ..FuzzerConfig::default() };
+        let client = Client::new();
+        let learned = learn(&config, &ScanState::default(), &client, 2, 50, 3).await.unwrap();
+
+        for word in ["dashboard", "reports", "static", "app", "api", "internal", "billing", "invoices"] {
+            assert!(learned.words.contains(&word.to_string()), "missing {}", word);
+        }
+        assert!(!learned.words.contains(&"quarterly-secrets".to_string()), "binary bodies are not mined");
+        assert!(!requested.lock().unwrap().contains(&"/logout".to_string()), "blocked paths are not crawled");
+        assert_eq!(learned.pages, 2);
+    }
+}
@@ -0,0 +1,383 @@
+use crate::error::CrawlerError;
+use crate::term;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use colored::*;
+
+
+/// Wordlist path standing for the list compiled into the binary
+pub const BUILTIN: &str = "builtin:common";
+
+/// Where downloaded wordlists are kept between runs
+pub const DEFAULT_CACHE: &str = "~/.dir_crawler/wordlists";
+
+/// Where `wordlists install seclists` puts the collection by default
+pub const DEFAULT_SECLISTS_DIR: &str = "~/.dir_crawler/seclists";
+
+/// File remembering where SecLists was installed
+const REGISTRY: &str = "~/.dir_crawler/seclists.path";
+
+const SECLISTS_REPO: &str = "https://github.com/danielmiessler/SecLists.git";
+const SECLISTS_TARBALL: &str = "https://github.com/danielmiessler/SecLists/archive/refs/heads/master.tar.gz";
+
+/// Directory-discovery lists inside a SecLists tree, in order of preference
+const SECLISTS_DEFAULTS: &[&str] = &[
+    "Discovery/Web-Content/raft-medium-directories-lowercase.txt",
+    "Discovery/Web-Content/directory-list-2.3-medium.txt",
+];
+
+/// Subdomain list inside a SecLists tree, for `dns`
+const SECLISTS_SUBDOMAINS: &str = "Discovery/DNS/subdomains-top1million-5000.txt";
+
+/// SecLists trees looked in when none is registered
+const SECLISTS_DIRS: &[&str] = &["/usr/share/seclists", "/opt/seclists", "~/seclists", "/usr/share/wordlists/seclists"];
+
+// Comprehensive SecLists wordlist locations
+const DEFAULT_WORDLISTS: &[&str] = &[
+    // Web Content Wordlists
+    "/usr/share/seclists/Discovery/Web-Content/raft-medium-directories-lowercase.txt",
+    "/opt/seclists/Discovery/Web-Content/raft-medium-directories-lowercase.txt",
+    "~/seclists/Discovery/Web-Content/raft-medium-directories-lowercase.txt",
+    "./raft-medium-directories-lowercase.txt",
+    "/usr/share/wordlists/seclists/raft-medium-directories-lowercase.txt",
+    "~/wordlists/seclists/raft-medium-directories-lowercase.txt",
+
+    // Additional Potential Locations
+    "/usr/share/seclists/Discovery/Web-Content/directory-list-2.3-medium.txt",
+    "/opt/seclists/Discovery/Web-Content/directory-list-2.3-medium.txt",
+    "~/seclists/Discovery/Web-Content/directory-list-2.3-medium.txt",
+    "/usr/share/wordlists/seclists/directory-list-2.3-medium.txt",
+];
+
+/// How long a wordlist download may take as a whole
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Small curated directory list so a fresh machine can scan without SecLists
+#[cfg(feature = "builtin-wordlist")]
+const BUILTIN_WORDS: Option<&str> = Some(include_str!("../wordlists/common.txt"));
+#[cfg(not(feature = "builtin-wordlist"))]
+const BUILTIN_WORDS: Option<&str> = None;
+
+/// SecLists tree registered by `wordlists install`, if it is still there
+fn registered_seclists() -> Option<PathBuf> {
+    let registry = shellexpand::tilde(REGISTRY).into_owned();
+    let dir = PathBuf::from(std::fs::read_to_string(registry).ok()?.trim());
+    dir.is_dir().then_some(dir)
+}
+
+/// The registered SecLists install first, then the well-known locations
+fn default_candidates() -> Vec<PathBuf> {
+    let registered = registered_seclists()
+        .into_iter()
+        .flat_map(|dir| SECLISTS_DEFAULTS.iter().map(move |list| dir.join(list)));
+    let known = DEFAULT_WORDLISTS.iter().map(|path| PathBuf::from(shellexpand::tilde(path).into_owned()));
+    registered.chain(known).collect()
+}
+
+/// First default wordlist present on this machine
+pub fn find_default() -> Option<PathBuf> {
+    default_candidates().into_iter().find(|path| path.exists())
+}
+
+/// Subdomain list of the registered or a well-known SecLists tree
+pub fn find_subdomains() -> Option<PathBuf> {
+    let known = SECLISTS_DIRS.iter().map(|dir| PathBuf::from(shellexpand::tilde(dir).into_owned()));
+    registered_seclists()
+        .into_iter()
+        .chain(known)
+        .map(|dir| dir.join(SECLISTS_SUBDOMAINS))
+        .find(|path| path.exists())
+}
+
+/// Whether this build carries the embedded wordlist
+pub fn builtin_available() -> bool {
+    BUILTIN_WORDS.is_some()
+}
+
+fn builtin_words(path: &Path) -> Option<&'static str> {
+    BUILTIN_WORDS.filter(|_| path == Path::new(BUILTIN))
+}
+
+/// Every entry of the wordlist at `path`, or of the embedded list
+pub fn load(path: &Path) -> std::io::Result<Vec<String>> {
+    if let Some(words) = builtin_words(path) {
+        return Ok(entries(words.lines().map(str::to_string)));
+    }
+    Ok(entries(BufReader::new(File::open(path)?).lines().collect::<Result<Vec<_>, _>>()?))
+}
+
+/// Fail as `load` would, without reading the list
+pub fn check(path: &Path) -> std::io::Result<()> {
+    if builtin_words(path).is_some() {
+        return Ok(());
+    }
+    match File::open(path)?.metadata()?.is_dir() {
+        true => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "it is a directory")),
+        false => Ok(()),
+    }
+}
+
+/// Lines trimmed of surrounding whitespace, minus blank ones and `#`
+/// comments like the licence headers SecLists files start with
+fn entries(lines: impl IntoIterator<Item = String>) -> Vec<String> {
+    lines.into_iter()
+        .filter_map(|line| {
+            let entry = line.trim();
+            (!entry.is_empty() && !entry.starts_with('#')).then(|| entry.to_string())
+        })
+        .collect()
+}
+
+/// Put the words in random order (Fisher-Yates over a clock-seeded xorshift)
+pub fn shuffle(words: &mut [String]) {
+    let seed = crate::calibrate::random_segment();
+    let mut state = u64::from_str_radix(&seed[..16], 16).unwrap_or(0x9E37_79B9_7F4A_7C15) | 1;
+    for i in (1..words.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        words.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+/// What a scan runs through: a plain list of entries, or multi-position
+/// combinations generated as they are scanned
+#[derive(Debug, Clone)]
+pub enum Entries {
+    List(Vec<String>),
+    Combinations(crate::template::Clusterbomb),
+}
+
+impl Entries {
+    pub fn len(&self) -> u64 {
+        match self {
+            Entries::List(words) => words.len() as u64,
+            Entries::Combinations(combos) => combos.len(),
+        }
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = String> + Send + '_> {
+        match self {
+            Entries::List(words) => Box::new(words.iter().cloned()),
+            Entries::Combinations(combos) => Box::new(combos.iter()),
+        }
+    }
+
+    /// Entries `start..end`, e.g. one distributed work unit
+    pub fn slice(&self, start: u64, end: u64) -> Vec<String> {
+        let end = end.min(self.len());
+        match self {
+            Entries::List(words) => words[start as usize..end as usize].to_vec(),
+            Entries::Combinations(combos) => (start..end).map(|index| combos.get(index)).collect(),
+        }
+    }
+
+    /// URLs requested for these entries under one base URL
+    pub fn count_urls(&self, base_url: &str, extensions: &[String]) -> u64 {
+        match self {
+            Entries::List(words) => crate::count_urls(base_url, words, extensions),
+            // Multi-position templates take no extensions: one request per combination
+            Entries::Combinations(combos) => combos.len(),
+        }
+    }
+
+    /// Randomize the order; combinations shuffle each position's list
+    pub fn shuffle(&mut self) {
+        match self {
+            Entries::List(words) => shuffle(words),
+            Entries::Combinations(combos) => combos.lists_mut().iter_mut().for_each(|list| shuffle(list)),
+        }
+    }
+}
+
+/// SHA-256 of the wordlist's contents, for the report's input hashes
+pub fn sha256(path: &Path) -> std::io::Result<String> {
+    match builtin_words(path) {
+        Some(words) => Ok(crate::signing::sha256_hex(words.as_bytes())),
+        None => crate::signing::sha256_file(path),
+    }
+}
+
+/// Whether `-w` names a list to download rather than a local file
+pub fn is_remote(spec: &str) -> bool {
+    spec.starts_with("http://") || spec.starts_with("https://")
+}
+
+/// Cache file for a URL: a hash of the URL keeps names unique, and the
+/// list's own file name keeps them recognizable
+fn cache_path(cache_dir: &Path, url: &str) -> PathBuf {
+    let key = &crate::signing::sha256_hex(url.as_bytes())[..16];
+    let name: String = url.split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    cache_dir.join(format!("{}-{}", key, name.trim_start_matches('.')))
+}
+
+/// Sidecar holding the SHA-256 recorded when a list was downloaded
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+/// Local copy of a remote wordlist. A cached copy is reused while it still
+/// matches the checksum recorded at download time (and `expected`, if given);
+/// otherwise the list is downloaded again. Returns the path and whether it
+/// was downloaded on this run.
+pub async fn fetch(url: &str, cache_dir: &Path, expected: Option<&str>) -> Result<(PathBuf, bool), CrawlerError> {
+    let expected = expected.map(str::to_ascii_lowercase);
+    let path = cache_path(cache_dir, url);
+    let sidecar = checksum_path(&path);
+
+    if let (Ok(hash), Ok(recorded)) = (crate::signing::sha256_file(&path), std::fs::read_to_string(&sidecar)) {
+        let intact = hash == recorded.trim();
+        if intact && expected.as_ref().is_none_or(|want| *want == hash) {
+            return Ok((path, false));
+        }
+    }
+
+    let body = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()?
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let hash = crate::signing::sha256_hex(&body);
+    if let Some(want) = expected.filter(|want| *want != hash) {
+        return Err(format!("downloaded list has SHA-256 {}, expected {}", hash, want).into());
+    }
+
+    // Write beside the final name and rename so an interrupted download
+    // never leaves a truncated list behind
+    std::fs::create_dir_all(cache_dir)
+        .map_err(|e| format!("Cannot create wordlist cache {}: {}", cache_dir.display(), e))?;
+    let partial = path.with_extension("part");
+    std::fs::write(&partial, &body)?;
+    std::fs::rename(&partial, &path)?;
+    std::fs::write(&sidecar, format!("{}\n", hash))?;
+    Ok((path, true))
+}
+
+/// Run an external command, failing with its name when it can't run or exits non-zero
+fn run(program: &str, args: &[&str]) -> Result<(), CrawlerError> {
+    let status = std::process::Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| format!("Cannot run {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status).into());
+    }
+    Ok(())
+}
+
+/// Fetch SecLists into `dir` (a shallow git clone, or the release tarball
+/// when git is missing) and register it as the default wordlist source.
+/// An existing clone is updated instead.
+pub fn install_seclists(dir: &Path) -> Result<(), CrawlerError> {
+    let target = dir.to_string_lossy();
+    if dir.join(".git").is_dir() {
+        term::out!("🔄 Updating SecLists in {}", target.blue());
+        run("git", &["-C", &target, "pull", "--ff-only"])?;
+    } else if dir.join("Discovery").is_dir() {
+        term::out!("📦 SecLists already present in {}", target.blue());
+    } else if run("git", &["--version"]).is_ok() {
+        term::out!("📥 Cloning SecLists into {}", target.blue());
+        run("git", &["clone", "--depth", "1", SECLISTS_REPO, &target])?;
+    } else {
+        term::out!("📥 Downloading SecLists into {}", target.blue());
+        std::fs::create_dir_all(dir)?;
+        let script = format!(
+            "curl -fsSL {} | tar -xz --strip-components=1 -C '{}'",
+            SECLISTS_TARBALL,
+            target.replace('\'', "'\\''")
+        );
+        run("sh", &["-c", &script])?;
+    }
+
+    let registry = PathBuf::from(shellexpand::tilde(REGISTRY).into_owned());
+    if let Some(parent) = registry.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let dir = dir.canonicalize()?;
+    std::fs::write(&registry, format!("{}\n", dir.display()))?;
+    term::out!("✅ Registered {} as the default wordlist source", dir.display().to_string().green());
+    Ok(())
+}
+
+fn count_entries(path: &Path) -> Option<usize> {
+    load(path).ok().map(|words| words.len())
+}
+
+/// Print every wordlist a scan could use: the built-in list, the defaults
+/// found on this machine, the registered SecLists discovery lists and
+/// cached downloads
+pub fn list(cache_dir: &Path) {
+    let print = |path: &Path| {
+        if let Some(count) = count_entries(path) {
+            term::out!("{:>9}  {}", count.to_string().green(), path.display());
+        }
+    };
+
+    term::out!("{:>9}  {}", "ENTRIES".bold(), "WORDLIST".bold());
+    if builtin_available() {
+        print(Path::new(BUILTIN));
+    }
+    let defaults: Vec<PathBuf> = default_candidates().into_iter().filter(|path| path.exists()).collect();
+    for path in &defaults {
+        print(path);
+    }
+
+    if let Some(dir) = registered_seclists() {
+        let mut lists: Vec<PathBuf> = std::fs::read_dir(dir.join("Discovery/Web-Content"))
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt") && !defaults.contains(path))
+            .collect();
+        lists.sort();
+        if !lists.is_empty() {
+            term::out!("\n📚 SecLists ({}):", dir.display());
+            for path in &lists {
+                print(path);
+            }
+        }
+    } else {
+        term::out!("\n💡 Run `dir_crawler wordlists install seclists` for the full SecLists collection");
+    }
+
+    let mut cached: Vec<PathBuf> = std::fs::read_dir(cache_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_none_or(|ext| ext != "sha256" && ext != "part"))
+        .collect();
+    cached.sort();
+    if !cached.is_empty() {
+        term::out!("\n📥 Downloaded ({}):", cache_dir.display());
+        for path in &cached {
+            print(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let lines = ["# Copyright 2024", "#", "admin", "  ", "api/v1 ", "", "\tlogin", "index.php#top"];
+        assert_eq!(entries(lines.map(String::from)), ["admin", "api/v1", "login", "index.php#top"]);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::error::CrawlerError;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+
+/// Where workspaces live unless `--workspace-root` says otherwise
+pub const DEFAULT_ROOT: &str = "~/.dir_crawler/workspaces";
+
+/// Metadata file identifying a directory as a scan workspace
+const SUMMARY_FILE: &str = "scan.json";
+
+/// Per-scan directory holding results, logs and stored responses
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+/// What a finished scan leaves behind in its workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanSummary {
+    pub name: String,
+    pub target: String,
+    pub scan_id: String,
+    pub started_at: u64,
+    pub duration_secs: f64,
+    pub hits: usize,
+    pub errors: u64,
+}
+
+/// UTC calendar date and time of a Unix timestamp
+fn civil(secs: u64) -> (i64, i64, i64, u64, u64, u64) {
+    // Days-to-civil conversion (Howard Hinnant's algorithm)
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day, rem / 3_600, rem % 3_600 / 60, rem % 60)
+}
+
+/// `YYYYmmdd-HHMMSS` in UTC, for use in file names
+pub fn format_timestamp(secs: u64) -> String {
+    let (year, month, day, hour, minute, second) = civil(secs);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, hour, minute, second)
+}
+
+/// `YYYY-mm-dd HH:MM:SS` in UTC, for display
+pub fn format_datetime(secs: u64) -> String {
+    let (year, month, day, hour, minute, second) = civil(secs);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, hour, minute, second)
+}
+
+/// `YYYY-mm-ddTHH:MM:SS.sssZ`, the ISO 8601 form archives and browsers expect
+pub fn format_iso8601(millis: u64) -> String {
+    let (year, month, day, hour, minute, second) = civil(millis / 1_000);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z", year, month, day, hour, minute, second, millis % 1_000)
+}
+
+/// Unix time of an HTTP date (`Sun, 06 Nov 1994 08:49:37 GMT`, or the
+/// obsolete RFC 850 and asctime forms some servers still send)
+pub fn parse_http_date(value: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    // The weekday adds nothing
+    let (_, rest) = value.trim().split_once([',', ' '])?;
+    let parts: Vec<&str> = rest.split([' ', '-']).filter(|part| !part.is_empty()).collect();
+    let (day, month, year, time) = match parts[..] {
+        [day, month, year, time, "GMT"] => (day, month, year, time),
+        [month, day, time, year] => (day, month, year, time),
+        _ => return None,
+    };
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let day: i64 = day.parse().ok().filter(|day| (1..=31).contains(day))?;
+    let year: i64 = match year.parse().ok()? {
+        year @ 0..=69 => year + 2000,
+        year @ 70..=99 => year + 1900,
+        year => year,
+    };
+    let time: Vec<u64> = time.split(':').map(str::parse).collect::<Result<_, _>>().ok()?;
+    let [hour @ 0..=23, minute @ 0..=59, second @ 0..=60] = time[..] else {
+        return None;
+    };
+
+    // Civil-to-days conversion (Howard Hinnant's algorithm)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let days = era * 146_097 + yoe * 365 + yoe / 4 - yoe / 100 + doy - 719_468;
+    Some(u64::try_from(days).ok()? * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+/// Default workspace name: the target's host (and port) plus a timestamp
+fn default_name(target: &str) -> String {
+    let host = Url::parse(target)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| match u.port() {
+            Some(port) => format!("{}_{}", h, port),
+            None => h.to_string(),
+        }))
+        .unwrap_or_else(|| "scan".to_string());
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    format!("{}-{}", host, format_timestamp(now))
+}
+
+/// Keep workspace names to a single safe path component
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+/// Expand `~` in a workspace root
+pub fn expand_root(root: &str) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(root).into_owned())
+}
+
+impl Workspace {
+    /// Create (or reuse) the named workspace under `root`; an empty name
+    /// derives one from the target and the current time
+    pub fn create(root: &Path, name: &str, target: &str) -> Result<Self, CrawlerError> {
+        let name = match sanitize(name) {
+            name if name.is_empty() => default_name(target),
+            name => name,
+        };
+        let dir = root.join(&name);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Cannot create workspace {}: {}", dir.display(), e))?;
+        Ok(Workspace { name, dir })
+    }
+
+    /// Place an output file in the workspace: relative paths are resolved
+    /// inside it, and `default` is used when the option wasn't given
+    pub fn place(&self, explicit: Option<&String>, default: Option<&str>) -> Option<PathBuf> {
+        match explicit.map(PathBuf::from) {
+            Some(path) if path.is_absolute() => Some(path),
+            Some(path) => Some(self.dir.join(path)),
+            None => default.map(|name| self.dir.join(name)),
+        }
+    }
+
+    /// Summary left by a finished scan, if this is a scan workspace
+    pub fn read_summary(&self) -> Option<ScanSummary> {
+        let data = std::fs::read(self.dir.join(SUMMARY_FILE)).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    /// Record the scan's outcome so the workspace can be listed later
+    pub fn write_summary(&self, summary: &ScanSummary) -> Result<(), CrawlerError> {
+        let json = serde_json::to_string_pretty(summary)?;
+        std::fs::write(self.dir.join(SUMMARY_FILE), json)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_http_dates() {
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(784_111_777));
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), Some(784_111_777));
+        assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), Some(784_111_777));
+        assert_eq!(parse_http_date("Thu, 29 Feb 2024 23:59:59 GMT").map(format_datetime).as_deref(), Some("2024-02-29 23:59:59"));
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 25:49:37 GMT"), None);
+        assert_eq!(parse_http_date("yesterday"), None);
+    }
+}